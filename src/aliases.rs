@@ -0,0 +1,259 @@
+/*!
+ * Rolling version aliases (`use --alias php8`)
+ *
+ * An alias such as `php8` or `php8.3` lives in the link directory next to
+ * `php` and points at the newest compiled install of its series. The aliases
+ * palawija created are listed in `~/.palawija/aliases`, one name per line,
+ * so a file of the same name that palawija didn't create is never replaced
+ * without `--force`.
+ *
+ * `relink` (and `--expose-versioned`) adds a `php<major>.<minor>` alias for
+ * every compiled branch, like Debian's `php8.2`, and drops aliases whose
+ * series has no compiled install left.
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::atomic;
+use crate::installs;
+use crate::link;
+use crate::paths;
+use crate::releases;
+
+/// Location of the list of managed aliases
+pub fn aliases_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::install_root()?.join("aliases"))
+}
+
+/**
+ * The version series an alias name stands for
+ *
+ * # Returns
+ * * `Result<&str, String>` - `8` for `php8`, `8.3` for `php8.3`, or why the name isn't an alias
+ */
+pub fn series(name: &str) -> Result<&str, String> {
+    let series = name.strip_prefix("php").unwrap_or("");
+    let valid = !series.is_empty()
+        && series.split('.').count() <= 2
+        && series.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    if valid {
+        Ok(series)
+    } else {
+        Err(format!("'{}' is not a version alias; use a name like php8 or php8.3", name))
+    }
+}
+
+/// Whether `version` belongs to the series of alias `name`
+pub fn matches(name: &str, version: &str) -> bool {
+    series(name).is_ok_and(|series| version.starts_with(&format!("{}.", series)))
+}
+
+/// The aliases palawija manages
+pub fn read_managed() -> Vec<String> {
+    let Ok(content) = aliases_path().and_then(|path| Ok(std::fs::read_to_string(path)?)) else {
+        return Vec::new();
+    };
+    content.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from).collect()
+}
+
+fn write_managed(names: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let content: String = names.iter().map(|name| format!("{}\n", name)).collect();
+    atomic::write_atomic(&aliases_path()?, content.as_bytes())
+}
+
+/**
+ * Points alias `name` in the link directory at `version` and records it
+ *
+ * # Arguments
+ * * `name` - The alias, e.g. `php8`
+ * * `version` - An installed, compiled version of the alias' series
+ * * `force` - Replace a file of that name that palawija doesn't manage
+ *
+ * # Returns
+ * * `Result<PathBuf, Box<dyn std::error::Error>>` - The alias link
+ */
+pub fn set(name: &str, version: &str, force: bool) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    series(name)?;
+    if !matches(name, version) {
+        return Err(format!("❌ PHP {} isn't in the {} series of alias {}", version, series(name)?, name).into());
+    }
+
+    let binary = paths::php_binary(&paths::version_dir(version)?);
+    if !binary.is_file() {
+        return Err(format!("❌ PHP {} isn't compiled, so alias {} can't point to it", version, name).into());
+    }
+
+    let link_path = paths::link_dir().join(name);
+    let mut managed = read_managed();
+    let is_managed = managed.iter().any(|alias| alias == name);
+    if std::fs::symlink_metadata(&link_path).is_ok() && !is_managed && !force {
+        return Err(format!(
+            "❌ {} exists and isn't an alias palawija manages\n💡 Replace it with: palawija use {} --alias {} --force",
+            link_path.display(), version, name
+        ).into());
+    }
+
+    link::replace_symlink(&binary, &link_path)?;
+    if !is_managed {
+        managed.push(name.to_string());
+        write_managed(&managed)?;
+    }
+    Ok(link_path)
+}
+
+/// Stops managing alias `name`, removing its link if it still points into the install root
+pub fn remove(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let link_path = paths::link_dir().join(name);
+    let install_root = paths::install_root()?;
+    if std::fs::read_link(&link_path).is_ok_and(|target| target.starts_with(&install_root)) {
+        std::fs::remove_file(&link_path)?;
+    }
+    let managed: Vec<String> = read_managed().into_iter().filter(|alias| alias != name).collect();
+    write_managed(&managed)
+}
+
+/// The newest compiled install of `series` among `installed`
+fn newest_compiled(installed: &[String], name: &str, install_root: &Path) -> Option<String> {
+    installed.iter()
+        .filter(|version| matches(name, version))
+        .filter(|version| paths::php_binary(&install_root.join(format!("php-{}", version))).is_file())
+        .max_by(|a, b| releases::compare_versions(a, b))
+        .cloned()
+}
+
+/// What `relink` changed in the link directory
+#[derive(Debug, Default)]
+pub struct Relink {
+    /// Versioned links now in place (or moved by the refresh) and their version
+    pub linked: Vec<(String, String)>,
+    /// Managed aliases removed because no compiled install of their series is left
+    pub removed: Vec<String>,
+    /// Versioned links not created because a file palawija doesn't manage is in the way
+    pub skipped: Vec<PathBuf>,
+}
+
+/// The `php<major>.<minor>` alias names for the branches of `versions`, sorted and unique
+pub fn versioned_names(versions: &[String]) -> Vec<String> {
+    let mut names: Vec<String> = versions.iter()
+        .filter(|version| releases::version_parts(version).len() >= 2)
+        .map(|version| format!("php{}", releases::branch_of(version)))
+        .filter(|name| series(name).is_ok())
+        .collect();
+    names.sort_by(|a, b| releases::compare_versions(&a[3..], &b[3..]));
+    names.dedup();
+    names
+}
+
+/**
+ * Reconciles the versioned links with the compiled installs
+ *
+ * Every compiled branch gets a `php<major>.<minor>` alias pointing at its
+ * newest patch, managed aliases of series with no compiled install left are
+ * removed, and the remaining aliases are refreshed.
+ *
+ * # Returns
+ * * `Result<Relink, Box<dyn std::error::Error>>` - What changed
+ */
+pub fn relink() -> Result<Relink, Box<dyn std::error::Error>> {
+    let install_root = paths::install_root()?;
+    let installed = if install_root.exists() { installs::scan(&install_root)?.versions } else { Vec::new() };
+    let compiled: Vec<String> = installed.iter()
+        .filter(|version| paths::php_binary(&install_root.join(format!("php-{}", version))).is_file())
+        .cloned()
+        .collect();
+    let mut result = Relink::default();
+
+    let mut managed = read_managed();
+    managed.retain(|name| {
+        if newest_compiled(&compiled, name, &install_root).is_some() {
+            return true;
+        }
+        // Only a link still pointing into an install is removed; anything else is no longer ours
+        let link_path = paths::link_dir().join(name);
+        if std::fs::read_link(&link_path).is_ok_and(|target| target.starts_with(&install_root)) {
+            let _ = std::fs::remove_file(&link_path);
+            result.removed.push(name.clone());
+        }
+        false
+    });
+
+    for name in versioned_names(&compiled) {
+        let Some(newest) = newest_compiled(&compiled, &name, &install_root) else {
+            continue;
+        };
+        let link_path = paths::link_dir().join(&name);
+        let is_managed = managed.contains(&name);
+        if std::fs::symlink_metadata(&link_path).is_ok() && !is_managed {
+            result.skipped.push(link_path);
+            continue;
+        }
+        link::replace_symlink(&paths::php_binary(&install_root.join(format!("php-{}", newest))), &link_path)?;
+        if !is_managed {
+            managed.push(name.clone());
+        }
+        result.linked.push((name, newest));
+    }
+
+    write_managed(&managed)?;
+    result.linked.extend(refresh());
+    Ok(result)
+}
+
+/**
+ * Re-points every managed alias at the newest compiled install of its series
+ *
+ * Called after an install finishes compiling, so `php8` follows new patch
+ * releases. Aliases whose link was replaced by something else are left alone.
+ *
+ * # Returns
+ * * `Vec<(String, String)>` - The aliases that moved and their new version
+ */
+pub fn refresh() -> Vec<(String, String)> {
+    let Ok(install_root) = paths::install_root() else {
+        return Vec::new();
+    };
+    let installed = installs::scan(&install_root).map(|scan| scan.versions).unwrap_or_default();
+    let mut moved = Vec::new();
+
+    for name in read_managed() {
+        let link_path = paths::link_dir().join(&name);
+        let Ok(current) = std::fs::read_link(&link_path) else {
+            continue;
+        };
+        if !current.starts_with(&install_root) {
+            continue;
+        }
+        let Some(newest) = newest_compiled(&installed, &name, &install_root) else {
+            continue;
+        };
+        let target = paths::php_binary(&install_root.join(format!("php-{}", newest)));
+        if current != target && link::replace_symlink(&target, &link_path).is_ok() {
+            moved.push((name, newest));
+        }
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias_names_map_to_series() {
+        assert_eq!(series("php8"), Ok("8"));
+        assert_eq!(series("php8.3"), Ok("8.3"));
+        assert!(series("php").is_err());
+        assert!(series("php8.3.1").is_err());
+        assert!(series("phpize").is_err());
+        assert!(matches("php8", "8.3.1"));
+        assert!(matches("php8.3", "8.3.12"));
+        assert!(!matches("php8.3", "8.30.0"));
+        assert!(!matches("php7", "8.0.0"));
+    }
+
+    #[test]
+    fn every_branch_gets_one_versioned_name() {
+        let versions: Vec<String> = ["8.3.1", "8.2.15", "8.3.0", "8.10.0", "git-master"].iter().map(|v| v.to_string()).collect();
+        assert_eq!(versioned_names(&versions), ["php8.2", "php8.3", "php8.10"]);
+    }
+}
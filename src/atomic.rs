@@ -0,0 +1,78 @@
+/*!
+ * Atomic writes for the small state files palawija keeps
+ *
+ * Manifests, the pin, the alias list and the remembered `available` filter
+ * are written to a temporary file in the same directory and renamed into
+ * place, so an interrupted run leaves either the old or the new contents and
+ * never a truncated file.
+ */
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+/**
+ * Replaces the contents of `path` with `contents` atomically
+ *
+ * Missing parent directories are created. Writing the contents a file
+ * already has leaves it untouched.
+ *
+ * # Arguments
+ * * `path` - The file to write
+ * * `contents` - Its new contents
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Why the file couldn't be written
+ */
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    if std::fs::read(path).is_ok_and(|current| current == contents) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let temp = temp_path(path);
+    let written = std::fs::File::create(&temp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| std::fs::rename(&temp, path)) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(format!("Failed to write {}: {}", path.display(), e).into());
+    }
+    Ok(())
+}
+
+/// Serializes `value` as pretty JSON and writes it to `path` atomically
+pub fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    let mut json = serde_json::to_string_pretty(value)?;
+    json.push('\n');
+    write_atomic(path, json.as_bytes())
+}
+
+/// Sibling of `path` the new contents are staged in (`.<name>.tmp-<pid>`)
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp-{}", name, std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_replace_contents_without_leaving_temp_files() {
+        let dir = std::env::temp_dir().join(format!("palawija-atomic-test-{}", std::process::id()));
+        let path = dir.join("nested").join("state.json");
+
+        write_json_atomic(&path, &vec!["8.3.1"]).unwrap();
+        write_json_atomic(&path, &vec!["8.3.2"]).unwrap();
+        write_json_atomic(&path, &vec!["8.3.2"]).unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[\n  \"8.3.2\"\n]\n");
+        assert_eq!(std::fs::read_dir(path.parent().unwrap()).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
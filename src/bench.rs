@@ -0,0 +1,152 @@
+/*!
+ * Comparative timings across installed versions (`palawija bench`)
+ *
+ * Each version's php runs the same workload: a script, an empty `-r ''`
+ * for startup time, or a built-in microbenchmark. Every version is run once
+ * to warm up the file cache and then timed with
+ * `Instant` for the requested number of iterations. php runs the way
+ * `exec` runs it, so its own php.ini is picked up.
+ */
+
+use std::io::Write;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use crate::exec;
+use crate::table::Table;
+
+/// Iterations unless `--iterations` says otherwise
+pub const DEFAULT_ITERATIONS: u32 = 10;
+
+/// Workload used without a script: array building, hashing and sorting
+const MICROBENCH: &str = "$a = []; for ($i = 0; $i < 200000; $i++) { $a[] = md5((string) $i); } sort($a); \
+                          $s = ''; foreach ($a as $h) { $s .= $h[0]; } echo strlen($s), PHP_EOL;";
+
+/// What each php run executes
+pub enum Workload<'a> {
+    Script(&'a Path),
+    Startup,
+    Microbench,
+}
+
+impl Workload<'_> {
+    fn args(&self) -> Vec<String> {
+        match self {
+            Workload::Script(path) => vec![path.to_string_lossy().into_owned()],
+            Workload::Startup => vec!["-r".to_string(), String::new()],
+            Workload::Microbench => vec!["-r".to_string(), MICROBENCH.to_string()],
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Workload::Script(path) => path.display().to_string(),
+            Workload::Startup => "startup (php -r '')".to_string(),
+            Workload::Microbench => "the built-in microbenchmark".to_string(),
+        }
+    }
+}
+
+/// Timings of one version
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub mean: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+/// Mean, fastest and slowest of a set of runs, `None` without any
+pub fn summarize(runs: &[Duration]) -> Option<Summary> {
+    let min = *runs.iter().min()?;
+    let max = *runs.iter().max()?;
+    let mean = runs.iter().sum::<Duration>() / runs.len() as u32;
+    Some(Summary { mean, min, max })
+}
+
+/**
+ * Times every version on the workload and prints a comparison table
+ *
+ * # Arguments
+ * * `versions` - Installed versions, already resolved
+ * * `workload` - What php runs
+ * * `iterations` - Timed runs per version, after one warm-up run
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if a version can't run
+ *   or php exits non-zero
+ */
+pub fn run_bench(versions: &[String], workload: &Workload, iterations: usize) -> Result<(), Box<dyn std::error::Error>> {
+    println!("⏱️  Benchmarking {} with {} run(s) per version\n", workload.describe(), iterations);
+    let args = workload.args();
+
+    let mut results = Vec::new();
+    for version in versions {
+        print!("   PHP {}: warming up", version);
+        let _ = std::io::stdout().flush();
+        run_once(version, &args)?;
+        let mut runs = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            runs.push(run_once(version, &args)?);
+        }
+        let summary = summarize(&runs).ok_or("no runs to summarize")?;
+        println!(", mean {}", millis(summary.mean));
+        results.push((version, summary));
+    }
+
+    let fastest = results.iter().map(|(_, summary)| summary.mean).min().unwrap_or_default();
+    let mut table = Table::new(&["VERSION", "MEAN", "MIN", "MAX", "RELATIVE"]);
+    for (version, summary) in &results {
+        let relative = summary.mean.as_secs_f64() / fastest.as_secs_f64().max(f64::EPSILON);
+        table.push(vec![
+            version.to_string(),
+            millis(summary.mean),
+            millis(summary.min),
+            millis(summary.max),
+            format!("{:.2}x", relative),
+        ]);
+    }
+    println!();
+    table.print();
+    Ok(())
+}
+
+/// Runs the workload once and returns the wall-clock time it took
+fn run_once(version: &str, args: &[String]) -> Result<Duration, Box<dyn std::error::Error>> {
+    let mut command = exec::php_command(version, false, &[])?;
+    command.args(args).stdout(Stdio::null()).stderr(Stdio::piped());
+    let started = Instant::now();
+    let output = command.output()
+        .map_err(|e| format!("❌ Could not run PHP {}: {}", version, e))?;
+    let elapsed = started.elapsed();
+    if !output.status.success() {
+        return Err(format!(
+            "\n❌ PHP {} exited with {}\n{}",
+            version,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ).into());
+    }
+    Ok(elapsed)
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_are_summarized() {
+        let runs = [Duration::from_millis(30), Duration::from_millis(10), Duration::from_millis(20)];
+        assert_eq!(summarize(&runs), Some(Summary {
+            mean: Duration::from_millis(20),
+            min: Duration::from_millis(10),
+            max: Duration::from_millis(30),
+        }));
+        assert_eq!(summarize(&[]), None);
+        assert_eq!(millis(Duration::from_micros(12_345)), "12.3 ms");
+    }
+}
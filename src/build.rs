@@ -0,0 +1,478 @@
+/*!
+ * Compilation of extracted PHP source trees
+ *
+ * Runs the standard `./configure && make && make install` pipeline, using the
+ * same configure flags that `print_compilation_instructions` shows for manual
+ * builds, installing into the version directory itself. The output of every
+ * step is always written to `build.log` in the version directory; the
+ * terminal gets it too unless the build is quiet.
+ *
+ * With a separate build directory, configure and make run there instead
+ * (a VPATH build), so object files never land in the source tree.
+ */
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::ccache;
+use crate::cleanup;
+use crate::progress;
+use crate::space;
+
+/// Upper bound on parallel `make` jobs, whatever was requested
+pub const MAX_JOBS: usize = 64;
+
+/// Memory budgeted per compiler job when choosing the default parallelism
+const MEMORY_PER_JOB: u64 = 1536 * 1024 * 1024;
+
+/// Lines of `config.log` shown when `install --check-config` fails
+const CONFIG_LOG_TAIL: usize = 25;
+
+/// How often a running step is checked against the build timeout
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A compile step was killed because the build exceeded `--build-timeout`
+#[derive(Debug)]
+pub struct BuildTimedOut {
+    /// The step that was running when the limit was hit
+    pub step: String,
+    /// The configured limit for the whole build
+    pub limit: Duration,
+}
+
+impl std::fmt::Display for BuildTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "❌ Build timed out after {}s during {} - the step and all its child processes were killed\n💡 Raise --build-timeout or check the output above for a hung step",
+            self.limit.as_secs(),
+            self.step
+        )
+    }
+}
+
+impl std::error::Error for BuildTimedOut {}
+
+/// Where a build's output goes
+#[derive(Clone)]
+struct BuildLog {
+    file: Arc<Mutex<File>>,
+    path: PathBuf,
+    /// Keep step output off the terminal and only show progress
+    quiet: bool,
+}
+
+impl BuildLog {
+    fn create(path: PathBuf, quiet: bool) -> std::io::Result<Self> {
+        Ok(BuildLog { file: Arc::new(Mutex::new(File::create(&path)?)), path, quiet })
+    }
+
+    /// Copies `source` into the log, and to `terminal` unless quiet, until it closes
+    fn tee(&self, mut source: impl Read + Send + 'static, mut terminal: impl Write + Send + 'static) -> std::thread::JoinHandle<()> {
+        let log = self.clone();
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 8192];
+            while let Ok(read) = source.read(&mut buffer) {
+                if read == 0 {
+                    break;
+                }
+                if let Ok(mut file) = log.file.lock() {
+                    let _ = file.write_all(&buffer[..read]);
+                }
+                if !log.quiet {
+                    let _ = terminal.write_all(&buffer[..read]);
+                    let _ = terminal.flush();
+                }
+            }
+        })
+    }
+}
+
+/// Point in time by which the whole build must be finished
+#[derive(Debug, Clone, Copy)]
+struct Deadline {
+    at: Instant,
+    limit: Duration,
+}
+
+/**
+ * The configure flags palawija builds PHP with
+ *
+ * # Arguments
+ * * `version_dir` - The version directory, used as the install prefix
+ */
+pub fn configure_flags(version_dir: &str) -> Vec<String> {
+    let mut flags = vec![
+        format!("--prefix={}", version_dir),
+        format!("--with-config-file-path={}/etc", version_dir),
+        "--enable-mbstring".to_string(),
+        "--enable-zip".to_string(),
+        "--with-curl".to_string(),
+        "--with-openssl".to_string(),
+        "--with-zlib".to_string(),
+        "--enable-soap".to_string(),
+    ];
+    flags.extend(EXTRA_FLAGS.get().into_iter().flatten().cloned());
+    flags
+}
+
+static EXTRA_FLAGS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Sets the configured `[build] configure_flags` for this run; only the first call counts
+pub fn set_extra_flags(flags: Vec<String>) {
+    let _ = EXTRA_FLAGS.set(flags);
+}
+
+/**
+ * Configures, compiles and installs the source tree in `version_dir`
+ *
+ * Output of every step is written to `build.log` in `version_dir` and,
+ * unless `quiet`, streamed to the terminal as well. With a `timeout`, the
+ * limit covers all steps together; a step still running when it expires is
+ * killed along with every process it spawned.
+ *
+ * # Arguments
+ * * `version_dir` - The extracted source tree, also used as install prefix
+ * * `build_dir` - Build out of tree in this directory instead of in `version_dir`
+ * * `timeout` - Optional limit for the whole build
+ * * `jobs` - Requested `make` parallelism (defaults to the core count)
+ * * `quiet` - Show only the phases and their result instead of the step output
+ *
+ * # Returns
+ * * `Result<Vec<String>, Box<dyn std::error::Error>>` - The configure flags used
+ */
+pub fn compile(version_dir: &Path, build_dir: Option<&Path>, timeout: Option<Duration>, jobs: Option<usize>, quiet: bool) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let flags = configure_flags(&version_dir.to_string_lossy());
+    // configure is then called by absolute path, so the build dir may live anywhere
+    let source_dir = std::fs::canonicalize(version_dir)?;
+    let (work_dir, configure) = match build_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir)?;
+            (std::fs::canonicalize(dir)?, source_dir.join("configure"))
+        }
+        None => (source_dir.clone(), PathBuf::from("./configure")),
+    };
+    let jobs = parallel_jobs(jobs);
+    let deadline = timeout.map(|limit| Deadline { at: Instant::now() + limit, limit });
+    let log = BuildLog::create(version_dir.join("build.log"), quiet)?;
+
+    println!("\n⚙️  Compiling PHP in {}", version_dir.display());
+    if work_dir != source_dir {
+        println!("📂 Building out of tree in {}", work_dir.display());
+    }
+    println!("📄 Build log: {}", log.path.display());
+    if let Some(limit) = timeout {
+        println!("⏱️  Build timeout: {}s", limit.as_secs());
+    }
+    if let Some(path) = ccache::enabled() {
+        println!("⚡ Compiling through ccache ({})", path.display());
+    }
+    let ccache_before = ccache::Stats::read();
+
+    // Git checkouts ship buildconf instead of a generated configure script
+    if !version_dir.join("configure").exists() && version_dir.join("buildconf").exists() {
+        println!("🧱 Generating configure script...");
+        run_phase(Command::new("./buildconf").arg("--force").current_dir(version_dir), "buildconf", deadline, &log)?;
+    }
+
+    println!("1️⃣  Configuring build...");
+    progress::emit(&progress::Event::Configure);
+    run_phase(Command::new(&configure).args(&flags).current_dir(&work_dir), "configure", deadline, &log)?;
+
+    println!("2️⃣  Compiling with {} parallel job(s) (this may take 10-30 minutes)...", jobs);
+    progress::emit(&progress::Event::Compile);
+    run_phase(Command::new("make").arg(format!("-j{}", jobs)).current_dir(&work_dir), "make", deadline, &log)?;
+
+    println!("3️⃣  Installing...");
+    progress::emit(&progress::Event::Install);
+    run_phase(Command::new("make").arg("install").current_dir(&work_dir), "make install", deadline, &log)?;
+
+    println!("✅ Compilation finished");
+    ccache::report(ccache_before);
+    Ok(flags)
+}
+
+/// Runs one phase of `compile`, reporting its duration when the build is quiet
+fn run_phase(command: &mut Command, name: &str, deadline: Option<Deadline>, log: &BuildLog) -> Result<(), Box<dyn std::error::Error>> {
+    ccache::wrap_compilers(command);
+    let started = Instant::now();
+    let result = run_step_until(command, name, deadline, Some(log));
+    if log.quiet && result.is_ok() {
+        println!("   ✅ {} done in {}s", name, started.elapsed().as_secs());
+    }
+    result
+}
+
+/**
+ * Runs only `./configure` on a source tree, with the flags a real build would use
+ *
+ * Used by `install --check-config` to find missing dependencies and bad flag
+ * combinations without waiting for `make`. On failure the tail of
+ * `config.log` is included in the error, since configure's own output often
+ * hides the actual test that failed.
+ *
+ * # Arguments
+ * * `source_dir` - The extracted source tree to configure
+ * * `prefix` - The version directory a real install would use as prefix
+ *
+ * # Returns
+ * * `Result<Vec<String>, Box<dyn std::error::Error>>` - The configure flags that were checked
+ */
+pub fn check_configure(source_dir: &Path, prefix: &Path) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let flags = configure_flags(&prefix.to_string_lossy());
+
+    if !source_dir.join("configure").exists() && source_dir.join("buildconf").exists() {
+        println!("🧱 Generating configure script...");
+        run_step_until(Command::new("./buildconf").arg("--force").current_dir(source_dir), "buildconf", None, None)?;
+    }
+
+    println!("🧪 Running ./configure only (nothing will be compiled)...");
+    if let Err(e) = run_step_until(Command::new("./configure").args(&flags).current_dir(source_dir), "configure", None, None) {
+        let log = std::fs::read_to_string(source_dir.join("config.log")).unwrap_or_default();
+        if log.is_empty() {
+            return Err(e);
+        }
+        return Err(format!("{}\n📄 Last lines of config.log:\n{}", e, tail(&log, CONFIG_LOG_TAIL)).into());
+    }
+    Ok(flags)
+}
+
+/// The last `count` lines of `text`, indented for display
+fn tail(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(count)..]
+        .iter()
+        .map(|line| format!("   {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/**
+ * Number of parallel `make` jobs to use
+ *
+ * Defaults to one per available CPU, capped at `MAX_JOBS` and at one job per
+ * `MEMORY_PER_JOB` of available RAM so the compiler isn't OOM-killed on small
+ * machines; the reasoning is printed when the cap applies. An explicit
+ * `--jobs` is only capped at `MAX_JOBS`, with a warning if it exceeds the
+ * memory budget.
+ *
+ * # Arguments
+ * * `requested` - Job count given with `--jobs`, if any
+ */
+pub fn parallel_jobs(requested: Option<usize>) -> usize {
+    let memory = available_memory();
+    if let Some(requested) = requested {
+        let jobs = requested.clamp(1, MAX_JOBS);
+        if let Some(memory) = memory
+            && jobs > clamp_jobs(jobs, Some(memory))
+        {
+            println!(
+                "⚠️  -j{} leaves less than {} per job of the {} available RAM - the compiler may be killed",
+                jobs, space::format_bytes(MEMORY_PER_JOB), space::format_bytes(memory)
+            );
+        }
+        return jobs;
+    }
+
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let jobs = clamp_jobs(cores, memory);
+    if let Some(memory) = memory
+        && jobs < cores.min(MAX_JOBS)
+    {
+        println!(
+            "🧠 Limiting to -j{} based on {} available RAM ({} per job, {} cores); override with --jobs",
+            jobs, space::format_bytes(memory), space::format_bytes(MEMORY_PER_JOB), cores
+        );
+    }
+    jobs
+}
+
+/// Caps a job count at `MAX_JOBS` and at one job per `MEMORY_PER_JOB` of memory
+fn clamp_jobs(wanted: usize, available_memory: Option<u64>) -> usize {
+    let memory_cap = available_memory
+        .map(|bytes| (bytes / MEMORY_PER_JOB).max(1) as usize)
+        .unwrap_or(MAX_JOBS);
+    wanted.clamp(1, MAX_JOBS.min(memory_cap))
+}
+
+/// `MemAvailable` from `/proc/meminfo` in bytes, if readable
+fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let kib: u64 = meminfo.lines()
+        .find_map(|line| line.strip_prefix("MemAvailable:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Runs one build step, turning a failed exit status into an error
+pub fn run_step(command: &mut Command, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    run_step_until(command, name, None, None)
+}
+
+/**
+ * Runs one build step, killing it if the build deadline passes
+ *
+ * The step runs in its own process group, so the whole tree (make and every
+ * compiler it spawned) can be signalled at once: at the deadline here, or by
+ * the interrupt handler, which a terminal Ctrl-C no longer reaches past
+ * palawija. With a `log`, stdout and stderr are copied into it.
+ */
+fn run_step_until(command: &mut Command, name: &str, deadline: Option<Deadline>, log: Option<&BuildLog>) -> Result<(), Box<dyn std::error::Error>> {
+    command.process_group(0);
+    if log.is_some() {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+    let mut child = command.spawn()
+        .map_err(|e| format!("❌ Could not start {}: {}", name, e))?;
+    let _group = cleanup::BuildGroup::register(child.id());
+
+    let mut readers = Vec::new();
+    if let Some(log) = log {
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(log.tee(stdout, std::io::stdout()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(log.tee(stderr, std::io::stderr()));
+        }
+    }
+    let finish_output = |readers: Vec<std::thread::JoinHandle<()>>| {
+        for reader in readers {
+            let _ = reader.join();
+        }
+    };
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Some(deadline) = deadline
+            && Instant::now() >= deadline.at
+        {
+            cleanup::kill_process_group(child.id());
+            let _ = child.kill();
+            let _ = child.wait();
+            finish_output(readers);
+            return Err(Box::new(BuildTimedOut { step: name.to_string(), limit: deadline.limit }));
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+    finish_output(readers);
+
+    if !status.success() {
+        return Err(match log {
+            Some(log) if log.quiet => format!(
+                "❌ {} failed ({})\n💡 The full output is in {}",
+                name, status, log.path.display()
+            ),
+            Some(log) => format!(
+                "❌ {} failed ({})\n💡 Check the output above (also saved to {}) for missing dependencies",
+                name, status, log.path.display()
+            ),
+            None => format!("❌ {} failed ({})\n💡 Check the output above for missing dependencies", name, status),
+        }.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_is_killed_with_its_children_at_the_deadline() {
+        let deadline = Deadline { at: Instant::now() + Duration::from_millis(300), limit: Duration::from_secs(1) };
+        let started = Instant::now();
+        let err = run_step_until(Command::new("sh").arg("-c").arg("sleep 30 & sleep 30"), "sleep", Some(deadline), None)
+            .unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let timed_out = err.downcast_ref::<BuildTimedOut>().expect("expected BuildTimedOut");
+        assert_eq!(timed_out.step, "sleep");
+    }
+
+    #[test]
+    fn failed_configure_reports_the_config_log_tail() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("palawija-check-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("configure");
+        std::fs::write(&script, "#!/bin/sh\necho 'checking for oniguruma... no' > config.log\nexit 1\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let err = check_configure(&dir, Path::new("/tmp/php-8.3.0")).unwrap_err().to_string();
+        assert!(err.contains("configure failed"));
+        assert!(err.contains("checking for oniguruma... no"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn out_of_tree_builds_leave_the_source_alone() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("palawija-vpath-{}", std::process::id()));
+        let source = root.join("php-8.3.0");
+        let build = root.join("build");
+        std::fs::create_dir_all(&source).unwrap();
+        let script = source.join("configure");
+        std::fs::write(&script, "#!/bin/sh\nprintf 'all:\\n\\ttouch built\\ninstall:\\n\\ttouch installed\\n' > Makefile\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        compile(&source, Some(&build), None, Some(1), true).unwrap();
+        assert!(build.join("Makefile").is_file() && build.join("built").is_file() && build.join("installed").is_file());
+        assert!(!source.join("Makefile").exists());
+        assert!(source.join("build.log").is_file());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn quiet_steps_are_logged_but_not_shown() {
+        let path = std::env::temp_dir().join(format!("palawija-build-log-{}", std::process::id()));
+        let log = BuildLog::create(path.clone(), true).unwrap();
+        run_step_until(Command::new("sh").arg("-c").arg("echo compiling; echo warning >&2"), "make", None, Some(&log)).unwrap();
+        let err = run_step_until(&mut Command::new("false"), "make install", None, Some(&log)).unwrap_err();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("compiling") && content.contains("warning"));
+        assert!(err.to_string().contains(&path.display().to_string()));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn jobs_are_clamped() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        assert_eq!(clamp_jobs(8, Some(16 * GIB)), 8);
+        assert_eq!(clamp_jobs(8, Some(4 * GIB)), 2);
+        assert_eq!(clamp_jobs(8, Some(GIB / 4)), 1);
+        assert_eq!(clamp_jobs(0, None), 1);
+        assert_eq!(clamp_jobs(500, None), MAX_JOBS);
+    }
+
+    #[test]
+    fn explicit_jobs_ignore_the_memory_budget() {
+        // Whatever this machine's RAM, --jobs is only bounded by 1 and MAX_JOBS
+        assert_eq!(parallel_jobs(Some(3)), 3);
+        assert_eq!(parallel_jobs(Some(0)), 1);
+        assert_eq!(parallel_jobs(Some(500)), MAX_JOBS);
+
+        let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let default = parallel_jobs(None);
+        assert!((1..=cores.min(MAX_JOBS)).contains(&default), "{} jobs on {} cores", default, cores);
+        assert_eq!(default, clamp_jobs(cores, available_memory()));
+    }
+
+    #[test]
+    fn step_without_deadline_reports_failure() {
+        assert!(run_step(&mut Command::new("false"), "false").is_err());
+        assert!(run_step(&mut Command::new("true"), "true").is_ok());
+    }
+}
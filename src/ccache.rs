@@ -0,0 +1,136 @@
+/*!
+ * Compiler caching with ccache (`--ccache`, `[build] use_ccache`)
+ *
+ * When enabled, every build step runs with `CC="ccache cc"` and
+ * `CXX="ccache c++"` (wrapping `$CC` / `$CXX` when those are set), so
+ * recompiling a version, or a nearby one, reuses the objects of earlier
+ * builds. The hit and miss counters of `ccache -s` are read before and
+ * after the build, and the difference is reported. ccache keeps global
+ * counters, so builds running in parallel are counted together.
+ */
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+static CCACHE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Builds through the ccache at `path` for this run; only the first call counts
+pub fn set_ccache(path: PathBuf) {
+    let _ = CCACHE.set(path);
+}
+
+/// The ccache builds go through, if enabled
+pub fn enabled() -> Option<&'static Path> {
+    CCACHE.get().map(PathBuf::as_path)
+}
+
+/// The first `ccache` executable in PATH
+pub fn find() -> Option<PathBuf> {
+    std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default())
+        .map(|dir| dir.join("ccache"))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Points `command`'s C and C++ compilers at ccache when it is enabled
+pub fn wrap_compilers(command: &mut Command) {
+    let Some(ccache) = enabled() else {
+        return;
+    };
+    for (variable, default) in [("CC", "cc"), ("CXX", "c++")] {
+        let compiler = std::env::var(variable).ok().filter(|compiler| !compiler.trim().is_empty());
+        command.env(variable, format!("{} {}", ccache.display(), compiler.as_deref().unwrap_or(default)));
+    }
+}
+
+/// Cache hits and misses as counted by ccache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Stats {
+    /// Current counters of the enabled ccache, `None` if they can't be read
+    pub fn read() -> Option<Stats> {
+        let output = Command::new(enabled()?).arg("-s").output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        parse_stats(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Counts since `earlier`
+    pub fn since(self, earlier: Stats) -> Stats {
+        Stats { hits: self.hits.saturating_sub(earlier.hits), misses: self.misses.saturating_sub(earlier.misses) }
+    }
+}
+
+/// Prints how well the cache served the build, given the counters before it
+pub fn report(before: Option<Stats>) {
+    let (Some(before), Some(after)) = (before, Stats::read()) else {
+        return;
+    };
+    let build = after.since(before);
+    let total = build.hits + build.misses;
+    if total == 0 {
+        println!("⚡ ccache: no cacheable compilations in this build");
+        return;
+    }
+    println!(
+        "⚡ ccache: {} hit(s), {} miss(es) in this build ({}% hit rate)",
+        build.hits,
+        build.misses,
+        build.hits * 100 / total
+    );
+}
+
+/**
+ * Reads the hit and miss counters from `ccache -s` output
+ *
+ * Understands ccache 4 (`Hits: 900 / 1234 (72.93%)`) and ccache 3
+ * (`cache hit (direct) 800`, `cache miss 334`).
+ *
+ * # Returns
+ * * `Option<Stats>` - The counters, `None` if neither format matched
+ */
+fn parse_stats(output: &str) -> Option<Stats> {
+    let mut stats = Stats::default();
+    let mut found = false;
+    for line in output.lines().map(str::trim) {
+        let (label, value) = match line.split_once(':') {
+            Some((label, value)) => (label.trim(), value),
+            None => match line.rfind(char::is_whitespace) {
+                Some(split) => (line[..split].trim(), &line[split..]),
+                None => continue,
+            },
+        };
+        let Some(count) = value.split_whitespace().next().and_then(|count| count.parse::<u64>().ok()) else {
+            continue;
+        };
+        match label {
+            "Hits" | "cache hit (direct)" | "cache hit (preprocessed)" => stats.hits += count,
+            "Misses" | "cache miss" => stats.misses += count,
+            _ => continue,
+        }
+        found = true;
+    }
+    found.then_some(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_of_ccache_3_and_4_are_parsed() {
+        let v4 = "Cacheable calls:   1234 / 1300 (94.92%)\n  Hits:             900 / 1234 (72.93%)\n    Direct:         800 /  900 (88.89%)\n    Preprocessed:   100 /  900 (11.11%)\n  Misses:           334 / 1234 (27.07%)\nUncacheable calls:   66 / 1300 ( 5.08%)\n";
+        assert_eq!(parse_stats(v4), Some(Stats { hits: 900, misses: 334 }));
+
+        let v3 = "cache directory                     /home/me/.ccache\ncache hit (direct)                   800\ncache hit (preprocessed)             100\ncache miss                           334\n";
+        assert_eq!(parse_stats(v3), Some(Stats { hits: 900, misses: 334 }));
+
+        assert_eq!(parse_stats("ccache: command not understood\n"), None);
+        assert_eq!(Stats { hits: 950, misses: 340 }.since(Stats { hits: 900, misses: 334 }), Stats { hits: 50, misses: 6 });
+    }
+}
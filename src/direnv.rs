@@ -0,0 +1,91 @@
+/*!
+ * direnv integration (`palawija hook direnv`, `palawija export`)
+ *
+ * `palawija hook direnv` prints a `use_palawija` function for direnv's
+ * library. Saved once with
+ *
+ * ```text
+ * palawija hook direnv > ~/.config/direnv/lib/palawija.sh
+ * ```
+ *
+ * an `.envrc` containing `use palawija` (or `use palawija 8.3`) selects a
+ * version whenever the directory is entered. The function evaluates
+ * `palawija export`, which prints the PATH, PHP_VERSION and PHP_INI_DIR
+ * exports for the version named in the nearest `.php-version`, falling back
+ * to `.palawijarc` and then the pinned default. The global symlink is never
+ * touched. PATH is rewritten rather than prepended to, with every other
+ * palawija entry dropped, so re-entering a directory, or reloading its
+ * `.envrc`, doesn't pile up entries.
+ */
+
+use std::path::{Path, PathBuf};
+
+/// File naming a directory tree's PHP version, shared with other version managers
+pub const VERSION_FILE: &str = ".php-version";
+
+/**
+ * Finds the nearest `.php-version`, in `start` or one of its ancestors
+ *
+ * # Returns
+ * * `Option<PathBuf>` - The file, `None` if no directory up to the root has one
+ */
+pub fn find_version_file(start: &Path) -> Option<PathBuf> {
+    start.ancestors().map(|dir| dir.join(VERSION_FILE)).find(|file| file.is_file())
+}
+
+/**
+ * Reads the version a `.php-version` names
+ *
+ * The first line that isn't empty or a `#` comment counts; a `php-` prefix
+ * (`php-8.3.0`) is dropped.
+ *
+ * # Returns
+ * * `Option<String>` - The version, `None` if the file can't be read or names none
+ */
+pub fn read_version_file(file: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(file).ok()?;
+    content.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.strip_prefix("php-").unwrap_or(line).to_string())
+}
+
+/// The direnv library function that `use palawija [VERSION]` calls
+pub fn hook_script(palawija: &Path) -> String {
+    format!(
+        r#"# palawija direnv integration, generated by `palawija hook direnv`
+# Save as ~/.config/direnv/lib/palawija.sh, then put `use palawija` (or `use palawija 8.3`) in an .envrc
+use_palawija() {{
+  watch_file {version_file} .palawijarc
+  local exports
+  exports="$("{palawija}" export "$@")" || return 1
+  eval "$exports"
+}}
+"#,
+        version_file = VERSION_FILE,
+        palawija = palawija.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_version_file_is_read() {
+        let root = std::env::temp_dir().join(format!("palawija-direnv-{}", std::process::id()));
+        let nested = root.join("app").join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_version_file(&nested).filter(|file| file.starts_with(&root)), None);
+
+        std::fs::write(root.join(VERSION_FILE), "8.2\n").unwrap();
+        std::fs::write(root.join("app").join(VERSION_FILE), "# pinned for the api\n\nphp-8.3.0\n").unwrap();
+        let file = find_version_file(&nested).unwrap();
+        assert_eq!(file, root.join("app").join(VERSION_FILE));
+        assert_eq!(read_version_file(&file).as_deref(), Some("8.3.0"));
+        assert_eq!(read_version_file(&root.join(VERSION_FILE)).as_deref(), Some("8.2"));
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(hook_script(Path::new("/usr/local/bin/palawija")).contains("\"/usr/local/bin/palawija\" export \"$@\""));
+    }
+}
@@ -0,0 +1,211 @@
+/*!
+ * Environment diagnostics (`palawija doctor`)
+ *
+ * Each check prints its own findings and reports whether it found a problem.
+ * With `--fix`, checks that have a safe remediation offer it, one
+ * confirmation per fix; nothing is changed when the problem isn't present.
+ */
+
+use std::io::Write;
+use std::process::Command;
+
+use crate::deps;
+use crate::paths;
+use crate::pin;
+use crate::prompt;
+use crate::shell::Shell;
+
+/// Tools needed to compile PHP from source
+const BUILD_TOOLS: &[&str] = &["cc", "make", "autoconf", "bison", "re2c", "pkg-config"];
+
+/**
+ * Runs every diagnostic check and prints a summary
+ *
+ * # Arguments
+ * * `fix` - Offer to repair the problems that have a safe fix
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if any problem remains
+ */
+pub fn run_doctor(fix: bool) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🩺 Running palawija diagnostics...\n");
+
+    let mut problems = 0;
+    problems += check_install_root(fix)?;
+    problems += check_path(fix)?;
+    problems += check_dangling_link(fix)?;
+    problems += check_build_tools();
+    problems += check_pin_drift()?;
+
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    if problems == 0 {
+        println!("✅ No problems found");
+        Ok(())
+    } else {
+        Err(format!("{} problem(s) found", problems).into())
+    }
+}
+
+/// Checks that `~/.palawija` exists; returns the number of problems left
+fn check_install_root(fix: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    println!("📂 Install root:");
+    let install_root = paths::install_root()?;
+    if install_root.is_dir() {
+        println!("   ✅ {} exists\n", install_root.display());
+        return Ok(0);
+    }
+
+    println!("   ⚠️  {} does not exist", install_root.display());
+    if fix && prompt::confirm(&format!("Create {}?", install_root.display())) {
+        std::fs::create_dir_all(&install_root)?;
+        println!("   🔧 Created {}\n", install_root.display());
+        return Ok(0);
+    }
+    println!("   💡 It's created by the first install, or by: palawija doctor --fix\n");
+    Ok(1)
+}
+
+/**
+ * Checks that the directory of the global php symlink is on PATH
+ *
+ * The fix appends the PATH line to the detected shell's rc file, which only
+ * takes effect in new shells.
+ *
+ * # Returns
+ * * `Result<usize, Box<dyn std::error::Error>>` - Number of problems left unresolved
+ */
+fn check_path(fix: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    println!("🛣️  PATH:");
+    let link_dir = paths::link_dir();
+    if crate::dir_in_path(&link_dir) {
+        println!("   ✅ {} is in PATH\n", link_dir.display());
+        return Ok(0);
+    }
+
+    let shell = Shell::detect();
+    let rc_path = paths::palawija_home()?.join(shell.rc_file());
+    let line = shell.path_prepend_line(&link_dir);
+    println!("   ⚠️  {} is not in PATH, so 'php' won't be found", link_dir.display());
+
+    let already_added = std::fs::read_to_string(&rc_path).is_ok_and(|content| content.lines().any(|l| l.trim() == line));
+    if already_added {
+        println!("   ℹ️  ~/{} already adds it - open a new terminal to pick it up\n", shell.rc_file());
+        return Ok(0);
+    }
+
+    if fix && prompt::confirm(&format!("Append '{}' to ~/{}?", line, shell.rc_file())) {
+        if let Some(parent) = rc_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut rc = std::fs::OpenOptions::new().create(true).append(true).open(&rc_path)?;
+        writeln!(rc, "\n# Added by palawija doctor --fix\n{}", line)?;
+        println!("   🔧 Updated {} - open a new terminal to pick it up\n", rc_path.display());
+        return Ok(0);
+    }
+    println!("   💡 Add this line to ~/{}: {}\n", shell.rc_file(), line);
+    Ok(1)
+}
+
+/**
+ * Checks for a global php symlink into `~/.palawija` whose target is gone
+ *
+ * Removing it may need root; in that case the exact sudo command is shown
+ * and only run after a separate confirmation.
+ *
+ * # Returns
+ * * `Result<usize, Box<dyn std::error::Error>>` - Number of problems left unresolved
+ */
+fn check_dangling_link(fix: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    println!("🔗 Global php symlink:");
+    let link_path = paths::php_link();
+    let install_root = paths::install_root()?;
+    let dangling = paths::active_php_binary()
+        .filter(|target| target.starts_with(&install_root))
+        .filter(|target| !target.exists());
+    let Some(target) = dangling else {
+        println!("   ✅ No dangling palawija symlink\n");
+        return Ok(0);
+    };
+
+    println!("   ⚠️  {} points to {}, which no longer exists", link_path.display(), target.display());
+    if fix && prompt::confirm(&format!("Remove the dangling symlink {}?", link_path.display())) {
+        match std::fs::remove_file(&link_path) {
+            Ok(()) => {
+                println!("   🔧 Removed {}\n", link_path.display());
+                return Ok(0);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied && !deps::is_root() => {
+                println!("   🔒 Removing it needs root. palawija would run:");
+                println!("      sudo rm {}", link_path.display());
+                if prompt::confirm("Run this command with sudo now?") {
+                    let status = Command::new("sudo").arg("rm").arg(&link_path).status()?;
+                    if status.success() {
+                        println!("   🔧 Removed {}\n", link_path.display());
+                        return Ok(0);
+                    }
+                    println!("   ❌ sudo rm failed ({})", status);
+                }
+            }
+            Err(e) => println!("   ❌ Could not remove it: {}", e),
+        }
+    }
+    println!("   💡 Switch to an installed version with: palawija use <version>\n");
+    Ok(1)
+}
+
+/// Reports build tools missing from PATH; returns the number of problems
+fn check_build_tools() -> usize {
+    println!("🔧 Build tools:");
+    let missing: Vec<_> = BUILD_TOOLS.iter()
+        .filter(|tool| crate::find_in_path(tool).is_empty())
+        .collect();
+
+    if missing.is_empty() {
+        println!("   ✅ All build tools found\n");
+        return 0;
+    }
+    for tool in &missing {
+        println!("   ⚠️  {} not found in PATH", tool);
+    }
+    println!("   💡 Install them with: palawija install <version> --compile --install-deps\n");
+    1
+}
+
+/**
+ * Compares the global symlink against the pinned default version
+ *
+ * When they've drifted apart (e.g. a package manager replaced the symlink),
+ * offers to re-apply the pin.
+ *
+ * # Returns
+ * * `Result<usize, Box<dyn std::error::Error>>` - Number of problems left unresolved
+ */
+fn check_pin_drift() -> Result<usize, Box<dyn std::error::Error>> {
+    println!("📌 Pinned default:");
+    let Some(pinned) = pin::read_pin() else {
+        println!("   ℹ️  No default pinned (set one with: palawija pin <version>)\n");
+        return Ok(0);
+    };
+
+    match paths::active_php_binary() {
+        // The pin is about the global link, so an environment shim doesn't count
+        _ if paths::linked_version().as_deref() == Some(pinned.as_str()) => {
+            println!("   ✅ {} points to pinned PHP {}\n", paths::php_link().display(), pinned);
+            return Ok(0);
+        }
+        Some(target) => {
+            println!("   ⚠️  {} points to {} but PHP {} is pinned", paths::php_link().display(), target.display(), pinned);
+        }
+        None => {
+            println!("   ⚠️  {} is missing or not a symlink but PHP {} is pinned", paths::php_link().display(), pinned);
+        }
+    }
+
+    if prompt::confirm(&format!("Re-apply pinned PHP {}?", pinned)) {
+        crate::use_php(&pinned, false, true)?;
+        println!();
+        return Ok(0);
+    }
+    println!("   💡 Re-apply it later with: palawija use {}\n", pinned);
+    Ok(1)
+}
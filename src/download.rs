@@ -0,0 +1,652 @@
+/*!
+ * Source tarball downloads with checksum verification and caching
+ *
+ * Tarballs are kept in `~/.palawija/cache/` so a reinstall can skip the
+ * download when the cached copy still matches the official SHA-256 published
+ * by php.net. The smaller `.tar.xz` is preferred whenever php.net publishes
+ * one for the version.
+ *
+ * With `--verify-gpg` the tarball's `.asc` signature is checked as well
+ * (see `signature`), and `--checksum-algo none` leaves that as the only check.
+ *
+ * A shared cache (`--tarball-cache-dir`) lets several users on one machine
+ * download each tarball once. It is consulted after the user's own cache,
+ * used only when the tarball there matches the official checksum, and
+ * filled after a download when it is writable. A read-only shared cache is
+ * fine: nothing is ever written to it then.
+ *
+ * Failed downloads are told apart by their HTTP status: a 404 is checked
+ * against the release list and reported as a missing version with the
+ * closest real one, 403 and 429 point at rate limiting or the mirror, and
+ * 5xx responses are retried a few times before giving up.
+ *
+ * Downloads go through curl rather than an in-process HTTP client, so a
+ * bandwidth limit (`--limit-rate`, `[download] limit_rate`) is handed to
+ * curl's own `--limit-rate` instead of pacing reads with a token bucket
+ * here. curl averages the rate over the transfer, so its progress bar and
+ * ETA already follow the limit.
+ */
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::cleanup;
+use crate::error::{NetworkError, PalawijaError};
+use crate::mirror;
+use crate::paths;
+use crate::progress;
+use crate::releases;
+use crate::signature;
+
+/// Seconds a tarball download may take unless `--download-timeout` says otherwise
+pub const DEFAULT_DOWNLOAD_TIMEOUT: u64 = 300;
+
+/// Seconds the checksum and size lookups before a download may take unless `--download-timeout` says otherwise
+pub const DEFAULT_LOOKUP_TIMEOUT: u64 = 30;
+
+/// Seconds to wait before each new attempt after a server error (5xx)
+const SERVER_ERROR_BACKOFF_SECS: [u64; 3] = [2, 5, 10];
+
+/// Checksum compared against the one php.net publishes (`--checksum-algo`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ChecksumAlgo {
+    /// SHA-256 from the php.net releases API
+    #[default]
+    Sha256,
+    /// No checksum; only allowed together with --verify-gpg
+    None,
+}
+
+/// How downloaded tarballs are verified
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Verification {
+    pub checksum: ChecksumAlgo,
+    /// Also check the `.asc` signature, failing if it is missing or invalid
+    pub gpg: bool,
+}
+
+static VERIFICATION: OnceLock<Verification> = OnceLock::new();
+
+static SHARED_CACHE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Checks `dir` for tarballs before downloading, for this run; only the first call counts
+pub fn set_shared_cache(dir: PathBuf) {
+    let _ = SHARED_CACHE.set(dir);
+}
+
+/// Verifies downloads this way for this run; only the first call counts
+pub fn set_verification(verification: Verification) {
+    let _ = VERIFICATION.set(verification);
+}
+
+fn verification() -> Verification {
+    VERIFICATION.get().copied().unwrap_or_default()
+}
+
+/// A source tarball ready for extraction
+pub struct Tarball {
+    /// Location of the tarball on disk
+    pub path: PathBuf,
+    /// URL the tarball was (or would have been) downloaded from
+    pub url: String,
+    /// SHA-256 of the tarball contents
+    pub sha256: Option<String>,
+}
+
+/// Compression of a source tarball
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ArchiveFormat {
+    /// gzip (`.tar.gz`)
+    Gz,
+    /// xz (`.tar.xz`)
+    Xz,
+}
+
+impl ArchiveFormat {
+    /// File extension of tarballs in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Gz => "tar.gz",
+            ArchiveFormat::Xz => "tar.xz",
+        }
+    }
+
+    /// The `tar` flag that decompresses this format
+    pub fn tar_flag(self) -> &'static str {
+        match self {
+            ArchiveFormat::Gz => "-z",
+            ArchiveFormat::Xz => "-J",
+        }
+    }
+
+    /// Formats to try when none was requested, most preferred first
+    pub fn candidates(requested: Option<ArchiveFormat>) -> Vec<ArchiveFormat> {
+        match requested {
+            Some(format) => vec![format],
+            None => vec![ArchiveFormat::Xz, ArchiveFormat::Gz],
+        }
+    }
+
+    /// Identifies a compression format from a file's leading magic bytes
+    pub fn from_magic(header: &[u8]) -> Option<ArchiveFormat> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(ArchiveFormat::Gz)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(ArchiveFormat::Xz)
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Detects the compression of a file on disk, ignoring its extension
+     *
+     * # Returns
+     * * `Option<ArchiveFormat>` - `None` if the file can't be read or isn't gzip/xz
+     */
+    pub fn detect(path: &Path) -> Option<ArchiveFormat> {
+        let mut header = [0u8; 6];
+        let mut file = std::fs::File::open(path).ok()?;
+        let read = file.read(&mut header).ok()?;
+        Self::from_magic(&header[..read])
+    }
+}
+
+/// Download URL of a PHP source tarball on the selected mirror (www.php.net by default)
+pub fn tarball_url(version: &str, format: ArchiveFormat) -> String {
+    format!("{}/distributions/php-{}.{}", mirror::base(), version, format.extension())
+}
+
+/// Where the tarball of a version is cached
+pub fn cached_tarball(version: &str, format: ArchiveFormat) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::cache_dir()?.join(format!("php-{}.{}", version, format.extension())))
+}
+
+/**
+ * Parses a bandwidth limit such as `500k` or `2m` into bytes per second
+ *
+ * Suffixes `k`, `m` and `g` (any case) are powers of 1024, matching curl.
+ *
+ * # Returns
+ * * `Result<u64, String>` - The rate, or a message describing the bad input
+ */
+pub fn parse_rate(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    let (digits, multiplier) = match input.chars().last().map(|c| c.to_ascii_lowercase()) {
+        Some('k') => (&input[..input.len() - 1], 1024),
+        Some('m') => (&input[..input.len() - 1], 1024 * 1024),
+        Some('g') => (&input[..input.len() - 1], 1024 * 1024 * 1024),
+        _ => (input, 1),
+    };
+    match digits.parse::<u64>().ok().and_then(|value| value.checked_mul(multiplier)) {
+        Some(rate) if rate > 0 => Ok(rate),
+        _ => Err(format!("invalid rate '{}', expected bytes per second like 500k or 2m", input)),
+    }
+}
+
+/// Parses a timeout in whole seconds, where 0 means no timeout
+pub fn parse_timeout(input: &str) -> Result<u64, String> {
+    input.trim().parse::<u64>()
+        .map_err(|_| format!("invalid timeout '{}', expected whole seconds (0 for none)", input))
+}
+
+/// Gives a curl command a total time limit, unless `seconds` is 0
+pub fn set_timeout(curl: &mut Command, seconds: u64) {
+    if seconds > 0 {
+        curl.arg("--max-time").arg(seconds.to_string());
+    }
+}
+
+/**
+ * Returns a verified source tarball for a version, downloading it if needed
+ *
+ * Without a requested format, `.tar.xz` is used when php.net lists one for
+ * the version and `.tar.gz` otherwise. A cached tarball is reused only when
+ * it passes verification (the official checksum and, if requested, the GPG
+ * signature); otherwise a fresh copy is downloaded to a `.part` file,
+ * verified, and then moved into the cache.
+ *
+ * # Arguments
+ * * `version` - PHP version string (e.g., "8.3.0")
+ * * `format` - Archive format to download, or `None` to prefer xz
+ * * `limit_rate` - Optional bandwidth limit in bytes per second; curl paces
+ *   the transfer itself, so its progress bar and ETA reflect the limit
+ * * `timeout` - Seconds the download may take (0 for no limit); `None` uses
+ *   `DEFAULT_DOWNLOAD_TIMEOUT`, or no limit when the bandwidth is limited
+ *
+ * # Returns
+ * * `Result<Tarball, Box<dyn std::error::Error>>` - The verified tarball
+ */
+pub fn fetch_tarball(
+    version: &str,
+    format: Option<ArchiveFormat>,
+    limit_rate: Option<u64>,
+    timeout: Option<u64>,
+) -> Result<Tarball, Box<dyn std::error::Error>> {
+    let cache_dir = paths::cache_dir()?;
+    std::fs::create_dir_all(&cache_dir)?;
+    let verification = verification();
+    if verification.gpg {
+        signature::check_branch_trusted(version)?;
+    }
+
+    let official = match verification.checksum {
+        ChecksumAlgo::Sha256 => {
+            println!("🔐 Looking up official checksum...");
+            fetch_official_checksums(version, timeout.unwrap_or(DEFAULT_LOOKUP_TIMEOUT))
+        }
+        ChecksumAlgo::None => None,
+    };
+    let (format, official_sha256) = match &official {
+        Some(sums) => ArchiveFormat::candidates(format)
+            .into_iter()
+            .find_map(|candidate| sums.iter().find(|(f, _)| *f == candidate).cloned())
+            .map(|(found, sum)| (found, Some(sum)))
+            .unwrap_or((format.unwrap_or(ArchiveFormat::Gz), None)),
+        // Without the release listing there's no telling whether an xz tarball exists
+        None => (format.unwrap_or(ArchiveFormat::Gz), None),
+    };
+
+    let url = tarball_url(version, format);
+    let cached_path = cached_tarball(version, format)?;
+    match (&official_sha256, verification.checksum) {
+        (Some(sum), _) => println!("✅ Official SHA-256: {}", sum),
+        (None, ChecksumAlgo::None) => println!("⏭️  Skipping the checksum (--checksum-algo none)"),
+        (None, ChecksumAlgo::Sha256) => println!("⚠️  No official checksum published - download can't be verified"),
+    }
+
+    if cached_path.exists() && (official_sha256.is_some() || verification.gpg) {
+        let checksum_ok = official_sha256.as_ref().is_none_or(|expected| sha256_file(&cached_path).as_deref() == Some(expected.as_str()));
+        if !checksum_ok {
+            println!("⚠️  Cached tarball failed checksum verification - downloading again");
+        } else if !verification.gpg {
+            println!("♻️  Using cached tarball (checksum verified): {}", cached_path.display());
+            return Ok(Tarball { path: cached_path, url, sha256: official_sha256 });
+        } else {
+            let signer = signature::verify_tarball(version, &url, &cached_path)?;
+            println!("♻️  Using cached tarball: {}", cached_path.display());
+            report_verification(official_sha256.is_some(), Some(&signer));
+            let sha256 = official_sha256.or_else(|| sha256_file(&cached_path));
+            return Ok(Tarball { path: cached_path, url, sha256 });
+        }
+    }
+
+    let shared_dir = SHARED_CACHE.get().filter(|dir| usable_shared_cache(dir));
+    if let (Some(dir), Some(expected)) = (shared_dir, &official_sha256) {
+        let shared = dir.join(format!("php-{}.{}", version, format.extension()));
+        if shared.is_file() {
+            if sha256_file(&shared).as_deref() == Some(expected.as_str()) {
+                let signer = if verification.gpg { Some(signature::verify_tarball(version, &url, &shared)?) } else { None };
+                println!("♻️  Using tarball from the shared cache: {}", shared.display());
+                report_verification(true, signer.as_deref());
+                return Ok(Tarball { path: shared, url, sha256: official_sha256 });
+            }
+            println!("⚠️  Ignoring {}: it doesn't match the official checksum", shared.display());
+        }
+    }
+
+    println!("🌐 Download URL: {}", url);
+    println!("⬇️  Starting download...");
+
+    let part_path = cache_dir.join(format!("php-{}.{}.part", version, format.extension()));
+    cleanup::track_partial_file(&part_path);
+
+    let mut curl = Command::new("curl");
+    curl.arg("-L")              // Follow redirects
+        .arg("-f")              // Fail on HTTP errors
+        .arg("--progress-bar")  // Show progress bar
+        .arg("--write-out").arg("%{http_code}"); // Status of the last response, on stdout
+    if let Some(rate) = limit_rate {
+        println!("🐢 Download limited to {} bytes/s", rate);
+        curl.arg("--limit-rate").arg(rate.to_string());
+    }
+    // A throttled download can legitimately take longer than the usual timeout
+    let timeout = timeout.unwrap_or(if limit_rate.is_some() { 0 } else { DEFAULT_DOWNLOAD_TIMEOUT });
+    set_timeout(&mut curl, timeout);
+    curl.arg(&url).arg("-o").arg(&part_path).stdout(Stdio::piped());
+
+    let mut retries = SERVER_ERROR_BACKOFF_SECS.iter();
+    loop {
+        let download = if progress::is_json() { run_reporting_progress(&mut curl)? } else { curl.stderr(Stdio::inherit()).output()? };
+        if download.status.success() {
+            break;
+        }
+
+        // Clean up partial download
+        let _ = std::fs::remove_file(&part_path);
+        let http_status = String::from_utf8_lossy(&download.stdout).trim().parse::<u16>().ok().filter(|status| *status != 0);
+        let error = NetworkError::from_curl_response(download.status, http_status, &url, timeout);
+        match (&error, retries.next()) {
+            (NetworkError::Http { status: status @ 500..=599, .. }, Some(delay)) => {
+                println!("🔁 Server error (HTTP {}), retrying in {}s...", status, delay);
+                std::thread::sleep(Duration::from_secs(*delay));
+            }
+            (NetworkError::Http { status: 404, .. }, _) => return Err(missing_tarball(version, error)),
+            (NetworkError::Failed { .. }, _) => {
+                return Err(format!(
+                    "❌ Download failed for PHP version {}.\n💡 Possible reasons:\n   • Version doesn't exist\n   • Network connection issues\n   • Server temporarily unavailable",
+                    version
+                ).into());
+            }
+            _ => return Err(error.into()),
+        }
+    }
+
+    println!("✅ Download completed successfully");
+
+    let actual_sha256 = sha256_file(&part_path);
+    if let Some(expected) = &official_sha256 {
+        if actual_sha256.as_deref() != Some(expected.as_str()) {
+            let _ = std::fs::remove_file(&part_path);
+            return Err(format!(
+                "❌ Checksum mismatch for php-{}.{}\n   expected: {}\n   actual:   {}",
+                version,
+                format.extension(),
+                expected,
+                actual_sha256.as_deref().unwrap_or("unknown")
+            ).into());
+        }
+        println!("🔐 Checksum verified");
+    }
+
+    let signer = if verification.gpg {
+        match signature::verify_tarball(version, &url, &part_path) {
+            Ok(signer) => Some(signer),
+            Err(e) => {
+                let _ = std::fs::remove_file(&part_path);
+                return Err(e);
+            }
+        }
+    } else {
+        None
+    };
+    report_verification(official_sha256.is_some(), signer.as_deref());
+
+    std::fs::rename(&part_path, &cached_path)?;
+    cleanup::release_partial_file(&part_path);
+    if let Some(dir) = shared_dir
+        && official_sha256.is_some()
+    {
+        share_tarball(dir, &cached_path);
+    }
+
+    Ok(Tarball { path: cached_path, url, sha256: actual_sha256 })
+}
+
+/**
+ * Explains a tarball the server doesn't have (HTTP 404)
+ *
+ * A version missing from php.net's release list is reported as not
+ * existing, with the closest released version; one that is listed points
+ * at the mirror instead.
+ *
+ * # Arguments
+ * * `version` - The version being downloaded
+ * * `not_found` - The 404 error, returned when the list can't settle it
+ */
+fn missing_tarball(version: &str, not_found: NetworkError) -> Box<dyn std::error::Error> {
+    let versions = match releases::fetch_available_versions(releases::DEFAULT_FETCH_TIMEOUT) {
+        Ok(list) => list.versions,
+        Err(_) => return not_found.into(),
+    };
+    if versions.iter().any(|released| released == version) {
+        return format!(
+            "{}\n💡 PHP {} was released, but this server doesn't have the tarball - try another mirror with --mirror <URL> or another --format",
+            not_found, version
+        ).into();
+    }
+    PalawijaError::VersionNotReleased {
+        version: version.to_string(),
+        suggestion: releases::closest_version(&versions, version).cloned(),
+    }.into()
+}
+
+/// Runs curl with its progress bar turned into `download` events
+fn run_reporting_progress(curl: &mut Command) -> std::io::Result<Output> {
+    let mut child = curl.stderr(Stdio::piped()).spawn()?;
+    let mut stderr = child.stderr.take().expect("curl's stderr is piped");
+    let mut buffer = [0u8; 4096];
+    let mut last = None;
+    while let Ok(read) = stderr.read(&mut buffer) {
+        if read == 0 {
+            break;
+        }
+        if let Some(pct) = progress::curl_percent(&String::from_utf8_lossy(&buffer[..read]))
+            && last != Some(pct)
+        {
+            progress::emit(&progress::Event::Download { pct });
+            last = Some(pct);
+        }
+    }
+    child.wait_with_output()
+}
+
+/// Whether the shared cache can be read; reports why not otherwise
+fn usable_shared_cache(dir: &Path) -> bool {
+    match std::fs::read_dir(dir) {
+        Ok(_) => true,
+        Err(e) => {
+            println!("⚠️  Not using the shared tarball cache {}: {}", dir.display(), e);
+            false
+        }
+    }
+}
+
+/**
+ * Copies a verified tarball into the shared cache, if it is writable
+ *
+ * The copy is staged next to its final name and renamed, so other users
+ * never pick up a half-written file, and made readable by everyone. A
+ * read-only cache is skipped quietly; other failures are reported, but
+ * neither fails the install.
+ */
+fn share_tarball(dir: &Path, tarball: &Path) {
+    let Some(name) = tarball.file_name() else {
+        return;
+    };
+    let target = dir.join(name);
+    if target.exists() {
+        return;
+    }
+    let staged = dir.join(format!(".{}.tmp-{}", name.to_string_lossy(), std::process::id()));
+    let copied = std::fs::copy(tarball, &staged).and_then(|_| {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o644))?;
+        }
+        std::fs::rename(&staged, &target)
+    });
+    match copied {
+        Ok(()) => println!("📤 Added the tarball to the shared cache: {}", target.display()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&staged);
+            if matches!(e.kind(), std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem) {
+                println!("ℹ️  The shared cache {} is read-only; not adding the tarball", dir.display());
+            } else {
+                println!("⚠️  Could not add the tarball to the shared cache {}: {}", dir.display(), e);
+            }
+        }
+    }
+}
+
+/// Lists the checks a tarball passed
+fn report_verification(checksum: bool, signer: Option<&str>) {
+    let mut passed = Vec::new();
+    if checksum {
+        passed.push("SHA-256 checksum".to_string());
+    }
+    if let Some(fingerprint) = signer {
+        passed.push(format!("GPG signature (key {})", fingerprint));
+    }
+    if passed.is_empty() {
+        println!("⚠️  The tarball passed no verification");
+    } else {
+        println!("🛡️  Verified: {}", passed.join(", "));
+    }
+}
+
+/**
+ * Uses a tarball already on disk instead of downloading one
+ *
+ * # Arguments
+ * * `path` - The local source tarball
+ * * `expected_sha256` - Checksum the tarball must match, if one was supplied
+ *
+ * # Returns
+ * * `Result<Tarball, Box<dyn std::error::Error>>` - The (verified) tarball
+ */
+pub fn local_tarball(path: &Path, expected_sha256: Option<&str>) -> Result<Tarball, Box<dyn std::error::Error>> {
+    if !path.is_file() {
+        return Err(format!("❌ Source tarball not found: {}", path.display()).into());
+    }
+    println!("📁 Using local source tarball: {}", path.display());
+
+    let actual_sha256 = sha256_file(path);
+    if let Some(expected) = expected_sha256 {
+        let expected = expected.to_lowercase();
+        if actual_sha256.as_deref() != Some(expected.as_str()) {
+            return Err(format!(
+                "❌ Checksum mismatch for {}\n   expected: {}\n   actual:   {}",
+                path.display(),
+                expected,
+                actual_sha256.as_deref().unwrap_or("unknown")
+            ).into());
+        }
+        println!("🔐 Checksum verified");
+    }
+
+    let path = std::fs::canonicalize(path)?;
+    Ok(Tarball { url: format!("file://{}", path.display()), path, sha256: actual_sha256 })
+}
+
+/**
+ * Looks up the official SHA-256 of each published tarball of a version
+ *
+ * # Arguments
+ * * `version` - PHP version string (e.g., "8.3.0")
+ * * `timeout` - Seconds the lookup may take, 0 for no limit
+ *
+ * # Returns
+ * * `Option<Vec<(ArchiveFormat, String)>>` - Lowercase hex digests per format,
+ *   or `None` if the php.net release API couldn't be queried
+ */
+pub fn fetch_official_checksums(version: &str, timeout: u64) -> Option<Vec<(ArchiveFormat, String)>> {
+    let api_url = format!("https://www.php.net/releases/index.php?json&version={}", version);
+    let mut curl = Command::new("curl");
+    curl.arg("-s")
+        .arg("-L");
+    set_timeout(&mut curl, timeout);
+    let output = curl.arg(&api_url).output().ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let release: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    Some(parse_checksums(&release, version))
+}
+
+/// Picks the `.tar.gz` and `.tar.xz` checksums out of a release API response
+fn parse_checksums(release: &serde_json::Value, version: &str) -> Vec<(ArchiveFormat, String)> {
+    let sources = release.get("source").and_then(|s| s.as_array()).map(Vec::as_slice).unwrap_or_default();
+    [ArchiveFormat::Xz, ArchiveFormat::Gz]
+        .into_iter()
+        .filter_map(|format| {
+            let filename = format!("php-{}.{}", version, format.extension());
+            let sum = sources.iter()
+                .find(|source| source.get("filename").and_then(|f| f.as_str()) == Some(filename.as_str()))?
+                .get("sha256")?
+                .as_str()?;
+            Some((format, sum.to_lowercase()))
+        })
+        .collect()
+}
+
+/**
+ * Computes the SHA-256 of a file using the system `sha256sum`
+ *
+ * # Returns
+ * * `Option<String>` - The lowercase hex digest, or `None` if it couldn't be computed
+ */
+pub fn sha256_file(path: &Path) -> Option<String> {
+    let output = Command::new("sha256sum").arg(path).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(|sum| sum.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_accept_human_suffixes() {
+        assert_eq!(parse_rate("1500"), Ok(1500));
+        assert_eq!(parse_rate("500k"), Ok(500 * 1024));
+        assert_eq!(parse_rate("2M"), Ok(2 * 1024 * 1024));
+        assert!(parse_rate("0").is_err());
+        assert!(parse_rate("fast").is_err());
+        assert!(parse_rate("k").is_err());
+    }
+
+    #[test]
+    fn tarballs_are_shared_once_and_world_readable() {
+        use std::os::unix::fs::PermissionsExt;
+        let root = std::env::temp_dir().join(format!("palawija-shared-{}", std::process::id()));
+        let shared = root.join("shared");
+        std::fs::create_dir_all(&shared).unwrap();
+        let tarball = root.join("php-8.3.0.tar.xz");
+        std::fs::write(&tarball, b"first").unwrap();
+
+        share_tarball(&shared, &tarball);
+        let target = shared.join("php-8.3.0.tar.xz");
+        assert_eq!(std::fs::read(&target).unwrap(), b"first");
+        assert_eq!(std::fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o644);
+
+        std::fs::write(&tarball, b"second").unwrap();
+        share_tarball(&shared, &tarball);
+        assert_eq!(std::fs::read(&target).unwrap(), b"first");
+        assert_eq!(std::fs::read_dir(&shared).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn zero_timeouts_set_no_max_time() {
+        let args = |seconds| {
+            let mut curl = Command::new("curl");
+            set_timeout(&mut curl, seconds);
+            curl.get_args().map(|arg| arg.to_string_lossy().into_owned()).collect::<Vec<_>>()
+        };
+        assert_eq!(args(45), ["--max-time", "45"]);
+        assert!(args(0).is_empty());
+    }
+
+    #[test]
+    fn formats_are_detected_by_magic_bytes() {
+        assert_eq!(ArchiveFormat::from_magic(&[0x1f, 0x8b, 0x08, 0, 0, 0]), Some(ArchiveFormat::Gz));
+        assert_eq!(ArchiveFormat::from_magic(b"\xfd7zXZ\x00"), Some(ArchiveFormat::Xz));
+        assert_eq!(ArchiveFormat::from_magic(b"ustar"), None);
+    }
+
+    #[test]
+    fn checksums_are_read_per_format() {
+        let release = serde_json::json!({"source": [
+            {"filename": "php-8.3.0.tar.gz", "sha256": "AA"},
+            {"filename": "php-8.3.0.tar.bz2", "sha256": "bb"},
+            {"filename": "php-8.3.0.tar.xz", "sha256": "cc"},
+        ]});
+        assert_eq!(
+            parse_checksums(&release, "8.3.0"),
+            [(ArchiveFormat::Xz, "cc".to_string()), (ArchiveFormat::Gz, "aa".to_string())]
+        );
+    }
+}
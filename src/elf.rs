@@ -0,0 +1,145 @@
+/*!
+ * ELF header inspection
+ *
+ * Lets `use` refuse a php binary built for another architecture before it
+ * becomes the system default and fails with "Exec format error".
+ */
+
+use std::io::Read;
+use std::path::Path;
+
+/// Architecture facts read from an ELF header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElfInfo {
+    /// `e_machine` field (e.g. 0x3E for x86_64)
+    pub machine: u16,
+    /// Whether the binary is 64-bit (`ELFCLASS64`)
+    pub is_64bit: bool,
+}
+
+impl ElfInfo {
+    /// Parses the identification bytes and `e_machine` from the start of a file
+    pub fn parse(header: &[u8]) -> Option<Self> {
+        if header.len() < 20 || &header[..4] != b"\x7fELF" {
+            return None;
+        }
+        let is_64bit = match header[4] {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+        let machine_bytes = [header[18], header[19]];
+        let machine = match header[5] {
+            1 => u16::from_le_bytes(machine_bytes),
+            2 => u16::from_be_bytes(machine_bytes),
+            _ => return None,
+        };
+        Some(ElfInfo { machine, is_64bit })
+    }
+
+    /// Reads the ELF header of a file; `None` if it isn't an ELF binary
+    pub fn read(path: &Path) -> Option<Self> {
+        let mut header = [0u8; 20];
+        std::fs::File::open(path).ok()?.read_exact(&mut header).ok()?;
+        Self::parse(&header)
+    }
+
+    /// Human-readable architecture name
+    pub fn arch_name(self) -> String {
+        match machine_name(self.machine) {
+            Some(name) => name.to_string(),
+            None => format!("unknown machine 0x{:x}", self.machine),
+        }
+    }
+}
+
+/// `e_machine` value and word size of binaries native to a Rust target arch
+pub fn expected_for_arch(arch: &str) -> Option<ElfInfo> {
+    let (machine, is_64bit) = match arch {
+        "x86_64" => (0x3E, true),
+        "x86" => (0x03, false),
+        "aarch64" => (0xB7, true),
+        "arm" => (0x28, false),
+        "riscv64" => (0xF3, true),
+        "powerpc64" => (0x15, true),
+        "s390x" => (0x16, true),
+        "loongarch64" => (0x102, true),
+        _ => return None,
+    };
+    Some(ElfInfo { machine, is_64bit })
+}
+
+fn machine_name(machine: u16) -> Option<&'static str> {
+    Some(match machine {
+        0x03 => "x86",
+        0x3E => "x86_64",
+        0x28 => "arm",
+        0xB7 => "aarch64",
+        0xF3 => "riscv",
+        0x15 => "powerpc64",
+        0x16 => "s390x",
+        0x102 => "loongarch",
+        _ => return None,
+    })
+}
+
+/**
+ * Checks that a binary can run on this machine
+ *
+ * Files that aren't ELF (e.g. wrapper scripts) and hosts palawija doesn't
+ * know the ELF machine of are let through.
+ *
+ * # Returns
+ * * `Result<(), String>` - Error describing the mismatch
+ */
+pub fn check_compatible(path: &Path) -> Result<(), String> {
+    let (Some(binary), Some(host)) = (ElfInfo::read(path), expected_for_arch(std::env::consts::ARCH)) else {
+        return Ok(());
+    };
+    if binary == host {
+        return Ok(());
+    }
+    Err(format!(
+        "❌ {} is a {}-bit {} binary, but this machine is {}-bit {}\n💡 Rebuild it here: palawija reinstall <version> --compile",
+        path.display(),
+        if binary.is_64bit { 64 } else { 32 },
+        binary.arch_name(),
+        if host.is_64bit { 64 } else { 32 },
+        host.arch_name()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(class: u8, data: u8, machine: [u8; 2]) -> Vec<u8> {
+        let mut bytes = vec![0x7f, b'E', b'L', b'F', class, data];
+        bytes.resize(18, 0);
+        bytes.extend_from_slice(&machine);
+        bytes
+    }
+
+    #[test]
+    fn headers_are_parsed() {
+        assert_eq!(ElfInfo::parse(&header(2, 1, [0x3E, 0x00])), expected_for_arch("x86_64"));
+        assert_eq!(ElfInfo::parse(&header(2, 1, [0xB7, 0x00])), expected_for_arch("aarch64"));
+        assert_eq!(ElfInfo::parse(&header(2, 2, [0x00, 0x16])), expected_for_arch("s390x"));
+        assert_eq!(ElfInfo::parse(b"#!/bin/sh\necho php\n"), None);
+        assert_eq!(ElfInfo::parse(&header(2, 1, [0x3E, 0x00])[..10]), None);
+    }
+
+    #[test]
+    fn word_size_is_part_of_compatibility() {
+        let i386 = ElfInfo::parse(&header(1, 1, [0x03, 0x00])).unwrap();
+        assert_eq!(i386, expected_for_arch("x86").unwrap());
+        assert_ne!(i386, expected_for_arch("x86_64").unwrap());
+        assert_eq!(i386.arch_name(), "x86");
+    }
+
+    #[test]
+    fn own_binary_is_compatible() {
+        let exe = std::env::current_exe().unwrap();
+        assert!(check_compatible(&exe).is_ok());
+    }
+}
@@ -0,0 +1,93 @@
+/*!
+ * Named environments (`palawija env create|activate`)
+ *
+ * An environment is a shim directory `~/.palawija/envs/<name>/bin` whose
+ * entries link to one installed version's binaries. Putting it first in PATH
+ * selects that version for one shell without touching the global symlink.
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::platform::symlink;
+
+/// Checks that an environment name is usable as a directory name
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid environment name: use letters, digits, '.', '-' and '_'", name))
+    }
+}
+
+/// Shim directory of a named environment
+pub fn bin_dir(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::envs_dir()?.join(name).join("bin"))
+}
+
+/**
+ * Creates (or re-points) an environment at an installed version
+ *
+ * Every executable in the version's `bin` directory gets a link in the
+ * environment's shim directory, so `phpize` and `php-config` follow along.
+ *
+ * # Arguments
+ * * `name` - Environment name
+ * * `version_dir` - Directory of the installed version, already checked to hold a php binary
+ *
+ * # Returns
+ * * `Result<PathBuf, Box<dyn std::error::Error>>` - The shim directory
+ */
+pub fn create(name: &str, version_dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    validate_name(name)?;
+    let shims = bin_dir(name)?;
+    if shims.exists() {
+        std::fs::remove_dir_all(&shims)?;
+    }
+    std::fs::create_dir_all(&shims)?;
+
+    for entry in std::fs::read_dir(version_dir.join("bin"))? {
+        let entry = entry?;
+        symlink(entry.path(), shims.join(entry.file_name()))?;
+    }
+    Ok(shims)
+}
+
+/**
+ * Finds the version an environment points to
+ *
+ * # Returns
+ * * `Result<(PathBuf, String), Box<dyn std::error::Error>>` - The shim directory and version
+ */
+pub fn resolve(name: &str) -> Result<(PathBuf, String), Box<dyn std::error::Error>> {
+    validate_name(name)?;
+    let shims = bin_dir(name)?;
+    let target = std::fs::read_link(shims.join("php")).map_err(|_| {
+        format!("Environment '{}' does not exist\n💡 Create it with: palawija env create {} --php <version>", name, name)
+    })?;
+    if !target.exists() {
+        return Err(format!(
+            "Environment '{}' points to {}, which no longer exists\n💡 Re-create it with: palawija env create {} --php <version>",
+            name, target.display(), name
+        ).into());
+    }
+    let version = paths::version_of_binary(&target)
+        .ok_or_else(|| format!("Environment '{}' doesn't point to a palawija install", name))?;
+    Ok((shims, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_must_be_plain_directory_names() {
+        assert!(validate_name("api-service_2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("..").is_err());
+        assert!(validate_name("a/b").is_err());
+    }
+}
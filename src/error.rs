@@ -0,0 +1,410 @@
+/*!
+ * Errors palawija reports with a dedicated type, and how failures are reported
+ *
+ * With `--error-format json` (implied by commands run with `--json`) a failing
+ * command writes a single object to stderr instead of the usual message:
+ *
+ * ```text
+ * {"error": {"kind": "version_not_installed", "message": "...", "version": "8.2.0"}}
+ * ```
+ *
+ * `kind` and the exit code are stable; scripts can branch on either:
+ *
+ * | kind                    | exit | extra fields                                  |
+ * |-------------------------|------|-----------------------------------------------|
+ * | `error`                 | 1    | (anything not listed below)                   |
+ * | `home_unavailable`      | 3    | `reason`                                      |
+ * | `root_not_writable`     | 3    | `root`, `read_only_filesystem`                |
+ * | `version_not_installed` | 4    | `version`                                     |
+ * | `version_not_compiled`  | 4    | `version`                                     |
+ * | `version_not_found`     | 5    | `version`, `suggestion` (or null)             |
+ * | `network`               | 6    | `reason` (timeout, resolve, connect, http, failed), `url`, `timeout_seconds` / `http_status` / `curl_exit_code` |
+ * | `unsupported_platform`  | 7    | `os`                                          |
+ * | `no_php`                | 8    | `name` (`which`: no such binary in PATH)      |
+ * | `unmanaged_php`         | 9    | `path` (`which`: the php in PATH isn't palawija's) |
+ *
+ * Exit code 2 stays reserved for invalid command lines. The exit codes apply
+ * in the human format as well. `palawija json-schema error` prints the
+ * object's JSON Schema.
+ */
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Failures that callers may want to tell apart from generic I/O errors
+#[derive(Debug, Clone)]
+pub enum PalawijaError {
+    /// No usable home directory: unset, missing, or not writable
+    HomeUnavailable { reason: String },
+    /// `~/.palawija` (or `--root`) can't be written: a read-only mount or missing permissions
+    RootNotWritable { root: String, read_only_filesystem: bool },
+    /// The version has no directory in `~/.palawija`
+    VersionNotInstalled { version: String },
+    /// The version's source is there, but no compiled php binary
+    VersionNotCompiled { version: String },
+    /// php.net never released the version
+    VersionNotReleased { version: String, suggestion: Option<String> },
+    /// The command needs Linux (`os` as in `std::env::consts::OS`)
+    UnsupportedPlatform { os: String },
+    /// No binary called `name` (the php link name) is in PATH
+    NoPhpInPath { name: String },
+    /// The php first in PATH isn't a palawija install
+    UnmanagedPhp { path: String },
+}
+
+impl PalawijaError {
+    /// The error for a version whose php binary is missing, depending on whether it's installed at all
+    pub fn missing_binary(version: &str, version_dir: &Path) -> Self {
+        let version = version.to_string();
+        if version_dir.is_dir() {
+            PalawijaError::VersionNotCompiled { version }
+        } else {
+            PalawijaError::VersionNotInstalled { version }
+        }
+    }
+
+    /// The stable `kind` string of the error
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PalawijaError::HomeUnavailable { .. } => "home_unavailable",
+            PalawijaError::RootNotWritable { .. } => "root_not_writable",
+            PalawijaError::VersionNotInstalled { .. } => "version_not_installed",
+            PalawijaError::VersionNotCompiled { .. } => "version_not_compiled",
+            PalawijaError::VersionNotReleased { .. } => "version_not_found",
+            PalawijaError::UnsupportedPlatform { .. } => "unsupported_platform",
+            PalawijaError::NoPhpInPath { .. } => "no_php",
+            PalawijaError::UnmanagedPhp { .. } => "unmanaged_php",
+        }
+    }
+
+    /// The exit code a command failing with this error ends with
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            PalawijaError::HomeUnavailable { .. } | PalawijaError::RootNotWritable { .. } => 3,
+            PalawijaError::VersionNotInstalled { .. } | PalawijaError::VersionNotCompiled { .. } => 4,
+            PalawijaError::VersionNotReleased { .. } => 5,
+            PalawijaError::UnsupportedPlatform { .. } => 7,
+            PalawijaError::NoPhpInPath { .. } => 8,
+            PalawijaError::UnmanagedPhp { .. } => 9,
+        }
+    }
+
+    fn fields(&self) -> serde_json::Map<String, serde_json::Value> {
+        let mut fields = serde_json::Map::new();
+        match self {
+            PalawijaError::HomeUnavailable { reason } => {
+                fields.insert("reason".to_string(), reason.clone().into());
+            }
+            PalawijaError::RootNotWritable { root, read_only_filesystem } => {
+                fields.insert("root".to_string(), root.clone().into());
+                fields.insert("read_only_filesystem".to_string(), (*read_only_filesystem).into());
+            }
+            PalawijaError::VersionNotInstalled { version } | PalawijaError::VersionNotCompiled { version } => {
+                fields.insert("version".to_string(), version.clone().into());
+            }
+            PalawijaError::VersionNotReleased { version, suggestion } => {
+                fields.insert("version".to_string(), version.clone().into());
+                fields.insert("suggestion".to_string(), suggestion.clone().into());
+            }
+            PalawijaError::UnsupportedPlatform { os } => {
+                fields.insert("os".to_string(), os.clone().into());
+            }
+            PalawijaError::NoPhpInPath { name } => {
+                fields.insert("name".to_string(), name.clone().into());
+            }
+            PalawijaError::UnmanagedPhp { path } => {
+                fields.insert("path".to_string(), path.clone().into());
+            }
+        }
+        fields
+    }
+}
+
+impl std::fmt::Display for PalawijaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PalawijaError::HomeUnavailable { reason } => write!(
+                f,
+                "❌ Home directory unavailable: {}\n💡 Set HOME to a writable directory (e.g. HOME=/home/<user> in cron or systemd units)",
+                reason
+            ),
+            PalawijaError::RootNotWritable { root, read_only_filesystem } => write!(
+                f,
+                "❌ {} is not writable: {}\n💡 Keep palawija's versions somewhere writable with --root <DIR> (or PALAWIJA_ROOT=<DIR>)",
+                root,
+                if *read_only_filesystem { "it is on a read-only filesystem" } else { "permission denied" }
+            ),
+            PalawijaError::VersionNotInstalled { version } => write!(
+                f,
+                "PHP {} is not installed\n💡 Install it with: palawija install {} --compile",
+                version, version
+            ),
+            PalawijaError::VersionNotCompiled { version } => write!(
+                f,
+                "PHP binary not found for version {}\n💡 Compile it first: palawija compile {}",
+                version, version
+            ),
+            PalawijaError::VersionNotReleased { version, suggestion } => {
+                write!(f, "PHP version {} does not exist", version)?;
+                if let Some(suggestion) = suggestion {
+                    write!(f, " - did you mean {}?", suggestion)?;
+                }
+                write!(f, "\n💡 Browse versions with: palawija available\n💡 Released minutes ago? Skip this check with --force")
+            }
+            PalawijaError::UnsupportedPlatform { os } => write!(
+                f,
+                "palawija currently supports Linux only (this system is {})\n💡 Listing and searching releases still works: palawija available",
+                os
+            ),
+            PalawijaError::NoPhpInPath { name } => write!(
+                f,
+                "No '{}' binary found in PATH\n💡 Install a PHP version with: palawija install <version>, then make it the default with: palawija use <version>",
+                name
+            ),
+            PalawijaError::UnmanagedPhp { path } => write!(
+                f,
+                "The php in PATH is not managed by palawija: {}\n💡 Switch to a palawija version with: palawija use <version>",
+                path
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PalawijaError {}
+
+/// Why a request to a remote server failed
+#[derive(Debug, Clone)]
+pub enum NetworkError {
+    /// The request didn't finish within the timeout
+    Timeout { url: String, seconds: u64 },
+    /// The host name couldn't be resolved
+    Resolve { url: String },
+    /// The server couldn't be reached
+    Connect { url: String },
+    /// The server answered with an HTTP error status
+    Http { url: String, status: u16 },
+    /// Any other failure (interrupted transfer, ...)
+    Failed { url: String, code: Option<i32> },
+}
+
+impl NetworkError {
+    /**
+     * Classifies a failed curl run by its exit code
+     *
+     * # Arguments
+     * * `status` - curl's exit status
+     * * `url` - The requested URL
+     * * `seconds` - The timeout curl was given, for the message
+     */
+    pub fn from_curl(status: std::process::ExitStatus, url: &str, seconds: u64) -> Self {
+        let url = url.to_string();
+        match status.code() {
+            Some(28) => NetworkError::Timeout { url, seconds },
+            Some(6) => NetworkError::Resolve { url },
+            Some(7) => NetworkError::Connect { url },
+            code => NetworkError::Failed { url, code },
+        }
+    }
+
+    /**
+     * Classifies a failed curl run that reported the HTTP status (`--write-out %{http_code}`)
+     *
+     * # Arguments
+     * * `status` - curl's exit status
+     * * `http_status` - The status of the last response, if one arrived
+     * * `url` - The requested URL
+     * * `seconds` - The timeout curl was given, for the message
+     */
+    pub fn from_curl_response(status: std::process::ExitStatus, http_status: Option<u16>, url: &str, seconds: u64) -> Self {
+        match http_status {
+            // curl -f exits with 22 when the server answered with an error status
+            Some(http_status) if status.code() == Some(22) && http_status >= 400 => {
+                NetworkError::Http { url: url.to_string(), status: http_status }
+            }
+            _ => Self::from_curl(status, url, seconds),
+        }
+    }
+}
+
+impl std::fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetworkError::Timeout { url, seconds } => write!(
+                f,
+                "⏱️  Request to {} timed out after {}s\n💡 Allow more time with --timeout (or --download-timeout for installs); 0 disables the limit",
+                url, seconds
+            ),
+            NetworkError::Resolve { url } => write!(f, "🌐 Could not resolve the host of {} - check your DNS settings", url),
+            NetworkError::Connect { url } => write!(f, "🌐 Could not connect to {} - check your internet connection", url),
+            NetworkError::Http { url, status: 404 } => write!(f, "🔍 {} was not found (HTTP 404)", url),
+            NetworkError::Http { url, status: status @ (403 | 429) } => write!(
+                f,
+                "🚦 {} refused the request (HTTP {}) - the server is rate limiting this machine or the mirror is misconfigured\n💡 Wait a few minutes, or download from another mirror with --mirror <URL> (or --auto-mirror)",
+                url, status
+            ),
+            NetworkError::Http { url, status: status @ 500..=599 } => write!(
+                f,
+                "🛠️  {} had a server error (HTTP {}) - this is usually temporary\n💡 Try again in a few minutes, or download from another mirror with --mirror <URL>",
+                url, status
+            ),
+            NetworkError::Http { url, status } => write!(f, "🌐 Request to {} failed (HTTP {})", url, status),
+            NetworkError::Failed { url, code: Some(code) } => write!(f, "🌐 Request to {} failed (curl exit code {})", url, code),
+            NetworkError::Failed { url, code: None } => write!(f, "🌐 Request to {} was interrupted", url),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl NetworkError {
+    fn fields(&self) -> serde_json::Map<String, serde_json::Value> {
+        let (reason, url) = match self {
+            NetworkError::Timeout { url, .. } => ("timeout", url),
+            NetworkError::Resolve { url } => ("resolve", url),
+            NetworkError::Connect { url } => ("connect", url),
+            NetworkError::Http { url, .. } => ("http", url),
+            NetworkError::Failed { url, .. } => ("failed", url),
+        };
+        let mut fields = serde_json::Map::new();
+        fields.insert("reason".to_string(), reason.into());
+        fields.insert("url".to_string(), url.clone().into());
+        match self {
+            NetworkError::Timeout { seconds, .. } => {
+                fields.insert("timeout_seconds".to_string(), (*seconds).into());
+            }
+            NetworkError::Http { status, .. } => {
+                fields.insert("http_status".to_string(), (*status).into());
+            }
+            NetworkError::Failed { code, .. } => {
+                fields.insert("curl_exit_code".to_string(), (*code).into());
+            }
+            _ => {}
+        }
+        fields
+    }
+}
+
+/// How a failing command reports its error on stderr
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// A message with hints, for people
+    Human,
+    /// One JSON object with a stable `kind`, for scripts
+    Json,
+}
+
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Selects the error format for the rest of the run
+pub fn set_format(format: ErrorFormat) {
+    JSON_ERRORS.store(format == ErrorFormat::Json, Ordering::Relaxed);
+}
+
+/**
+ * Describes an error as the JSON object written in JSON mode
+ *
+ * # Arguments
+ * * `context` - What was being done, prefixed to the message (may be empty)
+ * * `error` - The failure; `PalawijaError` and `NetworkError` add their fields
+ */
+pub fn to_json(context: &str, error: &(dyn std::error::Error + 'static)) -> serde_json::Value {
+    let (kind, mut fields) = if let Some(error) = error.downcast_ref::<PalawijaError>() {
+        (error.kind(), error.fields())
+    } else if let Some(error) = error.downcast_ref::<NetworkError>() {
+        ("network", error.fields())
+    } else {
+        ("error", serde_json::Map::new())
+    };
+    fields.insert("kind".to_string(), kind.into());
+    fields.insert("message".to_string(), message(context, error).into());
+    serde_json::json!({ "error": fields })
+}
+
+/// The context and the error's text, without the ❌ some messages start with
+fn message(context: &str, error: &dyn std::error::Error) -> String {
+    format!("{}{}", context, error.to_string().trim_start_matches("❌ "))
+}
+
+
+/// The exit code for a failure, by its kind
+pub fn exit_code(error: &(dyn std::error::Error + 'static)) -> i32 {
+    if let Some(error) = error.downcast_ref::<PalawijaError>() {
+        error.exit_code()
+    } else if error.is::<NetworkError>() {
+        6
+    } else {
+        1
+    }
+}
+
+/**
+ * Reports a failed command in the selected format and exits
+ *
+ * # Arguments
+ * * `context` - What was being done, e.g. "Failed to pin PHP version: " (may be empty)
+ * * `error` - The failure
+ */
+pub fn fail(context: &str, error: Box<dyn std::error::Error>) -> ! {
+    fail_with_hint(context, error, None)
+}
+
+/// Like `fail`, with a hint shown after the message in the human format
+pub fn fail_with_hint(context: &str, error: Box<dyn std::error::Error>, hint: Option<&str>) -> ! {
+    if JSON_ERRORS.load(Ordering::Relaxed) {
+        eprintln!("{}", to_json(context, error.as_ref()));
+    } else {
+        eprintln!("❌ {}", message(context, error.as_ref()));
+        if let Some(hint) = hint {
+            eprintln!("💡 {}", hint);
+        }
+    }
+    std::process::exit(exit_code(error.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    #[test]
+    fn structured_errors_keep_their_fields_in_json() {
+        let error: Box<dyn std::error::Error> = PalawijaError::VersionNotInstalled { version: "8.2.0".to_string() }.into();
+        let json = to_json("Failed to switch PHP version: ", error.as_ref());
+        assert_eq!(json["error"]["kind"], "version_not_installed");
+        assert_eq!(json["error"]["version"], "8.2.0");
+        assert!(json["error"]["message"].as_str().unwrap().starts_with("Failed to switch PHP version: PHP 8.2.0 is not installed"));
+        assert_eq!(exit_code(error.as_ref()), 4);
+
+        let error: Box<dyn std::error::Error> = NetworkError::Timeout { url: "https://php.net".to_string(), seconds: 5 }.into();
+        let json = to_json("", error.as_ref());
+        assert_eq!(json["error"]["kind"], "network");
+        assert_eq!(json["error"]["reason"], "timeout");
+        assert_eq!(json["error"]["timeout_seconds"], 5);
+        assert_eq!(exit_code(error.as_ref()), 6);
+
+        let error: Box<dyn std::error::Error> = PalawijaError::UnmanagedPhp { path: "/usr/bin/php".to_string() }.into();
+        assert_eq!(to_json("", error.as_ref())["error"]["kind"], "unmanaged_php");
+        assert_eq!(exit_code(error.as_ref()), 9);
+        assert_eq!(PalawijaError::NoPhpInPath { name: "php".to_string() }.exit_code(), 8);
+
+        let error: Box<dyn std::error::Error> = "❌ Something else".into();
+        assert_eq!(to_json("", error.as_ref())["error"], serde_json::json!({"kind": "error", "message": "Something else"}));
+        assert_eq!(exit_code(error.as_ref()), 1);
+    }
+
+    #[test]
+    fn curl_exit_codes_are_classified() {
+        let exit = |code: i32| std::process::ExitStatus::from_raw(code << 8);
+        assert!(matches!(NetworkError::from_curl(exit(28), "u", 5), NetworkError::Timeout { seconds: 5, .. }));
+        assert!(matches!(NetworkError::from_curl(exit(6), "u", 5), NetworkError::Resolve { .. }));
+        assert!(matches!(NetworkError::from_curl(exit(7), "u", 5), NetworkError::Connect { .. }));
+        assert!(matches!(NetworkError::from_curl(exit(22), "u", 5), NetworkError::Failed { code: Some(22), .. }));
+
+        let response = |code: i32, http_status| NetworkError::from_curl_response(exit(code), http_status, "u", 5);
+        assert!(matches!(response(22, Some(404)), NetworkError::Http { status: 404, .. }));
+        assert!(response(22, Some(503)).to_string().contains("usually temporary"));
+        assert!(response(22, Some(429)).to_string().contains("rate limiting"));
+        assert!(matches!(response(28, Some(200)), NetworkError::Timeout { .. }));
+        assert!(matches!(response(22, None), NetworkError::Failed { code: Some(22), .. }));
+    }
+}
@@ -0,0 +1,208 @@
+/*!
+ * Running a specific installed version without switching (`palawija exec`)
+ *
+ * The child environment is built in three layers, later ones winning:
+ * 1. the caller's environment, or only `MINIMAL_ENV` with `--clean-env`
+ * 2. `PHPRC`/`PHP_INI_DIR` pointing at the version's `etc` directory
+ * 3. every `-e KEY=VALUE`, in the order given
+ *
+ * Without a version argument (or with `--version-from-env`), the version is
+ * taken from `PALAWIJA_PHP_VERSION`, then the project's `.palawijarc`, then
+ * the pinned default.
+ */
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::aliases;
+use crate::error::PalawijaError;
+use crate::installs;
+use crate::paths;
+use crate::releases;
+
+/// Variables kept from the caller's environment with `--clean-env`
+pub const MINIMAL_ENV: &[&str] = &["PATH", "HOME", "TERM", "LANG", "USER"];
+
+/// Variable naming the version `exec` runs when none is passed, e.g. set once per CI job
+pub const VERSION_ENV: &str = "PALAWIJA_PHP_VERSION";
+
+/**
+ * Picks the version `exec` runs and says where it came from
+ *
+ * # Arguments
+ * * `explicit` - The version argument
+ * * `from_env` - The value of `PALAWIJA_PHP_VERSION`
+ * * `project` - The `.palawijarc` version and the file it was read from
+ * * `pinned` - The pinned global default
+ *
+ * # Returns
+ * * `Option<(String, String)>` - The first one set, and its source for messages
+ */
+pub fn pick_version(
+    explicit: Option<&str>,
+    from_env: Option<String>,
+    project: Option<(String, String)>,
+    pinned: Option<String>,
+) -> Option<(String, String)> {
+    let nonempty = |value: String| Some(value.trim().to_string()).filter(|value| !value.is_empty());
+    explicit.map(|version| (version.to_string(), "the command line".to_string()))
+        .or_else(|| from_env.and_then(nonempty).map(|version| (version, VERSION_ENV.to_string())))
+        .or(project)
+        .or_else(|| pinned.map(|version| (version, "the pinned default".to_string())))
+}
+
+/**
+ * Resolves a version string against the installed versions
+ *
+ * Full and partial versions, `latest` and aliases such as `php8.3` are
+ * accepted; a series resolves to its newest installed release. Anything
+ * that matches nothing is returned as it is, to be reported as not installed.
+ */
+pub fn resolve_version(input: &str, installed: &[String]) -> String {
+    let series = aliases::series(input).unwrap_or(input);
+    let mut installed = installed.to_vec();
+    installed.sort_by(|a, b| releases::compare_versions(b, a));
+    if series == "latest" {
+        return releases::newest_in_series(&installed, series, false)
+            .or(installed.first())
+            .cloned()
+            .unwrap_or_else(|| input.to_string());
+    }
+    installs::resolve_partial(&installed, series).into_iter().next().unwrap_or_else(|| input.to_string())
+}
+
+/// Parses a `-e KEY=VALUE` argument
+pub fn parse_env_var(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", arg)),
+    }
+}
+
+/**
+ * Builds the environment of the php child process
+ *
+ * # Arguments
+ * * `inherited` - The caller's environment
+ * * `clean` - Keep only `MINIMAL_ENV` from `inherited`
+ * * `version_dir` - The version being run; its `etc` directory holds php.ini
+ * * `overrides` - `-e` variables, applied last
+ */
+pub fn child_env(
+    inherited: impl IntoIterator<Item = (String, String)>,
+    clean: bool,
+    version_dir: &Path,
+    overrides: &[(String, String)],
+) -> Vec<(String, String)> {
+    let mut env: Vec<(String, String)> = inherited.into_iter()
+        .filter(|(key, _)| !clean || MINIMAL_ENV.contains(&key.as_str()))
+        .collect();
+
+    let ini_dir = paths::php_ini(version_dir)
+        .parent()
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let defaults = [("PHPRC".to_string(), ini_dir.clone()), ("PHP_INI_DIR".to_string(), ini_dir)];
+
+    for (key, value) in defaults.iter().chain(overrides) {
+        env.retain(|(existing, _)| existing != key);
+        env.push((key.clone(), value.clone()));
+    }
+    env
+}
+
+/**
+ * Prepares a command running a version's php binary in its own environment
+ *
+ * # Returns
+ * * `Result<Command, Box<dyn std::error::Error>>` - The command, or `missing_binary`
+ *   if the version isn't compiled
+ */
+pub fn php_command(
+    version: &str,
+    clean: bool,
+    overrides: &[(String, String)],
+) -> Result<Command, Box<dyn std::error::Error>> {
+    let version_dir = paths::version_dir(version)?;
+    let php_bin_path = paths::php_binary(&version_dir);
+    if !php_bin_path.exists() {
+        return Err(PalawijaError::missing_binary(version, &version_dir).into());
+    }
+
+    let mut command = Command::new(&php_bin_path);
+    command.env_clear().envs(child_env(std::env::vars(), clean, &version_dir, overrides));
+    Ok(command)
+}
+
+/**
+ * Runs a version's php binary with the given arguments
+ *
+ * # Returns
+ * * `Result<i32, Box<dyn std::error::Error>>` - php's exit code
+ */
+pub fn exec_php(
+    version: &str,
+    args: &[String],
+    clean: bool,
+    overrides: &[(String, String)],
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let mut command = php_command(version, clean, overrides)?;
+    let status = command.args(args)
+        .status()
+        .map_err(|e| format!("❌ Could not run {}: {}", command.get_program().to_string_lossy(), e))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn get<'a>(env: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        env.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn versions_follow_argument_env_project_default() {
+        let project = || Some(("8.2".to_string(), "./.palawijarc".to_string()));
+        let pinned = || Some("8.1.27".to_string());
+        assert_eq!(pick_version(Some("8.3"), Some("8.4".into()), project(), pinned()).unwrap().0, "8.3");
+        assert_eq!(pick_version(None, Some("8.4".into()), project(), pinned()).unwrap(), ("8.4".to_string(), VERSION_ENV.to_string()));
+        assert_eq!(pick_version(None, Some(" ".into()), project(), pinned()).unwrap().0, "8.2");
+        assert_eq!(pick_version(None, None, None, pinned()).unwrap().0, "8.1.27");
+        assert_eq!(pick_version(None, None, None, None), None);
+
+        let installed: Vec<String> = ["8.2.15", "8.3.0", "8.3.2", "8.4.0RC1"].iter().map(|v| v.to_string()).collect();
+        assert_eq!(resolve_version("8.3", &installed), "8.3.2");
+        assert_eq!(resolve_version("php8.2", &installed), "8.2.15");
+        assert_eq!(resolve_version("latest", &installed), "8.3.2");
+        assert_eq!(resolve_version("7.4", &installed), "7.4");
+    }
+
+    #[test]
+    fn env_vars_are_parsed() {
+        assert_eq!(parse_env_var("PHP_INI_SCAN_DIR=/etc/php.d"), Ok(("PHP_INI_SCAN_DIR".into(), "/etc/php.d".into())));
+        assert_eq!(parse_env_var("EMPTY="), Ok(("EMPTY".into(), String::new())));
+        assert!(parse_env_var("NOVALUE").is_err());
+        assert!(parse_env_var("=value").is_err());
+    }
+
+    #[test]
+    fn layers_apply_in_order() {
+        let dir = Path::new("/home/u/.palawija/php-8.3.0");
+        let inherited = vars(&[("PATH", "/usr/bin"), ("SECRET", "x"), ("PHPRC", "/elsewhere")]);
+
+        let env = child_env(inherited.clone(), false, dir, &[]);
+        assert_eq!(get(&env, "SECRET"), Some("x"));
+        assert_eq!(get(&env, "PHPRC"), Some("/home/u/.palawija/php-8.3.0/etc"));
+
+        let overrides = vars(&[("PHP_INI_DIR", "/custom"), ("SECRET", "y")]);
+        let env = child_env(inherited, true, dir, &overrides);
+        assert_eq!(get(&env, "PATH"), Some("/usr/bin"));
+        assert_eq!(get(&env, "PHP_INI_DIR"), Some("/custom"));
+        assert_eq!(get(&env, "SECRET"), Some("y"));
+    }
+}
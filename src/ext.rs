@@ -0,0 +1,342 @@
+/*!
+ * PECL-style extension management (`palawija ext`)
+ *
+ * Extensions are built against a specific installed version with that
+ * version's own `phpize` and `php-config`, so the resulting shared objects
+ * land in its extension directory and match its ABI.
+ */
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::build;
+use crate::manifest::{ExtensionFile, Manifest};
+use crate::paths;
+
+/// Where the source of an extension comes from
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExtSource {
+    /// An archive to download first
+    Url(String),
+    /// An archive already on disk
+    Archive(PathBuf),
+    /// An unpacked source tree, built in place
+    Directory(PathBuf),
+}
+
+impl ExtSource {
+    /// Classifies a command-line source argument
+    pub fn parse(source: &str) -> Self {
+        if source.starts_with("http://") || source.starts_with("https://") {
+            ExtSource::Url(source.to_string())
+        } else if Path::new(source).is_dir() {
+            ExtSource::Directory(PathBuf::from(source))
+        } else {
+            ExtSource::Archive(PathBuf::from(source))
+        }
+    }
+}
+
+/**
+ * Builds an extension from source and installs it into a version
+ *
+ * Runs `phpize`, `./configure --with-php-config=<php-config>`, `make` and
+ * `make install` with the target version's own tools, never whatever
+ * `php-config` is on PATH. The installed shared objects and the ini lines
+ * that enable them are recorded in the version manifest.
+ *
+ * # Arguments
+ * * `version` - The installed PHP version to build against
+ * * `source` - URL, archive path or source directory of the extension
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+pub fn install_extension(version: &str, source: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let version_dir = paths::version_dir(version)?;
+    let (phpize, php_config) = build_tools(version, &version_dir)?;
+    check_headers(version, &php_config)?;
+
+    let source_dir = prepare_source(ExtSource::parse(source))?;
+    println!("\n⚙️  Building extension in {}", source_dir.display());
+
+    println!("1️⃣  Running phpize...");
+    build::run_step(Command::new(&phpize).current_dir(&source_dir), "phpize")?;
+
+    println!("2️⃣  Configuring build...");
+    build::run_step(
+        Command::new("./configure")
+            .arg(format!("--with-php-config={}", php_config.display()))
+            .current_dir(&source_dir),
+        "configure",
+    )?;
+
+    let jobs = build::parallel_jobs(None);
+    println!("3️⃣  Compiling with {} parallel job(s)...", jobs);
+    build::run_step(
+        Command::new("make").arg(format!("-j{}", jobs)).current_dir(&source_dir),
+        "make",
+    )?;
+
+    println!("4️⃣  Installing...");
+    build::run_step(Command::new("make").arg("install").current_dir(&source_dir), "make install")?;
+
+    let names = built_modules(&source_dir.join("modules"));
+    if names.is_empty() {
+        return Err("❌ make install succeeded but no module was found in modules/".into());
+    }
+    let extension_dir = extension_dir(&php_config)?;
+    let files: Vec<ExtensionFile> = names.iter()
+        .map(|name| {
+            let path = extension_dir.join(format!("{}.so", name));
+            let ini_line = ini_line(name, is_zend_extension(&path));
+            ExtensionFile { name: name.clone(), path, ini_line }
+        })
+        .collect();
+
+    let mut manifest = Manifest::load(&version_dir).unwrap_or_else(|| Manifest {
+        version: version.to_string(),
+        ..Default::default()
+    });
+    for file in &files {
+        manifest.record_extension(file.clone());
+    }
+    manifest.save(&version_dir)?;
+
+    println!("\n🎉 Installed extension(s) for PHP {}: {}", version, names.join(", "));
+    println!("💡 Enable them in {}:", paths::php_ini(&version_dir).display());
+    for file in &files {
+        println!("   {}", file.ini_line);
+    }
+    Ok(())
+}
+
+/**
+ * Lists the shared objects in a version's extension directory
+ *
+ * Extensions installed through palawija are marked as such.
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+pub fn list_extensions(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let version_dir = paths::version_dir(version)?;
+    let (_, php_config) = build_tools(version, &version_dir)?;
+    let extension_dir = extension_dir(&php_config)?;
+    let recorded = Manifest::load(&version_dir).map(|m| m.extensions).unwrap_or_default();
+
+    println!("📂 Extension directory: {}", extension_dir.display());
+
+    let names = built_modules(&extension_dir);
+    if names.is_empty() {
+        println!("📭 No shared extensions installed for PHP {}", version);
+        println!("💡 Build one with: palawija ext {} install <source-url-or-path>", version);
+        return Ok(());
+    }
+
+    println!("✅ Found {} shared extension(s):", names.len());
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    for name in &names {
+        if recorded.contains(name) {
+            println!("   🧩 {} (installed by palawija)", name);
+        } else {
+            println!("   🧩 {}", name);
+        }
+    }
+    Ok(())
+}
+
+/// Locates the version's `phpize` and `php-config`, which only exist once it's compiled
+fn build_tools(version: &str, version_dir: &Path) -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error>> {
+    let phpize = paths::phpize(version_dir);
+    let php_config = paths::php_config(version_dir);
+    if !phpize.is_file() || !php_config.is_file() {
+        return Err(format!(
+            "phpize/php-config not found for PHP {}\n💡 Compile it first: palawija compile {}",
+            version, version
+        ).into());
+    }
+    Ok((phpize, php_config))
+}
+
+/**
+ * Checks that the version was installed with the headers extensions compile against
+ *
+ * `php-config` reports the include directory; without `main/php.h` and
+ * `Zend/zend.h` there, configure would pick up another PHP's headers or fail
+ * halfway through the build.
+ */
+fn check_headers(version: &str, php_config: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new(php_config).arg("--include-dir").output()
+        .map_err(|e| format!("❌ Could not run {}: {}", php_config.display(), e))?;
+    let include_dir = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+    let missing: Vec<&str> = ["main/php.h", "Zend/zend.h"].into_iter()
+        .filter(|header| !output.status.success() || !include_dir.join(header).is_file())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "❌ PHP {} has no development headers ({} missing from {}), so extensions can't be built against it\n💡 Rebuild it with headers: palawija reinstall {} --compile",
+            version, missing.join(", "), include_dir.display(), version
+        ).into());
+    }
+    Ok(())
+}
+
+/// Whether a shared object is a Zend extension (xdebug, opcache), loaded with `zend_extension=`
+fn is_zend_extension(path: &Path) -> bool {
+    std::fs::read(path).is_ok_and(|bytes| bytes.windows(ZEND_ENTRY_SYMBOL.len()).any(|window| window == ZEND_ENTRY_SYMBOL))
+}
+
+/// Symbol every Zend extension exports
+const ZEND_ENTRY_SYMBOL: &[u8] = b"zend_extension_entry";
+
+/// The php.ini line that loads extension `name`
+fn ini_line(name: &str, zend: bool) -> String {
+    if zend {
+        format!("zend_extension={}", name)
+    } else {
+        format!("extension={}", name)
+    }
+}
+
+/// Asks `php-config` where the version loads shared extensions from
+fn extension_dir(php_config: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let output = Command::new(php_config).arg("--extension-dir").output()
+        .map_err(|e| format!("❌ Could not run {}: {}", php_config.display(), e))?;
+    if !output.status.success() {
+        return Err(format!("❌ {} --extension-dir failed ({})", php_config.display(), output.status).into());
+    }
+    Ok(PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+/**
+ * Turns an extension source into a directory ready for `phpize`
+ *
+ * URLs are downloaded and archives extracted into `~/.palawija/cache/ext/`;
+ * directories are used as they are.
+ */
+fn prepare_source(source: ExtSource) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let archive = match source {
+        ExtSource::Directory(dir) => return Ok(dir),
+        ExtSource::Archive(path) => {
+            if !path.is_file() {
+                return Err(format!("❌ Extension source not found: {}", path.display()).into());
+            }
+            path
+        }
+        ExtSource::Url(url) => download(&url)?,
+    };
+
+    let file_name = archive.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let build_dir = paths::cache_dir()?.join("ext").join(archive_stem(&file_name));
+    if build_dir.exists() {
+        std::fs::remove_dir_all(&build_dir)?;
+    }
+    std::fs::create_dir_all(&build_dir)?;
+
+    println!("📦 Extracting {}...", archive.display());
+    let status = Command::new("tar")
+        .arg("-xf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&build_dir)
+        .arg("--strip-components=1")  // Remove top-level directory
+        .status()?;
+    if !status.success() {
+        return Err(format!("❌ Failed to extract {}", archive.display()).into());
+    }
+    Ok(build_dir)
+}
+
+/// Downloads an extension archive into `~/.palawija/cache/ext/`
+fn download(url: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let download_dir = paths::cache_dir()?.join("ext");
+    std::fs::create_dir_all(&download_dir)?;
+
+    let file_name = url.rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or("extension.tgz");
+    let path = download_dir.join(file_name);
+
+    println!("🌐 Download URL: {}", url);
+    println!("⬇️  Starting download...");
+    let status = Command::new("curl")
+        .arg("-L")              // Follow redirects
+        .arg("-f")              // Fail on HTTP errors
+        .arg("--progress-bar")  // Show progress bar
+        .arg("--max-time")      // Set timeout
+        .arg("300")             // 5 minutes timeout
+        .arg(url)
+        .arg("-o")
+        .arg(&path)
+        .status()?;
+
+    if !status.success() {
+        let _ = std::fs::remove_file(&path);
+        return Err(format!("❌ Download failed for {}", url).into());
+    }
+    println!("✅ Download completed successfully");
+    Ok(path)
+}
+
+/// Archive file name without its archive extensions (`redis-6.0.2.tgz` -> `redis-6.0.2`)
+fn archive_stem(file_name: &str) -> &str {
+    [".tar.gz", ".tar.bz2", ".tar.xz", ".tgz", ".tar"]
+        .iter()
+        .find_map(|ext| file_name.strip_suffix(ext))
+        .unwrap_or(file_name)
+}
+
+/// Names of the `.so` modules in a directory, sorted (empty if unreadable)
+fn built_modules(dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry.file_name().to_str()
+                .and_then(|name| name.strip_suffix(".so"))
+                .map(|name| name.to_string())
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_are_classified() {
+        assert_eq!(
+            ExtSource::parse("https://pecl.php.net/get/redis-6.0.2.tgz"),
+            ExtSource::Url("https://pecl.php.net/get/redis-6.0.2.tgz".to_string())
+        );
+        assert_eq!(
+            ExtSource::parse("/nonexistent/redis-6.0.2.tgz"),
+            ExtSource::Archive(PathBuf::from("/nonexistent/redis-6.0.2.tgz"))
+        );
+        assert_eq!(ExtSource::parse("/"), ExtSource::Directory(PathBuf::from("/")));
+    }
+
+    #[test]
+    fn zend_extensions_get_their_own_ini_directive() {
+        let dir = std::env::temp_dir().join(format!("palawija-ext-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let xdebug = dir.join("xdebug.so");
+        let redis = dir.join("redis.so");
+        std::fs::write(&xdebug, b"\x7fELF...zend_extension_entry...").unwrap();
+        std::fs::write(&redis, b"\x7fELF...get_module...").unwrap();
+
+        assert_eq!(ini_line("xdebug", is_zend_extension(&xdebug)), "zend_extension=xdebug");
+        assert_eq!(ini_line("redis", is_zend_extension(&redis)), "extension=redis");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn archive_extensions_are_stripped() {
+        assert_eq!(archive_stem("redis-6.0.2.tgz"), "redis-6.0.2");
+        assert_eq!(archive_stem("xdebug-3.3.1.tar.gz"), "xdebug-3.3.1");
+        assert_eq!(archive_stem("src"), "src");
+    }
+}
@@ -0,0 +1,211 @@
+/*!
+ * Install root consistency check (`palawija fsck`)
+ *
+ * Where `verify` checks one version, `fsck` checks the bookkeeping of the
+ * whole `~/.palawija` tree: managed aliases, the pinned default, manifests
+ * and the download cache. With `--fix`, the problems that have a safe fix
+ * offer it, one confirmation per fix, like `doctor --fix`.
+ */
+
+use std::path::{Path, PathBuf};
+
+use crate::aliases;
+use crate::download::ArchiveFormat;
+use crate::installs;
+use crate::manifest::Manifest;
+use crate::paths;
+use crate::pin;
+use crate::prompt;
+
+/**
+ * Checks the install root and prints a summary
+ *
+ * # Arguments
+ * * `fix` - Offer to repair the problems that have a safe fix
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if any problem remains
+ */
+pub fn run_fsck(fix: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let install_root = paths::install_root()?;
+    println!("🔎 Checking {}...\n", install_root.display());
+    if !install_root.is_dir() {
+        println!("ℹ️  {} does not exist yet, so there is nothing to check", install_root.display());
+        return Ok(());
+    }
+
+    let scan = installs::scan(&install_root)?;
+    let mut problems = 0;
+    problems += check_aliases(&install_root, fix)?;
+    problems += check_pin(&install_root, fix)?;
+    problems += check_installs(&install_root, &scan);
+    problems += check_archives(&scan.versions, fix)?;
+
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    if problems == 0 {
+        println!("✅ The install root is consistent");
+        Ok(())
+    } else {
+        Err(format!("{} problem(s) found", problems).into())
+    }
+}
+
+/// Checks that every managed alias still points at an existing binary; returns the problems left
+fn check_aliases(install_root: &Path, fix: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    println!("🔗 Aliases:");
+    let managed = aliases::read_managed();
+    if managed.is_empty() {
+        println!("   ℹ️  No aliases are managed\n");
+        return Ok(0);
+    }
+
+    let mut problems = 0;
+    for name in &managed {
+        let link_path = paths::link_dir().join(name);
+        let problem = match std::fs::read_link(&link_path) {
+            Err(_) if std::fs::symlink_metadata(&link_path).is_ok() => {
+                println!("   ℹ️  {} was replaced by a file palawija doesn't manage", link_path.display());
+                None
+            }
+            Err(_) => Some(format!("{} is recorded but missing from {}", name, paths::link_dir().display())),
+            Ok(target) if !target.exists() && target.starts_with(install_root) => {
+                Some(format!("{} points to {}, which no longer exists", link_path.display(), target.display()))
+            }
+            Ok(_) => None,
+        };
+        let Some(problem) = problem else {
+            continue;
+        };
+
+        println!("   ⚠️  {}", problem);
+        if fix && prompt::confirm(&format!("Remove the dead alias {}?", name)) {
+            aliases::remove(name)?;
+            println!("   🔧 Removed alias {}", name);
+        } else {
+            problems += 1;
+        }
+    }
+    if problems == 0 {
+        println!("   ✅ Every alias points to an installed version\n");
+    } else {
+        println!("   💡 Remove dead aliases with: palawija fsck --fix\n");
+    }
+    Ok(problems)
+}
+
+/// Checks that the pinned default is installed; returns the problems left
+fn check_pin(install_root: &Path, fix: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    println!("📌 Pinned default:");
+    let Some(pinned) = pin::read_pin() else {
+        println!("   ℹ️  No default pinned\n");
+        return Ok(0);
+    };
+
+    let version_dir = install_root.join(format!("php-{}", pinned));
+    if paths::php_binary(&version_dir).is_file() {
+        println!("   ✅ PHP {} is installed\n", pinned);
+        return Ok(0);
+    }
+    if version_dir.is_dir() {
+        println!("   ⚠️  PHP {} is pinned but not compiled", pinned);
+        println!("   💡 Compile it with: palawija compile {}\n", pinned);
+        return Ok(1);
+    }
+
+    println!("   ⚠️  PHP {} is pinned but not installed", pinned);
+    if fix && prompt::confirm(&format!("Clear the stale pin on PHP {}?", pinned)) {
+        pin::clear_pin()?;
+        println!("   🔧 Cleared the pin\n");
+        return Ok(0);
+    }
+    println!("   💡 Clear it with: palawija fsck --fix, or pin another version with: palawija pin <version>\n");
+    Ok(1)
+}
+
+/// Checks manifests against the installs they describe; returns the problems found
+fn check_installs(install_root: &Path, scan: &installs::InstallScan) -> usize {
+    println!("📦 Installs:");
+    let mut problems = 0;
+    for version in &scan.versions {
+        let version_dir = install_root.join(format!("php-{}", version));
+        let Some(manifest) = Manifest::load(&version_dir) else {
+            continue;
+        };
+        if manifest.compiled && !paths::php_binary(&version_dir).is_file() {
+            println!("   ⚠️  PHP {} is recorded as compiled but {} is missing", version, paths::php_binary(&version_dir).display());
+            println!("   💡 Rebuild it with: palawija compile {}", version);
+            problems += 1;
+        }
+    }
+    for dir in &scan.skipped {
+        println!("   ⚠️  {} is not an install (an interrupted extraction?)", dir.display());
+        println!("   💡 Clean up interrupted installs with: palawija prune");
+        problems += 1;
+    }
+    for error in &scan.errors {
+        println!("   ⚠️  Could not read an entry: {}", error);
+        problems += 1;
+    }
+    if problems == 0 {
+        println!("   ✅ {} install(s) checked, none contradicts its manifest", scan.versions.len());
+    }
+    println!();
+    problems
+}
+
+/// Checks the download cache for archives of versions that aren't installed; returns the problems left
+fn check_archives(installed: &[String], fix: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    println!("💾 Download cache:");
+    let orphaned: Vec<PathBuf> = match std::fs::read_dir(paths::cache_dir()?) {
+        Ok(entries) => {
+            let mut orphaned: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+                    archive_version(name).is_some_and(|version| !installed.iter().any(|v| v == version))
+                })
+                .collect();
+            orphaned.sort();
+            orphaned
+        }
+        Err(_) => Vec::new(),
+    };
+    if orphaned.is_empty() {
+        println!("   ✅ No orphaned archives\n");
+        return Ok(0);
+    }
+
+    for path in &orphaned {
+        println!("   ⚠️  {} belongs to a version that isn't installed", path.display());
+    }
+    if fix && prompt::confirm(&format!("Delete these {} archive(s)?", orphaned.len())) {
+        for path in &orphaned {
+            std::fs::remove_file(path)?;
+        }
+        println!("   🔧 Deleted {} archive(s)\n", orphaned.len());
+        return Ok(0);
+    }
+    println!("   💡 Delete them with: palawija fsck --fix\n");
+    Ok(orphaned.len())
+}
+
+/// The version a cached source archive (`php-8.3.0.tar.xz`) belongs to
+fn archive_version(file_name: &str) -> Option<&str> {
+    let stem = [ArchiveFormat::Xz, ArchiveFormat::Gz].into_iter()
+        .find_map(|format| file_name.strip_suffix(&format!(".{}", format.extension())))?;
+    stem.strip_prefix("php-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archives_are_matched_to_their_version() {
+        assert_eq!(archive_version("php-8.3.0.tar.xz"), Some("8.3.0"));
+        assert_eq!(archive_version("php-8.2.15.tar.gz"), Some("8.2.15"));
+        assert_eq!(archive_version("php-8.3.0.tar.xz.part"), None);
+        assert_eq!(archive_version("available-versions.json"), None);
+    }
+}
@@ -0,0 +1,96 @@
+/*!
+ * Installs built from php-src Git refs (`install --git <ref>`)
+ *
+ * A ref is installed as the version `git-<ref>` in `~/.palawija/php-git-<ref>`,
+ * so `list`, `use` and `reinstall` treat it like any other version; the
+ * commit it resolved to is kept in the manifest.
+ */
+
+use std::path::Path;
+use std::process::Command;
+
+/// Repository snapshots are fetched from
+pub const PHP_SRC_REPO: &str = "https://github.com/php/php-src";
+
+/// Prefix that marks a version name as a Git snapshot
+const VERSION_PREFIX: &str = "git-";
+
+/**
+ * Turns a Git ref into the version name it's installed under
+ *
+ * # Returns
+ * * `Result<String, String>` - `git-<ref>`, or why the ref can't be used as a
+ *   directory name
+ */
+pub fn version_name(git_ref: &str) -> Result<String, String> {
+    let valid = !git_ref.is_empty()
+        && !git_ref.starts_with('-')
+        && git_ref.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if !valid {
+        return Err(format!(
+            "'{}' can't be installed: refs may only contain letters, digits, '.', '-' and '_'",
+            git_ref
+        ));
+    }
+    Ok(format!("{}{}", VERSION_PREFIX, git_ref))
+}
+
+/// The Git ref of a `git-<ref>` version name, or `None` for release versions
+pub fn git_ref(version: &str) -> Option<&str> {
+    version.strip_prefix(VERSION_PREFIX).filter(|git_ref| !git_ref.is_empty())
+}
+
+/// The abbreviated form of a commit hash shown to users
+pub fn short_commit(commit: &str) -> &str {
+    &commit[..commit.len().min(7)]
+}
+
+/**
+ * Fetches `git_ref` of php-src into `dir` and checks it out
+ *
+ * Uses a shallow fetch of just that ref, which works for branches, tags and
+ * full commit hashes alike.
+ *
+ * # Returns
+ * * `Result<String, Box<dyn std::error::Error>>` - The checked-out commit hash
+ */
+pub fn checkout(git_ref: &str, dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir)?;
+
+    println!("🌐 Fetching {} from {}...", git_ref, PHP_SRC_REPO);
+    run_git(dir, &["init", "-q"])?;
+    run_git(dir, &["remote", "add", "origin", PHP_SRC_REPO])?;
+    run_git(dir, &["fetch", "--depth", "1", "origin", git_ref])
+        .map_err(|e| format!("{}\n💡 Check that '{}' is a branch, tag or full commit hash of php-src", e, git_ref))?;
+    run_git(dir, &["checkout", "-q", "FETCH_HEAD"])?;
+
+    let output = Command::new("git").arg("-C").arg(dir).args(["rev-parse", "HEAD"]).output()?;
+    if !output.status.success() {
+        return Err("❌ Could not determine the checked-out commit".into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let status = Command::new("git").arg("-C").arg(dir).args(args).status()
+        .map_err(|e| format!("❌ Could not run git: {}\n💡 Install git to build from php-src", e))?;
+    if !status.success() {
+        return Err(format!("❌ git {} failed ({})", args.join(" "), status).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refs_map_to_version_names() {
+        assert_eq!(version_name("master").as_deref(), Ok("git-master"));
+        assert_eq!(version_name("php-8.4.0RC1").as_deref(), Ok("git-php-8.4.0RC1"));
+        assert!(version_name("feature/jit").is_err());
+        assert!(version_name("--upload-pack").is_err());
+        assert_eq!(git_ref("git-master"), Some("master"));
+        assert_eq!(git_ref("8.3.0"), None);
+    }
+}
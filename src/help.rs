@@ -0,0 +1,71 @@
+/*!
+ * Context-aware help text
+ *
+ * `palawija help use` and `palawija help available` end with examples drawn
+ * from the user's own state - the versions actually installed and the last
+ * filter `available` succeeded with - instead of generic placeholders.
+ */
+
+use std::path::PathBuf;
+
+use crate::atomic;
+use crate::installs;
+use crate::paths;
+
+/// Help epilogue for `use`: the installed versions that can be switched to
+pub fn use_help() -> String {
+    let installed = paths::install_root().ok()
+        .filter(|root| root.is_dir())
+        .and_then(|root| installs::scan(&root).ok())
+        .map(|scan| scan.versions)
+        .unwrap_or_default();
+    let (ready, source_only): (Vec<_>, Vec<_>) = installed.iter()
+        .partition(|version| paths::version_dir(version).is_ok_and(|dir| paths::php_binary(&dir).exists()));
+
+    if ready.is_empty() {
+        let mut help = "No compiled PHP versions installed yet.\n  palawija install latest --compile".to_string();
+        if !source_only.is_empty() {
+            help.push_str(&format!("\n\nSource only (compile first): {}", join(&source_only)));
+        }
+        return help;
+    }
+
+    let mut help = "Installed versions you can switch to:".to_string();
+    for version in &ready {
+        help.push_str(&format!("\n  palawija use {}", version));
+    }
+    if !source_only.is_empty() {
+        help.push_str(&format!("\n\nSource only (compile first): {}", join(&source_only)));
+    }
+    help
+}
+
+/// Help epilogue for `available`: the last filter that produced results
+pub fn available_help() -> String {
+    match last_available_filter() {
+        Some(filter) => format!("Your last search:\n  palawija available {}", filter),
+        None => "Examples:\n  palawija available 8.3\n  palawija available 8.2,8.3\n  palawija available 8 --stable-only --since 8.1.0 --limit 5".to_string(),
+    }
+}
+
+/// Remembers a filter that `available` found versions for (best effort)
+pub fn record_available_filter(filter: &str) {
+    if let Some(path) = filter_path() {
+        let _ = atomic::write_atomic(&path, format!("{}\n", filter).as_bytes());
+    }
+}
+
+fn last_available_filter() -> Option<String> {
+    let content = std::fs::read_to_string(filter_path()?).ok()?;
+    let filter = content.trim();
+    (!filter.is_empty()).then(|| filter.to_string())
+}
+
+/// Location of the remembered filter (`~/.palawija/last-available-filter`), if the root exists
+fn filter_path() -> Option<PathBuf> {
+    paths::install_root().ok().filter(|root| root.is_dir()).map(|root| root.join("last-available-filter"))
+}
+
+fn join(versions: &[&String]) -> String {
+    versions.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,119 @@
+/*!
+ * Log of version switches (`~/.palawija/history.log`)
+ *
+ * Every successful `use` appends one tab-separated line:
+ * `<UTC timestamp>\t<previous version or ->\t<new version>`.
+ */
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::date::Date;
+use crate::paths;
+
+/// Placeholder written when no palawija version was active before a switch
+const NONE_MARKER: &str = "-";
+
+/// One recorded switch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// When the switch happened (`YYYY-MM-DDTHH:MM:SSZ`)
+    pub timestamp: String,
+    /// The palawija version active before the switch, if any
+    pub previous: Option<String>,
+    /// The version switched to
+    pub version: String,
+}
+
+impl Entry {
+    /// Parses one log line; malformed lines yield `None`
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let timestamp = fields.next()?.to_string();
+        let previous = fields.next()?;
+        let version = fields.next()?.to_string();
+        if fields.next().is_some() || version.is_empty() {
+            return None;
+        }
+        Some(Entry {
+            timestamp,
+            previous: (previous != NONE_MARKER).then(|| previous.to_string()),
+            version,
+        })
+    }
+
+    /// Formats the entry as a log line (without newline)
+    pub fn to_line(&self) -> String {
+        format!("{}\t{}\t{}", self.timestamp, self.previous.as_deref().unwrap_or(NONE_MARKER), self.version)
+    }
+}
+
+/// Location of the history log
+pub fn history_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::install_root()?.join("history.log"))
+}
+
+/// Appends a switch from `previous` to `version`, stamped with the current time
+pub fn record(previous: Option<&str>, version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let entry = Entry {
+        timestamp: now(),
+        previous: previous.map(|v| v.to_string()),
+        version: version.to_string(),
+    };
+
+    let path = history_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", entry.to_line())?;
+    Ok(())
+}
+
+/**
+ * Loads every recorded switch, oldest first
+ *
+ * # Returns
+ * * `Result<Vec<Entry>, Box<dyn std::error::Error>>` - Empty if nothing was recorded yet
+ */
+pub fn load() -> Result<Vec<Entry>, Box<dyn std::error::Error>> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(std::fs::read_to_string(path)?.lines().filter_map(Entry::parse).collect())
+}
+
+/// The current time as `YYYY-MM-DDTHH:MM:SSZ`
+pub fn now() -> String {
+    utc_timestamp(SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0))
+}
+
+/// Formats seconds since the epoch as `YYYY-MM-DDTHH:MM:SSZ`
+fn utc_timestamp(secs: u64) -> String {
+    let date = Date::from_days_since_epoch((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!("{}T{:02}:{:02}:{:02}Z", date, time / 3_600, time % 3_600 / 60, time % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip() {
+        let entry = Entry { timestamp: "2024-06-01T12:00:00Z".into(), previous: Some("8.2.15".into()), version: "8.3.0".into() };
+        assert_eq!(Entry::parse(&entry.to_line()), Some(entry));
+
+        let first = Entry::parse("2024-06-01T12:00:00Z\t-\t8.3.0").unwrap();
+        assert_eq!(first.previous, None);
+        assert_eq!(Entry::parse("garbage"), None);
+    }
+
+    #[test]
+    fn timestamps_are_utc() {
+        assert_eq!(utc_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_timestamp(1_717_243_323), "2024-06-01T12:02:03Z");
+    }
+}
@@ -0,0 +1,179 @@
+/*!
+ * User hooks (`~/.palawija/hooks/`)
+ *
+ * `post-install` runs after a version was installed successfully, e.g. to
+ * install composer or enable extensions. It gets the version and install
+ * path both as arguments and as `PALAWIJA_VERSION` / `PALAWIJA_INSTALL_PATH`.
+ * A failing hook is reported as a warning: the install itself is fine.
+ *
+ * `post-use` runs after `use` actually changed the global php, e.g. to
+ * restart php-fpm. It gets the previous version (empty if none was active)
+ * and the new one as arguments, and as `PALAWIJA_PREVIOUS_VERSION` /
+ * `PALAWIJA_VERSION`. `[hooks] post_use` replaces the script with a shell
+ * command, which sees the versions as `$1` and `$2`. `--no-hooks` skips both.
+ */
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::paths;
+
+/// File name of the hook run after an install
+pub const POST_INSTALL: &str = "post-install";
+
+/// File name of the hook run after a switch
+pub const POST_USE: &str = "post-use";
+
+static POST_USE_COMMAND: OnceLock<String> = OnceLock::new();
+
+static SWITCH_HOOKS_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Runs `command` after switches instead of the post-use script, for this run; only the first call counts
+pub fn set_post_use_command(command: String) {
+    let _ = POST_USE_COMMAND.set(command);
+}
+
+/// Skips the post-use hook for the rest of this run (`use --no-hooks`)
+pub fn disable_switch_hooks() {
+    SWITCH_HOOKS_DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// Location of a hook script, whether or not it exists
+pub fn hook_path(name: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::install_root()?.join("hooks").join(name))
+}
+
+/**
+ * Runs the post-install hook for a version if one is set up
+ *
+ * Never fails: a missing hook is skipped, and one that can't run or exits
+ * non-zero only produces a warning.
+ *
+ * # Arguments
+ * * `version` - The version that was installed
+ * * `version_dir` - Where it was installed
+ * * `compiled` - Whether the install includes a compiled binary
+ */
+pub fn run_post_install(version: &str, version_dir: &Path, compiled: bool) {
+    let Ok(hook) = hook_path(POST_INSTALL) else {
+        return;
+    };
+    if !hook.is_file() {
+        return;
+    }
+    if !is_executable(&hook) {
+        println!("⚠️  Skipping {}: it isn't executable (chmod +x it to enable it)", hook.display());
+        return;
+    }
+
+    println!("\n🪝 Running post-install hook {}...", hook.display());
+    let status = Command::new(&hook)
+        .arg(version)
+        .arg(version_dir)
+        .env("PALAWIJA_VERSION", version)
+        .env("PALAWIJA_INSTALL_PATH", version_dir)
+        .env("PALAWIJA_COMPILED", if compiled { "1" } else { "0" })
+        .status();
+    match status {
+        Ok(status) if status.success() => println!("✅ Post-install hook finished"),
+        Ok(status) => println!("⚠️  Post-install hook failed ({}); PHP {} itself installed fine", status, version),
+        Err(e) => println!("⚠️  Could not run the post-install hook: {}", e),
+    }
+}
+
+/**
+ * Runs the post-use hook after a switch, if one is set up and hooks are on
+ *
+ * Never fails: a hook that can't run or exits non-zero only produces a
+ * warning with its exit code, since the switch itself already happened.
+ *
+ * # Arguments
+ * * `previous` - The version linked before the switch, if any
+ * * `version` - The version now linked
+ */
+pub fn run_post_use(previous: Option<&str>, version: &str) {
+    if SWITCH_HOOKS_DISABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let previous = previous.unwrap_or_default();
+    let mut command = match POST_USE_COMMAND.get() {
+        Some(shell_command) => {
+            println!("\n🪝 Running post-use hook: {}", shell_command);
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(shell_command).arg(POST_USE).arg(previous).arg(version);
+            command
+        }
+        None => {
+            let Ok(hook) = hook_path(POST_USE) else {
+                return;
+            };
+            if !hook.is_file() {
+                return;
+            }
+            if !is_executable(&hook) {
+                println!("⚠️  Skipping {}: it isn't executable (chmod +x it to enable it)", hook.display());
+                return;
+            }
+            println!("\n🪝 Running post-use hook {}...", hook.display());
+            let mut command = Command::new(&hook);
+            command.arg(previous).arg(version);
+            command
+        }
+    };
+    let status = command
+        .env("PALAWIJA_PREVIOUS_VERSION", previous)
+        .env("PALAWIJA_VERSION", version)
+        .status();
+    match status {
+        Ok(status) if status.success() => println!("✅ Post-use hook finished"),
+        Ok(status) => println!("⚠️  Post-use hook failed ({}); PHP {} is active all the same", describe_exit(status), version),
+        Err(e) => println!("⚠️  Could not run the post-use hook: {}", e),
+    }
+}
+
+fn describe_exit(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exit code {}", code),
+        None => status.to_string(),
+    }
+}
+
+/// Returns true if any execute bit is set on `path` (or what it links to)
+pub fn is_executable(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn post_install_hook_gets_the_version_and_path() {
+        let (root, _lock) = paths::test_root();
+        let hook = hook_path(POST_INSTALL).unwrap();
+        let output = root.join("post-install.out");
+        std::fs::create_dir_all(hook.parent().unwrap()).unwrap();
+        let script = format!("#!/bin/sh\necho \"$1|$2|$PALAWIJA_VERSION|$PALAWIJA_COMPILED\" >> {}\n", output.display());
+        std::fs::write(&hook, script).unwrap();
+
+        // A hook that isn't executable is skipped
+        run_post_install("8.3.0", &root.join("php-8.3.0"), true);
+        assert!(!output.exists());
+
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+        run_post_install("8.3.0", &root.join("php-8.3.0"), true);
+        run_post_install("8.2.15", &root.join("php-8.2.15"), false);
+        let expected = format!("8.3.0|{0}/php-8.3.0|8.3.0|1\n8.2.15|{0}/php-8.2.15|8.2.15|0\n", root.display());
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), expected);
+
+        // A failing hook is only a warning
+        std::fs::write(&hook, "#!/bin/sh\nexit 3\n").unwrap();
+        run_post_install("8.3.0", &root.join("php-8.3.0"), true);
+
+        std::fs::remove_dir_all(hook.parent().unwrap()).unwrap();
+        std::fs::remove_file(&output).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+/*!
+ * Conditional-request cache for the php.net release listings
+ *
+ * Listing responses are kept in `~/.palawija/cache/http/`, one file per URL,
+ * together with the `ETag` and `Last-Modified` headers the server sent.
+ * Within `FRESH_SECS` a stored response is used as it is. After that the
+ * request is repeated with `If-None-Match` / `If-Modified-Since`; a
+ * `304 Not Modified` only bumps the stored timestamp, so an unchanged
+ * listing isn't downloaded again.
+ *
+ * Entries are written atomically, so runs refreshing the same listing at
+ * once never see a torn file; the last one to finish wins.
+ */
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::download;
+use crate::error::NetworkError;
+use crate::paths;
+
+/// How long a stored response is used without asking the server, in seconds
+pub const FRESH_SECS: u64 = 600;
+
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Reports how each listing was obtained from now on (`-v`)
+pub fn set_verbose() {
+    VERBOSE.store(true, Ordering::Relaxed);
+}
+
+/// A stored response and the validators to revalidate it with
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    /// Unix time the response was last fetched or revalidated
+    checked_at: u64,
+    body: String,
+}
+
+/// Status line and validators of the final response (after redirects)
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ResponseHeaders {
+    status: Option<u16>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/**
+ * Fetches `url`, answering from the cache while it is fresh or unchanged
+ *
+ * # Arguments
+ * * `url` - The listing to fetch
+ * * `timeout` - Seconds the request may take, 0 for no limit
+ * * `fail_on_http_error` - Treat 4xx/5xx answers as errors instead of returning their body
+ *
+ * # Returns
+ * * `Result<String, Box<dyn std::error::Error>>` - The response body, or the network error
+ */
+pub fn get(url: &str, timeout: u64, fail_on_http_error: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let path = paths::cache_dir()?.join("http").join(cache_name(url));
+    let now = unix_now();
+    let mut cached: Option<Entry> = std::fs::read_to_string(&path).ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .filter(|entry: &Entry| entry.url == url);
+
+    if let Some(entry) = cached.take_if(|entry| now.saturating_sub(entry.checked_at) < FRESH_SECS) {
+        report(url, &format!("served from cache (checked {} min ago)", now.saturating_sub(entry.checked_at) / 60));
+        return Ok(entry.body);
+    }
+
+    let headers_path = path.with_file_name(format!(".{}.headers-{}", cache_name(url), std::process::id()));
+    std::fs::create_dir_all(path.parent().unwrap_or(&path))?;
+    let mut curl = Command::new("curl");
+    curl.arg("-s")              // Silent mode
+        .arg("-L")              // Follow redirects
+        .arg("-D").arg(&headers_path);
+    if fail_on_http_error {
+        curl.arg("-f");         // Fail on HTTP errors
+    }
+    if let Some(entry) = &cached {
+        if let Some(etag) = &entry.etag {
+            curl.arg("-H").arg(format!("If-None-Match: {}", etag));
+        }
+        if let Some(last_modified) = &entry.last_modified {
+            curl.arg("-H").arg(format!("If-Modified-Since: {}", last_modified));
+        }
+    }
+    download::set_timeout(&mut curl, timeout);
+    let output = curl.arg(url).output();
+    let headers = parse_headers(&std::fs::read_to_string(&headers_path).unwrap_or_default());
+    let _ = std::fs::remove_file(&headers_path);
+    let output = output?;
+    if !output.status.success() {
+        return Err(NetworkError::from_curl(output.status, url, timeout).into());
+    }
+
+    let entry = match cached {
+        Some(entry) if headers.status == Some(304) => {
+            report(url, "revalidated (304 Not Modified)");
+            Entry { checked_at: now, ..entry }
+        }
+        _ => {
+            report(url, "refetched");
+            let body = String::from_utf8_lossy(&output.stdout).into_owned();
+            if !headers.status.is_some_and(|status| (200..300).contains(&status)) {
+                return Ok(body);
+            }
+            Entry { url: url.to_string(), etag: headers.etag, last_modified: headers.last_modified, checked_at: now, body }
+        }
+    };
+    if let Err(e) = atomic::write_json_atomic(&path, &entry) {
+        eprintln!("⚠️  Could not cache {}: {}", url, e);
+    }
+    Ok(entry.body)
+}
+
+/// Reported on stderr, so listings piped from stdout (`available --plain`) stay clean
+fn report(url: &str, status: &str) {
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("🗄️  {}: {}", url, status);
+    }
+}
+
+/// File name of a URL's entry, e.g. `www.php.net_releases_index.php_json.json`
+fn cache_name(url: &str) -> String {
+    let stem: String = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url)
+        .trim_end_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}.json", stem)
+}
+
+/// Reads the last header block curl dumped, so redirects don't count
+fn parse_headers(dump: &str) -> ResponseHeaders {
+    let mut headers = ResponseHeaders::default();
+    for line in dump.lines().map(str::trim_end) {
+        if line.starts_with("HTTP/") {
+            headers = ResponseHeaders {
+                status: line.split_whitespace().nth(1).and_then(|code| code.parse().ok()),
+                ..ResponseHeaders::default()
+            };
+        } else if let Some((name, value)) = line.split_once(':') {
+            let value = Some(value.trim().to_string()).filter(|value| !value.is_empty());
+            match name.trim().to_ascii_lowercase().as_str() {
+                "etag" => headers.etag = value,
+                "last-modified" => headers.last_modified = value,
+                _ => {}
+            }
+        }
+    }
+    headers
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validators_come_from_the_final_response() {
+        let dump = "HTTP/1.1 301 Moved Permanently\r\nLocation: https://www.php.net/releases/\r\nETag: \"old\"\r\n\r\n\
+                    HTTP/2 200\r\netag: \"abc123\"\r\nlast-modified: Tue, 14 Oct 2025 08:00:00 GMT\r\n\r\n";
+        assert_eq!(parse_headers(dump), ResponseHeaders {
+            status: Some(200),
+            etag: Some("\"abc123\"".to_string()),
+            last_modified: Some("Tue, 14 Oct 2025 08:00:00 GMT".to_string()),
+        });
+        assert_eq!(parse_headers("HTTP/1.1 304 Not Modified\r\n\r\n").status, Some(304));
+        assert_eq!(cache_name("https://www.php.net/releases/index.php?json&version=8"), "www.php.net_releases_index.php_json_version_8.json");
+    }
+}
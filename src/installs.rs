@@ -0,0 +1,239 @@
+/*!
+ * Discovery of installed PHP versions
+ *
+ * The install root also holds palawija's own bookkeeping (cache, backups,
+ * config), and interrupted installs can leave `php-` directories behind, so a
+ * directory only counts as an install if it looks like one.
+ */
+
+use std::collections::HashSet;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::manifest::Manifest;
+use crate::paths;
+use crate::releases;
+
+/// Result of scanning the install root
+#[derive(Debug, Default)]
+pub struct InstallScan {
+    /// Versions that look like real installs
+    pub versions: Vec<String>,
+    /// `php-` directories that don't look like installs
+    pub skipped: Vec<PathBuf>,
+    /// Entries that couldn't be read, with the reason
+    pub errors: Vec<String>,
+}
+
+/// Machine-readable description of one installed version (`list --json`)
+#[derive(Debug, Serialize)]
+pub struct InstallInfo {
+    pub version: String,
+    pub path: PathBuf,
+    /// Disk usage of the version directory; only computed with `--sizes`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    /// Whether the version has a php binary
+    pub compiled: bool,
+    /// Whether the global symlink points to this version
+    pub active: bool,
+    /// Source and build details recorded at install time
+    pub manifest: Option<Manifest>,
+}
+
+/**
+ * Checks whether a directory holds a PHP install or extracted source tree
+ *
+ * Accepts a palawija manifest, a compiled binary, or a recognizable PHP
+ * source marker at the directory root.
+ */
+pub fn looks_like_install(dir: &Path) -> bool {
+    Manifest::path(dir).is_file()
+        || paths::php_binary(dir).is_file()
+        || dir.join("main").join("php_version.h").is_file()
+        || dir.join("configure").is_file()
+}
+
+/**
+ * Finds the installed versions a possibly partial version refers to
+ *
+ * An exact match wins outright; otherwise every version in the `input`
+ * series matches (so "8.2" matches "8.2.15" but not "8.20.0"), as do
+ * suffixed builds of the `input` release ("8.2.15" matches "8.2.15-custom").
+ *
+ * # Returns
+ * * `Vec<String>` - Matching versions, newest first
+ */
+pub fn resolve_partial(installed: &[String], input: &str) -> Vec<String> {
+    if installed.iter().any(|version| version == input) {
+        return vec![input.to_string()];
+    }
+    let prefix = format!("{}.", input);
+    let mut matches: Vec<String> = installed.iter()
+        .filter(|version| version.starts_with(&prefix) || releases::release_of(version) == input)
+        .cloned()
+        .collect();
+    matches.sort_by(|a, b| releases::compare_versions(b, a));
+    matches
+}
+
+/**
+ * Reads the version a PHP source tree declares in `main/php_version.h`
+ *
+ * # Returns
+ * * `Option<String>` - The `PHP_VERSION` string, if the header is present and readable
+ */
+pub fn source_version(dir: &Path) -> Option<String> {
+    let header = std::fs::read_to_string(dir.join("main").join("php_version.h")).ok()?;
+    parse_version_header(&header)
+}
+
+/// Extracts `PHP_VERSION` from the contents of `php_version.h`
+fn parse_version_header(header: &str) -> Option<String> {
+    header.lines().find_map(|line| {
+        let value = line.trim().strip_prefix("#define PHP_VERSION ")?;
+        Some(value.trim().trim_matches('"').to_string())
+    })
+}
+
+/**
+ * Scans an install root for installed versions
+ *
+ * Unreadable entries are recorded in `errors` instead of aborting the scan.
+ *
+ * # Returns
+ * * `Result<InstallScan, std::io::Error>` - Only fails if the root itself can't be read
+ */
+pub fn scan(install_root: &Path) -> Result<InstallScan, std::io::Error> {
+    let mut scan = InstallScan::default();
+
+    for entry in std::fs::read_dir(install_root)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                scan.errors.push(e.to_string());
+                continue;
+            }
+        };
+        let path = entry.path();
+
+        if path.is_dir()
+            && let Some(name_str) = path.file_name().and_then(|name| name.to_str())
+            && let Some(version) = name_str.strip_prefix("php-")  // Remove "php-" prefix
+        {
+            if looks_like_install(&path) {
+                scan.versions.push(version.to_string());
+            } else {
+                scan.skipped.push(path);
+            }
+        }
+    }
+
+    scan.versions.sort();
+    Ok(scan)
+}
+
+/**
+ * Computes the total size of the files below a directory
+ *
+ * Symlinks are counted as links and never followed, and hard-linked files
+ * are only counted once.
+ */
+pub fn dir_size(dir: &Path) -> u64 {
+    let mut seen = HashSet::new();
+    let mut total = 0;
+    let mut stack = vec![dir.to_path_buf()];
+
+    while let Some(path) = stack.pop() {
+        let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+            continue;
+        };
+        if !seen.insert((metadata.dev(), metadata.ino())) {
+            continue;
+        }
+        total += metadata.len();
+
+        if metadata.is_dir()
+            && let Ok(entries) = std::fs::read_dir(&path)
+        {
+            stack.extend(entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()));
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_header_is_parsed() {
+        let header = "#define PHP_MAJOR_VERSION 8\n#define PHP_VERSION \"8.3.0\"\n#define PHP_VERSION_ID 80300\n";
+        assert_eq!(parse_version_header(header).as_deref(), Some("8.3.0"));
+        assert_eq!(parse_version_header("#define PHP_MAJOR_VERSION 8\n"), None);
+    }
+
+    #[test]
+    fn partial_versions_resolve_against_installs() {
+        let installed: Vec<String> = ["8.1.27", "8.2.1", "8.2.15", "8.20.0"].iter().map(|v| v.to_string()).collect();
+        assert_eq!(resolve_partial(&installed, "8.1"), ["8.1.27"]);
+        assert_eq!(resolve_partial(&installed, "8.2"), ["8.2.15", "8.2.1"]);
+        assert_eq!(resolve_partial(&installed, "8.2.1"), ["8.2.1"]);
+        assert_eq!(resolve_partial(&installed, "8"), ["8.20.0", "8.2.15", "8.2.1", "8.1.27"]);
+        assert!(resolve_partial(&installed, "7.4").is_empty());
+    }
+
+    #[test]
+    fn suffixed_builds_install_and_resolve_under_their_full_name() {
+        let root = std::env::temp_dir().join(format!("palawija-suffix-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("php-8.2.15-custom").join("bin")).unwrap();
+        std::fs::write(root.join("php-8.2.15-custom").join("bin").join("php"), "").unwrap();
+
+        let installed = scan(&root).unwrap().versions;
+        assert_eq!(installed, ["8.2.15-custom"]);
+        assert_eq!(resolve_partial(&installed, "8.2.15-custom"), ["8.2.15-custom"]);
+        assert_eq!(resolve_partial(&installed, "8.2.15"), ["8.2.15-custom"]);
+        assert_eq!(resolve_partial(&installed, "8.2"), ["8.2.15-custom"]);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn scan_skips_directories_that_are_not_installs() {
+        let root = std::env::temp_dir().join(format!("palawija-scan-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("php-8.3.0").join("main")).unwrap();
+        std::fs::write(root.join("php-8.3.0").join("main").join("php_version.h"), "").unwrap();
+        std::fs::create_dir_all(root.join("php-8.1.0")).unwrap();
+        std::fs::write(root.join("php-8.1.0").join("configure"), "").unwrap();
+        // Left behind by an interrupted install, and palawija's own bookkeeping
+        std::fs::create_dir_all(root.join("php-8.2.0")).unwrap();
+        std::fs::create_dir_all(root.join("cache")).unwrap();
+        std::fs::write(root.join("php-notes.txt"), "").unwrap();
+
+        let scan = scan(&root).unwrap();
+        assert_eq!(scan.versions, ["8.1.0", "8.3.0"]);
+        assert_eq!(scan.skipped, [root.join("php-8.2.0")]);
+        assert!(scan.errors.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(super::scan(&root).is_err());
+    }
+
+    #[test]
+    fn links_are_not_double_counted() {
+        let dir = std::env::temp_dir().join(format!("palawija-size-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("bin")).unwrap();
+        std::fs::write(dir.join("bin").join("php"), vec![0u8; 4096]).unwrap();
+        let before = dir_size(&dir);
+
+        std::fs::hard_link(dir.join("bin").join("php"), dir.join("php-hardlink")).unwrap();
+        std::os::unix::fs::symlink(dir.join("bin"), dir.join("bin-link")).unwrap();
+        let after = dir_size(&dir);
+        let link_len = std::fs::symlink_metadata(dir.join("bin-link")).unwrap().len();
+
+        assert!(before >= 4096);
+        assert_eq!(after, before + link_len);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,274 @@
+/*!
+ * Atomic replacement of the global php symlink and its toolchain links
+ *
+ * `use` must never leave a moment where `/usr/local/bin/php` is missing, as
+ * another process may run php mid-switch. The new link is created next to the
+ * target and renamed over it, which replaces it atomically on Linux.
+ *
+ * `php` and `php-fpm` are switched as a group: both new links are staged
+ * before either is renamed into place, and a failure part-way through points
+ * the links already switched back at their old targets.
+ */
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::platform::symlink;
+
+/// How a link was put in place
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replacement {
+    /// Renamed over the old link; it always resolved to one of the two targets
+    Atomic,
+    /// The atomic rename wasn't permitted, so the old link was removed first
+    RemovedFirst,
+}
+
+/**
+ * Points `link` at `target`, replacing whatever is at `link`
+ *
+ * # Returns
+ * * `io::Result<Replacement>` - How the link was replaced, or the error of the
+ *   remove-then-create fallback
+ */
+pub fn replace_symlink(target: &Path, link: &Path) -> io::Result<Replacement> {
+    let staging = staging_path(link);
+    let atomic = symlink(target, &staging).and_then(|_| std::fs::rename(&staging, link));
+    if atomic.is_ok() {
+        return Ok(Replacement::Atomic);
+    }
+    let _ = std::fs::remove_file(&staging);
+
+    match std::fs::remove_file(link) {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    symlink(target, link)?;
+    Ok(Replacement::RemovedFirst)
+}
+
+/**
+ * Points several links at new targets so that they all switch or none do
+ *
+ * Every new link is staged next to its destination first, so a directory
+ * that can't be written fails before anything changed. The staged links are
+ * then renamed into place one by one; if a rename fails, the links already
+ * switched go back to their previous targets (or are removed if there was
+ * no link before) and the error is returned.
+ *
+ * # Arguments
+ * * `links` - `(target, link)` pairs
+ *
+ * # Returns
+ * * `io::Result<()>` - The first error, after rolling back
+ */
+pub fn replace_together(links: &[(PathBuf, PathBuf)]) -> io::Result<()> {
+    let mut staged: Vec<PathBuf> = Vec::new();
+    for (target, link) in links {
+        let staging = staging_path(link);
+        let _ = std::fs::remove_file(&staging);
+        if let Err(e) = symlink(target, &staging) {
+            for staging in &staged {
+                let _ = std::fs::remove_file(staging);
+            }
+            return Err(e);
+        }
+        staged.push(staging);
+    }
+
+    let previous: Vec<Option<PathBuf>> = links.iter().map(|(_, link)| std::fs::read_link(link).ok()).collect();
+    for (index, ((_, link), staging)) in links.iter().zip(&staged).enumerate() {
+        if let Err(e) = std::fs::rename(staging, link) {
+            for staging in &staged[index..] {
+                let _ = std::fs::remove_file(staging);
+            }
+            for ((_, link), previous) in links[..index].iter().zip(&previous) {
+                let _ = match previous {
+                    Some(old_target) => replace_symlink(old_target, link).map(|_| ()),
+                    None => std::fs::remove_file(link),
+                };
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// What `link_toolchain` did in the link directory
+#[derive(Debug, Default)]
+pub struct ToolchainLinks {
+    /// Tools now linked to the new version
+    pub linked: Vec<String>,
+    /// Links of the previous version that the new one has no tool for
+    pub removed: Vec<String>,
+    /// Tools left alone because a file palawija doesn't manage is in the way
+    pub skipped: Vec<String>,
+}
+
+/**
+ * Links every executable of a version's `bin` directory into `link_dir`
+ *
+ * `php` itself is left to the caller, which handles a foreign php with a
+ * backup. Tool links into `install_root` that the new version has no tool
+ * for are removed; version aliases and files palawija doesn't manage are
+ * never touched.
+ *
+ * # Arguments
+ * * `bin_dir` - The selected version's `bin` directory
+ * * `link_dir` - Directory holding the global links (e.g. `/usr/local/bin`)
+ * * `install_root` - `~/.palawija`, used to recognize palawija's own links
+ *
+ * # Returns
+ * * `io::Result<ToolchainLinks>` - The tools linked, removed and skipped
+ */
+pub fn link_toolchain(bin_dir: &Path, link_dir: &Path, install_root: &Path) -> io::Result<ToolchainLinks> {
+    let mut result = ToolchainLinks::default();
+    let mut tools: Vec<String> = std::fs::read_dir(bin_dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string()))
+        .filter(|name| name != "php" && name != "php-fpm")
+        .collect();
+    tools.sort();
+
+    // Only tool links (named like their target) are stale; aliases such as php8 aren't
+    for (name, target) in managed_links(link_dir, install_root) {
+        let tool_link = target.file_name().is_some_and(|file| file == name.as_str());
+        if tool_link && name != "php" && name != "php-fpm" && !tools.contains(&name) {
+            std::fs::remove_file(link_dir.join(&name))?;
+            result.removed.push(name);
+        }
+    }
+
+    for tool in tools {
+        let link = link_dir.join(&tool);
+        let ours = std::fs::read_link(&link).is_ok_and(|target| target.starts_with(install_root));
+        if std::fs::symlink_metadata(&link).is_ok() && !ours {
+            result.skipped.push(tool);
+            continue;
+        }
+        replace_symlink(&bin_dir.join(&tool), &link)?;
+        result.linked.push(tool);
+    }
+    Ok(result)
+}
+
+/**
+ * Lists the links in `link_dir` that point into a palawija install
+ *
+ * # Returns
+ * * `Vec<(String, PathBuf)>` - Link names and their targets, sorted by name
+ */
+pub fn managed_links(link_dir: &Path, install_root: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(link_dir) else {
+        return Vec::new();
+    };
+    let mut links: Vec<(String, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let target = std::fs::read_link(entry.path()).ok()?;
+            let name = entry.file_name().to_str()?.to_string();
+            target.starts_with(install_root).then_some((name, target))
+        })
+        .collect();
+    links.sort();
+    links
+}
+
+/// Temporary link path in the same directory as `link`, so the rename stays on one filesystem
+fn staging_path(link: &Path) -> PathBuf {
+    let name = link.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    link.with_file_name(format!(".{}.palawija-{}", name, std::process::id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn link_always_resolves_during_swaps() {
+        let dir = std::env::temp_dir().join(format!("palawija-link-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (old, new) = (dir.join("php-old"), dir.join("php-new"));
+        std::fs::write(&old, b"old").unwrap();
+        std::fs::write(&new, b"new").unwrap();
+        let link = dir.join("php");
+        assert_eq!(replace_symlink(&old, &link).unwrap(), Replacement::Atomic);
+
+        let done = Arc::new(AtomicBool::new(false));
+        let watcher = {
+            let (link, done) = (link.clone(), done.clone());
+            std::thread::spawn(move || {
+                let mut misses = 0;
+                while !done.load(Ordering::Relaxed) {
+                    if std::fs::metadata(&link).is_err() {
+                        misses += 1;
+                    }
+                }
+                misses
+            })
+        };
+        for i in 0..2_000 {
+            let target = if i % 2 == 0 { &new } else { &old };
+            assert_eq!(replace_symlink(target, &link).unwrap(), Replacement::Atomic);
+        }
+        done.store(true, Ordering::Relaxed);
+
+        assert_eq!(watcher.join().unwrap(), 0);
+        assert_eq!(std::fs::read_link(&link).unwrap(), old);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_failed_fpm_link_reverts_the_php_link() {
+        let dir = std::env::temp_dir().join(format!("palawija-together-{}", std::process::id()));
+        let (old, new) = (dir.join("php-8.2.0"), dir.join("php-8.3.0"));
+        for version_dir in [&old, &new] {
+            std::fs::create_dir_all(version_dir.join("sbin")).unwrap();
+            std::fs::write(version_dir.join("php"), b"").unwrap();
+            std::fs::write(version_dir.join("sbin").join("php-fpm"), b"").unwrap();
+        }
+        let (php_link, fpm_link) = (dir.join("php"), dir.join("php-fpm"));
+        replace_together(&[(old.join("php"), php_link.clone()), (old.join("sbin/php-fpm"), fpm_link.clone())]).unwrap();
+
+        // A non-empty directory where the fpm link goes makes its rename fail
+        std::fs::remove_file(&fpm_link).unwrap();
+        std::fs::create_dir_all(fpm_link.join("in-the-way")).unwrap();
+        let switched = replace_together(&[(new.join("php"), php_link.clone()), (new.join("sbin/php-fpm"), fpm_link.clone())]);
+
+        assert!(switched.is_err());
+        assert_eq!(std::fs::read_link(&php_link).unwrap(), old.join("php"));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 4, "staging links are cleaned up");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn toolchains_replace_stale_links_but_not_foreign_files() {
+        let root = std::env::temp_dir().join(format!("palawija-toolchain-{}", std::process::id()));
+        let (old_bin, new_bin, link_dir) = (root.join("php-8.2.0/bin"), root.join("php-8.3.0/bin"), root.join("links"));
+        for dir in [&old_bin, &new_bin, &link_dir] {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        for tool in ["php", "phpdbg", "phpize"] {
+            std::fs::write(old_bin.join(tool), b"").unwrap();
+        }
+        for tool in ["php", "phpize", "php-config"] {
+            std::fs::write(new_bin.join(tool), b"").unwrap();
+        }
+        std::fs::write(link_dir.join("php-config"), b"system").unwrap();
+
+        let first = link_toolchain(&old_bin, &link_dir, &root).unwrap();
+        assert_eq!(first.linked, ["phpdbg", "phpize"]);
+
+        let second = link_toolchain(&new_bin, &link_dir, &root).unwrap();
+        assert_eq!(second.linked, ["phpize"]);
+        assert_eq!(second.removed, ["phpdbg"]);
+        assert_eq!(second.skipped, ["php-config"]);
+        assert_eq!(std::fs::read_link(link_dir.join("phpize")).unwrap(), new_bin.join("phpize"));
+        assert_eq!(managed_links(&link_dir, &root).len(), 1);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
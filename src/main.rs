@@ -16,14 +16,70 @@
  * License: MIT
  */
 
-use clap::{ Parser, Subcommand };
-use std::process::Command;
+use clap::{ CommandFactory, FromArgMatches, Parser, Subcommand };
+use clap::error::ErrorKind;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
 use std::env;
 use std::path::{Path, PathBuf};
 
-// For symbolic links on Linux - required for the 'use' command
-#[cfg(target_os = "linux")]
-use std::os::unix::fs::symlink;
+mod aliases;
+mod atomic;
+mod bench;
+mod build;
+mod ccache;
+mod cleanup;
+mod completions;
+mod composer;
+mod config;
+mod date;
+mod deps;
+mod direnv;
+mod doctor;
+mod download;
+mod elf;
+mod envs;
+mod error;
+mod exec;
+mod ext;
+mod fsck;
+mod git;
+mod help;
+mod history;
+mod hooks;
+mod http_cache;
+mod installs;
+mod link;
+mod manifest;
+mod mirror;
+mod paths;
+mod pin;
+mod platform;
+mod probe;
+mod progress;
+mod prompt;
+mod releases;
+mod report;
+mod schema;
+mod self_update;
+mod shell;
+mod signature;
+mod space;
+mod state;
+mod stats;
+mod support;
+mod table;
+mod tools;
+mod tree;
+mod verify;
+
+use error::PalawijaError;
+use manifest::Manifest;
+use support::{BranchStatus, SupportSchedule};
+
+// Symbolic links are Linux-only for now; elsewhere this reports UnsupportedPlatform
+use platform::symlink;
+use std::os::unix::process::ExitStatusExt;
 
 /// Main CLI structure using clap derive macros
 #[derive(Parser)]
@@ -37,6 +93,35 @@ use std::os::unix::fs::symlink;
 )]
 #[command(propagate_version = true)]
 struct Cli {
+    /// Config file layered over ~/.palawija/config.toml (environment variables and flags still win)
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Keep versions, cache and config here instead of ~/.palawija (or set PALAWIJA_ROOT)
+    #[arg(long, global = true, value_name = "DIR")]
+    root: Option<PathBuf>,
+
+    /// Directory for the php symlink and its toolchain links (overrides [use] link_dir; default /usr/local/bin)
+    #[arg(long, global = true, value_name = "DIR")]
+    link_dir: Option<PathBuf>,
+
+    /// Basename of the php symlink, e.g. php-palawija to keep a system php (overrides [use] link_name; default php)
+    #[arg(long, global = true, value_name = "NAME", value_parser = paths::parse_link_name)]
+    link_name: Option<String>,
+
+    /// Answer yes to every confirmation prompt (questions and answers are still printed)
+    #[arg(short = 'y', long, global = true)]
+    yes: bool,
+
+    /// Print extra detail, such as whether release listings came from the cache, were revalidated or refetched
+    // Not global: after `exec <version>`, -v belongs to php
+    #[arg(short = 'v', long)]
+    verbose: bool,
+
+    /// How failures are reported on stderr; json prints {"error": {"kind": ...}} for scripts (implied by --json)
+    #[arg(long, global = true, value_enum, value_name = "FORMAT", default_value_t = error::ErrorFormat::Human)]
+    error_format: error::ErrorFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -48,230 +133,1510 @@ enum Commands {
     #[command(about = "Downloads and extracts PHP source code for compilation")]
     Install {
         /// The PHP version to install (e.g., 8.3.0, 8.2.15, 7.4.33)
-        #[arg(help = "PHP version in format: major.minor.patch (e.g., 8.3.0)")]
+        #[arg(id = "php_version", value_name = "VERSION", help = "PHP version (e.g., 8.3.0), a series like 8.3, or 'latest'",
+              conflicts_with_all = ["git", "from_file"])]
+        version: Option<String>,
+
+        /// Install every version listed in a file (one per line, # comments), or '-' for stdin
+        #[arg(long, value_name = "PATH", conflicts_with_all = ["git", "extract_to", "from_source"])]
+        from_file: Option<String>,
+
+        /// With --from-file, stop at the first failed install instead of continuing
+        #[arg(long, requires = "from_file")]
+        fail_fast: bool,
+
+        /// Build a snapshot of php-src at this branch, tag or commit (installed as git-<REF>)
+        #[arg(long, value_name = "REF", conflicts_with = "from_source")]
+        git: Option<String>,
+
+        /// Let "latest" or a partial version like 8.3 resolve to alpha, beta and RC releases
+        #[arg(long, conflicts_with = "git")]
+        include_prerelease: bool,
+
+        /// Only download, verify and extract the source into DIR; nothing is registered in ~/.palawija
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["git", "compile"])]
+        extract_to: Option<PathBuf>,
+
+        /// Download and extract the source, then only run ./configure to check the build would start
+        #[arg(long, conflicts_with_all = ["git", "compile", "extract_to", "from_file"])]
+        check_config: bool,
+
+        /// Skip checking that the version was released, e.g. for a release newer than php.net's list
+        #[arg(long, conflicts_with = "git")]
+        force: bool,
+
+        #[command(flatten)]
+        options: InstallOptions,
+    },
+
+    /// 🔁 Remove and rebuild an installed PHP version from scratch
+    #[command(about = "Wipes a version directory and installs it again, keeping php.ini and manifest")]
+    Reinstall {
+        /// The installed PHP version to rebuild (e.g., 8.3.0)
+        #[arg(id = "php_version", value_name = "VERSION", help = "Installed PHP version to reinstall")]
         version: String,
+
+        #[command(flatten)]
+        options: InstallOptions,
+
+        /// Reinstall even if this is the currently active version
+        #[arg(long)]
+        force: bool,
     },
     
+    /// 🛠️ Compile a version whose source is already extracted
+    #[command(about = "Runs configure, make and make install on an installed source tree, without downloading it again")]
+    Compile {
+        /// The installed PHP version to compile (e.g., 8.3.0)
+        #[arg(id = "php_version", value_name = "VERSION", help = "Installed PHP version to compile")]
+        version: String,
+
+        #[command(flatten)]
+        build: BuildOptions,
+
+        /// Compile again even if the version already has a binary
+        #[arg(long)]
+        force: bool,
+    },
+
     /// ✨ Switch to a different installed PHP version as the system default
     #[command(about = "Sets the global PHP version by creating symbolic links")]
     Use {
         /// The PHP version to use (must be already installed)
-        #[arg(help = "Previously installed PHP version to switch to")]
-        version: String,
+        #[arg(id = "php_version", value_name = "VERSION", help = "Previously installed PHP version to switch to",
+              conflicts_with_all = ["rollback", "from_composer"])]
+        version: Option<String>,
+
+        /// Replace a php that palawija doesn't manage without asking (it's still backed up); also relinks a version that is already active
+        #[arg(long)]
+        force: bool,
+
+        /// Switch back to the version that was active before the last switch
+        #[arg(long)]
+        rollback: bool,
+
+        /// Switch to the newest installed version satisfying require.php in ./composer.json
+        #[arg(long, conflicts_with = "rollback")]
+        from_composer: bool,
+
+        /// Also point a rolling alias like php8 or php8.3 at this version; installs re-point it to newer patches
+        #[arg(long, value_name = "NAME", conflicts_with = "rollback")]
+        alias: Vec<String>,
+
+        /// Only set the --alias links and leave the php link alone
+        #[arg(long, requires = "alias")]
+        alias_only: bool,
+
+        /// Skip running the linked php to confirm the switch (for scripted, trusted switches)
+        #[arg(long, alias = "no-symlink-check")]
+        no_verify: bool,
+
+        /// Also keep php8.3-style links for every compiled branch (see palawija relink)
+        #[arg(long)]
+        expose_versioned: bool,
+
+        /// Switch only while this shell command runs, then switch back and exit with its exit code
+        #[arg(long, value_name = "COMMAND", conflicts_with_all = ["rollback", "alias"])]
+        run: Option<String>,
+
+        /// Don't run ~/.palawija/hooks/post-use (or [hooks] post_use) after the switch
+        #[arg(long)]
+        no_hooks: bool,
     },
     
+    /// ▶️ Run a specific installed PHP version without switching to it
+    #[command(
+        about = "Runs the version's php binary with its own php.ini picked up",
+        after_help = "Environment precedence: --clean-env reduces the inherited environment to \
+                      PATH, HOME, TERM, LANG and USER; PHPRC and PHP_INI_DIR are then set to the \
+                      version's etc directory; -e variables are applied last and override both.\n\n\
+                      Version precedence: the VERSION argument, then PALAWIJA_PHP_VERSION, then \
+                      php_version in ./.palawijarc, then the pinned default. Versions may be partial \
+                      (8.3), latest, or an alias such as php8.3."
+    )]
+    Exec {
+        /// The installed PHP version to run (e.g., 8.3.0)
+        #[arg(id = "php_version", value_name = "VERSION", allow_hyphen_values = true, help = "Compiled PHP version to run; defaults to PALAWIJA_PHP_VERSION, .palawijarc or the pinned version")]
+        version: Option<String>,
+
+        /// Take no VERSION argument (every argument goes to php) and use PALAWIJA_PHP_VERSION, .palawijarc or the pinned version
+        #[arg(long)]
+        version_from_env: bool,
+
+        /// Run php with a minimal environment instead of inheriting this one
+        #[arg(long)]
+        clean_env: bool,
+
+        /// Set a variable in php's environment (repeatable)
+        #[arg(short = 'e', long = "env", value_name = "KEY=VALUE", value_parser = exec::parse_env_var)]
+        env: Vec<(String, String)>,
+
+        /// Arguments passed to php
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, value_name = "ARGS")]
+        args: Vec<String>,
+    },
+
+    /// ⏱️ Compare how fast installed versions run a script
+    #[command(
+        about = "Times each version's php on a script, its startup, or a built-in microbenchmark",
+        after_help = "Versions resolve like exec's (8.3, latest, php8.3); without any, every compiled version is compared. \
+                      Each version runs once to warm up before the timed runs."
+    )]
+    Bench {
+        /// Versions to compare (default: every compiled version)
+        #[arg(value_name = "VERSION")]
+        versions: Vec<String>,
+
+        /// PHP script to run (default: a built-in microbenchmark)
+        #[arg(long, value_name = "PATH")]
+        script: Option<PathBuf>,
+
+        /// Time startup alone (php -r '') instead of a script
+        #[arg(long, conflicts_with = "script")]
+        startup: bool,
+
+        /// Timed runs per version
+        #[arg(short = 'n', long, value_name = "N", default_value_t = bench::DEFAULT_ITERATIONS,
+              value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+    },
+
+    /// 🐚 Print shell exports for a PHP version, or manage named environments
+    #[command(
+        about = "Prints PATH, PHP_VERSION and PHP_INI_DIR exports without changing anything",
+        args_conflicts_with_subcommands = true
+    )]
+    Env {
+        #[command(subcommand)]
+        action: Option<EnvAction>,
+
+        /// The installed PHP version (defaults to the active one)
+        #[arg(id = "php_version", value_name = "VERSION", help = "Installed PHP version; defaults to the active version")]
+        version: Option<String>,
+
+        /// Shell syntax to emit (defaults to the shell in $SHELL)
+        #[arg(long, value_enum)]
+        shell: Option<shell::Shell>,
+
+        #[command(flatten)]
+        path: PathOptions,
+    },
+
+    /// 📤 Print the exports direnv needs for the directory's PHP version
+    #[command(
+        about = "Prints PATH, PHP_VERSION and PHP_INI_DIR exports for the version of the current directory",
+        after_help = "Version precedence: the VERSION argument, then the nearest .php-version (this directory \
+                      or a parent), then php_version in ./.palawijarc, then the pinned default. PATH loses every \
+                      other ~/.palawija entry, so repeated exports don't pile up. Used by `use palawija` in an \
+                      .envrc; see `palawija hook direnv`."
+    )]
+    Export {
+        /// The installed PHP version (defaults to the directory's version)
+        #[arg(id = "php_version", value_name = "VERSION")]
+        version: Option<String>,
+    },
+
+    /// 🪝 Print the integration snippet for a tool that switches versions per directory
+    #[command(about = "Prints a snippet that lets another tool select palawija versions")]
+    Hook {
+        #[command(subcommand)]
+        target: HookTarget,
+    },
+
     /// 📜 Display all installed PHP versions with their status
     #[command(about = "Shows installed versions and highlights the currently active one")]
-    List,
+    List {
+        /// Print a machine-readable JSON inventory instead
+        #[arg(long)]
+        json: bool,
+
+        /// Include each version's disk usage in the JSON (walks every directory)
+        #[arg(long, requires = "json")]
+        sizes: bool,
+
+        /// Check php.net for newer patch releases of each installed branch
+        #[arg(long, conflicts_with = "json")]
+        remote: bool,
+
+        /// Layout of the listing (table: version, status, compiled, active, path)
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = table::Layout::List, conflicts_with_all = ["json", "remote"])]
+        format: table::Layout,
+
+        /// Show each version's bin, etc, lib and include directories with their sizes and manifest highlights
+        #[arg(long, conflicts_with_all = ["json", "remote", "format"])]
+        tree: bool,
+
+        /// With --tree, how many directory levels to show below each version
+        #[arg(long, value_name = "N", requires = "tree", default_value_t = tree::DEFAULT_DEPTH,
+              value_parser = clap::value_parser!(u32).range(1..=tree::MAX_DEPTH as i64))]
+        depth: u32,
+
+        /// Only show versions that are downloaded but not compiled yet
+        #[arg(long, conflicts_with = "compiled")]
+        source_only: bool,
+
+        /// Only show versions with a compiled php binary
+        #[arg(long, alias = "binary-only")]
+        compiled: bool,
+    },
     
+    /// 🕘 Show recent version switches
+    #[command(about = "Prints the switches recorded in ~/.palawija/history.log, newest first")]
+    History {
+        /// Show at most N entries
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+    },
+
+    /// 🚨 Report installed versions that are end of life or have a newer patch
+    #[command(about = "Checks each installed version against the support schedule and php.net releases; exits 1 if any is EOL")]
+    Outdated {
+        /// Seconds to wait for the release listing (0 for no timeout)
+        #[arg(long, value_name = "SECS", value_parser = download::parse_timeout, default_value_t = releases::DEFAULT_FETCH_TIMEOUT)]
+        timeout: u64,
+    },
+
+    /// 🔗 Point php8.3-style links at every compiled branch
+    #[command(about = "Creates php<major>.<minor> links for each compiled branch and removes links to branches that are gone")]
+    Relink,
+
+    /// 📊 Show your own usage statistics (kept locally, never sent anywhere)
+    #[command(about = "Prints the install and switch counts kept in ~/.palawija/stats.json on this machine")]
+    Stats {
+        /// Delete the collected statistics
+        #[arg(long)]
+        reset: bool,
+    },
+
     /// 🔍 Show the path to the currently active PHP binary
-    #[command(about = "Displays the full path to the current PHP executable")]
-    Which,
+    #[command(
+        about = "Displays the full path to the current PHP executable",
+        after_help = "Exit codes: 0 when the php in PATH is a palawija install, 8 (no_php) when there is no php in PATH, \
+                      9 (unmanaged_php) when it isn't managed by palawija."
+    )]
+    Which {
+        /// List every linked tool of the active version and where it points
+        #[arg(long)]
+        all: bool,
+
+        /// Follow every symlink to the real php file and show the version directory it belongs to
+        #[arg(long)]
+        resolve_real: bool,
+    },
+
+    /// 🧰 Print the path of a companion tool (phpize, php-config, pecl, ...) of a version
+    #[command(
+        about = "Prints a tool's path in a version's bin directory, or lists the tools available",
+        after_help = "With a single argument that names a tool (e.g. 'palawija bin phpize'), the active version is used."
+    )]
+    Bin {
+        /// The installed PHP version (defaults to the active one)
+        #[arg(id = "php_version", value_name = "VERSION")]
+        version: Option<String>,
+
+        /// The tool to locate, e.g. phpize or php-config (lists every tool when omitted)
+        #[arg(value_name = "TOOL")]
+        tool: Option<String>,
+    },
     
+    /// 📌 Record a version as the persistent global default
+    #[command(about = "Writes the default version to ~/.palawija/default so drift can be detected")]
+    Pin {
+        /// The installed PHP version to pin (e.g., 8.3.0)
+        #[arg(id = "php_version", value_name = "VERSION", help = "Installed PHP version to pin as default")]
+        version: String,
+    },
+
+    /// 🧹 Remove what interrupted installs left behind
+    #[command(about = "Deletes leftover install state, the incomplete source trees it points to and partial downloads")]
+    Prune {
+        /// Only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// 🩺 Diagnose common environment problems
+    #[command(about = "Checks build tools and whether the active php matches the pinned default")]
+    Doctor {
+        /// Offer to repair problems that have a safe fix (each one asks first)
+        #[arg(long, conflicts_with = "report")]
+        fix: bool,
+
+        /// Print a diagnostic bundle to paste into a bug report instead of running the checks
+        #[arg(long)]
+        report: bool,
+
+        /// With --report, emit JSON instead of Markdown
+        #[arg(long, requires = "report")]
+        json: bool,
+    },
+
+    /// 🔎 Check the install root for inconsistencies
+    #[command(about = "Checks aliases, the pinned default, manifests and cached archives across ~/.palawija")]
+    Fsck {
+        /// Offer to repair the problems that have a safe fix (each one asks first)
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// ⌨️ Generate shell completions
+    #[command(about = "Prints a completion script for the shell, or installs it with --install")]
+    Completions {
+        /// Shell to generate completions for (bash, zsh or fish)
+        #[arg(value_enum)]
+        shell: shell::Shell,
+
+        /// Write the script to the shell's completion directory instead of stdout
+        #[arg(long)]
+        install: bool,
+
+        /// With --install, overwrite a file palawija didn't write
+        #[arg(long, requires = "install")]
+        force: bool,
+    },
+
+    /// 📐 Print the JSON Schema of a JSON output format
+    #[command(hide = true, about = "Prints the JSON Schema that list --json, doctor --report --json, --error-format json or --progress json output follows")]
+    JsonSchema {
+        /// The output to describe
+        #[arg(value_enum)]
+        output: schema::Output,
+    },
+
+    /// ℹ️ Show what palawija knows about a version
+    #[command(about = "Prints the install manifest of a version; --remote adds its release date and changelog links")]
+    Info {
+        /// The PHP version to describe (e.g., 8.3.1)
+        #[arg(id = "php_version", value_name = "VERSION", help = "PHP version to describe")]
+        version: String,
+
+        /// Also fetch the release date, announcement and changelog links from php.net
+        #[arg(long)]
+        remote: bool,
+
+        /// Seconds the php.net request may take (0 = no limit)
+        #[arg(long, value_name = "SECONDS", requires = "remote", value_parser = download::parse_timeout,
+              default_value_t = releases::DEFAULT_FETCH_TIMEOUT)]
+        timeout: u64,
+    },
+
+    /// 🔎 Check that an installed PHP version is intact and runnable
+    #[command(about = "Checks the directory, binary and reported version; exits non-zero on any failure")]
+    Verify {
+        /// The installed PHP version to verify (e.g., 8.3.0)
+        #[arg(id = "php_version", value_name = "VERSION", help = "Installed PHP version to verify")]
+        version: String,
+    },
+
+    /// ⚙️ Inspect palawija's configuration
+    #[command(about = "Shows the configuration palawija uses after merging files, environment and defaults")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// 🧩 Build and inspect extensions of an installed PHP version
+    #[command(about = "Compiles PECL-style extensions with the version's phpize and php-config")]
+    Ext {
+        /// The installed PHP version the extension belongs to (e.g., 8.3.0)
+        #[arg(id = "php_version", value_name = "VERSION", help = "Compiled PHP version to manage extensions for")]
+        version: String,
+
+        #[command(subcommand)]
+        action: ExtAction,
+    },
+
+    /// ⬆️ Upgrade palawija itself to the latest release
+    #[command(about = "Downloads the latest palawija release from GitHub and replaces this binary")]
+    SelfUpdate,
+
     /// 🌐 Browse available PHP versions from the official website
     #[command(about = "Fetches and displays available PHP versions with their status")]
     Available {
-        /// Filter by major version (e.g., 7, 8, 8.1, 8.2)
-        #[arg(help = "Version prefix to filter results (e.g., '8' for PHP 8.x, '8.2' for 8.2.x)")]
+        /// Filter by major version (e.g., 7, 8, 8.1, 8.2), several separated by commas
+        #[arg(id = "php_version", value_name = "VERSION", help = "Version prefix(es) to filter results (e.g., '8' for PHP 8.x, '8.1,8.2', or '8.2.15' for an exact match); omit for a per-branch summary")]
         version: Option<String>,
+
+        /// Without a filter, list every version instead of one summary line per branch
+        #[arg(long, conflicts_with = "php_version")]
+        all: bool,
+
+        /// Only versions released on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = date::parse_arg)]
+        released_after: Option<date::Date>,
+
+        /// Only versions released on or before this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = date::parse_arg)]
+        released_before: Option<date::Date>,
+
+        /// Layout of the listing (table: one row per version with branch, status and release date)
+        #[arg(long, value_enum, value_name = "FORMAT", default_value_t = table::Layout::List)]
+        format: table::Layout,
+
+        /// Print only the matching versions, one per line, for pipes like `| xargs palawija install`; everything else goes to stderr
+        #[arg(long, alias = "minimal", conflicts_with_all = ["format", "all"])]
+        plain: bool,
+
+        #[command(flatten)]
+        query: VersionQuery,
+
+        /// Seconds to wait for the release listing (0 for no timeout)
+        #[arg(long, value_name = "SECS", value_parser = download::parse_timeout, default_value_t = releases::DEFAULT_FETCH_TIMEOUT)]
+        timeout: u64,
+    },
+}
+
+/// Actions of the `ext` command
+#[derive(Subcommand)]
+enum ExtAction {
+    /// Download (if needed), phpize, configure, make and install an extension
+    Install {
+        /// Archive URL, archive path or unpacked source directory
+        #[arg(value_name = "SOURCE", help = "Extension source: URL, .tgz/.tar.gz path or directory")]
+        source: String,
+    },
+
+    /// List the shared extensions in the version's extension directory
+    List,
+}
+
+/// Actions of the `config` command
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective configuration and where each value came from
+    Dump,
+}
+
+/// Actions of the `env` command
+#[derive(Subcommand)]
+enum EnvAction {
+    /// Create (or re-point) a named environment at an installed version
+    Create {
+        /// Environment name (e.g., api)
+        name: String,
+
+        /// The installed PHP version the environment uses
+        #[arg(id = "php_version", long = "php", value_name = "VERSION")]
+        version: String,
+    },
+
+    /// Print the exports that put a named environment first in PATH
+    Activate {
+        /// Environment name
+        name: String,
+
+        /// Shell syntax to emit (defaults to the shell in $SHELL)
+        #[arg(long, value_enum)]
+        shell: Option<shell::Shell>,
+
+        #[command(flatten)]
+        path: PathOptions,
     },
 }
 
+/// Tools `hook` prints a snippet for
+#[derive(Subcommand)]
+enum HookTarget {
+    /// direnv: defines `use palawija [VERSION]` for .envrc files
+    #[command(after_help = "Setup:\n  palawija hook direnv > ~/.config/direnv/lib/palawija.sh\n  echo 'use palawija' >> .envrc\n  direnv allow")]
+    Direnv,
+}
+
+/// How the PATH printed by `env` treats entries palawija added before
+#[derive(clap::Args)]
+struct PathOptions {
+    /// Drop every ~/.palawija entry from PATH before adding this one (the default)
+    #[arg(long, conflicts_with = "prepend")]
+    replace: bool,
+
+    /// Keep other ~/.palawija entries; only move this one to the front
+    #[arg(long, alias = "prepend-path")]
+    prepend: bool,
+}
+
+impl PathOptions {
+    fn mode(&self) -> shell::PathMode {
+        if self.prepend { shell::PathMode::Prepend } else { shell::PathMode::Replace }
+    }
+}
+
+/// Build flags shared by `compile` and `install`/`reinstall --compile`
+#[derive(clap::Args)]
+struct BuildOptions {
+    /// Install the distro's PHP build dependencies before compiling (asks first)
+    #[arg(long)]
+    install_deps: bool,
+
+    /// Number of parallel make jobs (default: the CPU count, capped at one job per 1.5 GiB of available RAM)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(usize))]
+    jobs: Option<usize>,
+
+    /// Kill the compile if configure, make and make install take longer than this many seconds
+    #[arg(long, value_name = "SECS")]
+    build_timeout: Option<u64>,
+
+    /// Show only the build phases and their result; the output is in build.log either way
+    #[arg(long)]
+    quiet_build: bool,
+
+    /// Run configure and make in DIR (an out-of-tree build), keeping objects out of the source tree
+    #[arg(long, value_name = "DIR")]
+    build_dir: Option<PathBuf>,
+
+    /// Compile through ccache to speed up rebuilds (skipped with a warning if it isn't installed)
+    #[arg(long)]
+    ccache: bool,
+
+    /// Don't run ~/.palawija/hooks/post-install afterwards
+    #[arg(long)]
+    no_hooks: bool,
+
+    /// After compiling, keep php8.3-style links for every compiled branch (see palawija relink)
+    #[arg(long)]
+    expose_versioned: bool,
+}
+
+/// Options shared by `install` and `reinstall`; the build flags other than --no-hooks need --compile
+#[derive(clap::Args)]
+#[command(group(clap::ArgGroup::new("compile_only").multiple(true).requires("compile")
+    .args(["install_deps", "jobs", "build_timeout", "quiet_build", "build_dir", "ccache", "expose_versioned"])))]
+struct InstallOptions {
+    /// Run configure, make and make install after extracting
+    #[arg(long)]
+    compile: bool,
+
+    #[command(flatten)]
+    build: BuildOptions,
+
+    /// Install from a local source tarball or directory instead of downloading
+    #[arg(long, value_name = "PATH")]
+    from_source: Option<PathBuf>,
+
+    /// Expected SHA-256 of the --from-source tarball
+    #[arg(long, value_name = "SHA256", requires = "from_source")]
+    checksum: Option<String>,
+
+    /// Source archive format to download (default: xz when published, otherwise gz)
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "from_source")]
+    format: Option<download::ArchiveFormat>,
+
+    /// Skip the check that ~/.palawija has enough free space for the install
+    #[arg(long)]
+    no_space_check: bool,
+
+    /// Limit the download bandwidth in bytes/s (suffixes k, m, g; overrides PALAWIJA_LIMIT_RATE and [download] limit_rate)
+    #[arg(long, value_name = "RATE", value_parser = download::parse_rate)]
+    limit_rate: Option<u64>,
+
+    /// Download the source from this php.net mirror instead of www.php.net (checksums still come from www.php.net)
+    #[arg(long, value_name = "URL", value_parser = mirror::parse_base, conflicts_with = "from_source")]
+    mirror: Option<String>,
+
+    /// Probe the built-in mirrors and download from the fastest (--mirror takes precedence)
+    #[arg(long, conflicts_with = "from_source")]
+    auto_mirror: bool,
+
+    /// Shared tarball cache checked before downloading and filled afterwards when writable (overrides PALAWIJA_TARBALL_CACHE_DIR and [download] tarball_cache_dir)
+    #[arg(long, value_name = "DIR", conflicts_with = "from_source")]
+    tarball_cache_dir: Option<PathBuf>,
+
+    /// Also check the tarball's GPG signature against the PHP release managers' keys bundled with palawija or listed in ~/.palawija/trusted-keys; fails if it is missing or invalid
+    #[arg(long, conflicts_with = "from_source")]
+    verify_gpg: bool,
+
+    /// Checksum compared against php.net's; none skips it and requires --verify-gpg
+    #[arg(long, value_enum, value_name = "ALGO", default_value_t = download::ChecksumAlgo::Sha256,
+          conflicts_with = "from_source", requires_if("none", "verify_gpg"))]
+    checksum_algo: download::ChecksumAlgo,
+
+    /// Seconds the source download may take (0 for no timeout; default 300, or none with a rate limit); the checksum and size lookups before it default to 30
+    #[arg(long, value_name = "SECS", value_parser = download::parse_timeout)]
+    download_timeout: Option<u64>,
+
+    /// How to report progress; json writes one event per line to stderr instead of progress bars
+    #[arg(long, alias = "report-progress", value_enum, value_name = "FORMAT", default_value_t = progress::ProgressFormat::Human)]
+    progress: progress::ProgressFormat,
+
+    /// Write --progress json events to this open file descriptor instead of stderr
+    #[arg(long, value_name = "FD")]
+    progress_fd: Option<u32>,
+}
+
+/// Filters of `available` beyond the version prefix and release dates; all of them compose
+#[derive(clap::Args, Default)]
+struct VersionQuery {
+    /// Exclude alpha, beta and RC releases
+    #[arg(long)]
+    stable_only: bool,
+
+    /// Only show versions whose branch is end-of-life
+    #[arg(long)]
+    eol: bool,
+
+    /// Only show versions newer than this one (e.g., 8.1.0)
+    #[arg(long, value_name = "VERSION")]
+    since: Option<String>,
+
+    /// Show at most N results
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+}
+
+impl VersionQuery {
+    /// Whether any filter was given
+    fn is_set(&self) -> bool {
+        self.stable_only || self.eol || self.since.is_some() || self.limit.is_some()
+    }
+
+    /// The versions passing every filter, in their original order, branch status taken as of `today`
+    fn apply<'a>(&self, versions: impl IntoIterator<Item = &'a String>, schedule: &SupportSchedule, today: date::Date) -> Vec<&'a String> {
+        versions.into_iter()
+            .filter(|v| !self.stable_only || !releases::is_prerelease(v))
+            .filter(|v| !self.eol || schedule.status(&releases::branch_of(v), today) == BranchStatus::Eol)
+            .filter(|v| self.since.as_ref()
+                .is_none_or(|since| releases::compare_versions(v, since) == std::cmp::Ordering::Greater))
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect()
+    }
+}
+
 /// Application entry point - parses CLI arguments and dispatches to appropriate handlers
 fn main() {
-    println!("🎯 Palawija PHP Version Manager v1.0.0");
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+    let cli = parse_cli();
+    cleanup::install_handler();
+    if matches!(cli.command, Commands::List { json: true, .. } | Commands::Doctor { json: true, .. }) {
+        error::set_format(error::ErrorFormat::Json);
+    } else {
+        error::set_format(cli.error_format);
+    }
+
+    // Commands meant for scripts keep stdout for their result only
+    if matches!(cli.command, Commands::Which { .. } | Commands::Bin { .. } | Commands::Exec { .. }
+        | Commands::Env { action: None | Some(EnvAction::Activate { .. }), .. } | Commands::List { json: true, .. }
+        | Commands::Completions { install: false, .. } | Commands::Doctor { report: true, .. } | Commands::JsonSchema { .. }
+        | Commands::Available { plain: true, .. } | Commands::Export { .. } | Commands::Hook { .. }) {
+        eprintln!("🎯 Palawija PHP Version Manager v1.0.0");
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+    } else {
+        println!("🎯 Palawija PHP Version Manager v1.0.0");
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n");
+    }
+
+    // Before the config is loaded, since it lives in the root
+    if let Some(root) = cli.root.clone().or_else(|| env::var_os(paths::ROOT_ENV).filter(|root| !root.is_empty()).map(PathBuf::from)) {
+        paths::set_root(root);
+    }
+    let config = config::Config::load(cli.config.as_deref()).unwrap_or_else(|e| error::fail("", e.into()));
+    if let Some(link_dir) = cli.link_dir.clone().or_else(|| config.r#use.link_dir.clone()) {
+        paths::set_link_dir(link_dir);
+    }
+    if let Some(link_name) = cli.link_name.clone().or_else(|| config.r#use.link_name.clone()) {
+        paths::set_link_name(link_name);
+    }
+    if let Some(flags) = &config.build.configure_flags {
+        build::set_extra_flags(flags.clone());
+    }
+    if cli.yes {
+        prompt::set_assume_yes();
+    }
+    if cli.verbose {
+        http_cache::set_verbose();
+    }
+
+    let expose_by_default = config.r#use.expose_versioned.unwrap_or(false);
+    if let Some(command) = &config.hooks.post_use {
+        hooks::set_post_use_command(command.clone());
+    }
+    if let Commands::Use { no_hooks: true, .. } = &cli.command {
+        hooks::disable_switch_hooks();
+    }
+
+    let ccache_flag = match &cli.command {
+        Commands::Install { options, .. } | Commands::Reinstall { options, .. } if options.compile => Some(options.build.ccache),
+        Commands::Compile { build, .. } => Some(build.ccache),
+        _ => None,
+    };
+    if let Some(flag) = ccache_flag
+        && (flag || config.build.use_ccache.unwrap_or(false))
+    {
+        match ccache::find() {
+            Some(path) => ccache::set_ccache(path),
+            None => println!("⚠️  ccache was requested but isn't installed - compiling without it\n"),
+        }
+    }
+
+    // Commands that write into the install root find out it's read-only before they start; the others never write there
+    if matches!(cli.command, Commands::Install { .. } | Commands::Reinstall { .. } | Commands::Compile { .. } | Commands::Use { .. }
+        | Commands::Env { action: Some(EnvAction::Create { .. }), .. } | Commands::Pin { .. } | Commands::Prune { dry_run: false }
+        | Commands::Stats { reset: true } | Commands::Doctor { fix: true, .. } | Commands::Fsck { fix: true }
+        | Commands::Ext { action: ExtAction::Install { .. }, .. })
+        && let Err(e) = paths::check_root_writable()
+    {
+        error::fail("", e.into());
+    }
+
+    // Anything that builds, links or runs a version relies on Linux; the read-only commands work everywhere
+    if matches!(cli.command, Commands::Install { .. } | Commands::Reinstall { .. } | Commands::Compile { .. } | Commands::Use { .. }
+        | Commands::Exec { .. } | Commands::Bench { .. } | Commands::Env { .. } | Commands::Export { .. } | Commands::Relink | Commands::Verify { .. }
+        | Commands::Ext { .. } | Commands::SelfUpdate | Commands::Doctor { fix: true, .. } | Commands::Fsck { fix: true })
+        && let Err(e) = platform::ensure_supported()
+    {
+        error::fail("", e.into());
+    }
 
-    let cli = Cli::parse();
+    if let Commands::Install { options, .. } | Commands::Reinstall { options, .. } = &cli.command {
+        match (&options.mirror, options.auto_mirror) {
+            (Some(mirror), _) => mirror::set(mirror.clone()),
+            (None, true) => mirror::set(mirror::auto_select()),
+            (None, false) => {}
+        }
+        download::set_verification(download::Verification { checksum: options.checksum_algo, gpg: options.verify_gpg });
+        if let Some(dir) = options.tarball_cache_dir.clone().or_else(|| config.download.tarball_cache_dir.clone()) {
+            download::set_shared_cache(dir);
+        }
+        if options.progress == progress::ProgressFormat::Json
+            && let Err(e) = progress::set_json(options.progress_fd)
+        {
+            error::fail("", e);
+        }
+    }
 
     // Match and execute the appropriate command
     match &cli.command {
-        Commands::Install { version } => {
-            println!("🚀 Starting PHP installation process...\n");
-            if let Err(e) = install_php(version) {
-                eprintln!("❌ Installation failed: {}", e);
-                eprintln!("💡 Tip: Ensure you have internet connection and sufficient disk space");
-                std::process::exit(1);
-            }
-        }
-        
-        Commands::Use { version } => {
-            println!("🔄 Switching PHP version...\n");
-            if let Err(e) = use_php(version) {
-                eprintln!("❌ Failed to switch PHP version: {}", e);
-                eprintln!("💡 Tip: Make sure the version is installed first using 'palawija install {}'", version);
-                std::process::exit(1);
+        Commands::Install { from_file: Some(list), include_prerelease, fail_fast, force, options, .. } => {
+            let check_released = !*force && options.from_source.is_none();
+            if let Err(e) = batch_install(list, *include_prerelease, check_released, *fail_fast, options, &config) {
+                error::fail("", e);
             }
         }
-        
-        Commands::List => {
-            println!("📋 Scanning for installed PHP versions...\n");
-            if let Err(e) = list_installed_versions() {
-                eprintln!("❌ Error while listing versions: {}", e);
-                std::process::exit(1);
+
+        Commands::Install { version, git, include_prerelease, extract_to, check_config, force, options, .. } => {
+            let project_version = config.project.php_version.clone().filter(|_| git.is_none());
+            if version.is_none() && let Some(version) = &project_version {
+                println!("📄 Using PHP {} from {}", version, config.origin("project.php_version"));
             }
-        }
-        
-        Commands::Which => {
-            println!("🔍 Locating current PHP binary...\n");
-            match Command::new("which").arg("php").output() {
-                Ok(output) => {
-                    let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                    if path.is_empty() {
-                        println!("⚠️  No PHP binary found in system PATH");
-                        println!("💡 Install a PHP version with: palawija install <version>");
-                        println!("💡 Then set it as default with: palawija use <version>");
-                    } else {
-                        println!("📍 Current PHP binary location:");
-                        println!("   {}", path);
-                        
-                        // Try to get PHP version info
-                        if let Ok(version_output) = Command::new("php").arg("--version").output() {
-                            let version_info = String::from_utf8_lossy(&version_output.stdout);
-                            if let Some(first_line) = version_info.lines().next() {
-                                println!("ℹ️  Version info: {}", first_line);
-                            }
-                        }
-                    }
+            let check_released = !*force && options.from_source.is_none();
+            let version = match (version.clone().or(project_version), git) {
+                (Some(version), _) => resolve_install_version(&version, *include_prerelease, check_released).unwrap_or_else(|e| error::fail("", e)),
+                (None, Some(git_ref)) => git::version_name(git_ref).unwrap_or_else(|e| error::fail("Invalid Git ref: ", e.into())),
+                (None, None) if prompt::is_interactive() => pick_available_version(*include_prerelease).unwrap_or_else(|e| error::fail("", e)),
+                (None, None) => missing_version_error("install"),
+            };
+
+            if *check_config {
+                println!("🧪 Checking whether PHP {} would configure...\n", version);
+                if let Err(e) = check_install_config(&version, options, &config) {
+                    error::fail("Configure check failed: ", e);
                 }
-                Err(e) => {
-                    eprintln!("❌ Failed to locate PHP binary: {}", e);
+            } else if let Some(dir) = extract_to {
+                println!("🚀 Extracting PHP source...\n");
+                if let Err(e) = extract_source_to(&version, dir, options, &config) {
+                    error::fail("Extraction failed: ", e);
+                }
+            } else {
+                println!("🚀 Starting PHP installation process...\n");
+                if let Err(e) = report_outcome(&version, install_php(&version, options, &config, true)) {
+                    error::fail_with_hint("Installation failed: ", e, Some("Tip: Ensure you have internet connection and sufficient disk space"));
                 }
             }
         }
-        
-        Commands::Available { version } => {
-            if version.is_none() {
-                eprintln!("❌ Missing required parameter!");
-                eprintln!("📝 Usage: palawija available <version-prefix>");
-                eprintln!("📝 Examples:");
-                eprintln!("   palawija available 8     # Show all PHP 8.x versions");
-                eprintln!("   palawija available 8.2   # Show all PHP 8.2.x versions");
-                eprintln!("   palawija available 7.4   # Show all PHP 7.4.x versions");
-                std::process::exit(1);
+
+        Commands::Reinstall { version, options, force } => {
+            println!("🔁 Starting PHP reinstallation...\n");
+            if let Err(e) = report_outcome(version, reinstall_php(version, options, *force, &config)) {
+                error::fail("Reinstallation failed: ", e);
             }
-            println!("🌐 Fetching available PHP versions from official website...\n");
-            if let Err(e) = show_available_versions(version) {
-                eprintln!("❌ Failed to fetch available versions: {}", e);
-                eprintln!("💡 Check your internet connection and try again");
-                std::process::exit(1);
+        }
+        
+        Commands::Compile { version, build, force } => {
+            let result = resolve_installed_version(version)
+                .and_then(|version| compile_php(&version, build, *force, build.expose_versioned || expose_by_default));
+            if let Err(e) = result {
+                error::fail("Compilation failed: ", e);
             }
         }
-    }
-}
 
-/**
- * Fetches and displays available PHP versions from php.net
- * 
- * This function scrapes the PHP releases page to get available versions,
- * sorts them by version number, and displays them with status indicators.
- * 
- * # Arguments
- * * `filter` - Optional version prefix to filter results (e.g., "8", "8.2")
- * 
- * # Returns
- * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
- * 
- * # Status Indicators
- * * ⚡ Active - Currently supported and actively developed
- * * 🔒 LTS - Long Term Support, recommended for production
- * * ☠️ EOL - End of Life, no longer supported
- */
-fn show_available_versions(filter: &Option<String>) -> Result<(), Box<dyn std::error::Error>> {
-    println!("📡 Connecting to https://www.php.net/releases/...");
-    
-    let output = Command::new("curl")
-        .arg("-s")              // Silent mode
-        .arg("-L")              // Follow redirects
-        .arg("--max-time")      // Set timeout
-        .arg("30")
-        .arg("https://www.php.net/releases/")
-        .output()?;
-
-    if !output.status.success() {
-        return Err("🌐 Failed to fetch PHP releases page. Check your internet connection.".into());
-    }
-
-    println!("✅ Successfully retrieved releases page");
-    println!("🔍 Parsing available versions...\n");
-
-    let html = String::from_utf8_lossy(&output.stdout);
-    let mut versions = Vec::new();
-
-    // Parse HTML to extract PHP version numbers
-    for line in html.lines() {
-        if line.contains("php-") && line.contains(".tar.gz") {
-            if let Some(start) = line.find("php-") {
-                let start_idx = start + 4;
-                if let Some(end) = line[start_idx..].find(".tar.gz") {
-                    let version = &line[start_idx..start_idx + end];
-                    // Validate version format (should contain dots and numbers)
-                    if version.contains('.') && version.chars().any(|c| c.is_numeric()) {
-                        versions.push(version.to_string());
-                    }
+        Commands::Use { version, force, from_composer, no_verify, run: Some(command), .. } => {
+            let version = match version {
+                Some(version) => Ok(version.clone()),
+                None if *from_composer => composer_version(),
+                None => missing_version_error("use"),
+            };
+            match version.and_then(|version| run_temporarily(&version, *force, !*no_verify, command)) {
+                Ok(code) => std::process::exit(code),
+                Err(e) => {
+                    error::fail("", e);
                 }
             }
         }
-    }
-
-    // Sort versions in descending order (newest first)
-    versions.sort_by(|a, b| {
-        let a_parts: Vec<u32> = a.split('.').filter_map(|s| s.parse().ok()).collect();
-        let b_parts: Vec<u32> = b.split('.').filter_map(|s| s.parse().ok()).collect();
-        b_parts.cmp(&a_parts)
-    });
-    versions.dedup(); // Remove duplicates
 
-    // PHP version status definitions (as of 2024)
-    let active_versions = vec!["8.3", "8.2"];     // Currently active branches
-    let lts_versions = vec!["8.1"];               // Long Term Support
-    // Everything else is considered EOL (End of Life)
+        Commands::Use { version: Some(version), force, alias, alias_only, no_verify, expose_versioned, .. } => {
+            println!("🔄 Switching PHP version...\n");
+            if let Err(e) = switch_php(version, *force, !*no_verify, alias, *alias_only, *expose_versioned || expose_by_default) {
+                error::fail("Failed to switch PHP version: ", e);
+            }
+        }
+
+        Commands::Use { version: None, force, from_composer: true, alias, alias_only, no_verify, expose_versioned, .. } => {
+            println!("🔄 Switching PHP version...\n");
+            let result = composer_version()
+                .and_then(|version| switch_php(&version, *force, !*no_verify, alias, *alias_only, *expose_versioned || expose_by_default));
+            if let Err(e) = result {
+                error::fail("Failed to switch PHP version: ", e);
+            }
+        }
+
+        Commands::Use { version: None, force, rollback: false, alias, alias_only, no_verify, expose_versioned, .. } => {
+            let version = if let Some(version) = &config.project.php_version {
+                println!("📄 Using PHP {} from {}", version, config.origin("project.php_version"));
+                resolve_installed_version(version).unwrap_or_else(|e| error::fail("", e))
+            } else if prompt::is_interactive() {
+                pick_installed_version().unwrap_or_else(|e| error::fail("", e))
+            } else {
+                missing_version_error("use")
+            };
+            println!("🔄 Switching PHP version...\n");
+            if let Err(e) = switch_php(&version, *force, !*no_verify, alias, *alias_only, *expose_versioned || expose_by_default) {
+                error::fail("Failed to switch PHP version: ", e);
+            }
+        }
+
+        Commands::Use { version: None, force, rollback: true, no_verify, .. } => {
+            println!("⏪ Rolling back to the previous PHP version...\n");
+            if let Err(e) = rollback_php(*force, !*no_verify) {
+                error::fail("Failed to roll back: ", e);
+            }
+        }
+
+        Commands::History { limit } => {
+            if let Err(e) = show_history(*limit) {
+                error::fail("Failed to read the switch history: ", e);
+            }
+        }
+        
+        Commands::Exec { version, version_from_env, clean_env, env, args } => {
+            // With --version-from-env the first positional is php's first argument
+            let (explicit, args) = match (version, *version_from_env) {
+                (Some(first), true) => (None, [vec![first.clone()], args.clone()].concat()),
+                (explicit, _) => (explicit.as_deref(), args.clone()),
+            };
+            let project = config.project.php_version.clone()
+                .map(|version| (version, config.origin("project.php_version").to_string()));
+            let Some((requested, source)) = exec::pick_version(explicit, env::var(exec::VERSION_ENV).ok(), project, pin::read_pin()) else {
+                error::fail("", format!(
+                    "❌ No PHP version to run\n💡 Pass one (palawija exec 8.3 -- script.php), set {}, add php_version to ./.palawijarc or pin a default",
+                    exec::VERSION_ENV
+                ).into());
+            };
+            let installed = paths::install_root().ok()
+                .filter(|root| root.exists())
+                .and_then(|root| installs::scan(&root).ok())
+                .map(|scan| scan.versions)
+                .unwrap_or_default();
+            let version = exec::resolve_version(&requested, &installed);
+            if explicit.is_none() {
+                eprintln!("📄 Using PHP {} from {}", version, source);
+            }
+            match exec::exec_php(&version, &args, *clean_env, env) {
+                Ok(code) => std::process::exit(code),
+                Err(e) => {
+                    error::fail(&format!("Failed to run PHP {}: ", version), e);
+                }
+            }
+        }
+
+        Commands::Bench { versions, script, startup, iterations } => {
+            let workload = match (script, *startup) {
+                (Some(script), _) => bench::Workload::Script(script),
+                (None, true) => bench::Workload::Startup,
+                (None, false) => bench::Workload::Microbench,
+            };
+            if let Err(e) = bench_versions(versions, &workload, *iterations as usize) {
+                error::fail("Benchmark failed: ", e);
+            }
+        }
+
+        Commands::Env { action: Some(EnvAction::Create { name, version }), .. } => {
+            if let Err(e) = create_env(name, version) {
+                error::fail("Failed to create environment: ", e);
+            }
+        }
+
+        Commands::Env { action: Some(EnvAction::Activate { name, shell, path }), .. } => {
+            let result = envs::resolve(name).and_then(|(shims, version)| {
+                print_env_exports(&shims, &version, shell.unwrap_or_else(shell::Shell::detect), path.mode())
+            });
+            if let Err(e) = result {
+                error::fail("", e);
+            }
+        }
+
+        Commands::Env { action: None, version, shell, path } => {
+            if let Err(e) = print_shell_env(version.as_deref(), shell.unwrap_or_else(shell::Shell::detect), path.mode()) {
+                error::fail("", e);
+            }
+        }
+
+        Commands::Export { version } => {
+            let project = env::current_dir().ok()
+                .and_then(|dir| direnv::find_version_file(&dir))
+                .and_then(|file| direnv::read_version_file(&file).map(|version| (version, file.display().to_string())))
+                .or_else(|| config.project.php_version.clone()
+                    .map(|version| (version, config.origin("project.php_version").to_string())));
+            let Some((requested, source)) = exec::pick_version(version.as_deref(), None, project, pin::read_pin()) else {
+                error::fail("", format!(
+                    "❌ No PHP version for this directory\n💡 Name one in {} (echo 8.3 > {}), add php_version to ./.palawijarc or pin a default",
+                    direnv::VERSION_FILE, direnv::VERSION_FILE
+                ).into());
+            };
+            let installed = paths::install_root().ok()
+                .filter(|root| root.exists())
+                .and_then(|root| installs::scan(&root).ok())
+                .map(|scan| scan.versions)
+                .unwrap_or_default();
+            let version = exec::resolve_version(&requested, &installed);
+            eprintln!("📄 Using PHP {} from {}", version, source);
+            if let Err(e) = print_shell_env(Some(&version), shell::Shell::Bash, shell::PathMode::Replace) {
+                error::fail("", e);
+            }
+        }
+
+        Commands::Hook { target: HookTarget::Direnv } => {
+            let palawija = env::current_exe().unwrap_or_else(|_| PathBuf::from("palawija"));
+            print!("{}", direnv::hook_script(&palawija));
+        }
+
+        Commands::List { json: true, sizes, source_only, compiled, .. } => {
+            if let Err(e) = print_installs_json(*sizes, compiled_filter(*source_only, *compiled)) {
+                error::fail("Error while listing versions: ", e);
+            }
+        }
+
+        Commands::List { json: false, tree: true, depth, source_only, compiled, .. } => {
+            if let Err(e) = print_installs_tree(compiled_filter(*source_only, *compiled), *depth) {
+                error::fail("Error while listing versions: ", e);
+            }
+        }
+
+        Commands::List { json: false, format: table::Layout::Table, source_only, compiled, .. } => {
+            if let Err(e) = print_installs_table(compiled_filter(*source_only, *compiled)) {
+                error::fail("Error while listing versions: ", e);
+            }
+        }
+
+        Commands::List { json: false, remote, source_only, compiled, .. } => {
+            println!("📋 Scanning for installed PHP versions...\n");
+            if let Err(e) = list_installed_versions(*remote, compiled_filter(*source_only, *compiled)) {
+                error::fail("Error while listing versions: ", e);
+            }
+        }
+        
+        Commands::Outdated { timeout } => {
+            match show_outdated(*timeout) {
+                Ok(true) => std::process::exit(1),
+                Ok(false) => {}
+                Err(e) => error::fail("Failed to check for outdated versions: ", e),
+            }
+        }
+
+        Commands::Relink => {
+            if let Err(e) = relink() {
+                error::fail("Failed to relink: ", e);
+            }
+        }
+
+        Commands::Stats { reset } => {
+            if let Err(e) = show_stats(*reset) {
+                error::fail("Failed to read the stats: ", e);
+            }
+        }
+
+        Commands::Which { all, resolve_real } => {
+            eprintln!("🔍 Locating current PHP binary...\n");
+            if let Err(e) = show_current_php(*resolve_real) {
+                error::fail("", e);
+            }
+            if *all {
+                show_linked_tools();
+            }
+        }
+        
+        Commands::Bin { version, tool } => {
+            if let Err(e) = print_tool_path(version.as_deref(), tool.as_deref()) {
+                error::fail("", e);
+            }
+        }
+
+        Commands::Pin { version } => {
+            if let Err(e) = pin_version(version) {
+                error::fail("Failed to pin PHP version: ", e);
+            }
+        }
+
+        Commands::Prune { dry_run } => {
+            if let Err(e) = prune(*dry_run) {
+                error::fail("Failed to prune: ", e);
+            }
+        }
+
+        Commands::Fsck { fix } => {
+            if let Err(e) = fsck::run_fsck(*fix) {
+                error::fail("", e);
+            }
+        }
+
+        Commands::Doctor { report: true, json, .. } => {
+            if let Err(e) = report::print_report(*json) {
+                error::fail("", e);
+            }
+        }
+
+        Commands::Doctor { fix, .. } => {
+            if let Err(e) = doctor::run_doctor(*fix) {
+                error::fail("", e);
+            }
+        }
+
+        Commands::Completions { shell, install, force } => {
+            if let Err(e) = print_completions(*shell, *install, *force) {
+                error::fail("", e);
+            }
+        }
+
+        Commands::JsonSchema { output } => {
+            match serde_json::to_string_pretty(&schema::schema(*output)) {
+                Ok(schema) => println!("{}", schema),
+                Err(e) => error::fail("", e.into()),
+            }
+        }
+
+        Commands::Info { version, remote, timeout } => {
+            if let Err(e) = show_info(version, *remote, *timeout) {
+                error::fail(&format!("Failed to show PHP {}: ", version), e);
+            }
+        }
+
+        Commands::Verify { version } => {
+            if let Err(e) = verify::run_verify(version) {
+                error::fail("", e);
+            }
+        }
+
+        Commands::Config { action: ConfigAction::Dump } => config.dump(),
+
+        Commands::Ext { version, action } => {
+            let result = match action {
+                ExtAction::Install { source } => {
+                    println!("🧩 Installing extension for PHP {}...\n", version);
+                    ext::install_extension(version, source)
+                }
+                ExtAction::List => {
+                    println!("🧩 Scanning extensions of PHP {}...\n", version);
+                    ext::list_extensions(version)
+                }
+            };
+            if let Err(e) = result {
+                error::fail("Extension command failed: ", e);
+            }
+        }
+
+        Commands::SelfUpdate => {
+            println!("⬆️  Checking for a newer palawija...\n");
+            if let Err(e) = self_update::self_update() {
+                error::fail("Self-update failed: ", e);
+            }
+        }
+
+        Commands::Available { version, all, released_after, released_before, format, plain, query, timeout } => {
+            if let Some(filter) = version
+                && let Err(e) = releases::VersionFilter::parse_list(filter)
+            {
+                error::fail("Invalid version filter: ", e.into());
+            }
+            if let (Some(after), Some(before)) = (released_after, released_before)
+                && after > before
+            {
+                error::fail("", format!("--released-after {} is later than --released-before {}", after, before).into());
+            }
+            let window = releases::ReleaseWindow { after: *released_after, before: *released_before };
+            let result = if *plain {
+                releases::set_status_on_stderr();
+                eprintln!("🌐 Fetching available PHP versions from official website...\n");
+                print_available_plain(version, window, query, *timeout)
+            } else {
+                println!("🌐 Fetching available PHP versions from official website...\n");
+                show_available_versions(version, *all, window, query, *format, *timeout)
+            };
+            if let Err(e) = result {
+                error::fail_with_hint("Failed to fetch available versions: ", e, Some("Check your internet connection and try again"));
+            }
+        }
+    }
+}
+
+/**
+ * Prints the php binary the shell will run, if palawija manages it
+ * 
+ * The location and version go to stdout; all guidance goes to stderr, so
+ * stdout is empty whenever this fails.
+ * 
+ * # Arguments
+ * * `resolve_real` - Also show the symlink chain, the real file and its
+ *   version directory (on stderr for a php palawija doesn't manage)
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - `NoPhpInPath` if no php is in
+ *   PATH, `UnmanagedPhp` if it isn't a palawija install
+ */
+fn show_current_php(resolve_real: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let path_var = env::var_os("PATH").unwrap_or_default();
+    let path = match locate_managed_php(paths::link_name(), &path_var, paths::install_root().ok().as_deref()) {
+        Ok(path) => path,
+        Err(e) => {
+            if resolve_real && let PalawijaError::UnmanagedPhp { path } = &e {
+                for line in describe_real_binary(Path::new(path)) {
+                    eprintln!("{}", line);
+                }
+            }
+            return Err(e.into());
+        }
+    };
+
+    println!("📍 Current PHP binary location:");
+    println!("   {}", path.display());
+    if let Some(version) = paths::active_version() {
+        println!("🎯 Active palawija version: {}", version);
+    }
+    if resolve_real {
+        for line in describe_real_binary(&path) {
+            println!("{}", line);
+        }
+    }
+
+    // Try to get PHP version info
+    match probe::php_version(&path) {
+        probe::Probe::TimedOut => eprintln!("⚠️  {}", probe::TIMEOUT_MESSAGE),
+        version_probe => {
+            if let Some(first_line) = version_probe.first_line() {
+                println!("ℹ️  Version info: {}", first_line);
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Finds the php the shell would run and checks that palawija manages it
+ *
+ * Symlinks are resolved, so the global php link counts as managed when it
+ * points into the install root.
+ *
+ * # Arguments
+ * * `name` - Basename of the php link
+ * * `path_var` - The PATH to search
+ * * `install_root` - Where palawija installs versions, `None` if unknown
+ *
+ * # Returns
+ * * `Result<PathBuf, PalawijaError>` - The php found, or `NoPhpInPath` / `UnmanagedPhp`
+ */
+fn locate_managed_php(name: &str, path_var: &std::ffi::OsStr, install_root: Option<&Path>) -> Result<PathBuf, PalawijaError> {
+    let Some(path) = find_in_dirs(name, path_var).into_iter().next() else {
+        return Err(PalawijaError::NoPhpInPath { name: name.to_string() });
+    };
+    let managed = install_root
+        .and_then(|root| std::fs::canonicalize(root).ok())
+        .zip(std::fs::canonicalize(&path).ok())
+        .is_some_and(|(root, target)| target.starts_with(root));
+    if !managed {
+        return Err(PalawijaError::UnmanagedPhp { path: path.display().to_string() });
+    }
+    Ok(path)
+}
+
+/// Lines describing the symlink chain from `path`, the real file and its version directory
+fn describe_real_binary(path: &Path) -> Vec<String> {
+    let chain = symlink_chain(path);
+    let mut lines = Vec::new();
+    if chain.len() > 1 {
+        lines.push("🔗 Symlink chain:".to_string());
+        lines.push(format!("   {}", chain[0].display()));
+        lines.extend(chain[1..].iter().map(|hop| format!("   -> {}", hop.display())));
+    }
+    let Ok(real) = std::fs::canonicalize(path) else {
+        lines.push(format!("⚠️  {} can't be resolved (broken link or loop)", chain.last().unwrap_or(&path.to_path_buf()).display()));
+        return lines;
+    };
+    lines.push(format!("📄 Real file: {}", real.display()));
+
+    let root = paths::install_root().ok().and_then(|root| std::fs::canonicalize(root).ok());
+    let version_dir = real.parent().and_then(Path::parent).filter(|dir| dir.parent() == root.as_deref());
+    match version_dir.and_then(|dir| Some((dir, dir.file_name()?.to_str()?.strip_prefix("php-")?))) {
+        Some((dir, version)) => lines.push(format!("📦 Version directory: {} (PHP {})", dir.display(), version)),
+        None => lines.push("📦 Not inside a palawija version directory".to_string()),
+    }
+    lines
+}
+
+/**
+ * Follows `path` link by link, relative targets taken from the link's directory
+ *
+ * Stops after `MAX_SYMLINK_HOPS` hops so a loop can't hang `which`.
+ *
+ * # Returns
+ * * `Vec<PathBuf>` - `path` followed by the target of each link in turn
+ */
+fn symlink_chain(path: &Path) -> Vec<PathBuf> {
+    const MAX_SYMLINK_HOPS: usize = 40;
+    let mut chain = vec![path.to_path_buf()];
+    while chain.len() <= MAX_SYMLINK_HOPS {
+        let current = chain.last().expect("the chain starts with path");
+        let Ok(target) = std::fs::read_link(current) else {
+            break;
+        };
+        let next = current.parent().map(|dir| dir.join(&target)).unwrap_or(target);
+        chain.push(next);
+    }
+    chain
+}
+
+/// Prints every link palawija manages next to the global php, with its target
+fn show_linked_tools() {
+    let link_dir = paths::link_dir();
+    let links = paths::install_root()
+        .map(|root| link::managed_links(&link_dir, &root))
+        .unwrap_or_default();
+    if links.is_empty() {
+        eprintln!("\n⚠️  No palawija-managed links in {}", link_dir.display());
+        return;
+    }
+
+    println!("\n🧰 Linked tools:");
+    let width = links.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, target) in &links {
+        let marker = if target.exists() { "" } else { " (missing)" };
+        println!("   {:<width$} -> {}{}", name, target.display(), marker, width = width);
+    }
+}
+
+/**
+ * Prints the available versions matching the filters, one per line, newest first
+ *
+ * stdout gets nothing but the versions, so the output can be piped straight
+ * into other commands; notes about the window go to stderr.
+ *
+ * # Arguments
+ * * `filter` - Optional comma-separated version prefixes, as for `available`
+ * * `window` - Only versions released in this window
+ * * `query` - Stability, support status, minimum version and count filters
+ * * `timeout` - Seconds each release-listing request may take, 0 for no limit
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if the listing couldn't be fetched
+ */
+fn print_available_plain(filter: &Option<String>, window: releases::ReleaseWindow, query: &VersionQuery, timeout: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let filters = filter.as_deref().map(releases::VersionFilter::parse_list).transpose()?;
+    let release_list = releases::fetch_available_versions(timeout)?;
+    let schedule = SupportSchedule::load();
+    let (versions, undated) = plain_versions(&release_list, filters.as_deref(), window, query, &schedule, date::Date::today());
+    for version in &versions {
+        println!("{}", version);
+    }
+    if undated > 0 {
+        eprintln!("ℹ️  Skipped {} version(s) without a known release date", undated);
+    }
+    if versions.is_empty() {
+        eprintln!("😔 No available versions match");
+    }
+    Ok(())
+}
+
+/// The versions `available --plain` prints, and how many were dropped for lacking a release date
+fn plain_versions<'a>(
+    release_list: &'a releases::ReleaseList,
+    filters: Option<&[releases::VersionFilter]>,
+    window: releases::ReleaseWindow,
+    query: &VersionQuery,
+    schedule: &SupportSchedule,
+    today: date::Date,
+) -> (Vec<&'a String>, usize) {
+    let versions: Vec<&String> = match filters {
+        Some(filters) => releases::filter_versions(&release_list.versions, filters),
+        None => release_list.versions.iter().collect(),
+    };
+    if !window.is_set() {
+        return (query.apply(versions, schedule, today), 0);
+    }
+
+    let mut undated = 0;
+    let dated: Vec<&String> = versions.into_iter()
+        .filter(|version| match release_list.release_date(version) {
+            Some(date) => window.contains(date),
+            None => {
+                undated += 1;
+                false
+            }
+        })
+        .collect();
+    (query.apply(dated, schedule, today), undated)
+}
+
+/**
+ * Fetches and displays available PHP versions from php.net
+ * 
+ * This function scrapes the PHP releases page to get available versions,
+ * sorts them by version number, and displays them with status indicators.
+ * 
+ * # Arguments
+ * * `filter` - Optional comma-separated version prefixes to filter results
+ *   (e.g., "8", "8.1,8.2"); a full version such as "8.2.15" matches exactly.
+ *   Without one, versions are grouped by branch.
+ * * `all` - Without a filter, list every version rather than one line per branch
+ * * `window` - Only versions released in this window; when set, every
+ *   matching version is listed with its release date
+ * * `query` - Stability, support status, minimum version and count filters,
+ *   applied after the prefix and the window; when set, every match is listed
+ * * `layout` - `Table` prints every matching version as a row, even without `all`
+ * * `timeout` - Seconds each release-listing request may take, 0 for no limit
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ * 
+ * # Status Indicators
+ * * ⚡ Active - Currently supported and actively developed
+ * * 🔒 LTS - Long Term Support, recommended for production
+ * * ☠️ EOL - End of Life, no longer supported
+ * 
+ * Status is derived from the support schedule (see `support.rs`) as of today.
+ */
+fn show_available_versions(filter: &Option<String>, all: bool, window: releases::ReleaseWindow, query: &VersionQuery, layout: table::Layout, timeout: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let filters = filter.as_deref().map(releases::VersionFilter::parse_list).transpose()?;
+    let release_list = releases::fetch_available_versions(timeout)?;
+    let (mut versions, source) = (&release_list.versions, release_list.source);
+    let schedule = SupportSchedule::load();
+    let today = date::Date::today();
 
     if versions.is_empty() {
-        println!("⚠️  Could not parse any versions from the releases page.");
+        println!("⚠️  Could not parse any versions from the releases page or the GitHub tags.");
         println!("🔄 The website format might have changed. Please try again later.");
         return Ok(());
     }
 
+    // Release dates only come from the releases API; undated versions can't match a window
+    let windowed: Vec<String>;
+    let all = all || window.is_set() || query.is_set();
+    if window.is_set() {
+        windowed = versions.iter()
+            .filter(|version| release_list.release_date(version).is_some_and(|date| window.contains(date)))
+            .cloned()
+            .collect();
+        let undated = versions.iter().filter(|version| release_list.release_date(version).is_none()).count();
+        println!("📅 Versions released {}", window);
+        if undated > 0 {
+            println!("ℹ️  Skipped {} version(s) without a known release date", undated);
+        }
+        versions = &windowed;
+        if versions.is_empty() {
+            println!("😔 No versions were released in that window");
+            return Ok(());
+        }
+    }
+
+    if layout == table::Layout::Table {
+        let shown = match &filters {
+            Some(filters) => releases::filter_versions(versions, filters),
+            None => versions.iter().collect(),
+        };
+        let shown = query.apply(shown, &schedule, today);
+        let mut rows = table::Table::new(&["VERSION", "BRANCH", "STATUS", "RELEASED"]);
+        for version in shown {
+            let branch = releases::branch_of(version);
+            rows.push(vec![
+                version.clone(),
+                branch.clone(),
+                schedule.status_today(&branch).label().to_string(),
+                release_list.date(version).unwrap_or("-").to_string(),
+            ]);
+        }
+        rows.print();
+        println!("\n📡 Versions from the {}", source);
+        return Ok(());
+    }
+
     // Display filtered results
-    if let Some(filter_str) = filter {
+    if let (Some(filter_str), Some(filters)) = (filter, &filters) {
         println!("🎯 Available PHP versions matching '{}':", filter_str);
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         
-        // Create filter prefix (ensure it ends with dot for proper matching)
-        let prefix = if filter_str.contains('.') {
-            format!("{}.", filter_str)
-        } else {
-            format!("{}.", filter_str)
-        };
-
-        let filtered: Vec<_> = versions.iter()
-            .filter(|v| v.starts_with(&prefix))
-            .collect();
+        let filtered = query.apply(releases::filter_versions(versions, filters), &schedule, today);
 
         if filtered.is_empty() {
             println!("😔 No versions found matching '{}'", filter_str);
-            println!("💡 Try a broader search like 'palawija available 8' or 'palawija available 7'");
+            if query.is_set() {
+                println!("💡 Try removing a filter, e.g. drop --since or --stable-only");
+            } else {
+                println!("💡 Try a broader search like 'palawija available 8' or 'palawija available 7'");
+            }
         } else {
             println!("📊 Found {} matching versions:\n", filtered.len());
+            help::record_available_filter(filter_str);
             
             for version in filtered {
-                // Extract major.minor for status checking
-                let short = version.split('.').take(2).collect::<Vec<_>>().join(".");
-                
-                // Display version with appropriate status indicator
-                if active_versions.contains(&short.as_str()) {
-                    println!("   📦 {} ⚡ (Active - Recommended)", version);
-                } else if lts_versions.contains(&short.as_str()) {
-                    println!("   📦 {} 🔒 (LTS - Stable)", version);
-                } else {
-                    println!("   📦 {} ☠️  (EOL - Not Recommended)", version);
+                print_version_with_status(version, release_list.date(version), &schedule);
+            }
+        }
+    } else {
+        let queried: Vec<String>;
+        if query.is_set() {
+            queried = query.apply(versions, &schedule, today).into_iter().cloned().collect();
+            versions = &queried;
+            if versions.is_empty() {
+                println!("😔 No versions match the given filters");
+                println!("💡 Try removing a filter, e.g. drop --since or --stable-only");
+                return Ok(());
+            }
+        }
+        let branches = releases::group_by_branch(versions);
+        println!("📚 Available PHP versions by branch ({} versions in {} branches):", versions.len(), branches.len());
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+        for (branch, branch_versions) in &branches {
+            if all {
+                println!("\n🌿 PHP {}.x ({} versions)", branch, branch_versions.len());
+                for version in branch_versions {
+                    print_version_with_status(version, release_list.date(version), &schedule);
                 }
+            } else {
+                let icon = match schedule.status_today(branch) {
+                    BranchStatus::Active => "⚡",
+                    BranchStatus::Lts => "🔒",
+                    BranchStatus::Eol => "☠️ ",
+                };
+                println!("   🌿 {}.x {} {:>3} versions, newest {}", branch, icon, branch_versions.len(), branch_versions[0]);
             }
         }
+        if !all {
+            println!("\n💡 List a branch with 'palawija available 8.3', or everything with 'palawija available --all'");
+        }
+    }
+
+    print_status_legend(source);
+    
+    Ok(())
+}
+
+/// Prints a single version line with the status indicator of its branch and, when known, its release date
+fn print_version_with_status(version: &str, date: Option<&str>, schedule: &SupportSchedule) {
+    let date = date.map(|date| format!(" - released {}", date)).unwrap_or_default();
+    // Display version with appropriate status indicator
+    match schedule.status_today(&releases::branch_of(version)) {
+        BranchStatus::Active => println!("   📦 {} ⚡ (Active - Recommended){}", version, date),
+        BranchStatus::Lts => println!("   📦 {} 🔒 (LTS - Stable){}", version, date),
+        BranchStatus::Eol => println!("   📦 {} ☠️  (EOL - Not Recommended){}", version, date),
     }
+}
 
-    // Display legend and usage instructions
+/// Prints the status legend, usage examples and version source shown after version listings
+fn print_status_legend(source: releases::ReleaseSource) {
     println!("\n📚 Status Legend:");
     println!("   ⚡ Active    - Latest stable versions with active development");
     println!("   🔒 LTS       - Long Term Support, perfect for production");
@@ -281,8 +1646,8 @@ fn show_available_versions(filter: &Option<String>) -> Result<(), Box<dyn std::e
     println!("   palawija install 8.3.0    # Install latest PHP 8.3");
     println!("   palawija install 8.2.15   # Install specific PHP 8.2 version");
     println!("   palawija use 8.3.0        # Switch to PHP 8.3.0");
-    
-    Ok(())
+
+    println!("\n📡 Versions from the {}", source);
 }
 
 /**
@@ -291,12 +1656,16 @@ fn show_available_versions(filter: &Option<String>) -> Result<(), Box<dyn std::e
  * Scans ~/.palawija directory for installed PHP versions and displays them
  * with indicators showing which version is currently active.
  * 
+ * # Arguments
+ * * `remote` - Also fetch the available versions and flag installs that have
+ *   a newer patch release; without network access only the local view is shown
+ * * `compiled` - Only show compiled (`Some(true)`) or source-only (`Some(false)`) versions
+ * 
  * # Returns
  * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
  */
-fn list_installed_versions() -> Result<(), Box<dyn std::error::Error>> {
-    let home = env::var("HOME")?;
-    let install_dir = PathBuf::from(format!("{}/.palawija", home));
+fn list_installed_versions(remote: bool, compiled: Option<bool>) -> Result<(), Box<dyn std::error::Error>> {
+    let install_dir = paths::install_root()?;
 
     println!("📂 Scanning installation directory: ~/.palawija");
 
@@ -309,65 +1678,80 @@ fn list_installed_versions() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    let mut installed_versions = Vec::new();
-
     // Scan for installed PHP directories
-    for entry in std::fs::read_dir(&install_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        
-        if path.is_dir() {
-            if let Some(name) = path.file_name() {
-                if let Some(name_str) = name.to_str() {
-                    if name_str.starts_with("php-") {
-                        let version = &name_str[4..];  // Remove "php-" prefix
-                        installed_versions.push(version.to_string());
-                    }
-                }
-            }
+    let scan = installs::scan(&install_dir)?;
+    let mut installed_versions = scan.versions;
+
+    for error in &scan.errors {
+        println!("⚠️  Could not read an entry in ~/.palawija: {}", error);
+    }
+    if !scan.skipped.is_empty() {
+        println!("🚧 Skipped {} director(ies) that don't look like PHP installs:", scan.skipped.len());
+        for path in &scan.skipped {
+            println!("   • {}", path.display());
         }
+        println!("💡 These may be interrupted installs; remove them or run 'palawija reinstall <version>'\n");
     }
 
-    if installed_versions.is_empty() {
+    let found_any = !installed_versions.is_empty();
+    installed_versions.retain(|version| is_wanted(&install_dir.join(format!("php-{}", version)), compiled));
+
+    if found_any && installed_versions.is_empty() {
+        match compiled {
+            Some(true) => println!("📭 None of the installed versions is compiled yet"),
+            _ => println!("✅ Every installed version is compiled"),
+        }
+    } else if installed_versions.is_empty() {
         println!("📭 Installation directory exists but no PHP versions found.\n");
         println!("💡 Try installing a PHP version:");
         println!("   palawija available 8    # Browse available versions");
         println!("   palawija install 8.3.0  # Install PHP 8.3.0");
     } else {
-        // Sort versions for better display
-        installed_versions.sort();
-        
-        println!("✅ Found {} installed PHP version(s):", installed_versions.len());
-        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        
-        for version in installed_versions {
-            let php_bin_path = install_dir.join(format!("php-{}", version)).join("bin").join("php");
-            
-            // Check if this version is currently active by examining the symlink
-            let is_active = if php_bin_path.exists() {
-                if let Ok(output) = Command::new("readlink").arg("/usr/local/bin/php").output() {
-                    let current_path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                    current_path == php_bin_path.to_string_lossy()
-                } else {
-                    false
+        let available = if remote {
+            match releases::fetch_available_versions(releases::DEFAULT_FETCH_TIMEOUT) {
+                Ok(list) => Some(list.versions),
+                Err(e) => {
+                    println!("⚠️  Could not fetch available versions ({}) - showing local versions only\n", e);
+                    None
                 }
-            } else {
-                false
+            }
+        } else {
+            None
+        };
+
+        println!("✅ Found {} installed PHP version(s):", installed_versions.len());
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        
+        let active = paths::active_version();
+        for version in installed_versions {
+            let php_bin_path = install_dir.join(format!("php-{}", version)).join("bin").join("php");
+            let is_active = php_bin_path.exists() && active.as_ref() == Some(&version);
+
+            // Git snapshots show the commit they were built from
+            let mut label = match Manifest::load(&install_dir.join(format!("php-{}", version))).and_then(|m| m.git_commit) {
+                Some(commit) => format!("{} (commit {})", version, git::short_commit(&commit)),
+                None => version.clone(),
             };
+            if let Some(newer) = available.as_deref().and_then(|available| newer_patch(available, &version)) {
+                label.push_str(&format!(" (update available: {})", newer));
+            }
 
             // Display version with status indicator
             if is_active {
-                println!("   📦 {} ⭐ (Currently Active)", version);
+                println!("   📦 {} ⭐ (Currently Active)", label);
             } else {
                 // Check if the binary actually exists (compiled)
                 if php_bin_path.exists() {
-                    println!("   📦 {} ✅ (Ready to use)", version);
+                    println!("   📦 {} ✅ (Ready to use)", label);
                 } else {
-                    println!("   📦 {} ⚠️  (Source only - needs compilation)", version);
+                    println!("   📦 {} ⚠️  (Source only - needs compilation)", label);
                 }
             }
         }
         
+        if compiled == Some(false) {
+            println!("\n💡 Compile one with: palawija compile <version>");
+        }
         println!("\n💡 Management Commands:");
         println!("   palawija use <version>     # Switch to a different version");
         println!("   palawija which             # Show current PHP binary path");
@@ -377,30 +1761,375 @@ fn list_installed_versions() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Where an installed version stands, as reported by `outdated`
+#[derive(Debug, PartialEq, Eq)]
+enum InstallStatus<'a> {
+    /// The branch no longer gets fixes, with the branch's newest release if it's newer
+    Eol(Option<&'a String>),
+    /// A newer patch release of the branch exists
+    UpdateAvailable(&'a String),
+    /// The newest release of a supported branch
+    Current,
+    /// Supported, but without the release list there's no telling about patches
+    Unchecked,
+}
+
+/// Classifies an installed version; EOL wins over an available patch
+fn install_status<'a>(version: &str, schedule: &SupportSchedule, available: Option<&'a [String]>) -> InstallStatus<'a> {
+    let newer = available.and_then(|available| newer_patch(available, version));
+    if schedule.status_today(&releases::branch_of(version)) == BranchStatus::Eol {
+        return InstallStatus::Eol(newer);
+    }
+    match (available, newer) {
+        (_, Some(newer)) => InstallStatus::UpdateAvailable(newer),
+        (Some(_), None) => InstallStatus::Current,
+        (None, None) => InstallStatus::Unchecked,
+    }
+}
+
+/**
+ * Prints the support and patch status of every installed version
+ *
+ * Without network access the EOL check still runs; only the patch check is
+ * skipped. Git snapshots have no branch and are listed as not checked.
+ *
+ * # Arguments
+ * * `timeout` - Seconds the release listing may take, 0 for no limit
+ *
+ * # Returns
+ * * `Result<bool, Box<dyn std::error::Error>>` - Whether any installed version is EOL
+ */
+fn show_outdated(timeout: u64) -> Result<bool, Box<dyn std::error::Error>> {
+    let install_root = paths::install_root()?;
+    let installed = if install_root.exists() { installs::scan(&install_root)?.versions } else { Vec::new() };
+    if installed.is_empty() {
+        println!("📭 No PHP versions installed yet");
+        return Ok(false);
+    }
+
+    let schedule = SupportSchedule::load();
+    let available = match releases::fetch_available_versions(timeout) {
+        Ok(list) => Some(list.versions),
+        Err(e) => {
+            println!("⚠️  Could not fetch available versions ({}) - only checking support status\n", e);
+            None
+        }
+    };
+
+    println!("🚨 Installed versions by support status:");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    let mut eol = 0;
+    let mut updates = 0;
+    for version in &installed {
+        if git::git_ref(version).is_some() {
+            println!("   ⚪ {} (Git snapshot - not checked)", version);
+            continue;
+        }
+        match install_status(version, &schedule, available.as_deref()) {
+            InstallStatus::Eol(newer) => {
+                eol += 1;
+                let newer = newer.map(|newer| format!(", last release {}", newer)).unwrap_or_default();
+                println!("   🔴 {} (branch {} is EOL - no more security fixes{})", version, releases::branch_of(version), newer);
+            }
+            InstallStatus::UpdateAvailable(newer) => {
+                updates += 1;
+                println!("   🟡 {} (update available: {})", version, newer);
+            }
+            InstallStatus::Current => println!("   🟢 {} (current)", version),
+            InstallStatus::Unchecked => println!("   🟢 {} (supported; patch level not checked)", version),
+        }
+    }
+
+    println!();
+    if eol > 0 {
+        println!("❌ {} installed version(s) are end of life - move to a supported branch (palawija available)", eol);
+    }
+    if updates > 0 {
+        println!("💡 {} version(s) have a newer patch: palawija install <version> --compile", updates);
+    }
+    if eol == 0 && updates == 0 {
+        println!("✅ Everything installed is supported{}", if available.is_some() { " and up to date" } else { "" });
+    }
+    Ok(eol > 0)
+}
+
+/// The `list` state filter: `Some(true)` for `--compiled`, `Some(false)` for `--source-only`
+fn compiled_filter(source_only: bool, compiled: bool) -> Option<bool> {
+    match (source_only, compiled) {
+        (true, _) => Some(false),
+        (_, true) => Some(true),
+        _ => None,
+    }
+}
+
+/// Whether a version directory passes the `list` state filter
+fn is_wanted(version_dir: &Path, compiled: Option<bool>) -> bool {
+    compiled.is_none_or(|compiled| paths::php_binary(version_dir).exists() == compiled)
+}
+
+/// The newest release of `version`'s branch, if it's newer than `version`
+fn newer_patch<'a>(available: &'a [String], version: &str) -> Option<&'a String> {
+    if git::git_ref(version).is_some() {
+        return None;
+    }
+    let newest = releases::newest_in_series(available, &releases::branch_of(version), releases::is_prerelease(version))?;
+    (releases::compare_versions(newest, version) == std::cmp::Ordering::Greater).then_some(newest)
+}
+
+/**
+ * Prints shell exports that make a version the one found first in PATH
+ * 
+ * Nothing is changed on disk; the output is meant for `eval "$(palawija env)"`,
+ * which is safe to repeat (e.g. from a prompt hook): PATH is rebuilt from its
+ * current value instead of growing with every call.
+ * 
+ * # Arguments
+ * * `version` - The version to describe, or `None` for the active one
+ * * `shell` - The shell syntax to emit
+ * * `mode` - What happens to palawija entries already in PATH
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if no version is active
+ *   or the given one isn't compiled
+ */
+fn print_shell_env(version: Option<&str>, shell: shell::Shell, mode: shell::PathMode) -> Result<(), Box<dyn std::error::Error>> {
+    let version = match version {
+        Some(version) => version.to_string(),
+        None => paths::active_version()
+            .ok_or("No palawija-managed PHP is active\n💡 Pass a version: palawija env <version>")?,
+    };
+
+    let version_dir = paths::version_dir(&version)?;
+    let php_bin_path = paths::php_binary(&version_dir);
+    if !php_bin_path.exists() {
+        return Err(PalawijaError::missing_binary(&version, &version_dir).into());
+    }
+
+    print_env_exports(php_bin_path.parent().unwrap_or(&version_dir), &version, shell, mode)
+}
+
+/// Prints the exports that put `bin_dir` first in PATH for `version`
+fn print_env_exports(bin_dir: &Path, version: &str, shell: shell::Shell, mode: shell::PathMode) -> Result<(), Box<dyn std::error::Error>> {
+    let ini_path = paths::php_ini(&paths::version_dir(version)?);
+    let ini_dir = ini_path.parent().unwrap_or(&ini_path);
+    let current: Vec<PathBuf> = env::split_paths(&env::var_os("PATH").unwrap_or_default()).collect();
+    println!("{}", shell.path_set_line(&shell::rewrite_path(&current, bin_dir, &paths::install_root()?, mode)));
+    println!("{}", shell.export_line("PHP_VERSION", version));
+    println!("{}", shell.export_line("PHP_INI_DIR", &ini_dir.to_string_lossy()));
+    Ok(())
+}
+
+/**
+ * Creates a named environment for an installed version
+ *
+ * The version is looked up the same way `use` does - partial versions are
+ * resolved and the binary must exist and match this machine - but only the
+ * environment's shim directory is written; the global symlink is untouched.
+ *
+ * # Arguments
+ * * `name` - Environment name
+ * * `version` - Installed (possibly partial) PHP version
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn create_env(name: &str, version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    envs::validate_name(name)?;
+    let version = resolve_installed_version(version)?;
+    let version_dir = paths::version_dir(&version)?;
+    let php_bin_path = paths::php_binary(&version_dir);
+    if !php_bin_path.exists() {
+        return Err(PalawijaError::missing_binary(&version, &version_dir).into());
+    }
+    elf::check_compatible(&php_bin_path)?;
+
+    let shims = envs::create(name, &version_dir)?;
+    println!("✅ Environment '{}' now uses PHP {}", name, version);
+    println!("📂 Shims: {}", shims.display());
+    println!("💡 Activate it in this shell with: eval \"$(palawija env activate {})\"", name);
+    Ok(())
+}
+
+/**
+ * Prints the path of a version's companion tool, or lists its tools
+ *
+ * Only the path (or the tool names) goes to stdout, so the output can be
+ * used directly, e.g. `$(palawija bin 8.3.0 phpize)`.
+ *
+ * # Arguments
+ * * `version` - Installed version, or the active one if `None`; a lone
+ *   argument naming a known tool is taken as the tool
+ * * `tool` - Tool to locate, or `None` to list every tool
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn print_tool_path(version: Option<&str>, tool: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let (version, tool) = match (version, tool) {
+        (Some(name), None) if tools::is_known_tool(name) => (None, Some(name)),
+        other => other,
+    };
+    let version = match version {
+        Some(version) => version.to_string(),
+        None => paths::active_version()
+            .ok_or("No palawija-managed PHP is active\n💡 Pass a version: palawija bin <version> [tool]")?,
+    };
+
+    let version_dir = paths::version_dir(&version)?;
+    if !version_dir.is_dir() {
+        return Err(PalawijaError::VersionNotInstalled { version }.into());
+    }
+
+    match tool {
+        Some(tool) => println!("{}", tools::tool_path(&version_dir, &version, tool)?.display()),
+        None => {
+            let names = tools::list_tools(&version_dir);
+            if names.is_empty() {
+                return Err(format!(
+                    "No tools found for PHP {} - is it compiled?\n💡 Compile it with: palawija compile {}",
+                    version, version
+                ).into());
+            }
+            eprintln!("🧰 Tools of PHP {}:", version);
+            for name in names {
+                println!("{}", name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Prints the installed versions as a JSON document
+ * 
+ * Output has the shape `{"install_root": ..., "versions": [InstallInfo, ...]}`
+ * and is the only thing written to stdout.
+ * 
+ * # Arguments
+ * * `sizes` - Also compute `size_bytes` for every version (slow on big trees)
+ * * `compiled` - Only include compiled (`Some(true)`) or source-only (`Some(false)`) versions
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn print_installs_json(sizes: bool, compiled: Option<bool>) -> Result<(), Box<dyn std::error::Error>> {
+    let install_root = paths::install_root()?;
+    let document = serde_json::json!({
+        "install_root": install_root,
+        "versions": install_infos(&install_root, sizes, compiled)?,
+    });
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+/**
+ * Prints the installed versions as an aligned table
+ *
+ * Rows come from the same `InstallInfo` records as `list --json`; the
+ * status is the support status of each version's branch.
+ *
+ * # Arguments
+ * * `compiled` - Only include compiled (`Some(true)`) or source-only (`Some(false)`) versions
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn print_installs_table(compiled: Option<bool>) -> Result<(), Box<dyn std::error::Error>> {
+    let infos = install_infos(&paths::install_root()?, false, compiled)?;
+    if infos.is_empty() {
+        println!("📭 No matching PHP versions installed");
+        return Ok(());
+    }
+
+    let schedule = SupportSchedule::load();
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" }.to_string();
+    let mut rows = table::Table::new(&["VERSION", "STATUS", "COMPILED", "ACTIVE", "PATH"]);
+    for info in infos {
+        rows.push(vec![
+            info.version.clone(),
+            schedule.status_today(&releases::branch_of(&info.version)).label().to_string(),
+            yes_no(info.compiled),
+            yes_no(info.active),
+            info.path.display().to_string(),
+        ]);
+    }
+    rows.print();
+    Ok(())
+}
+
+/// Shows the layout and disk usage of the installed versions (`list --tree`)
+fn print_installs_tree(compiled: Option<bool>, depth: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let infos = install_infos(&paths::install_root()?, false, compiled)?;
+    if infos.is_empty() {
+        println!("📭 No matching PHP versions installed");
+        return Ok(());
+    }
+
+    println!("📊 Disk usage of {} installed version(s)\n", infos.len());
+    tree::print_tree(&infos, depth);
+    println!("💡 Leftovers of interrupted installs and partial downloads can go with: palawija prune");
+    Ok(())
+}
+
+/// Describes the installed versions that pass the `compiled` filter, computing sizes if asked
+fn install_infos(install_root: &Path, sizes: bool, compiled: Option<bool>) -> Result<Vec<installs::InstallInfo>, Box<dyn std::error::Error>> {
+    let versions = if install_root.exists() {
+        installs::scan(install_root)?.versions
+    } else {
+        Vec::new()
+    };
+    let active = paths::active_version();
+
+    Ok(versions.into_iter()
+        .filter(|version| is_wanted(&install_root.join(format!("php-{}", version)), compiled))
+        .map(|version| {
+            let path = install_root.join(format!("php-{}", version));
+            let php_bin_path = paths::php_binary(&path);
+            installs::InstallInfo {
+                size_bytes: sizes.then(|| installs::dir_size(&path)),
+                compiled: php_bin_path.exists(),
+                active: php_bin_path.exists() && active.as_ref() == Some(&version),
+                manifest: Manifest::load(&path),
+                version,
+                path,
+            }
+        })
+        .collect())
+}
+
 /**
  * Downloads and extracts PHP source code for a specific version
  * 
  * This function downloads the official PHP source tarball from php.net,
- * extracts it to ~/.palawija/php-<version>/, and provides compilation instructions.
+ * verifies it against the published checksum, extracts it to
+ * ~/.palawija/php-<version>/, and provides compilation instructions.
+ * With `--from-source`, a local tarball is extracted (or a local source
+ * directory copied) instead, skipping the download. `git-<ref>` versions
+ * (from `install --git <ref>`) are checked out from php-src instead.
  * 
- * Note: This only downloads and extracts source code. The user needs to compile
- * it manually using the standard ./configure && make && make install process.
+ * Note: Unless `--compile` is given, this only downloads and extracts source
+ * code. The user then compiles it manually using the standard
+ * ./configure && make && make install process.
  * 
  * # Arguments
  * * `version` - PHP version string (e.g., "8.3.0", "8.2.15")
+ * * `options` - Install options such as `--compile` and `--from-source`
+ * * `config` - Effective configuration, for settings the flags leave unset
+ * * `run_hook` - Run the post-install hook on success (unless `--no-hooks`)
  * 
  * # Returns
  * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
  */
-fn install_php(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+fn install_php(version: &str, options: &InstallOptions, config: &config::Config, run_hook: bool) -> Result<(), Box<dyn std::error::Error>> {
     println!("🎯 Target PHP version: {}", version);
     
-    // Validate version format (basic check)
-    if !version.contains('.') || !version.chars().any(|c| c.is_numeric()) {
+    // Validate version format (basic check); git-<ref> versions were validated when named
+    let git_ref = git::git_ref(version);
+    if git_ref.is_none() && (!version.contains('.') || !version.chars().any(|c| c.is_numeric())) {
         return Err("❌ Invalid version format. Use format like '8.3.0' or '8.2.15'".into());
     }
 
-    let install_dir = format!("{}/.palawija", env::var("HOME")?);
+    let install_dir = paths::install_root()?.to_string_lossy().into_owned();
     println!("📁 Installation directory: {}", install_dir);
     
     // Create installation directory if it doesn't exist
@@ -408,9 +2137,25 @@ fn install_php(version: &str) -> Result<(), Box<dyn std::error::Error>> {
     println!("✅ Installation directory ready");
 
     let version_dir = format!("{}/php-{}", install_dir, version);
+
+    // Pick up where an interrupted install of this version stopped
+    let mut progress = state::InstallState::load(version);
+    if let Some(state) = &progress {
+        println!("♻️  Resuming an interrupted install ({} on {})", state.phase.label(), state.updated_at);
+        if state.phase == state::Phase::Downloaded && Path::new(&version_dir).exists() {
+            println!("🧹 Removing the incomplete source tree left by the interrupted extraction...");
+            std::fs::remove_dir_all(&version_dir)?;
+        }
+    }
+    let resumes_build = options.compile
+        && Path::new(&version_dir).exists()
+        && progress.as_ref().is_some_and(|state| state.phase >= state::Phase::Extracted);
+    let guard = cleanup::InstallGuard::register(Path::new(&version_dir));
     
     // Check if version already exists
-    if Path::new(&version_dir).exists() {
+    if Path::new(&version_dir).exists() && !resumes_build {
+        // Without --compile, the source tree is all this install would have produced
+        state::clear(version);
         println!("⚠️  PHP version {} is already downloaded!", version);
         println!("📂 Location: {}", version_dir);
         println!("💡 To use this version: palawija use {}", version);
@@ -426,215 +2171,2043 @@ fn install_php(version: &str) -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
-    // Download PHP source code
-    let php_url = format!("https://www.php.net/distributions/php-{}.tar.gz", version);
-    println!("🌐 Download URL: {}", php_url);
-    println!("⬇️  Starting download...");
-    
-    let tar_gz_path = format!("{}/php-{}.tar.gz", install_dir, version);
-
-    let download_result = Command::new("curl")
-        .arg("-L")              // Follow redirects
-        .arg("-f")              // Fail on HTTP errors
-        .arg("--progress-bar")  // Show progress bar
-        .arg("--max-time")      // Set timeout
-        .arg("300")             // 5 minutes timeout
-        .arg(&php_url)
-        .arg("-o")
-        .arg(&tar_gz_path)
-        .status()?;
-
-    if !download_result.success() {
-        // Clean up partial download
-        let _ = std::fs::remove_file(&tar_gz_path);
-        return Err(format!(
-            "❌ Download failed for PHP version {}.\n💡 Possible reasons:\n   • Version doesn't exist\n   • Network connection issues\n   • Server temporarily unavailable", 
-            version
-        ).into());
+    // Git checkouts have no tarball to size up, and directories are copied as they are
+    let local_tarball = options.from_source.as_deref().filter(|path| !path.is_dir());
+    let copies_directory = options.from_source.as_deref().is_some_and(Path::is_dir);
+    if !options.no_space_check && git_ref.is_none() && !copies_directory {
+        space::preflight(Path::new(&install_dir), releases::release_of(version), local_tarball, options.format, options.compile, options.download_timeout)?;
     }
-    
-    println!("✅ Download completed successfully");
-    
-    // Extract the tarball
-    println!("📦 Extracting source code...");
-    let extracted_dir = format!("{}/php-{}", install_dir, version);
-    std::fs::create_dir_all(&extracted_dir)?;
-
-    let extract_result = Command::new("tar")
-        .arg("-xzf")
-        .arg(&tar_gz_path)
-        .arg("-C")
-        .arg(&extracted_dir)
-        .arg("--strip-components=1")  // Remove top-level directory
-        .status()?;
-    
-    if !extract_result.success() {
-        return Err("❌ Failed to extract PHP source code".into());
+
+    let extracted_dir = version_dir;
+    let mut manifest = if resumes_build {
+        println!("⏭️  Source tree already extracted to: {}", extracted_dir);
+        let state = progress.as_ref().expect("resumed builds have a state");
+        Manifest::load(Path::new(&extracted_dir)).unwrap_or_else(|| {
+            Manifest::new(version, state.url.as_deref().unwrap_or_default(), state.sha256.clone())
+        })
+    } else if let Some(git_ref) = git_ref {
+        let commit = git::checkout(git_ref, Path::new(&extracted_dir))?;
+        println!("✅ Checked out {} at commit {}", git_ref, git::short_commit(&commit));
+        let mut manifest = Manifest::new(version, git::PHP_SRC_REPO, None);
+        manifest.git_ref = Some(git_ref.to_string());
+        manifest.git_commit = Some(commit);
+        manifest
+    } else {
+        match &options.from_source {
+            Some(source_dir) if source_dir.is_dir() => {
+                if options.checksum.is_some() {
+                    return Err("❌ --checksum only applies to tarballs, not source directories".into());
+                }
+                println!("📁 Copying source tree from {}...", source_dir.display());
+                std::fs::create_dir_all(&extracted_dir)?;
+                let copy_result = Command::new("cp")
+                    .arg("-a")
+                    .arg(source_dir.join("."))
+                    .arg(&extracted_dir)
+                    .status()?;
+                if !copy_result.success() {
+                    return Err("❌ Failed to copy PHP source tree".into());
+                }
+                println!("✅ Source code copied to: {}", extracted_dir);
+
+                let source_url = format!("file://{}", std::fs::canonicalize(source_dir)?.display());
+                Manifest::new(version, &source_url, None)
+            }
+            _ => {
+                // Use the local tarball, or download (or reuse a verified cached copy of) the PHP source code
+                let resumed_tarball = progress.as_ref()
+                    .and_then(|state| state.verified_tarball())
+                    .filter(|tarball| options.format.is_none_or(|format| download::ArchiveFormat::detect(&tarball.path) == Some(format)));
+                let tarball = match (&options.from_source, resumed_tarball) {
+                    (Some(path), _) => download::local_tarball(path, options.checksum.as_deref())?,
+                    (None, Some(tarball)) => {
+                        println!("⏭️  Reusing the tarball downloaded earlier (checksum verified): {}", tarball.path.display());
+                        tarball
+                    }
+                    (None, None) => {
+                        let limit_rate = options.limit_rate.or(config.download.limit_rate);
+                        // A suffixed build ("8.2.15-custom") is made from the plain release's source
+                        let tarball = download::fetch_tarball(releases::release_of(version), options.format, limit_rate, options.download_timeout)?;
+                        let downloaded = state::InstallState::downloaded(version, &tarball);
+                        downloaded.record();
+                        progress = Some(downloaded);
+                        tarball
+                    }
+                };
+
+                extract_tarball(&tarball.path, Path::new(&extracted_dir))?;
+                println!("✅ Source code extracted to: {}", extracted_dir);
+                if options.from_source.is_none() {
+                    println!("💾 Download archive kept in cache: {}", tarball.path.display());
+                }
+                Manifest::new(version, &tarball.url, tarball.sha256)
+            }
+        }
+    };
+
+    // The target directory is named after the requested version, so flag sources that disagree
+    match installs::source_version(Path::new(&extracted_dir)) {
+        Some(found) if releases::release_of(&found) != releases::release_of(version) && git_ref.is_none() => {
+            println!("⚠️  The source declares PHP {} but is being installed as {}", found, version);
+            println!("💡 Double-check the source, or reinstall it under the right version");
+        }
+        _ => {}
     }
-    
-    // Clean up downloaded tarball
-    std::fs::remove_file(&tar_gz_path)?;
-    println!("✅ Source code extracted to: {}", extracted_dir);
-    println!("🗑️  Cleaned up download archive");
+    manifest.save(Path::new(&extracted_dir))?;
+    guard.finish_extraction();
+
+    if options.compile {
+        let state = state::mark_extracted(&mut progress, version);
+
+        if state.phase >= state::Phase::Compiled && paths::php_binary(Path::new(&extracted_dir)).is_file() {
+            println!("⏭️  PHP {} was already compiled before the interruption", version);
+        } else {
+            if options.build.install_deps {
+                deps::install_build_dependencies()?;
+            }
+            manifest.configure_flags = build::compile(
+                Path::new(&extracted_dir),
+                options.build.build_dir.as_deref(),
+                options.build.build_timeout.map(std::time::Duration::from_secs),
+                options.build.jobs,
+                options.build.quiet_build,
+            )?;
+            state.advance(state::Phase::Compiled);
+        }
+        manifest.compiled = true;
+        manifest.save(Path::new(&extracted_dir))?;
+        state::clear(version);
+
+        stats::record_install();
+        let expose_versioned = options.build.expose_versioned || config.r#use.expose_versioned.unwrap_or(false);
+        finish_compiled(version, Path::new(&extracted_dir), expose_versioned, run_hook && !options.build.no_hooks);
+        return Ok(());
+    }
+
+    state::clear(version);
 
     // Provide compilation instructions
     print_compilation_instructions(&extracted_dir);
 
     println!("\n🎉 PHP {} source code ready for compilation!", version);
     println!("📝 After successful compilation, use: palawija use {}", version);
+    stats::record_install();
+    if run_hook && !options.build.no_hooks {
+        hooks::run_post_install(version, Path::new(&extracted_dir), false);
+    }
     
     Ok(())
 }
 
 /**
- * Prints detailed compilation instructions for PHP source code
- * 
- * # Arguments  
- * * `source_dir` - Path to the extracted PHP source directory
- */
-fn print_compilation_instructions(source_dir: &str) {
-    println!("\n⚙️  Compilation Instructions:");
-    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-    println!("📋 Step-by-step compilation process:");
-    println!("");
-    println!("1️⃣  Navigate to source directory:");
-    println!("   cd {}", source_dir);
-    println!("");
-    println!("2️⃣  Configure build (basic configuration):");
-    println!("   ./configure \\");
-    println!("     --prefix={}/bin \\", source_dir);
-    println!("     --with-config-file-path={}/etc \\", source_dir);
-    println!("     --enable-mbstring \\");
-    println!("     --enable-zip \\");
-    println!("     --with-curl \\");
-    println!("     --with-openssl \\");
-    println!("     --with-zlib \\");
-    println!("     --enable-soap");
-    println!("");
-    println!("3️⃣  Compile (this may take 10-30 minutes):");
-    println!("   make -j$(nproc)");
-    println!("");
-    println!("4️⃣  Install:");
-    println!("   make install");
-    println!("");
-    println!("📝 Note: You may need to install development packages:");
-    println!("   # Ubuntu/Debian:");
-    println!("   sudo apt-get install build-essential libxml2-dev libssl-dev libcurl4-openssl-dev");
-    println!("   # CentOS/RHEL/Fedora:");
-    println!("   sudo yum install gcc libxml2-devel openssl-devel curl-devel");
-}
-
-/**
- * Switches the system default PHP version by creating symbolic links
- * 
- * This function creates a symbolic link from /usr/local/bin/php to the
- * specified PHP version's binary, making it the system default.
- * 
+ * Compiles the already-extracted source tree of an installed version
+ *
+ * Uses the same configure flags as `install --compile`, so a version
+ * installed without `--compile` (or whose build failed) can be built later
+ * without downloading it again.
+ *
  * # Arguments
- * * `version` - The PHP version to switch to (must be compiled and installed)
- * 
+ * * `version` - The installed version to compile
+ * * `build` - The build flags given to `compile`
+ * * `force` - Compile even if the version already has a binary
+ * * `expose_versioned` - Refresh the php8.3-style links afterwards
+ *
  * # Returns
- * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
- * 
- * # Security Note
- * This function requires write permissions to /usr/local/bin/ which typically
- * requires sudo privileges or proper user permissions.
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error
  */
-fn use_php(version: &str) -> Result<(), Box<dyn std::error::Error>> {
-    println!("🎯 Target version: {}", version);
+fn compile_php(version: &str, build: &BuildOptions, force: bool, expose_versioned: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let version_dir = paths::version_dir(version)?;
+    if !version_dir.exists() {
+        return Err(PalawijaError::VersionNotInstalled { version: version.to_string() }.into());
+    }
+    if !version_dir.join("configure").exists() && !version_dir.join("buildconf").exists() {
+        return Err(format!(
+            "{} holds no PHP source tree to compile\n💡 Fetch the source again with: palawija reinstall {} --compile",
+            version_dir.display(),
+            version
+        ).into());
+    }
+    if paths::php_binary(&version_dir).is_file() && !force {
+        return Err(format!(
+            "PHP {} is already compiled\n💡 Re-run with --force to build it again, or use palawija reinstall {} --compile for a clean rebuild",
+            version,
+            version
+        ).into());
+    }
 
-    let home = env::var("HOME")?;
-    let install_dir = PathBuf::from(format!("{}/.palawija", home));
+    println!("🛠️  Compiling PHP {} from {}\n", version, version_dir.display());
+    if build.install_deps {
+        deps::install_build_dependencies()?;
+    }
+    let configure_flags = build::compile(
+        &version_dir,
+        build.build_dir.as_deref(),
+        build.build_timeout.map(std::time::Duration::from_secs),
+        build.jobs,
+        build.quiet_build,
+    )?;
 
-    // Construct path to the PHP binary
-    let php_bin_path = install_dir
-        .join(format!("php-{}", version))
-        .join("bin")
-        .join("php");
+    let mut manifest = Manifest::load(&version_dir).unwrap_or_else(|| Manifest::new(version, "", None));
+    manifest.configure_flags = configure_flags;
+    manifest.compiled = true;
+    manifest.save(&version_dir)?;
+    state::clear(version);
 
-    println!("🔍 Looking for PHP binary at: {}", php_bin_path.display());
+    finish_compiled(version, &version_dir, expose_versioned, !build.no_hooks);
+    Ok(())
+}
 
-    // Verify the PHP binary exists and is executable
-    if !php_bin_path.exists() {
-        println!("❌ PHP version {} not found!", version);
-        println!("📂 Expected location: {}", php_bin_path.display());
-        println!("");
-        println!("🔧 Possible solutions:");
-        println!("   1. Install the version: palawija install {}", version);
-        println!("   2. Check installed versions: palawija list");
-        println!("   3. Verify compilation completed successfully");
-        
-        return Err(format!("PHP binary not found for version {}", version).into());
+/**
+ * Reports a finished compile and updates everything that follows compiled versions
+ *
+ * # Arguments
+ * * `version` - The version just compiled
+ * * `version_dir` - Where it is installed
+ * * `expose_versioned` - Refresh the php8.3-style links as well
+ * * `run_hook` - Run the post-install hook
+ */
+fn finish_compiled(version: &str, version_dir: &Path, expose_versioned: bool, run_hook: bool) {
+    println!("\n🎉 PHP {} compiled and installed!", version);
+    for (alias, newest) in aliases::refresh() {
+        println!("🔗 Alias {} now points to PHP {}", alias, newest);
+    }
+    if expose_versioned && let Err(e) = relink() {
+        println!("⚠️  Could not update the versioned links: {}", e);
     }
+    println!("📝 Switch to it with: palawija use {}", version);
+    if run_hook {
+        hooks::run_post_install(version, version_dir, true);
+    }
+}
 
-    // Test if the binary is actually executable
-    match Command::new(&php_bin_path).arg("--version").output() {
-        Ok(output) => {
-            if output.status.success() {
-                let version_info = String::from_utf8_lossy(&output.stdout);
-                if let Some(first_line) = version_info.lines().next() {
-                    println!("✅ Found working PHP binary: {}", first_line.trim());
-                }
-            } else {
-                println!("⚠️  PHP binary exists but may not be working properly");
-            }
+/**
+ * Installs every version listed in a file or on stdin, one after another
+ *
+ * Each entry is resolved like a version given on the command line. Failures
+ * are collected and the remaining versions still installed, unless
+ * `fail_fast` is set; a report of every entry is printed at the end.
+ *
+ * # Arguments
+ * * `list` - Path of the version list, or "-" for stdin
+ * * `include_prerelease` - Passed through to `resolve_install_version`
+ * * `check_released` - Passed through to `resolve_install_version`
+ * * `fail_fast` - Stop at the first failure
+ * * `options` - Install options applied to every version
+ * * `config` - Effective configuration, passed to each install
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if the list couldn't be read or any install failed
+ */
+fn batch_install(list: &str, include_prerelease: bool, check_released: bool, fail_fast: bool, options: &InstallOptions, config: &config::Config) -> Result<(), Box<dyn std::error::Error>> {
+    let content = if list == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(list).map_err(|e| format!("Could not read version list {}: {}", list, e))?
+    };
+    let requested = parse_version_list(&content);
+    if requested.is_empty() {
+        return Err(format!("No versions listed in {}", if list == "-" { "stdin" } else { list }).into());
+    }
+
+    println!("📋 Installing {} version(s): {}\n", requested.len(), requested.join(", "));
+    let mut results: Vec<(&str, Option<Result<String, String>>)> = requested.iter().map(|v| (v.as_str(), None)).collect();
+    for (index, (input, result)) in results.iter_mut().enumerate() {
+        println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        println!("🚀 [{}/{}] Installing {}...\n", index + 1, requested.len(), input);
+        let outcome = resolve_install_version(input, include_prerelease, check_released)
+            .and_then(|version| report_outcome(&version, install_php(&version, options, config, true)).map(|_| version))
+            .map_err(|e| e.to_string());
+        if let Err(e) = &outcome {
+            eprintln!("❌ Installation of {} failed: {}", input, e);
         }
-        Err(_) => {
-            println!("⚠️  Could not verify PHP binary - proceeding anyway");
+        let failed = outcome.is_err();
+        *result = Some(outcome);
+        if failed && fail_fast {
+            break;
         }
     }
 
-    // Path for the global symlink
-    let link_path = Path::new("/usr/local/bin/php");
-    println!("🔗 Creating symlink at: {}", link_path.display());
-
-    // Remove existing symlink if present
-    if link_path.exists() {
-        println!("🗑️  Removing existing PHP symlink...");
-        match std::fs::remove_file(link_path) {
-            Ok(_) => println!("✅ Old symlink removed successfully"),
-            Err(e) => {
-                return Err(format!(
-                    "❌ Failed to remove existing symlink: {}\n💡 You may need sudo privileges: sudo palawija use {}", 
-                    e, version
-                ).into());
+    println!("\n📊 Batch install report:");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    let mut failures = 0;
+    for (input, result) in &results {
+        match result {
+            Some(Ok(version)) if version != input => println!("   ✅ {} (as {})", input, version),
+            Some(Ok(_)) => println!("   ✅ {}", input),
+            Some(Err(e)) => {
+                failures += 1;
+                println!("   ❌ {}: {}", input, e.lines().next().unwrap_or_default());
             }
+            None => println!("   ⏭️  {} (skipped after --fail-fast)", input),
         }
     }
-    
-    // Create new symlink
-    println!("🔗 Creating new symlink...");
-    match symlink(&php_bin_path, &link_path) {
-        Ok(_) => {
-            println!("✅ Symlink created successfully!");
-        }
+
+    if failures == 0 {
+        println!("\n🎉 All {} version(s) installed", results.len());
+        Ok(())
+    } else {
+        Err(format!("{} of {} install(s) failed", failures, results.len()).into())
+    }
+}
+
+/// Sends the `done` or `error` progress event that ends an install of `version`
+fn report_outcome(version: &str, result: Result<(), Box<dyn std::error::Error>>) -> Result<(), Box<dyn std::error::Error>> {
+    match &result {
+        Ok(()) => progress::emit(&progress::Event::Done { version }),
+        Err(e) => progress::emit(&progress::Event::Error { version, message: &e.to_string() }),
+    }
+    result
+}
+
+/// Versions in a list file: one per line, blank lines and `#` comments ignored
+fn parse_version_list(content: &str) -> Vec<String> {
+    content.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/**
+ * Parses the command line, with help epilogues that reflect the user's installs
+ *
+ * The epilogues scan the install root, so they're only built when help is
+ * asked for; `export` runs on every `cd` under direnv.
+ */
+fn parse_cli() -> Cli {
+    let args: Vec<std::ffi::OsString> = env::args_os().collect();
+    let mut command = Cli::command();
+    if help_requested(&args[1..]) {
+        command = command
+            .mut_subcommand("use", |use_cmd| use_cmd.after_help(help::use_help()))
+            .mut_subcommand("available", |available| available.after_help(help::available_help()));
+    }
+    Cli::from_arg_matches(&command.get_matches_from(args)).unwrap_or_else(|e| e.exit())
+}
+
+/// Whether the arguments ask for help (`-h`, `--help` or the `help` subcommand)
+fn help_requested(args: &[std::ffi::OsString]) -> bool {
+    args.iter().any(|arg| arg == "-h" || arg == "--help" || arg == "help")
+}
+
+/**
+ * Prints the completion script for a shell, or installs it
+ *
+ * # Arguments
+ * * `shell` - The shell to generate for
+ * * `install` - Write the script to the shell's completion directory
+ * * `force` - Overwrite a file there that palawija didn't write
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if the shell is unsupported or writing fails
+ */
+fn print_completions(shell: shell::Shell, install: bool, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut command = Cli::command();
+    command.build();
+    let script = completions::generate(&command, shell).map_err(|e| format!("❌ {}", e))?;
+    if !install {
+        print!("{}", script);
+        return Ok(());
+    }
+
+    let path = completions::install(&script, shell, force)?;
+    println!("✅ Installed completions to {}", path.display());
+    println!("💡 Load them in this shell with: {}", completions::reload_hint(shell, &path));
+    if let Some(line) = completions::fpath_hint(shell, &path) {
+        println!("⚠️  {} isn't on zsh's fpath in new shells; add this line to ~/.zshrc before compinit runs:", path.parent().unwrap_or(&path).display());
+        println!("   {}", line);
+    }
+    Ok(())
+}
+
+/// Exits with clap's usual missing-argument error for a subcommand's VERSION
+fn missing_version_error(subcommand: &str) -> ! {
+    let mut command = Cli::command();
+    command.build();
+    let subcommand = command.find_subcommand_mut(subcommand).expect("subcommand is defined");
+    subcommand
+        .error(ErrorKind::MissingRequiredArgument, "the following required arguments were not provided:\n  <VERSION>")
+        .exit()
+}
+
+/**
+ * Resolves the versions to benchmark and runs the benchmark
+ *
+ * # Arguments
+ * * `requested` - Versions as given; empty for every compiled version
+ * * `workload` - What each php runs
+ * * `iterations` - Timed runs per version
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if nothing can be benchmarked or a run fails
+ */
+fn bench_versions(requested: &[String], workload: &bench::Workload, iterations: usize) -> Result<(), Box<dyn std::error::Error>> {
+    if let bench::Workload::Script(script) = workload
+        && !script.is_file()
+    {
+        return Err(format!("❌ Script not found: {}", script.display()).into());
+    }
+    let install_root = paths::install_root()?;
+    let mut installed = if install_root.exists() { installs::scan(&install_root)?.versions } else { Vec::new() };
+    installed.sort_by(|a, b| releases::compare_versions(b, a));
+
+    let mut versions: Vec<String> = if requested.is_empty() {
+        installed.iter()
+            .filter(|version| paths::version_dir(version).is_ok_and(|dir| paths::php_binary(&dir).exists()))
+            .cloned()
+            .collect()
+    } else {
+        requested.iter().map(|version| exec::resolve_version(version, &installed)).collect()
+    };
+    versions.dedup();
+    if versions.is_empty() {
+        return Err("No compiled PHP versions installed\n💡 Install one with: palawija install latest --compile".into());
+    }
+    bench::run_bench(&versions, workload, iterations)
+}
+
+/**
+ * Lets the user pick a compiled installed version when `use` got none
+ *
+ * Only called on a terminal; scripts get the missing-argument error instead.
+ *
+ * # Returns
+ * * `Result<String, Box<dyn std::error::Error>>` - The chosen version
+ */
+fn pick_installed_version() -> Result<String, Box<dyn std::error::Error>> {
+    let install_root = paths::install_root()?;
+    let mut versions = if install_root.exists() { installs::scan(&install_root)?.versions } else { Vec::new() };
+    versions.retain(|version| paths::version_dir(version).is_ok_and(|dir| paths::php_binary(&dir).exists()));
+    versions.sort_by(|a, b| releases::compare_versions(b, a));
+    if versions.is_empty() {
+        return Err("No compiled PHP versions installed\n💡 Install one with: palawija install latest --compile".into());
+    }
+
+    let active = paths::active_version();
+    let labels: Vec<String> = versions.iter()
+        .map(|version| if active.as_ref() == Some(version) { format!("{} ⭐ (active)", version) } else { version.clone() })
+        .collect();
+    let index = prompt::pick("Which installed version should become the default?", &labels)
+        .ok_or("❌ No version selected")?;
+    Ok(versions.swap_remove(index))
+}
+
+/**
+ * Picks the installed version for the project in the current directory
+ *
+ * Reads `require.php` from `./composer.json` and returns the newest compiled
+ * install satisfying it. When none does, the error names the newest release
+ * that would, if the release list can be fetched.
+ *
+ * # Returns
+ * * `Result<String, Box<dyn std::error::Error>>` - The version to switch to
+ */
+fn composer_version() -> Result<String, Box<dyn std::error::Error>> {
+    let requirement = composer::php_requirement(&env::current_dir()?).map_err(|e| format!("❌ {}", e))?;
+    let constraint = composer::Constraint::parse(&requirement)
+        .map_err(|e| format!("❌ Unsupported require.php constraint '{}': {}", requirement, e))?;
+    println!("📄 composer.json requires PHP {}", requirement);
+
+    let install_root = paths::install_root()?;
+    let mut installed = if install_root.exists() { installs::scan(&install_root)?.versions } else { Vec::new() };
+    installed.retain(|version| paths::php_binary(&install_root.join(format!("php-{}", version))).is_file());
+    if let Some(version) = constraint.newest(&installed) {
+        println!("🎯 Newest installed version satisfying it: {}", version);
+        return Ok(version.clone());
+    }
+
+    let hint = match releases::fetch_available_versions(releases::DEFAULT_FETCH_TIMEOUT) {
+        Ok(available) => match constraint.newest(&available.versions) {
+            Some(version) => format!("💡 Install one with: palawija install {} --compile", version),
+            None => "💡 No PHP release satisfies it either - check the constraint".to_string(),
+        },
+        Err(_) => "💡 Find a matching release with: palawija available".to_string(),
+    };
+    Err(format!("❌ No compiled install satisfies PHP {}\n{}", requirement, hint).into())
+}
+
+/// How many branches `install` offers when no version was given
+const RECENT_BRANCHES: usize = 8;
+
+/**
+ * Lets the user pick a recent release when `install` got no version
+ *
+ * Offers the newest release of each of the most recent branches, skipping
+ * prereleases unless `include_prerelease` is set.
+ *
+ * # Returns
+ * * `Result<String, Box<dyn std::error::Error>>` - The chosen version
+ */
+fn pick_available_version(include_prerelease: bool) -> Result<String, Box<dyn std::error::Error>> {
+    let releases::ReleaseList { versions, .. } = releases::fetch_available_versions(releases::DEFAULT_FETCH_TIMEOUT)?;
+    let mut choices = install_choices(&versions, include_prerelease);
+    if choices.is_empty() {
+        return Err("No releases found to choose from\n💡 Pass a version: palawija install <version>".into());
+    }
+
+    let index = prompt::pick("Which PHP version should be installed?", &choices)
+        .ok_or("❌ No version selected")?;
+    Ok(choices.swap_remove(index))
+}
+
+/// The newest release of each of the `RECENT_BRANCHES` newest branches, as offered by the install picker
+fn install_choices(versions: &[String], include_prerelease: bool) -> Vec<String> {
+    releases::group_by_branch(versions)
+        .into_iter()
+        .filter_map(|(_, members)| members.into_iter().find(|v| include_prerelease || !releases::is_prerelease(v)).cloned())
+        .take(RECENT_BRANCHES)
+        .collect()
+}
+
+/**
+ * Turns the version given to `install` into the release to install
+ *
+ * "latest" and partial versions like "8.3" resolve to the newest matching
+ * release, skipping prereleases unless `include_prerelease` is set. A full
+ * version is used as given, with a warning if it's a prerelease, after
+ * checking that it was released when `check_released` is set (off for
+ * `--force` and `--from-source`). Either way the release list is fetched at
+ * most once.
+ *
+ * # Returns
+ * * `Result<String, Box<dyn std::error::Error>>` - The full version to install
+ */
+fn resolve_install_version(input: &str, include_prerelease: bool, check_released: bool) -> Result<String, Box<dyn std::error::Error>> {
+    if !releases::is_version_series(input) {
+        if releases::is_prerelease(input) {
+            println!("⚠️  PHP {} is a prerelease - not recommended for production\n", input);
+        }
+        if check_released {
+            check_release_exists(releases::release_of(input))?;
+        }
+        return Ok(input.to_string());
+    }
+
+    let releases::ReleaseList { versions, .. } = releases::fetch_available_versions(releases::DEFAULT_FETCH_TIMEOUT)?;
+    match releases::newest_in_series(&versions, input, include_prerelease) {
+        Some(version) => {
+            println!("🔎 Resolved {} to PHP {}\n", input, version);
+            Ok(version.clone())
+        }
+        None if releases::newest_in_series(&versions, input, true).is_some() => Err(format!(
+            "Only prereleases match '{}'\n💡 Pass --include-prerelease to install one anyway",
+            input
+        ).into()),
+        None => Err(format!("No PHP release matches '{}'\n💡 Browse versions with: palawija available", input).into()),
+    }
+}
+
+/**
+ * Confirms that a version was released before anything is downloaded
+ *
+ * Prereleases are published elsewhere and aren't in the release list, so
+ * they aren't checked. If the list can't be fetched the check is skipped
+ * with a warning, and the download reports a missing version instead.
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error naming the closest released version
+ */
+fn check_release_exists(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if releases::is_prerelease(version) {
+        return Ok(());
+    }
+    let versions = match releases::fetch_available_versions(releases::DEFAULT_FETCH_TIMEOUT) {
+        Ok(list) => list.versions,
+        Err(e) => {
+            println!("⚠️  Could not check that PHP {} exists ({}); trying the download anyway\n", version, e);
+            return Ok(());
+        }
+    };
+    if versions.iter().any(|released| released == version) {
+        return Ok(());
+    }
+
+    Err(PalawijaError::VersionNotReleased {
+        version: version.to_string(),
+        suggestion: releases::closest_version(&versions, version).cloned(),
+    }.into())
+}
+
+/**
+ * Downloads, verifies and extracts a PHP source tarball into any directory
+ *
+ * Used by `install --extract-to`: unlike `install_php`, nothing is registered
+ * as a managed version - no manifest, no cleanup guard, no symlinks - so the
+ * tree can live in e.g. a Docker build context.
+ *
+ * # Arguments
+ * * `version` - PHP version string (e.g., "8.3.0")
+ * * `dir` - Target directory; must be empty or not exist yet
+ * * `options` - Install options (`--from-source`, `--checksum`, `--limit-rate`)
+ * * `config` - Effective configuration, for settings the flags leave unset
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn extract_source_to(version: &str, dir: &Path, options: &InstallOptions, config: &config::Config) -> Result<(), Box<dyn std::error::Error>> {
+    println!("🎯 Target PHP version: {}", version);
+    if !version.contains('.') || !version.chars().any(|c| c.is_numeric()) {
+        return Err("❌ Invalid version format. Use format like '8.3.0' or '8.2.15'".into());
+    }
+    if dir.read_dir().is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(format!("❌ {} is not empty\n💡 Choose an empty or new directory", dir.display()).into());
+    }
+
+    let tarball = match &options.from_source {
+        Some(path) if path.is_dir() => return Err("❌ --extract-to needs a tarball, not a source directory".into()),
+        Some(path) => download::local_tarball(path, options.checksum.as_deref())?,
+        None => {
+            let limit_rate = options.limit_rate.or(config.download.limit_rate);
+            download::fetch_tarball(releases::release_of(version), options.format, limit_rate, options.download_timeout)?
+        }
+    };
+    extract_tarball(&tarball.path, dir)?;
+
+    let source_dir = std::fs::canonicalize(dir)?;
+    print_compilation_instructions(&source_dir.to_string_lossy());
+    println!("\n🎉 PHP {} source code extracted to {}", version, source_dir.display());
+    println!("📝 This tree isn't managed by palawija; 'palawija use' won't see it");
+    Ok(())
+}
+
+/**
+ * Runs `./configure` on a version's source without installing anything
+ *
+ * The source is extracted into a scratch directory under `~/.palawija`,
+ * configured with the flags (and prefix) a real install would use, and
+ * removed again whatever the outcome.
+ *
+ * # Arguments
+ * * `version` - The resolved PHP version to check
+ * * `options` - Install options; the source and dependency options apply
+ * * `config` - Effective configuration, for the download settings
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if the source can't be prepared or configure fails
+ */
+fn check_install_config(version: &str, options: &InstallOptions, config: &config::Config) -> Result<(), Box<dyn std::error::Error>> {
+    let install_root = paths::install_root()?;
+    let scratch = install_root.join(format!(".check-php-{}-{}", version, std::process::id()));
+    let guard = cleanup::InstallGuard::register(&scratch);
+
+    let tarball = match &options.from_source {
+        Some(path) if path.is_dir() => return Err("❌ --check-config needs a tarball, not a source directory".into()),
+        Some(path) => download::local_tarball(path, options.checksum.as_deref())?,
+        None => {
+            let limit_rate = options.limit_rate.or(config.download.limit_rate);
+            download::fetch_tarball(releases::release_of(version), options.format, limit_rate, options.download_timeout)?
+        }
+    };
+    if options.build.install_deps {
+        deps::install_build_dependencies()?;
+    }
+
+    let result = extract_tarball(&tarball.path, &scratch)
+        .and_then(|()| build::check_configure(&scratch, &paths::version_dir(version)?));
+    let _ = std::fs::remove_dir_all(&scratch);
+    drop(guard);
+
+    let flags = result?;
+    println!("\n✅ ./configure succeeded for PHP {} with:", version);
+    for flag in &flags {
+        println!("   {}", flag);
+    }
+    println!("📝 Build it with: palawija install {} --compile", version);
+    Ok(())
+}
+
+/// Files one of which sits at the root of every PHP source tree (release tarballs and php-src snapshots)
+const SOURCE_MARKERS: &[&str] = &["main/php_version.h", "configure", "buildconf"];
+
+/**
+ * Extracts a PHP source tarball into `dir`, dropping its top-level directories
+ *
+ * The compression is taken from the file's magic bytes, so a mislabeled
+ * archive still extracts. Official tarballs have a single `php-<version>/`
+ * directory, but repacked ones may have none or several levels, so the
+ * directories every entry shares are found by listing the archive first.
+ * The result must have a PHP source marker at its root.
+ *
+ * If anything fails and `dir` didn't exist before, it is removed again, so
+ * a corrupt or truncated archive leaves no half-extracted tree behind to be
+ * mistaken for a source-only install or to block a retry.
+ */
+fn extract_tarball(tarball: &Path, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let created = !dir.exists();
+    let result = unpack_tarball(tarball, dir);
+    if result.is_err() && created && dir.exists() {
+        println!("🧹 Removing the partially extracted {}...", dir.display());
+        if let Err(e) = std::fs::remove_dir_all(dir) {
+            println!("⚠️  Could not remove {}: {}", dir.display(), e);
+        }
+    }
+    result
+}
+
+fn unpack_tarball(tarball: &Path, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let format = download::ArchiveFormat::detect(tarball)
+        .ok_or_else(|| format!("❌ {} is neither a gzip nor an xz archive", tarball.display()))?;
+
+    let listing = Command::new("tar").arg("-t").arg(format.tar_flag()).arg("-f").arg(tarball).output()?;
+    if !listing.status.success() {
+        return Err(format!("❌ Could not list the contents of {}", tarball.display()).into());
+    }
+    let listing = String::from_utf8_lossy(&listing.stdout);
+    let strip = shared_prefix_depth(listing.lines());
+
+    println!("📦 Extracting source code ({})...", format.extension());
+    std::fs::create_dir_all(dir)?;
+
+    let mut tar = Command::new("tar");
+    tar.arg("-x")
+        .arg(format.tar_flag())
+        .arg("-f")
+        .arg(tarball)
+        .arg("-C")
+        .arg(dir)
+        .arg(format!("--strip-components={}", strip));  // Remove the shared top-level directories
+    let extract_result = if progress::is_json() {
+        // -v lists each entry on stdout as it is extracted
+        let mut child = tar.arg("-v").stdout(Stdio::piped()).spawn()?;
+        let listing = child.stdout.take().expect("tar's stdout is piped");
+        for file in BufReader::new(listing).lines().map_while(Result::ok) {
+            progress::emit(&progress::Event::Extract { file: &file });
+        }
+        child.wait()?
+    } else {
+        tar.status()?
+    };
+
+    if !extract_result.success() {
+        return Err("❌ Failed to extract PHP source code".into());
+    }
+
+    if !SOURCE_MARKERS.iter().any(|marker| dir.join(marker).is_file()) {
+        return Err(format!(
+            "❌ {} doesn't look like a PHP source archive: none of {} ended up in {}\n💡 Repack it so the PHP sources are at the top (or in a single top-level directory), or use --from-source with the unpacked directory",
+            tarball.display(),
+            SOURCE_MARKERS.join(", "),
+            dir.display()
+        ).into());
+    }
+    Ok(())
+}
+
+/**
+ * How many leading path components every entry of an archive listing shares
+ *
+ * Files count up to their parent directory, so a file is never stripped
+ * away. Directory entries (ending in `/`) that are ancestors of that shared
+ * directory don't limit it, since stripping them leaves nothing behind.
+ * `./` counts as a component, as it does for `tar --strip-components`.
+ */
+fn shared_prefix_depth<'a>(entries: impl IntoIterator<Item = &'a str>) -> usize {
+    fn common<'b>(a: Vec<&'b str>, b: &[&'b str]) -> Vec<&'b str> {
+        a.into_iter().zip(b).take_while(|(x, y)| x == *y).map(|(x, _)| x).collect()
+    }
+
+    let (dirs, files): (Vec<&str>, Vec<&str>) = entries.into_iter()
+        .filter(|entry| !entry.is_empty())
+        .partition(|entry| entry.ends_with('/'));
+    let components = |entry: &'a str| -> Vec<&'a str> { entry.split('/').filter(|component| !component.is_empty()).collect() };
+
+    let mut shared: Option<Vec<&str>> = None;
+    for file in files {
+        let mut parent = components(file);
+        parent.pop();
+        shared = Some(match shared {
+            None => parent,
+            Some(prefix) => common(prefix, &parent),
+        });
+    }
+    for dir in dirs {
+        let dir = components(dir);
+        shared = Some(match shared {
+            None => dir,
+            Some(prefix) if prefix.starts_with(&dir) => prefix,
+            Some(prefix) => common(prefix, &dir),
+        });
+    }
+    shared.map_or(0, |prefix| prefix.len())
+}
+
+/**
+ * Rebuilds an installed PHP version from scratch
+ * 
+ * Removes ~/.palawija/php-<version>/ and installs it again, reusing the cached
+ * tarball when it still passes checksum verification. The version's php.ini
+ * and manifest are backed up beforehand and restored afterwards, so local
+ * configuration survives the rebuild. Unless the source is local, a version
+ * php.net never released is refused before anything is removed.
+ * 
+ * # Arguments
+ * * `version` - PHP version string (e.g., "8.3.0")
+ * * `options` - Install options applied to the fresh install
+ * * `force` - Allow reinstalling the currently active version
+ * * `config` - Effective configuration, passed to the fresh install
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn reinstall_php(version: &str, options: &InstallOptions, force: bool, config: &config::Config) -> Result<(), Box<dyn std::error::Error>> {
+    let version_dir = paths::version_dir(version)?;
+
+    if !version_dir.exists() {
+        return Err(PalawijaError::VersionNotInstalled { version: version.to_string() }.into());
+    }
+
+    let is_active = paths::active_php_binary()
+        .is_some_and(|target| target == paths::php_binary(&version_dir));
+    if is_active && !force {
+        return Err(format!(
+            "PHP {} is the active version - reinstalling would break 'php' while it rebuilds\n💡 Re-run with --force to reinstall it anyway",
+            version
+        ).into());
+    }
+
+    if options.from_source.is_none() {
+        check_release_exists(releases::release_of(version))?;
+    }
+
+    // Back up per-version files that must survive the rebuild
+    let backup_dir = paths::install_root()?.join(format!(".reinstall-php-{}", version));
+    std::fs::create_dir_all(&backup_dir)?;
+    let ini_path = paths::php_ini(&version_dir);
+    let ini_backup = backup_dir.join("php.ini");
+    let old_manifest = Manifest::load(&version_dir);
+
+    if ini_path.exists() {
+        std::fs::copy(&ini_path, &ini_backup)?;
+        println!("💾 Backed up php.ini to {}", ini_backup.display());
+    }
+    if old_manifest.is_some() {
+        std::fs::copy(Manifest::path(&version_dir), backup_dir.join(manifest::MANIFEST_FILE))?;
+        println!("💾 Backed up manifest");
+    }
+
+    println!("🗑️  Removing {}...", version_dir.display());
+    std::fs::remove_dir_all(&version_dir)?;
+
+    // The hook runs below, once php.ini and the manifest are restored
+    if let Err(e) = install_php(version, options, config, false) {
+        println!("⚠️  Backups were kept in {}", backup_dir.display());
+        return Err(e);
+    }
+
+    // Restore the backups into the fresh tree
+    if ini_backup.exists() {
+        if let Some(parent) = ini_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&ini_backup, &ini_path)?;
+        println!("♻️  Restored php.ini");
+    }
+    if let Some(mut manifest) = old_manifest {
+        if let Some(fresh) = Manifest::load(&version_dir) {
+            manifest.merge_install_details(&fresh);
+        }
+        manifest.save(&version_dir)?;
+        println!("♻️  Restored manifest");
+    }
+    std::fs::remove_dir_all(&backup_dir)?;
+
+    println!("\n✅ PHP {} reinstalled from scratch", version);
+    if !options.build.no_hooks {
+        hooks::run_post_install(version, &version_dir, options.compile);
+    }
+    Ok(())
+}
+
+/**
+ * Prints detailed compilation instructions for PHP source code
+ * 
+ * # Arguments  
+ * * `source_dir` - Path to the extracted PHP source directory
+ */
+fn print_compilation_instructions(source_dir: &str) {
+    println!("\n⚙️  Compilation Instructions:");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("📋 Step-by-step compilation process:");
+    println!();
+    println!("1️⃣  Navigate to source directory:");
+    println!("   cd {}", source_dir);
+    println!();
+    if !Path::new(source_dir).join("configure").exists() {
+        println!("   # Git checkouts have no configure script yet; generate it first:");
+        println!("   ./buildconf --force");
+        println!();
+    }
+    println!("2️⃣  Configure build (basic configuration):");
+    println!("   ./configure \\");
+    let flags = build::configure_flags(source_dir);
+    for (index, flag) in flags.iter().enumerate() {
+        let continuation = if index + 1 < flags.len() { " \\" } else { "" };
+        println!("     {}{}", flag, continuation);
+    }
+    println!();
+    println!("3️⃣  Compile (this may take 10-30 minutes):");
+    println!("   make -j$(nproc)");
+    println!();
+    println!("4️⃣  Install:");
+    println!("   make install");
+    println!();
+    println!("📝 Note: You may need to install development packages:");
+    println!("   # Ubuntu/Debian:");
+    println!("   sudo apt-get install build-essential libxml2-dev libssl-dev libcurl4-openssl-dev");
+    println!("   # CentOS/RHEL/Fedora:");
+    println!("   sudo yum install gcc libxml2-devel openssl-devel curl-devel");
+    if paths::install_root().is_ok_and(|root| Path::new(source_dir).starts_with(root)) {
+        println!();
+        println!("🤖 Or let palawija build it: palawija compile <version>");
+    }
+}
+
+/**
+ * Switches the system default PHP version by creating symbolic links
+ * 
+ * This function creates a symbolic link `php` in the link directory
+ * (`/usr/local/bin` unless `--link-dir` or `[use] link_dir` say otherwise)
+ * to the specified PHP version's binary, making it the system default.
+ * 
+ * An existing php that palawija doesn't manage (a regular file, or a symlink
+ * pointing outside ~/.palawija) is only replaced after confirmation or with
+ * `force`, and is backed up to ~/.palawija/backups/ first.
+ * 
+ * A partial version (e.g. "8.2") is resolved against the installed versions
+ * first; see `resolve_installed_version`.
+ * 
+ * The binary is only run once, through the new link after the switch, so
+ * each switch costs a single `php --version`; `verify = false` skips even
+ * that for scripted switches.
+ * 
+ * # Arguments
+ * * `version` - The PHP version to switch to (must be compiled and installed)
+ * * `force` - Replace a non-palawija php without asking
+ * * `verify` - Run the linked php afterwards to confirm the switch
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ * 
+ * # Security Note
+ * This function requires write permissions to the link directory, which for
+ * `/usr/local/bin` typically requires sudo privileges.
+ */
+fn use_php(version: &str, force: bool, verify: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let resolved = resolve_installed_version(version)?;
+    let version = resolved.as_str();
+    println!("🎯 Target version: {}", version);
+    let previous_version = paths::linked_version();
+
+    let install_dir = paths::install_root()?;
+
+    // Construct path to the PHP binary
+    let php_bin_path = install_dir
+        .join(format!("php-{}", version))
+        .join("bin")
+        .join("php");
+
+    println!("🔍 Looking for PHP binary at: {}", php_bin_path.display());
+
+    // Verify the PHP binary exists and is executable
+    if !php_bin_path.exists() {
+        println!("❌ PHP version {} not found!", version);
+        println!("📂 Expected location: {}", php_bin_path.display());
+        println!();
+        println!("🔧 Possible solutions:");
+        println!("   1. Install the version: palawija install {}", version);
+        println!("   2. Check installed versions: palawija list");
+        println!("   3. Verify compilation completed successfully");
+        
+        return Err(PalawijaError::missing_binary(version, &install_dir.join(format!("php-{}", version))).into());
+    }
+
+    // Refuse binaries built for another architecture before they become the default
+    elf::check_compatible(&php_bin_path)?;
+
+    // Path for the global symlink; make sure its directory is usable before touching anything
+    let link_dir = paths::link_dir();
+    if let Err(e) = paths::check_link_dir(&link_dir) {
+        return Err(format!(
+            "❌ Can't create the php symlink: {}\n💡 Use sudo (sudo palawija use {}) or pick another directory with --link-dir",
+            e, version
+        ).into());
+    }
+    let link_path = link_dir.join(paths::link_name());
+    let link_path = link_path.as_path();
+    println!("🔗 Creating symlink at: {}", link_path.display());
+
+    // Check what is currently at the link path (symlink_metadata so dangling links count too)
+    if let Ok(metadata) = std::fs::symlink_metadata(link_path) {
+        let foreign = if metadata.file_type().is_symlink() {
+            let target = std::fs::read_link(link_path)?;
+            let target = link_path.parent().map(|dir| dir.join(&target)).unwrap_or(target);
+            (!target.starts_with(&install_dir)).then(|| format!("a symlink to {}", target.display()))
+        } else {
+            Some("a regular file, probably installed by your package manager".to_string())
+        };
+
+        if let Some(description) = foreign {
+            println!("⚠️  {} is {}, not a palawija-managed PHP", link_path.display(), description);
+            if !force && !prompt::confirm("Replace it? The original will be backed up first") {
+                return Err("❌ Left the existing php in place\n💡 Re-run with --force to replace it without asking".into());
+            }
+            let backup = backup_existing_php(link_path)?;
+            println!("💾 Original saved to {}", backup.display());
+            println!("💡 Restore it with: sudo cp -a {} {}", backup.display(), link_path.display());
+        }
+    }
+    
+    // php-fpm switches together with php, so the two never disagree on the version
+    let fpm_bin_path = install_dir.join(format!("php-{}", version)).join("sbin").join("php-fpm");
+    let fpm_link_path = link_dir.join("php-fpm");
+    let fpm_link_ours = std::fs::read_link(&fpm_link_path).is_ok_and(|target| target.starts_with(&install_dir));
+    let fpm_link_free = fpm_link_ours || std::fs::symlink_metadata(&fpm_link_path).is_err();
+    if fpm_bin_path.is_file() && !fpm_link_free {
+        println!("⚠️  Not linking php-fpm: {} is not managed by palawija", fpm_link_path.display());
+    }
+
+    // Swap the symlink in one step so php never disappears mid-switch
+    println!("🔗 Switching symlink...");
+    let switched = if fpm_bin_path.is_file() && fpm_link_free {
+        link::replace_together(&[(php_bin_path.clone(), link_path.to_path_buf()), (fpm_bin_path.clone(), fpm_link_path.clone())])
+            .map(|_| link::Replacement::Atomic)
+    } else {
+        link::replace_symlink(&php_bin_path, link_path)
+    };
+    match switched {
+        Ok(link::Replacement::Atomic) if fpm_bin_path.is_file() && fpm_link_free => {
+            println!("✅ php and php-fpm switched together!");
+        }
+        Ok(link::Replacement::Atomic) => {
+            println!("✅ Symlink switched atomically!");
+        }
+        Ok(link::Replacement::RemovedFirst) => {
+            println!("✅ Symlink created (atomic replacement wasn't permitted, so the old link was removed first)");
+        }
+        Err(e) => {
+            return Err(format!(
+                "❌ Failed to create symlink: {}\n💡 You may need sudo privileges: sudo palawija use {}", 
+                e, version
+            ).into());
+        }
+    }
+    if !fpm_bin_path.is_file() && fpm_link_ours {
+        match std::fs::remove_file(&fpm_link_path) {
+            Ok(()) => println!("🧹 Removed the php-fpm link: PHP {} was built without FPM", version),
+            Err(e) => println!("⚠️  Could not remove the stale php-fpm link: {}", e),
+        }
+    }
+
+    // Link the rest of the toolchain (phpize, php-config, phpdbg, ...) so
+    // they always match the active php
+    let bin_dir = php_bin_path.parent().unwrap_or(&php_bin_path);
+    match link::link_toolchain(bin_dir, &link_dir, &install_dir) {
+        Ok(tools) => {
+            if !tools.linked.is_empty() {
+                println!("🔗 Linked {}", tools.linked.join(", "));
+            }
+            if !tools.removed.is_empty() {
+                println!("🧹 Removed stale links: {}", tools.removed.join(", "));
+            }
+            for tool in &tools.skipped {
+                println!("⚠️  Not linking {}: {} is not managed by palawija", tool, link_dir.join(tool).display());
+            }
+        }
+        Err(e) => println!("⚠️  Could not link the rest of the toolchain: {}", e),
+    }
+    
+    // Verify the switch by running the freshly-linked binary directly, so a
+    // different php earlier in PATH can't produce a misleading success message
+    if verify {
+        verify_switch(version, link_path, &php_bin_path, &install_dir);
+    } else {
+        println!("⏭️  Skipped verifying the switch (--no-verify)");
+    }
+
+    // Make sure the linked php is the one the shell will actually find
+    let path_hits = find_in_path(paths::link_name());
+    match path_hits.first() {
+        _ if !dir_in_path(&link_dir) => print_missing_path_warning(&link_dir),
+        Some(first) if first.as_path() == link_path => {}
+        Some(first) => {
+            println!("\n⚠️  PATH precedence: '{}' will be found before {}", first.display(), link_path.display());
+            println!("📋 {} binaries in PATH order:", paths::link_name());
+            for (index, hit) in path_hits.iter().enumerate() {
+                let marker = if hit.as_path() == link_path { " ⬅️  palawija" } else { "" };
+                println!("   {}. {}{}", index + 1, hit.display(), marker);
+            }
+            println!("💡 Move {} before {} in your PATH",
+                link_dir.display(),
+                first.parent().unwrap_or(first).display());
+        }
+        None => {}
+    }
+    
+    // Keep the persistent pin in sync with the symlink
+    match pin::write_pin(version) {
+        Ok(()) => println!("📌 Pinned {} as the default version", version),
+        Err(e) => println!("⚠️  Could not update the pinned default: {}", e),
+    }
+    if let Err(e) = history::record(previous_version.as_deref(), version) {
+        println!("⚠️  Could not record the switch in the history: {}", e);
+    }
+    stats::record_switch(version);
+
+    println!("\n✅ PHP version {} is now your system default! 🚀", version);
+    println!("💡 Try running: {} --version", paths::link_name());
+    println!("💡 Location: {}", link_path.display());
+    hooks::run_post_use(previous_version.as_deref(), version);
+    
+    Ok(())
+}
+
+/**
+ * Copies a php that palawija is about to replace into ~/.palawija/backups/
+ * 
+ * Symlinks are saved as symlinks with the same target, regular files as copies.
+ * 
+ * # Returns
+ * * `Result<PathBuf, Box<dyn std::error::Error>>` - Location of the backup
+ */
+fn backup_existing_php(link_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let backups_dir = paths::backups_dir()?;
+    std::fs::create_dir_all(&backups_dir)?;
+
+    let today = date::Date::today();
+    let backup = (0..)
+        .map(|n| match n {
+            0 => backups_dir.join(format!("php-{}", today)),
+            n => backups_dir.join(format!("php-{}.{}", today, n)),
+        })
+        .find(|candidate| std::fs::symlink_metadata(candidate).is_err())
+        .ok_or("❌ Could not pick a backup file name")?;
+
+    if std::fs::symlink_metadata(link_path)?.file_type().is_symlink() {
+        symlink(std::fs::read_link(link_path)?, &backup)?;
+    } else {
+        std::fs::copy(link_path, &backup)?;
+    }
+    Ok(backup)
+}
+
+/**
+ * Warns that the symlink's directory isn't on PATH, so `php` won't be found
+ * 
+ * # Arguments
+ * * `link_dir` - Directory holding the global php symlink
+ */
+fn print_missing_path_warning(link_dir: &Path) {
+    let shell = shell::Shell::detect();
+    println!();
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("🚨 {} is not in your PATH!", link_dir.display());
+    println!("   Your shell will report 'php: command not found' until it is.");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    println!("💡 Add this line to ~/{}:", shell.rc_file());
+    println!("   {}", shell.path_prepend_line(link_dir));
+    println!("💡 Then open a new terminal or run: source ~/{}", shell.rc_file());
+}
+
+/**
+ * Runs the freshly linked php once and reports whether it is the requested version
+ *
+ * # Arguments
+ * * `version` - The version that was switched to
+ * * `link_path` - The global php symlink
+ * * `php_bin_path` - The binary it points to
+ * * `install_dir` - The palawija install root
+ */
+fn verify_switch(version: &str, link_path: &Path, php_bin_path: &Path, install_dir: &Path) {
+    println!("🧪 Verifying the switch...");
+    match probe::php_version(link_path) {
+        probe::Probe::Output(version_output) => {
+            match parse_php_version(&version_output) {
+                Some(reported) if reported == version => {
+                    println!("🎊 Success! Linked PHP reports version: {}", reported);
+                }
+                Some(reported) if git::git_ref(version).is_some() => {
+                    let commit = Manifest::load(&install_dir.join(format!("php-{}", version)))
+                        .and_then(|m| m.git_commit)
+                        .map(|commit| format!(" (commit {})", git::short_commit(&commit)))
+                        .unwrap_or_default();
+                    println!("🎊 Success! Linked PHP reports version: {}{}", reported, commit);
+                }
+                Some(reported) => {
+                    println!("⚠️  Linked PHP reports version {} but {} was requested", reported, version);
+                    println!("💡 The build in {} may be stale - consider recompiling", php_bin_path.display());
+                }
+                None => {
+                    println!("⚠️  Could not parse version from linked PHP output");
+                }
+            }
+        }
+        probe::Probe::Failed(status) => {
+            println!("⚠️  Linked PHP exited with {} - the build may be broken", status);
+        }
+        probe::Probe::TimedOut => {
+            println!("⚠️  {}", probe::TIMEOUT_MESSAGE);
+        }
+        probe::Probe::SpawnFailed(e) => {
+            println!("⚠️  Could not run the linked PHP ({}), but the symlink was created", e);
+        }
+    }
+}
+
+/**
+ * Runs `use` for a version and points the requested aliases at it
+ *
+ * Alias names are checked before anything is switched. A version that is
+ * already active is left linked as it is (only the pin is brought in line),
+ * so repeating `use` in provisioning scripts touches nothing.
+ *
+ * # Arguments
+ * * `version` - The version to switch to, possibly partial
+ * * `force` - Replace a php or alias file that palawija doesn't manage, and
+ *   relink even when the version is already active
+ * * `verify` - Passed through to `use_php`
+ * * `alias_names` - Rolling aliases (e.g. php8) to point at the version
+ * * `alias_only` - Leave the php link alone and only set the aliases
+ * * `expose_versioned` - Reconcile the php8.3-style links afterwards
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn switch_php(version: &str, force: bool, verify: bool, alias_names: &[String], alias_only: bool, expose_versioned: bool) -> Result<(), Box<dyn std::error::Error>> {
+    for name in alias_names {
+        aliases::series(name)?;
+    }
+    let version = resolve_installed_version(version)?;
+    // The shims of an activated environment don't count: the global link is what `use` changes
+    let already_active = paths::active_version().as_deref() == Some(version.as_str())
+        && paths::linked_version().as_deref() == Some(version.as_str());
+    if !alias_only && already_active && !force {
+        println!("✅ PHP {} is already active", version);
+        if pin::read_pin().as_deref() != Some(version.as_str()) {
+            pin::write_pin(&version)?;
+            println!("📌 Pinned {} as the default version", version);
+        }
+        println!("💡 Pass --force to relink it anyway");
+    } else if !alias_only {
+        use_php(&version, force, verify)?;
+    }
+
+    for name in alias_names {
+        let link = aliases::set(name, &version, force)?;
+        println!("🔗 Alias {} now points to PHP {} (installs of newer {} patches move it along)", link.display(), version, aliases::series(name)?);
+    }
+    if expose_versioned {
+        relink()?;
+    }
+    Ok(())
+}
+
+/**
+ * Reconciles the php8.3-style links with the compiled installs and reports the changes
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if a link couldn't be written
+ */
+fn relink() -> Result<(), Box<dyn std::error::Error>> {
+    println!("\n🔗 Reconciling versioned links in {}...", paths::link_dir().display());
+    let result = aliases::relink()?;
+    for (name, version) in &result.linked {
+        println!("   {} -> PHP {}", name, version);
+    }
+    for name in &result.removed {
+        println!("   🧹 Removed {} (no compiled install of its branch is left)", name);
+    }
+    for path in &result.skipped {
+        println!("   ⚠️  Skipped {}: it exists and palawija doesn't manage it", path.display());
+    }
+    if result.linked.is_empty() && result.removed.is_empty() && result.skipped.is_empty() {
+        println!("   No compiled versions to link");
+    }
+    Ok(())
+}
+
+/**
+ * Resolves a possibly partial version against the installed versions
+ * 
+ * One match is used directly. Several matches are offered in a picker, or
+ * listed in the error when stdin isn't interactive. Without any match the
+ * input is returned unchanged, so the caller reports it as not installed.
+ * 
+ * # Returns
+ * * `Result<String, Box<dyn std::error::Error>>` - The full version to use
+ */
+fn resolve_installed_version(input: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let install_root = paths::install_root()?;
+    let installed = if install_root.exists() {
+        installs::scan(&install_root)?.versions
+    } else {
+        Vec::new()
+    };
+
+    let mut candidates = installs::resolve_partial(&installed, input);
+    match candidates.len() {
+        0 => Ok(input.to_string()),
+        1 => {
+            let version = candidates.remove(0);
+            if version != input {
+                println!("🔎 Resolved {} to installed version {}", input, version);
+            }
+            Ok(version)
+        }
+        _ if prompt::is_interactive() => {
+            let index = prompt::pick(&format!("Several installed versions match {}:", input), &candidates)
+                .ok_or("❌ No version selected")?;
+            Ok(candidates.remove(index))
+        }
+        _ => Err(format!(
+            "'{}' matches several installed versions: {}\n💡 Pass the full version, e.g. palawija use {}",
+            input,
+            candidates.join(", "),
+            candidates[0]
+        ).into()),
+    }
+}
+
+/**
+ * Switches back to the version that was active before the last recorded switch
+ * 
+ * The rollback is itself recorded, so rolling back twice returns to where
+ * you started.
+ * 
+ * # Arguments
+ * * `force` - Passed through to `use_php`
+ * * `verify` - Passed through to `use_php`
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn rollback_php(force: bool, verify: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let last = history::load()?.pop()
+        .ok_or("No version switches recorded yet - nothing to roll back")?;
+    let previous = last.previous
+        .ok_or(format!("No palawija version was active before the switch to {} at {}", last.version, last.timestamp))?;
+
+    println!("🕘 Last switch: {} -> {} at {}", previous, last.version, last.timestamp);
+    use_php(&previous, force, verify)
+}
+
+/**
+ * Switches to a version for the duration of one shell command
+ *
+ * The previous version is restored whatever the command's outcome, the same
+ * way `use --rollback` would switch back; if no palawija version was linked
+ * before, the php link is removed again. The pinned default is put back as it
+ * was, and both switches are recorded in the history. Ctrl-C goes to the command, so the switch back still happens.
+ *
+ * # Arguments
+ * * `version` - The version to run the command with, possibly partial
+ * * `force` - Passed through to `use_php`
+ * * `verify` - Passed through to `use_php`
+ * * `command` - Run with `sh -c`, with the version's bin directory first in PATH
+ *
+ * # Returns
+ * * `Result<i32, Box<dyn std::error::Error>>` - The command's exit code, or why a switch failed
+ */
+fn run_temporarily(version: &str, force: bool, verify: bool, command: &str) -> Result<i32, Box<dyn std::error::Error>> {
+    let previous = paths::linked_version();
+    let pinned = pin::read_pin();
+    let version = resolve_installed_version(version)?;
+    println!("🔄 Switching to PHP {} for one command...\n", version);
+    use_php(&version, force, verify).map_err(|e| format!("Failed to switch PHP version: {}", e))?;
+
+    let bin_dir = paths::version_dir(&version)?.join("bin");
+    let path = std::env::join_paths(std::iter::once(bin_dir).chain(std::env::var_os("PATH").iter().flat_map(std::env::split_paths)))?;
+    println!("\n▶️  Running: {}", command);
+    let status = {
+        let _foreground = cleanup::ForegroundChild::enter();
+        Command::new("sh").arg("-c").arg(command).env("PATH", path).status()
+    };
+    let code = match &status {
+        Ok(status) => status.code().unwrap_or_else(|| 128 + status.signal().unwrap_or(0)),
         Err(e) => {
-            return Err(format!(
-                "❌ Failed to create symlink: {}\n💡 You may need sudo privileges: sudo palawija use {}", 
-                e, version
-            ).into());
+            eprintln!("❌ Could not run the command: {}", e);
+            127
+        }
+    };
+
+    match &previous {
+        Some(previous) => {
+            println!("\n⏪ Command exited with {}; switching back to PHP {}...\n", code, previous);
+            use_php(previous, true, verify).map_err(|e| format!("Failed to switch back to PHP {}: {}\n💡 Retry with: palawija use --rollback", previous, e))?;
+        }
+        None => {
+            let link_path = paths::php_link();
+            std::fs::remove_file(&link_path).map_err(|e| format!("Failed to remove {} again: {}", link_path.display(), e))?;
+            println!("\n⏪ Command exited with {}; removed {} again (no palawija version was active before)", code, link_path.display());
         }
     }
-    
-    // Verify the switch was successful
-    println!("🧪 Verifying the switch...");
-    match Command::new("php").arg("--version").output() {
-        Ok(output) => {
-            if output.status.success() {
-                let version_output = String::from_utf8_lossy(&output.stdout);
-                if let Some(first_line) = version_output.lines().next() {
-                    println!("🎊 Success! Current PHP version: {}", first_line.trim());
+    let restored = match &pinned {
+        Some(pinned) => pin::write_pin(pinned),
+        None => pin::pin_path().and_then(|path| std::fs::remove_file(path).map_err(Into::into)),
+    };
+    if let Err(e) = restored {
+        println!("⚠️  Could not restore the pinned default: {}", e);
+    }
+    Ok(code)
+}
+
+/**
+ * Prints the recorded version switches, newest first
+ * 
+ * # Arguments
+ * * `limit` - Show at most this many entries
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn show_history(limit: Option<usize>) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = history::load()?;
+    if entries.is_empty() {
+        println!("📭 No version switches recorded yet");
+        println!("💡 Switches are recorded by: palawija use <version>");
+        return Ok(());
+    }
+
+    println!("🕘 Recent version switches (newest first):");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    for entry in entries.iter().rev().take(limit.unwrap_or(usize::MAX)) {
+        println!("   {}  {} -> {}", entry.timestamp, entry.previous.as_deref().unwrap_or("(none)"), entry.version);
+    }
+    println!("\n💡 Undo the last switch with: palawija use --rollback");
+    Ok(())
+}
+
+/**
+ * Prints the manifest of an installed version and, with `remote`, its release notes
+ *
+ * A version that isn't installed can still be described with `remote`.
+ *
+ * # Arguments
+ * * `version` - The version to describe
+ * * `remote` - Also fetch the release date and links from php.net
+ * * `timeout` - Seconds the php.net request may take, 0 for no limit
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if the version is unknown locally without `remote`, or the fetch fails
+ */
+fn show_info(version: &str, remote: bool, timeout: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let version_dir = paths::version_dir(version)?;
+    println!("ℹ️  PHP {}", version);
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    if !version_dir.is_dir() {
+        if !remote {
+            return Err(format!("not installed\n💡 See the release notes with: palawija info {} --remote", version).into());
+        }
+        println!("📭 Not installed");
+    } else {
+        println!("📂 Location: {}", version_dir.display());
+        println!("⚙️  Compiled: {}", if paths::php_binary(&version_dir).is_file() { "yes" } else { "no (source only)" });
+        match Manifest::load(&version_dir) {
+            Some(manifest) => {
+                println!("📅 Installed: {}", manifest.installed_at);
+                println!("🌐 Source: {}", manifest.source_url);
+                if let Some(sha256) = &manifest.sha256 {
+                    println!("🔐 SHA-256: {}", sha256);
+                }
+                if let (Some(git_ref), Some(commit)) = (&manifest.git_ref, &manifest.git_commit) {
+                    println!("🌿 Git: {} at {}", git_ref, git::short_commit(commit));
+                }
+                if !manifest.configure_flags.is_empty() {
+                    println!("🔧 Configure flags: {}", manifest.configure_flags.join(" "));
+                }
+                if !manifest.extensions.is_empty() {
+                    println!("🧩 Extensions: {}", manifest.extensions.join(", "));
                 }
             }
+            None => println!("⚠️  No install manifest recorded"),
         }
-        Err(_) => {
-            println!("⚠️  Could not verify the switch, but symlink was created");
+    }
+
+    if remote {
+        println!();
+        let notes = releases::fetch_release_notes(version, timeout)?;
+        if notes.from_api {
+            println!("📅 Released: {}", notes.date.as_deref().unwrap_or("unknown"));
+            if !notes.tags.is_empty() {
+                println!("🏷️  Tags: {}", notes.tags.join(", "));
+            }
+            if let Some(announcement) = &notes.announcement {
+                println!("📣 Announcement: {}", announcement);
+            }
+        } else {
+            println!("⚠️  The php.net releases API doesn't list PHP {} (older releases predate it)", version);
         }
+        println!("📝 Changelog: {}", notes.changelog);
+    }
+    Ok(())
+}
+
+/**
+ * Prints the local usage statistics, or deletes them
+ *
+ * # Arguments
+ * * `reset` - Delete the stats file instead of printing it
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn show_stats(reset: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let path = stats::stats_path()?;
+    if reset {
+        if stats::reset()? {
+            println!("🧹 Deleted {}", path.display());
+        } else {
+            println!("✨ No stats recorded yet");
+        }
+        return Ok(());
+    }
+
+    let stats = stats::Stats::load();
+    let install_root = paths::install_root()?;
+    let installed = if install_root.exists() { installs::scan(&install_root)?.versions } else { Vec::new() };
+    let disk_used: u64 = installed.iter().map(|version| installs::dir_size(&install_root.join(format!("php-{}", version)))).sum();
+
+    println!("📊 Your palawija usage");
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    if let Some(since) = &stats.since {
+        println!("🕘 Counting since: {}", since);
+    }
+    println!("📦 Installs: {}", stats.installs);
+    println!("💾 Installed now: {} version(s), {}", installed.len(), space::format_bytes(disk_used));
+    println!("🔄 Switches: {}", stats.switches);
+    if let Some((version, count)) = stats.most_switched() {
+        println!("⭐ Most switched to: PHP {} ({} time(s))", version, count);
+    }
+    if let Some(last) = &stats.last_switch {
+        println!("⏱️  Last switch: {}", last);
+    }
+    println!("\n🔒 Stored only on this machine in {} and never transmitted", path.display());
+    println!("💡 Clear it with: palawija stats --reset");
+    Ok(())
+}
+
+/**
+ * Records an installed version as the persistent default
+ * 
+ * The pin is what `palawija doctor` compares the global symlink against; it
+ * doesn't switch versions by itself.
+ * 
+ * # Arguments
+ * * `version` - The PHP version to pin (must be compiled and installed)
+ * 
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn pin_version(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let version_dir = paths::version_dir(version)?;
+    let php_bin_path = paths::php_binary(&version_dir);
+    if !php_bin_path.exists() {
+        return Err(PalawijaError::missing_binary(version, &version_dir).into());
+    }
+
+    pin::write_pin(version)?;
+    println!("📌 Pinned PHP {} as the default version", version);
+
+    if paths::active_php_binary().as_ref() != Some(&php_bin_path) {
+        println!("⚠️  The active php is not PHP {}", version);
+        println!("💡 Apply the pin with: palawija use {}", version);
+    }
+    Ok(())
+}
+
+/**
+ * Removes what interrupted installs left behind
+ *
+ * Deletes the install state files (so the next `install` starts over), the
+ * version directories of extractions that never finished, and `.part`
+ * downloads in the cache. Extracted trees are complete installs and stay.
+ * Everything is listed and confirmed (or `--yes`) before it is removed.
+ *
+ * # Arguments
+ * * `dry_run` - Only list what would be removed
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+fn prune(dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut targets: Vec<PathBuf> = Vec::new();
+    for (path, install) in state::leftovers() {
+        if let Some(install) = install
+            && install.phase == state::Phase::Downloaded
+        {
+            let version_dir = paths::version_dir(&install.version)?;
+            if version_dir.exists() {
+                targets.push(version_dir);
+            }
+        }
+        targets.push(path);
+    }
+    if let Ok(entries) = std::fs::read_dir(paths::cache_dir()?) {
+        let mut partial: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "part"))
+            .collect();
+        partial.sort();
+        targets.extend(partial);
+    }
+
+    if targets.is_empty() {
+        println!("✨ Nothing to prune");
+        return Ok(());
+    }
+    if dry_run {
+        for target in &targets {
+            println!("   Would remove {}", target.display());
+        }
+        println!("💡 Run without --dry-run to remove {} item(s)", targets.len());
+        return Ok(());
+    }
+
+    println!("🧹 Leftovers of interrupted installs:");
+    for target in &targets {
+        println!("   {}", target.display());
+    }
+    if !prompt::confirm(&format!("Remove these {} item(s)?", targets.len())) {
+        println!("💡 Nothing removed");
+        return Ok(());
+    }
+    for target in &targets {
+        if target.is_dir() {
+            std::fs::remove_dir_all(target)?;
+        } else {
+            std::fs::remove_file(target)?;
+        }
+        println!("🧹 Removed {}", target.display());
     }
-    
-    println!("\n✅ PHP version {} is now your system default! 🚀", version);
-    println!("💡 Try running: php --version");
-    println!("💡 Location: {}", link_path.display());
-    
     Ok(())
-}
\ No newline at end of file
+}
+
+/**
+ * Extracts the version number from `php --version` output
+ * 
+ * # Arguments
+ * * `output` - Raw stdout of `php --version` (e.g. "PHP 8.3.0 (cli) (built: ...)")
+ * 
+ * # Returns
+ * * `Option<String>` - The reported version (e.g. "8.3.0"), if recognizable
+ */
+fn parse_php_version(output: &str) -> Option<String> {
+    let first_line = output.lines().next()?;
+    let mut words = first_line.split_whitespace();
+    if words.next()? != "PHP" {
+        return None;
+    }
+    words.next().map(|v| v.to_string())
+}
+
+/**
+ * Finds every executable named `name` in PATH, in lookup order
+ *
+ * Like the shell, files without an execute bit are skipped.
+ * 
+ * # Arguments
+ * * `name` - Executable file name to look for (e.g. "php")
+ * 
+ * # Returns
+ * * `Vec<PathBuf>` - Matching paths; the first entry is what the shell will run
+ */
+fn find_in_path(name: &str) -> Vec<PathBuf> {
+    find_in_dirs(name, &env::var_os("PATH").unwrap_or_default())
+}
+
+/// `find_in_path` over the directories of a PATH-style list
+fn find_in_dirs(name: &str, path_var: &std::ffi::OsStr) -> Vec<PathBuf> {
+    env::split_paths(path_var)
+        .map(|dir| dir.join(name))
+        .filter(|candidate| candidate.is_file() && hooks::is_executable(candidate))
+        .collect()
+}
+
+/// Returns true if `dir` is one of the entries of PATH
+fn dir_in_path(dir: &Path) -> bool {
+    let path_var = env::var_os("PATH").unwrap_or_default();
+    env::split_paths(&path_var).any(|entry| entry == dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn path_lookup_skips_files_that_are_not_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("palawija-path-{}", std::process::id()));
+        let (plain, bin, dir) = (root.join("plain"), root.join("bin"), root.join("dir"));
+        for (path, mode) in [(&plain, 0o644), (&bin, 0o755)] {
+            std::fs::create_dir_all(path).unwrap();
+            std::fs::write(path.join("php"), "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(path.join("php"), std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+        std::fs::create_dir_all(dir.join("php")).unwrap();
+
+        let path_var = env::join_paths([&plain, &dir, &bin]).unwrap();
+        assert_eq!(find_in_dirs("php", &path_var), [bin.join("php")]);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn which_tells_missing_and_unmanaged_php_apart() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("palawija-which-{}", std::process::id()));
+        let (install_root, links, system) = (root.join("install"), root.join("links"), root.join("system"));
+        let binary = install_root.join("php-8.3.0").join("bin").join("php");
+        std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        std::fs::create_dir_all(&links).unwrap();
+        std::fs::create_dir_all(&system).unwrap();
+        for php in [&binary, &system.join("php")] {
+            std::fs::write(php, "#!/bin/sh\n").unwrap();
+            std::fs::set_permissions(php, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        symlink(&binary, links.join("php")).unwrap();
+
+        let path_var = |dirs: &[&PathBuf]| env::join_paths(dirs).unwrap();
+        let located = locate_managed_php("php", &path_var(&[&links, &system]), Some(&install_root));
+        assert_eq!(located.unwrap(), links.join("php"));
+
+        let unmanaged = locate_managed_php("php", &path_var(&[&system, &links]), Some(&install_root)).unwrap_err();
+        assert_eq!((unmanaged.kind(), unmanaged.exit_code()), ("unmanaged_php", 9));
+        let unknown_root = locate_managed_php("php", &path_var(&[&links]), None).unwrap_err();
+        assert_eq!(unknown_root.kind(), "unmanaged_php");
+        let missing = locate_managed_php("php", &path_var(&[&root]), Some(&install_root)).unwrap_err();
+        assert_eq!((missing.kind(), missing.exit_code()), ("no_php", 8));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn replaced_php_is_backed_up_as_file_or_link() {
+        let (root, _lock) = paths::test_root();
+        let backups = root.join("backups");
+        let system = std::env::temp_dir().join(format!("palawija-system-php-{}", std::process::id()));
+        std::fs::create_dir_all(&system).unwrap();
+        std::fs::write(system.join("php"), "system php").unwrap();
+        symlink("/usr/bin/php8.1", system.join("php-link")).unwrap();
+
+        let today = date::Date::today();
+        let copy = backup_existing_php(&system.join("php")).unwrap();
+        assert_eq!(copy, backups.join(format!("php-{}", today)));
+        assert_eq!(std::fs::read_to_string(&copy).unwrap(), "system php");
+
+        // A second backup on the same day gets its own name, and links stay links
+        let link = backup_existing_php(&system.join("php-link")).unwrap();
+        assert_eq!(link, backups.join(format!("php-{}.1", today)));
+        assert_eq!(std::fs::read_link(&link).unwrap(), Path::new("/usr/bin/php8.1"));
+
+        std::fs::remove_dir_all(&backups).unwrap();
+        std::fs::remove_dir_all(&system).unwrap();
+    }
+
+    #[test]
+    fn extract_to_unpacks_only_into_an_empty_directory() {
+        let root = std::env::temp_dir().join(format!("palawija-extract-to-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("php-8.3.0").join("main")).unwrap();
+        std::fs::write(root.join("php-8.3.0").join("main").join("php_version.h"), "#define PHP_VERSION \"8.3.0\"\n").unwrap();
+        std::fs::write(root.join("php-8.3.0").join("configure"), "").unwrap();
+        let tarball = root.join("php-8.3.0.tar.gz");
+        let status = Command::new("tar").arg("-czf").arg(&tarball).arg("-C").arg(&root).arg("php-8.3.0").status().unwrap();
+        assert!(status.success());
+
+        let target = root.join("context").join("php-src");
+        let line = format!("palawija install 8.3.0 --extract-to {} --from-source {}", target.display(), tarball.display());
+        let Commands::Install { options, .. } = Cli::try_parse_from(line.split_whitespace()).unwrap().command else { unreachable!() };
+        let config = config::Config::default();
+
+        extract_source_to("8.3.0", &target, &options, &config).unwrap();
+        assert!(target.join("configure").is_file());
+        assert_eq!(installs::source_version(&target).as_deref(), Some("8.3.0"));
+
+        let error = extract_source_to("8.3.0", &target, &options, &config).unwrap_err();
+        assert!(error.to_string().contains("is not empty"), "{}", error);
+        assert!(extract_source_to("latest", &root.join("other"), &options, &config).is_err());
+        assert!(!root.join("other").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn install_picker_offers_the_newest_release_per_branch() {
+        let mut versions: Vec<String> = ["8.4.0RC1", "8.3.4", "8.3.3", "8.2.15", "8.1.27", "8.0.30", "7.4.33", "7.3.33", "7.2.34", "7.1.33"]
+            .iter().map(|v| v.to_string()).collect();
+        assert_eq!(install_choices(&versions, false), ["8.3.4", "8.2.15", "8.1.27", "8.0.30", "7.4.33", "7.3.33", "7.2.34", "7.1.33"]);
+        assert_eq!(install_choices(&versions, true)[..2], ["8.4.0RC1", "8.3.4"]);
+        assert_eq!(install_choices(&versions, true).len(), RECENT_BRANCHES);
+
+        versions.retain(|version| releases::is_prerelease(version));
+        assert!(install_choices(&versions, false).is_empty());
+    }
+
+    /// Installs a php in the test root that logs each run to `<root>/runs-<version>`
+    fn fake_php(root: &Path, version: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let binary = paths::php_binary(&root.join(format!("php-{}", version)));
+        std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        let script = format!("#!/bin/sh\necho run >> {}\necho 'PHP {} (cli) (NTS)'\n", root.join(format!("runs-{}", version)).display(), version);
+        std::fs::write(&binary, script).unwrap();
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        binary
+    }
+
+    /// How often the `fake_php` of a version has run
+    fn runs(root: &Path, version: &str) -> usize {
+        std::fs::read_to_string(root.join(format!("runs-{}", version))).map_or(0, |log| log.lines().count())
+    }
+
+    #[test]
+    fn use_probes_the_new_php_once_unless_told_not_to() {
+        let (root, _lock) = paths::test_root();
+        let binary = fake_php(&root, "8.3.600");
+
+        use_php("8.3.600", true, true).unwrap();
+        assert_eq!(std::fs::read_link(paths::php_link()).unwrap(), binary);
+        assert_eq!(runs(&root, "8.3.600"), 1);
+
+        use_php("8.3.600", true, false).unwrap();
+        assert_eq!(runs(&root, "8.3.600"), 1);
+        assert!(Cli::try_parse_from(["palawija", "use", "8.3", "--no-symlink-check"]).is_ok());
+
+        std::fs::remove_dir_all(root.join("php-8.3.600")).unwrap();
+        std::fs::remove_file(root.join("runs-8.3.600")).unwrap();
+        std::fs::remove_file(paths::php_link()).unwrap();
+    }
+
+    #[test]
+    fn use_run_switches_back_after_the_command() {
+        let (root, _lock) = paths::test_root();
+        let previous = fake_php(&root, "8.2.608");
+        fake_php(&root, "8.3.608");
+        use_php("8.2.608", true, false).unwrap();
+
+        let output = root.join("run-608.out");
+        let command = format!("php --version > {}; exit 7", output.display());
+        assert_eq!(run_temporarily("8.3.608", true, false, &command).unwrap(), 7);
+        assert!(std::fs::read_to_string(&output).unwrap().starts_with("PHP 8.3.608 "));
+        assert_eq!(std::fs::read_link(paths::php_link()).unwrap(), previous);
+        assert_eq!(pin::read_pin().as_deref(), Some("8.2.608"));
+
+        // Without a version linked before, the link and the pin are removed again
+        std::fs::remove_file(paths::php_link()).unwrap();
+        pin::clear_pin().unwrap();
+        assert_eq!(run_temporarily("8.3.608", true, false, "true").unwrap(), 0);
+        assert!(std::fs::symlink_metadata(paths::php_link()).is_err());
+        assert_eq!(pin::read_pin(), None);
+
+        for dir in ["php-8.2.608", "php-8.3.608"] {
+            std::fs::remove_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::remove_file(&output).unwrap();
+    }
+
+    #[test]
+    fn list_filters_by_compiled_state() {
+        let root = std::env::temp_dir().join(format!("palawija-list-filter-{}", std::process::id()));
+        let (compiled, source) = (root.join("php-8.3.0"), root.join("php-8.2.15"));
+        std::fs::create_dir_all(paths::php_binary(&compiled).parent().unwrap()).unwrap();
+        std::fs::write(paths::php_binary(&compiled), "").unwrap();
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("configure"), "").unwrap();
+
+        let shown = |source_only, compiled_only| -> Vec<bool> {
+            let filter = compiled_filter(source_only, compiled_only);
+            [&compiled, &source].iter().map(|dir| is_wanted(dir, filter)).collect()
+        };
+        assert_eq!(shown(false, false), [true, true]);
+        assert_eq!(shown(false, true), [true, false]);
+        assert_eq!(shown(true, false), [false, true]);
+        assert!(Cli::try_parse_from(["palawija", "list", "--source-only", "--compiled"]).is_err());
+        assert!(Cli::try_parse_from(["palawija", "list", "--binary-only"]).is_ok());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn use_leaves_the_active_version_alone() {
+        let (root, _lock) = paths::test_root();
+        fake_php(&root, "8.3.631");
+        switch_php("8.3.631", false, true, &[], false, false).unwrap();
+        assert_eq!(runs(&root, "8.3.631"), 1);
+
+        // Only the pin is brought back in line
+        pin::clear_pin().unwrap();
+        switch_php("8.3.631", false, true, &[], false, false).unwrap();
+        assert_eq!(runs(&root, "8.3.631"), 1);
+        assert_eq!(pin::read_pin().as_deref(), Some("8.3.631"));
+
+        switch_php("8.3.631", true, true, &[], false, false).unwrap();
+        assert_eq!(runs(&root, "8.3.631"), 2);
+
+        std::fs::remove_dir_all(root.join("php-8.3.631")).unwrap();
+        std::fs::remove_file(root.join("runs-8.3.631")).unwrap();
+        std::fs::remove_file(paths::php_link()).unwrap();
+    }
+
+    #[test]
+    fn plain_listing_applies_prefixes_and_dates() {
+        let dated = |date: &str| releases::ReleaseDetails { date: Some(date.to_string()), ..Default::default() };
+        let release_list = releases::ReleaseList {
+            versions: ["8.3.4", "8.3.3", "8.2.17", "7.4.33"].iter().map(|v| v.to_string()).collect(),
+            source: releases::ReleaseSource::ReleasesApi,
+            details: [("8.3.4".to_string(), dated("14 Mar 2024")), ("8.3.3".to_string(), dated("15 Feb 2024")), ("8.2.17".to_string(), dated("14 Mar 2024"))]
+                .into_iter().collect(),
+        };
+        let everything = releases::ReleaseWindow::default();
+        let filters = releases::VersionFilter::parse_list("8.3, 7").unwrap();
+
+        let schedule = SupportSchedule::embedded();
+        let today = date::Date::new(2025, 6, 1);
+        let no_query = VersionQuery::default();
+        let plain = |filters, window, query: &VersionQuery| plain_versions(&release_list, filters, window, query, &schedule, today);
+
+        assert_eq!(plain(None, everything, &no_query), (release_list.versions.iter().collect(), 0));
+        assert_eq!(plain(Some(&filters), everything, &no_query).0, ["8.3.4", "8.3.3", "7.4.33"]);
+        let march = releases::ReleaseWindow { after: date::Date::parse("2024-03-01"), before: None };
+        assert_eq!(plain(None, march, &no_query), (vec![&release_list.versions[0], &release_list.versions[2]], 1));
+        let newest = VersionQuery { limit: Some(1), ..VersionQuery::default() };
+        assert_eq!(plain(Some(&filters), everything, &newest).0, ["8.3.4"]);
+        let eol = VersionQuery { eol: true, ..VersionQuery::default() };
+        assert_eq!(plain(Some(&filters), everything, &eol).0, ["7.4.33"]);
+        assert!(Cli::try_parse_from(["palawija", "available", "--minimal", "--all"]).is_err());
+    }
+
+    #[test]
+    fn build_flags_are_shared_by_install_and_compile() {
+        let parse = |line: &str| Cli::try_parse_from(line.split_whitespace());
+        let Commands::Compile { build, .. } = parse("palawija compile 8.3 --jobs 4 --ccache --no-hooks").unwrap().command else { unreachable!() };
+        assert_eq!((build.jobs, build.ccache, build.no_hooks), (Some(4), true, true));
+        let Commands::Install { options, .. } = parse("palawija install 8.3 --compile --jobs 4").unwrap().command else { unreachable!() };
+        assert_eq!(options.build.jobs, Some(4));
+
+        // Only --no-hooks means something to an install that doesn't compile
+        assert!(parse("palawija install 8.3 --jobs 4").is_err());
+        assert!(parse("palawija reinstall 8.3 --expose-versioned").is_err());
+        assert!(parse("palawija install 8.3 --no-hooks").is_ok());
+    }
+
+    #[test]
+    fn help_epilogues_are_only_built_for_help() {
+        let args = |line: &str| line.split_whitespace().map(std::ffi::OsString::from).collect::<Vec<_>>();
+        assert!(help_requested(&args("use --help")));
+        assert!(help_requested(&args("help available")));
+        assert!(help_requested(&args("available -h")));
+        assert!(!help_requested(&args("export")));
+        assert!(!help_requested(&args("exec 8.3 -- -r echo")));
+    }
+
+    #[test]
+    fn available_filters_compose() {
+        let versions: Vec<String> = ["8.4.0RC2", "8.3.14", "8.3.0", "8.1.31", "8.0.30", "7.4.33"].map(String::from).to_vec();
+        let schedule = SupportSchedule::embedded();
+        let today = date::Date::new(2025, 6, 1);
+        let query = |line: &str| {
+            let Commands::Available { version, query, .. } = Cli::try_parse_from(line.split_whitespace()).unwrap().command else { unreachable!() };
+            let filters = version.map(|filter| releases::VersionFilter::parse_list(&filter).unwrap());
+            let prefixed = match &filters {
+                Some(filters) => releases::filter_versions(&versions, filters),
+                None => versions.iter().collect(),
+            };
+            query.apply(prefixed, &schedule, today)
+        };
+
+        assert_eq!(query("palawija available").len(), versions.len());
+        assert_eq!(query("palawija available 8.3"), ["8.3.14", "8.3.0"]);
+        assert_eq!(query("palawija available 8 --stable-only --limit 2"), ["8.3.14", "8.3.0"]);
+        assert_eq!(query("palawija available --eol"), ["8.0.30", "7.4.33"]);
+        assert_eq!(query("palawija available --stable-only --since 8.1.31"), ["8.3.14", "8.3.0"]);
+        assert_eq!(query("palawija available --since 8.4.0RC1"), ["8.4.0RC2"]);
+        assert!(query("palawija available 9").is_empty());
+        assert!(Cli::try_parse_from(["palawija", "available", "--plain", "--eol", "--limit", "3"]).is_ok());
+        assert!(Cli::try_parse_from(["palawija", "search", "--eol"]).is_err());
+    }
+
+    #[test]
+    fn failed_extractions_leave_no_version_directory() {
+        let root = std::env::temp_dir().join(format!("palawija-extract-{}", std::process::id()));
+        let source = root.join("php-8.3.0");
+        std::fs::create_dir_all(source.join("main")).unwrap();
+        std::fs::write(source.join("main/php_version.h"), "x".repeat(64 * 1024)).unwrap();
+        std::fs::write(source.join("README"), "php").unwrap();
+        let pack = |archive: &str, entry: &str| {
+            let status = Command::new("tar").arg("-czf").arg(root.join(archive)).arg("-C").arg(&root).arg(entry).status().unwrap();
+            assert!(status.success());
+        };
+        pack("good.tar.gz", "php-8.3.0");
+        pack("docs.tar.gz", "php-8.3.0/README");
+
+        // A download cut short halfway through
+        let good = std::fs::read(root.join("good.tar.gz")).unwrap();
+        std::fs::write(root.join("corrupt.tar.gz"), &good[..good.len() / 2]).unwrap();
+
+        for archive in ["corrupt.tar.gz", "docs.tar.gz"] {
+            let version_dir = root.join("install").join("php-8.3.0");
+            assert!(extract_tarball(&root.join(archive), &version_dir).is_err(), "{} extracted", archive);
+            assert!(!version_dir.exists(), "{} left {}", archive, version_dir.display());
+        }
+
+        let version_dir = root.join("install").join("php-8.3.0");
+        extract_tarball(&root.join("good.tar.gz"), &version_dir).unwrap();
+        assert!(version_dir.join("main/php_version.h").is_file());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn eol_outranks_available_updates() {
+        let schedule = SupportSchedule::embedded();
+        let available: Vec<String> = ["99.1.2", "99.1.1", "7.4.33", "7.4.32"].iter().map(|v| v.to_string()).collect();
+        assert_eq!(install_status("7.4.32", &schedule, Some(&available)), InstallStatus::Eol(Some(&available[2])));
+        assert_eq!(install_status("99.1.1", &schedule, Some(&available)), InstallStatus::UpdateAvailable(&available[0]));
+        assert_eq!(install_status("99.1.2", &schedule, Some(&available)), InstallStatus::Current);
+        assert_eq!(install_status("99.1.2", &schedule, None), InstallStatus::Unchecked);
+    }
+
+    #[test]
+    fn newer_patches_are_found_within_the_branch() {
+        let available: Vec<String> = ["8.3.2", "8.3.1", "8.4.0RC1", "8.2.15"].iter().map(|v| v.to_string()).collect();
+        assert_eq!(newer_patch(&available, "8.3.1").map(String::as_str), Some("8.3.2"));
+        assert_eq!(newer_patch(&available, "8.2.15"), None);
+        assert_eq!(newer_patch(&available, "7.4.33"), None);
+        assert_eq!(newer_patch(&available, "git-master"), None);
+    }
+
+    #[test]
+    fn shared_archive_prefixes_are_measured() {
+        let official = ["php-8.3.1/", "php-8.3.1/configure", "php-8.3.1/main/", "php-8.3.1/main/php_version.h"];
+        assert_eq!(shared_prefix_depth(official), 1);
+        assert_eq!(shared_prefix_depth(["./php-8.3.1/configure", "./php-8.3.1/main/php_version.h"]), 2);
+        assert_eq!(shared_prefix_depth(["mirror/", "mirror/src/", "mirror/src/php-8.3.1/configure", "mirror/src/php-8.3.1/main/php.h"]), 3);
+        assert_eq!(shared_prefix_depth(["php-8.3.1/configure", "docs/"]), 0);
+        assert_eq!(shared_prefix_depth(["configure", "main/php_version.h"]), 0);
+        assert_eq!(shared_prefix_depth(["php-8.3.1/configure"]), 1);
+    }
+
+    #[test]
+    fn version_lists_skip_comments_and_blank_lines() {
+        let list = "# CI matrix\n8.3.0\n\n  8.2  # newest 8.2\nlatest\n";
+        assert_eq!(parse_version_list(list), ["8.3.0", "8.2", "latest"]);
+    }
+
+    #[test]
+    fn symlink_chains_are_followed_hop_by_hop() {
+        let dir = std::env::temp_dir().join(format!("palawija-chain-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("shims")).unwrap();
+        std::fs::write(dir.join("php-real"), "").unwrap();
+        std::os::unix::fs::symlink("../php-real", dir.join("shims").join("php")).unwrap();
+        std::os::unix::fs::symlink(dir.join("shims").join("php"), dir.join("php")).unwrap();
+        std::os::unix::fs::symlink("loop", dir.join("loop")).unwrap();
+
+        assert_eq!(symlink_chain(&dir.join("php")), [dir.join("php"), dir.join("shims").join("php"), dir.join("shims").join("../php-real")]);
+        assert_eq!(symlink_chain(&dir.join("php-real")).len(), 1);
+        assert_eq!(symlink_chain(&dir.join("loop")).len(), 41);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
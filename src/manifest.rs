@@ -0,0 +1,152 @@
+/*!
+ * Per-version install manifest
+ *
+ * Each installed version records how it was obtained and built in
+ * `~/.palawija/php-<version>/palawija-manifest.json`.
+ */
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::date::Date;
+
+/// File name of the manifest inside a version directory
+pub const MANIFEST_FILE: &str = "palawija-manifest.json";
+
+/// Metadata palawija keeps about an installed version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    /// PHP version this directory holds
+    pub version: String,
+    /// Where the source tarball came from
+    pub source_url: String,
+    /// SHA-256 of the source tarball, if it was computed
+    pub sha256: Option<String>,
+    /// Date of the install (YYYY-MM-DD)
+    pub installed_at: String,
+    /// Whether palawija compiled the source tree
+    pub compiled: bool,
+    /// Flags passed to ./configure when compiled
+    pub configure_flags: Vec<String>,
+    /// Extensions built against this version with `palawija ext install`
+    pub extensions: Vec<String>,
+    /// Shared object and ini line of each extension in `extensions`, for removal
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extension_files: Vec<ExtensionFile>,
+    /// php-src ref this version was checked out from (`install --git`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    /// Commit the Git ref resolved to at install time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+}
+
+/// Where `palawija ext install` put an extension and how it is enabled
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionFile {
+    /// Extension name (the `.so` file name without its suffix)
+    pub name: String,
+    /// The installed shared object
+    pub path: PathBuf,
+    /// The php.ini line that loads it, e.g. `extension=redis`
+    pub ini_line: String,
+}
+
+impl Manifest {
+    /// Creates a manifest for a freshly extracted source tree
+    pub fn new(version: &str, source_url: &str, sha256: Option<String>) -> Self {
+        Manifest {
+            version: version.to_string(),
+            source_url: source_url.to_string(),
+            sha256,
+            installed_at: Date::today().to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Path of the manifest file for a version directory
+    pub fn path(version_dir: &Path) -> PathBuf {
+        version_dir.join(MANIFEST_FILE)
+    }
+
+    /**
+     * Loads the manifest of a version directory
+     *
+     * A manifest that doesn't parse is reported on stderr and treated as
+     * missing, so one damaged file doesn't stop commands that only read it.
+     *
+     * # Returns
+     * * `Option<Manifest>` - `None` if the file is missing, unreadable or corrupt
+     */
+    pub fn load(version_dir: &Path) -> Option<Self> {
+        let path = Self::path(version_dir);
+        let content = std::fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                eprintln!("⚠️  Ignoring corrupt manifest {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Records an installed extension, keeping the lists sorted and unique
+    pub fn record_extension(&mut self, file: ExtensionFile) {
+        if !self.extensions.contains(&file.name) {
+            self.extensions.push(file.name.clone());
+            self.extensions.sort();
+        }
+        self.extension_files.retain(|recorded| recorded.name != file.name);
+        self.extension_files.push(file);
+        self.extension_files.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Writes the manifest into its version directory atomically
+    pub fn save(&self, version_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        atomic::write_json_atomic(&Self::path(version_dir), self)
+    }
+
+    /**
+     * Takes over the details of a fresh install while keeping everything else
+     *
+     * Used by `reinstall` so user-facing data recorded in the previous manifest
+     * survives, while source, checksum and build state describe the new tree.
+     * Extensions are build state too: the rebuild wipes their shared objects.
+     */
+    pub fn merge_install_details(&mut self, fresh: &Manifest) {
+        self.version = fresh.version.clone();
+        self.source_url = fresh.source_url.clone();
+        self.sha256 = fresh.sha256.clone();
+        self.installed_at = fresh.installed_at.clone();
+        self.compiled = fresh.compiled;
+        self.configure_flags = fresh.configure_flags.clone();
+        self.extensions = fresh.extensions.clone();
+        self.extension_files = fresh.extension_files.clone();
+        self.git_ref = fresh.git_ref.clone();
+        self.git_commit = fresh.git_commit.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reinstalled_extensions_replace_their_record() {
+        let mut manifest = Manifest::default();
+        let file = |name: &str, dir: &str| ExtensionFile {
+            name: name.to_string(),
+            path: PathBuf::from(format!("{}/{}.so", dir, name)),
+            ini_line: format!("extension={}", name),
+        };
+        manifest.record_extension(file("redis", "/old"));
+        manifest.record_extension(file("apcu", "/ext"));
+        manifest.record_extension(file("redis", "/new"));
+
+        assert_eq!(manifest.extensions, vec!["apcu", "redis"]);
+        assert_eq!(manifest.extension_files, vec![file("apcu", "/ext"), file("redis", "/new")]);
+    }
+}
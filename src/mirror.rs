@@ -0,0 +1,174 @@
+/*!
+ * Download mirrors (`install --mirror`, `install --auto-mirror`)
+ *
+ * Source tarballs can come from any host that serves php.net's
+ * `/distributions/` layout. Checksums and release listings are always read
+ * from www.php.net, so a tarball from a mirror is verified like any other.
+ *
+ * `--auto-mirror` probes the built-in mirrors with a quick HEAD request and
+ * downloads from the fastest. The winner is cached in
+ * `~/.palawija/cache/mirror.json` for `CACHE_TTL_SECS`, so back-to-back
+ * installs don't probe again. An explicit `--mirror` always wins.
+ */
+
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::paths;
+
+/// The canonical download host, used when no mirror is chosen
+pub const CANONICAL: &str = "https://www.php.net";
+
+/// Hosts probed by `--auto-mirror`
+pub const MIRRORS: &[&str] = &[
+    CANONICAL,
+    "https://de.php.net",
+    "https://uk.php.net",
+    "https://us1.php.net",
+    "https://jp.php.net",
+];
+
+/// How long a probed mirror is reused, in seconds
+pub const CACHE_TTL_SECS: u64 = 3600;
+
+/// Seconds each probe may take
+const PROBE_TIMEOUT_SECS: u64 = 3;
+
+static SELECTED: OnceLock<String> = OnceLock::new();
+
+/// Downloads from `base` for this run; only the first call counts
+pub fn set(base: String) {
+    let _ = SELECTED.set(base);
+}
+
+/// Base URL source tarballs are downloaded from
+pub fn base() -> &'static str {
+    SELECTED.get().map(String::as_str).unwrap_or(CANONICAL)
+}
+
+/// Parses a `--mirror` value into a base URL without a trailing slash
+pub fn parse_base(input: &str) -> Result<String, String> {
+    let base = input.trim().trim_end_matches('/');
+    let host = base.strip_prefix("https://").or_else(|| base.strip_prefix("http://")).unwrap_or("");
+    if host.is_empty() {
+        return Err(format!("invalid mirror '{}', expected a URL such as https://de.php.net", input));
+    }
+    Ok(base.to_string())
+}
+
+/// The mirror remembered by the last probe
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedMirror {
+    mirror: String,
+    /// Unix time of the probe
+    probed_at: u64,
+}
+
+/**
+ * Picks the fastest reachable mirror, reusing a recent probe
+ *
+ * Every mirror is probed at the same time. When none answers, the canonical
+ * host is used and nothing is cached, so the next install probes again.
+ *
+ * # Returns
+ * * `String` - The base URL to download from
+ */
+pub fn auto_select() -> String {
+    let now = unix_now();
+    if let Some(cached) = load_cache()
+        && now.saturating_sub(cached.probed_at) < CACHE_TTL_SECS
+    {
+        println!("🌍 Using mirror {} (probed {} min ago)", cached.mirror, now.saturating_sub(cached.probed_at) / 60);
+        return cached.mirror;
+    }
+
+    println!("📡 Probing {} download mirrors...", MIRRORS.len());
+    let probes: Vec<_> = MIRRORS.iter()
+        .filter_map(|mirror| spawn_probe(mirror).map(|child| (*mirror, child)))
+        .collect();
+    let timings: Vec<(&str, Option<f64>)> = probes.into_iter()
+        .map(|(mirror, child)| (mirror, child.wait_with_output().ok().and_then(|output| parse_probe(&output.stdout))))
+        .collect();
+
+    let Some((fastest, seconds)) = fastest(&timings) else {
+        println!("⚠️  No mirror answered - downloading from {}", CANONICAL);
+        return CANONICAL.to_string();
+    };
+    println!("🌍 Fastest mirror: {} ({:.0} ms)", fastest, seconds * 1000.0);
+    let cached = CachedMirror { mirror: fastest.to_string(), probed_at: now };
+    if let Err(e) = cache_path().and_then(|path| atomic::write_json_atomic(&path, &cached)) {
+        println!("⚠️  Could not remember the mirror: {}", e);
+    }
+    cached.mirror
+}
+
+fn cache_path() -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::cache_dir()?.join("mirror.json"))
+}
+
+fn load_cache() -> Option<CachedMirror> {
+    let content = std::fs::read_to_string(cache_path().ok()?).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Starts a HEAD request against a mirror that writes its status and total time
+fn spawn_probe(mirror: &str) -> Option<std::process::Child> {
+    Command::new("curl")
+        .arg("-s")              // Silent mode
+        .arg("-I")              // HEAD request
+        .arg("-L")              // Follow redirects
+        .arg("-o").arg("/dev/null")
+        .arg("-w").arg("%{http_code} %{time_total}")
+        .arg("--max-time").arg(PROBE_TIMEOUT_SECS.to_string())
+        .arg(format!("{}/distributions/", mirror))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()
+}
+
+/// Seconds a probe took, if the host answered at all (any status below 500)
+fn parse_probe(output: &[u8]) -> Option<f64> {
+    let output = String::from_utf8_lossy(output);
+    let (code, seconds) = output.trim().split_once(' ')?;
+    let code: u16 = code.parse().ok()?;
+    // A listing may be forbidden; the host still answered
+    (200..500).contains(&code).then(|| seconds.parse().ok()).flatten()
+}
+
+/// The mirror with the lowest time among those that answered
+fn fastest<'a>(timings: &[(&'a str, Option<f64>)]) -> Option<(&'a str, f64)> {
+    timings.iter()
+        .filter_map(|(mirror, seconds)| seconds.map(|seconds| (*mirror, seconds)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_fastest_answering_mirror_wins() {
+        assert_eq!(parse_probe(b"200 0.120"), Some(0.12));
+        assert_eq!(parse_probe(b"403 0.050"), Some(0.05));
+        assert_eq!(parse_probe(b"000 3.001"), None);
+        let timings = [(CANONICAL, Some(0.3)), ("https://de.php.net", Some(0.1)), ("https://uk.php.net", None)];
+        assert_eq!(fastest(&timings), Some(("https://de.php.net", 0.1)));
+        assert_eq!(fastest(&[(CANONICAL, None)]), None);
+    }
+
+    #[test]
+    fn mirror_urls_are_validated() {
+        assert_eq!(parse_base("https://de.php.net/"), Ok("https://de.php.net".to_string()));
+        assert!(parse_base("de.php.net").is_err());
+        assert!(parse_base("https://").is_err());
+    }
+}
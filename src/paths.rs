@@ -0,0 +1,289 @@
+/*!
+ * Well-known filesystem locations used by palawija
+ */
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::error::PalawijaError;
+
+/// Directory of the global php symlink unless `--link-dir` or `[use] link_dir` say otherwise
+pub const DEFAULT_LINK_DIR: &str = "/usr/local/bin";
+
+/// Basename of the global php symlink unless `--link-name` or `[use] link_name` says otherwise
+pub const DEFAULT_LINK_NAME: &str = "php";
+
+/// Environment variable relocating the install root, like `--root`
+pub const ROOT_ENV: &str = "PALAWIJA_ROOT";
+
+static ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+static LINK_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+static LINK_NAME: OnceLock<String> = OnceLock::new();
+
+/// Keeps versions, cache and config in `dir` instead of `~/.palawija` for this run; only the first call counts
+pub fn set_root(dir: PathBuf) {
+    let _ = ROOT.set(std::path::absolute(&dir).unwrap_or(dir));
+}
+
+/// Sets the directory of the global php symlink for this run; only the first call counts
+pub fn set_link_dir(dir: PathBuf) {
+    let _ = LINK_DIR.set(dir);
+}
+
+/// Directory holding the global php symlink and its toolchain links
+pub fn link_dir() -> PathBuf {
+    LINK_DIR.get().cloned().unwrap_or_else(|| PathBuf::from(DEFAULT_LINK_DIR))
+}
+
+/// Names the global php symlink for this run; only the first call counts
+pub fn set_link_name(name: String) {
+    let _ = LINK_NAME.set(name);
+}
+
+/// Basename of the global php symlink, e.g. `php` or `php-palawija`
+pub fn link_name() -> &'static str {
+    LINK_NAME.get().map(String::as_str).unwrap_or(DEFAULT_LINK_NAME)
+}
+
+/// Checks a `--link-name` value: a plain file name, not a path
+pub fn parse_link_name(input: &str) -> Result<String, String> {
+    let name = input.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return Err(format!("invalid link name '{}', expected a file name such as php-palawija", input));
+    }
+    Ok(name.to_string())
+}
+
+/// Global symlink that makes a palawija-managed php the system default
+pub fn php_link() -> PathBuf {
+    link_dir().join(link_name())
+}
+
+/**
+ * Checks that the link directory can hold the php symlink
+ *
+ * # Returns
+ * * `Result<(), String>` - Why the directory can't be used
+ */
+pub fn check_link_dir(dir: &Path) -> Result<(), String> {
+    if !dir.is_dir() {
+        return Err(format!("{} does not exist or is not a directory", dir.display()));
+    }
+    let probe = dir.join(format!(".palawija-write-test-{}", std::process::id()));
+    match std::fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) => Err(format!("{} is not writable ({})", dir.display(), e)),
+    }
+}
+
+/**
+ * The user's home directory, checked once per run
+ *
+ * Uses `$HOME`, or the current user's passwd entry when it's unset (as in
+ * cron and systemd units). Nothing is written here, so read-only commands
+ * work on a read-only or shared home; commands that write call
+ * [`check_root_writable`] first.
+ *
+ * # Returns
+ * * `Result<PathBuf, PalawijaError>` - `HomeUnavailable` explaining what's wrong
+ */
+pub fn palawija_home() -> Result<PathBuf, PalawijaError> {
+    static HOME: OnceLock<Result<PathBuf, PalawijaError>> = OnceLock::new();
+    HOME.get_or_init(find_home).clone()
+}
+
+fn find_home() -> Result<PathBuf, PalawijaError> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| PalawijaError::HomeUnavailable { reason: "HOME is unset and the current user has no passwd entry".to_string() })?;
+    if !home.is_dir() {
+        return Err(PalawijaError::HomeUnavailable { reason: format!("{} does not exist", home.display()) });
+    }
+    Ok(home)
+}
+
+/**
+ * Checks that the install root can be written, before a command writes to it
+ *
+ * The root (`~/.palawija` or `--root`) is probed, or the nearest existing
+ * directory above it when it doesn't exist yet, so a read-only mount is
+ * reported before any download starts.
+ *
+ * # Returns
+ * * `Result<(), PalawijaError>` - `RootNotWritable` for a read-only or
+ *   permission-denied root, `HomeUnavailable` for other failures
+ */
+pub fn check_root_writable() -> Result<(), PalawijaError> {
+    let home = palawija_home()?;
+    let root = ROOT.get().cloned().unwrap_or_else(|| home.join(".palawija"));
+    let target = root.ancestors().find(|dir| dir.is_dir()).unwrap_or(&home);
+    let probe = target.join(format!(".palawija-write-test-{}", std::process::id()));
+    match std::fs::File::create(&probe) {
+        Ok(_) => {
+            let _ = std::fs::remove_file(&probe);
+            Ok(())
+        }
+        Err(e) => Err(not_writable(&root, target, e)),
+    }
+}
+
+/// Explains a failed write probe in `target` on the way to `root`
+fn not_writable(root: &Path, target: &Path, error: std::io::Error) -> PalawijaError {
+    match error.kind() {
+        std::io::ErrorKind::ReadOnlyFilesystem | std::io::ErrorKind::PermissionDenied => PalawijaError::RootNotWritable {
+            root: root.display().to_string(),
+            read_only_filesystem: error.kind() == std::io::ErrorKind::ReadOnlyFilesystem,
+        },
+        _ => PalawijaError::HomeUnavailable { reason: format!("{} is not writable ({})", target.display(), error) },
+    }
+}
+
+/// Root directory holding every palawija-managed PHP version (`~/.palawija` or `--root`)
+pub fn install_root() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = palawija_home()?;
+    Ok(ROOT.get().cloned().unwrap_or_else(|| home.join(".palawija")))
+}
+
+/**
+ * A scratch install root (and link directory, `<root>/bin`) shared by the tests that need one
+ *
+ * The roots are process-wide, so the returned guard keeps other tests out
+ * of them until it is dropped.
+ */
+#[cfg(test)]
+pub fn test_root() -> (PathBuf, std::sync::MutexGuard<'static, ()>) {
+    static IN_USE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let guard = IN_USE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let root = std::env::temp_dir().join(format!("palawija-test-root-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("bin")).unwrap();
+    set_root(root.clone());
+    set_link_dir(root.join("bin"));
+    (install_root().unwrap(), guard)
+}
+
+/// Directory of a single installed version (`~/.palawija/php-<version>`)
+pub fn version_dir(version: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(install_root()?.join(format!("php-{}", version)))
+}
+
+/// Directory where downloaded source tarballs are kept for reinstalls
+pub fn cache_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(install_root()?.join("cache"))
+}
+
+/// Directory holding the state of interrupted installs (`~/.palawija/state`)
+pub fn state_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(install_root()?.join("state"))
+}
+
+/// Directory where non-palawija files replaced by `use` are saved
+pub fn backups_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(install_root()?.join("backups"))
+}
+
+/// Directory holding the named environments (`~/.palawija/envs`)
+pub fn envs_dir() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(install_root()?.join("envs"))
+}
+
+/// Path of the php binary inside a version directory
+pub fn php_binary(version_dir: &Path) -> PathBuf {
+    version_dir.join("bin").join("php")
+}
+
+/// Path of the `phpize` script inside a version directory
+pub fn phpize(version_dir: &Path) -> PathBuf {
+    version_dir.join("bin").join("phpize")
+}
+
+/// Path of the `php-config` script inside a version directory
+pub fn php_config(version_dir: &Path) -> PathBuf {
+    version_dir.join("bin").join("php-config")
+}
+
+/// Path of the per-version php.ini (matches `--with-config-file-path`)
+pub fn php_ini(version_dir: &Path) -> PathBuf {
+    version_dir.join("etc").join("php.ini")
+}
+
+/// The binary the global php symlink currently points to, if any
+pub fn active_php_binary() -> Option<PathBuf> {
+    std::fs::read_link(php_link()).ok()
+}
+
+/**
+ * The palawija version that is active, the single answer to "what's active"
+ *
+ * The php symlink in the configured link directory decides. When it is
+ * missing or doesn't point into a palawija install, the first environment
+ * shim directory (`~/.palawija/envs/<name>/bin`) in PATH is used instead.
+ *
+ * # Returns
+ * * `Option<String>` - The active version, if any palawija install is active
+ */
+pub fn active_version() -> Option<String> {
+    linked_version().or_else(|| {
+        let shims = first_shim_dir(&std::env::var_os("PATH")?, &envs_dir().ok()?)?;
+        version_of_binary(&std::fs::read_link(shims.join("php")).ok()?)
+    })
+}
+
+/// The version the php symlink in the link directory points to, ignoring environment shims
+pub fn linked_version() -> Option<String> {
+    version_of_binary(&active_php_binary()?)
+}
+
+/// The first directory in a PATH value that is an environment's shim directory
+fn first_shim_dir(path: &std::ffi::OsStr, envs_dir: &Path) -> Option<PathBuf> {
+    std::env::split_paths(path).find(|dir| {
+        dir.strip_prefix(envs_dir).is_ok_and(|rest| rest.components().count() == 2 && rest.ends_with("bin"))
+    })
+}
+
+/// The version a php binary belongs to, if it's inside a palawija install
+pub fn version_of_binary(binary: &Path) -> Option<String> {
+    let version_dir = binary.parent()?.parent()?;
+    if version_dir.parent()? != install_root().ok()? {
+        return None;
+    }
+    version_dir.file_name()?.to_str()?.strip_prefix("php-").map(|version| version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_only_roots_point_at_root() {
+        let root = Path::new("/home/me/.palawija");
+        let error = not_writable(root, Path::new("/home/me"), std::io::Error::from(std::io::ErrorKind::ReadOnlyFilesystem));
+        assert!(matches!(&error, PalawijaError::RootNotWritable { read_only_filesystem: true, .. }));
+        assert!(error.to_string().contains("--root"));
+
+        let error = not_writable(root, root, std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+        assert!(matches!(error, PalawijaError::RootNotWritable { read_only_filesystem: false, .. }));
+        let error = not_writable(root, root, std::io::Error::from(std::io::ErrorKind::StorageFull));
+        assert!(matches!(error, PalawijaError::HomeUnavailable { .. }));
+    }
+
+    #[test]
+    fn link_names_must_be_plain_file_names() {
+        assert_eq!(parse_link_name("php-palawija").as_deref(), Ok("php-palawija"));
+        assert_eq!(parse_link_name(" php8 ").as_deref(), Ok("php8"));
+        for bad in ["", "  ", ".", "..", "bin/php", "/usr/bin/php"] {
+            assert!(parse_link_name(bad).is_err(), "{:?} was accepted", bad);
+        }
+    }
+
+    #[test]
+    fn shim_directories_are_found_in_path() {
+        let envs = Path::new("/home/al/.palawija/envs");
+        let path = std::ffi::OsString::from("/usr/bin:/home/al/.palawija/envs/api/lib:/home/al/.palawija/envs/api/bin:/bin");
+        assert_eq!(first_shim_dir(&path, envs), Some(PathBuf::from("/home/al/.palawija/envs/api/bin")));
+        assert_eq!(first_shim_dir(std::ffi::OsStr::new("/usr/bin:/usr/local/bin"), envs), None);
+    }
+}
@@ -0,0 +1,61 @@
+/*!
+ * Persistent record of the user's chosen default version
+ *
+ * The global symlink can be overwritten by a system package manager, so the
+ * intended default is also written to `~/.palawija/default`.
+ */
+
+use std::path::PathBuf;
+
+use crate::atomic;
+use crate::paths;
+
+/// Location of the pin file (`~/.palawija/default`)
+pub fn pin_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::install_root()?.join("default"))
+}
+
+/// The pinned default version, if one has been recorded
+pub fn read_pin() -> Option<String> {
+    let content = std::fs::read_to_string(pin_path().ok()?).ok()?;
+    let version = content.trim();
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// Removes the pin file; a missing pin is fine
+pub fn clear_pin() -> Result<(), Box<dyn std::error::Error>> {
+    match std::fs::remove_file(pin_path()?) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Records `version` as the pinned default
+pub fn write_pin(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    atomic::write_atomic(&pin_path()?, format!("{}\n", version).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pin_is_written_read_and_cleared() {
+        let (root, _lock) = paths::test_root();
+        assert_eq!(pin_path().unwrap(), root.join("default"));
+
+        write_pin("8.3.0").unwrap();
+        assert_eq!(std::fs::read_to_string(root.join("default")).unwrap(), "8.3.0\n");
+        assert_eq!(read_pin().as_deref(), Some("8.3.0"));
+
+        // A hand-edited pin is trimmed, and an empty one counts as none
+        std::fs::write(root.join("default"), "  8.2.15  \n\n").unwrap();
+        assert_eq!(read_pin().as_deref(), Some("8.2.15"));
+        std::fs::write(root.join("default"), "\n").unwrap();
+        assert_eq!(read_pin(), None);
+
+        clear_pin().unwrap();
+        assert_eq!(read_pin(), None);
+        clear_pin().unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+/*!
+ * Platform support
+ *
+ * palawija manages Linux installs only: builds, links and binaries are
+ * checked against Linux conventions. On other systems the crate still
+ * compiles, the read-only commands work, and everything that links or
+ * builds stops early with `PalawijaError::UnsupportedPlatform`.
+ */
+
+use std::io;
+use std::path::Path;
+
+use crate::error::PalawijaError;
+
+/// Whether this build runs on a supported operating system
+pub const SUPPORTED: bool = cfg!(target_os = "linux");
+
+/**
+ * Fails unless palawija supports this operating system
+ *
+ * # Returns
+ * * `Result<(), PalawijaError>` - `UnsupportedPlatform` naming the system otherwise
+ */
+pub fn ensure_supported() -> Result<(), PalawijaError> {
+    if SUPPORTED {
+        Ok(())
+    } else {
+        Err(PalawijaError::UnsupportedPlatform { os: std::env::consts::OS.to_string() })
+    }
+}
+
+/// Creates a symbolic link at `link` pointing to `target`
+#[cfg(target_os = "linux")]
+pub fn symlink(target: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Creates a symbolic link; not supported off Linux yet
+#[cfg(not(target_os = "linux"))]
+pub fn symlink(_target: impl AsRef<Path>, _link: impl AsRef<Path>) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, ensure_supported().unwrap_err()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_linux_is_supported() {
+        assert_eq!(ensure_supported().is_ok(), cfg!(target_os = "linux"));
+
+        let error = PalawijaError::UnsupportedPlatform { os: "macos".to_string() };
+        assert_eq!((error.kind(), error.exit_code()), ("unsupported_platform", 7));
+        assert!(error.to_string().contains("Linux only"), "{}", error);
+
+        let dir = std::env::temp_dir().join(format!("palawija-platform-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let linked = symlink("/usr/bin/php", dir.join("php"));
+        assert_eq!(linked.is_ok(), SUPPORTED);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
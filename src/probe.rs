@@ -0,0 +1,105 @@
+/*!
+ * Bounded `php --version` probes
+ *
+ * A broken ini (e.g. one loading a stuck extension) can make php hang on
+ * startup, so every probe palawija runs is given a deadline.
+ */
+
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long `php --version` may take before it's considered hung
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a running probe is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Message shown whenever a probe hits its deadline
+pub const TIMEOUT_MESSAGE: &str = "php --version timed out (possible broken configuration)";
+
+/// Outcome of running a probe
+#[derive(Debug)]
+pub enum Probe {
+    /// The command succeeded; holds its stdout
+    Output(String),
+    /// The command ran but exited unsuccessfully
+    Failed(ExitStatus),
+    /// The command didn't finish in time and was killed
+    TimedOut,
+    /// The command couldn't be started
+    SpawnFailed(std::io::Error),
+}
+
+impl Probe {
+    /// The first line of a successful probe's output
+    pub fn first_line(&self) -> Option<&str> {
+        match self {
+            Probe::Output(stdout) => stdout.lines().next().map(|line| line.trim()),
+            _ => None,
+        }
+    }
+}
+
+/// Runs `<binary> --version` with the standard timeout
+pub fn php_version(binary: &Path) -> Probe {
+    run_with_timeout(Command::new(binary).arg("--version"), PROBE_TIMEOUT)
+}
+
+/**
+ * Runs a command, capturing stdout, and kills it if it exceeds `timeout`
+ *
+ * stdout is drained on a separate thread so a chatty child can't block on a
+ * full pipe while we wait for it.
+ */
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> Probe {
+    let mut child = match command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::null()).spawn() {
+        Ok(child) => child,
+        Err(e) => return Probe::SpawnFailed(e),
+    };
+
+    let mut stdout = child.stdout.take();
+    let reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(stdout) = stdout.as_mut() {
+            let _ = stdout.read_to_string(&mut output);
+        }
+        output
+    });
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Probe::Output(reader.join().unwrap_or_default()),
+            Ok(Some(status)) => return Probe::Failed(status),
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Probe::TimedOut;
+            }
+            Err(e) => return Probe::SpawnFailed(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_is_captured() {
+        let probe = run_with_timeout(Command::new("echo").arg("PHP 8.3.0 (cli)"), PROBE_TIMEOUT);
+        assert_eq!(probe.first_line(), Some("PHP 8.3.0 (cli)"));
+        assert!(matches!(run_with_timeout(&mut Command::new("false"), PROBE_TIMEOUT), Probe::Failed(_)));
+    }
+
+    #[test]
+    fn hung_commands_time_out() {
+        let started = Instant::now();
+        let probe = run_with_timeout(Command::new("sleep").arg("30"), Duration::from_millis(200));
+        assert!(matches!(probe, Probe::TimedOut));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+}
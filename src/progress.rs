@@ -0,0 +1,137 @@
+/*!
+ * Machine-readable install progress (`install --progress json`)
+ *
+ * In JSON mode curl's progress bar is replaced by newline-delimited JSON
+ * events, written to stderr or to the descriptor given with `--progress-fd`,
+ * so stdout keeps the usual messages and the final result. Every event is
+ * one object on its own line with a `phase` field:
+ *
+ * ```text
+ * {"phase":"download","pct":42}
+ * {"phase":"extract","file":"php-8.3.0/main/php.h"}
+ * {"phase":"configure"}
+ * {"phase":"compile"}
+ * {"phase":"install"}
+ * {"phase":"done","version":"8.3.0"}
+ * {"phase":"error","version":"8.3.0","message":"..."}
+ * ```
+ *
+ * `download` is sent whenever the whole percentage changes, `extract` once
+ * per archive entry, and the three build phases as each step starts. Every
+ * requested version ends with exactly one `done` or `error`. New fields may
+ * be added to events; existing ones keep their meaning.
+ */
+
+use std::fs::File;
+use std::io::Write;
+use std::sync::{Mutex, OnceLock};
+
+use serde::Serialize;
+
+/// How install progress is reported (`--progress`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum ProgressFormat {
+    /// Progress bars and messages, for people
+    #[default]
+    Human,
+    /// Newline-delimited JSON events, for wrapping tools
+    Json,
+}
+
+/// One step of an install, as written in JSON mode
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "phase", rename_all = "lowercase")]
+pub enum Event<'a> {
+    Download { pct: u8 },
+    Extract { file: &'a str },
+    Configure,
+    Compile,
+    Install,
+    Done { version: &'a str },
+    Error { version: &'a str, message: &'a str },
+}
+
+/// Where events go; `None` writes them to stderr
+static SINK: OnceLock<Mutex<Option<File>>> = OnceLock::new();
+
+/**
+ * Switches to JSON events for this run; only the first call counts
+ *
+ * # Arguments
+ * * `fd` - Already-open descriptor to write events to instead of stderr
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if `fd` isn't open for writing
+ */
+pub fn set_json(fd: Option<u32>) -> Result<(), Box<dyn std::error::Error>> {
+    let file = match fd {
+        Some(fd) => Some(
+            std::fs::OpenOptions::new()
+                .append(true)
+                .open(format!("/dev/fd/{}", fd))
+                .map_err(|e| format!("❌ Can't write progress events to file descriptor {}: {}", fd, e))?,
+        ),
+        None => None,
+    };
+    let _ = SINK.set(Mutex::new(file));
+    Ok(())
+}
+
+/// Whether events replace the human progress display
+pub fn is_json() -> bool {
+    SINK.get().is_some()
+}
+
+/// Writes an event in JSON mode; does nothing otherwise
+pub fn emit(event: &Event) {
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+    let Ok(line) = serde_json::to_string(event) else {
+        return;
+    };
+    let Ok(mut sink) = sink.lock() else {
+        return;
+    };
+    // A reader that went away mustn't fail the install
+    let _ = match sink.as_mut() {
+        Some(file) => writeln!(file, "{}", line),
+        None => writeln!(std::io::stderr().lock(), "{}", line),
+    };
+}
+
+/**
+ * Percentage in a chunk of curl's `--progress-bar` output
+ *
+ * curl redraws the bar with carriage returns (`#####      42.3%`), so the
+ * last figure in the chunk is the current one.
+ *
+ * # Returns
+ * * `Option<u8>` - The whole percentage, `None` if the chunk holds none
+ */
+pub fn curl_percent(chunk: &str) -> Option<u8> {
+    chunk.split(['\r', '\n', ' ', '#'])
+        .filter_map(|token| token.strip_suffix('%'))
+        .filter_map(|number| number.parse::<f64>().ok())
+        .next_back()
+        .map(|pct| pct.clamp(0.0, 100.0) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_and_curl_progress_are_read_and_written() {
+        assert_eq!(serde_json::to_string(&Event::Download { pct: 42 }).unwrap(), r#"{"phase":"download","pct":42}"#);
+        assert_eq!(serde_json::to_string(&Event::Configure).unwrap(), r#"{"phase":"configure"}"#);
+        assert_eq!(
+            serde_json::to_string(&Event::Error { version: "8.3.0", message: "boom" }).unwrap(),
+            r#"{"phase":"error","version":"8.3.0","message":"boom"}"#
+        );
+
+        assert_eq!(curl_percent("\r######                      12.5%\r##########               41.9%"), Some(41));
+        assert_eq!(curl_percent("\r#################### 100.0%\n"), Some(100));
+        assert_eq!(curl_percent("\r                                  "), None);
+    }
+}
@@ -0,0 +1,105 @@
+/*!
+ * Interactive prompts
+ *
+ * The global `--yes` flag is applied here once, so every yes/no question is
+ * answered the same way without each caller checking for it.
+ */
+
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Answers every following `confirm` with yes (`--yes`)
+pub fn set_assume_yes() {
+    ASSUME_YES.store(true, Ordering::Relaxed);
+}
+
+/**
+ * Asks a yes/no question on the terminal
+ *
+ * # Arguments
+ * * `question` - The question to print (without the `[y/N]` suffix)
+ *
+ * With `--yes` the question is still printed, followed by the automatic
+ * answer, so the output records what was agreed to.
+ *
+ * # Returns
+ * * `bool` - True only if the user answered yes; anything else (including
+ *   EOF on a non-interactive stdin) counts as no
+ */
+pub fn confirm(question: &str) -> bool {
+    print!("❓ {} [y/N] ", question);
+    read_confirmation(ASSUME_YES.load(Ordering::Relaxed), &mut io::stdin().lock())
+}
+
+/// Reads the answer to a question `confirm` just printed; with `assume_yes` nothing is read
+fn read_confirmation(assume_yes: bool, input: &mut impl BufRead) -> bool {
+    if assume_yes {
+        println!("y (--yes)");
+        return true;
+    }
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+    if input.read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Returns true when stdin is a terminal a user can answer prompts on
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal()
+}
+
+/**
+ * Asks the user to pick one of several options by number
+ *
+ * # Arguments
+ * * `question` - The question printed above the numbered options
+ * * `options` - The choices to list
+ *
+ * # Returns
+ * * `Option<usize>` - Index of the chosen option; `None` on an invalid answer or EOF
+ */
+pub fn pick(question: &str, options: &[String]) -> Option<usize> {
+    println!("❓ {}", question);
+    for (index, option) in options.iter().enumerate() {
+        println!("   {}. {}", index + 1, option);
+    }
+    print!("   Enter a number [1-{}]: ", options.len());
+    let _ = io::stdout().flush();
+    read_choice(options.len(), &mut io::stdin().lock())
+}
+
+/// Reads the 1-based number of one of `count` options, returning its index
+fn read_choice(count: usize, input: &mut impl BufRead) -> Option<usize> {
+    let mut answer = String::new();
+    input.read_line(&mut answer).ok()?;
+    let choice: usize = answer.trim().parse().ok()?;
+    (1..=count).contains(&choice).then(|| choice - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assume_yes_confirms_without_reading_stdin() {
+        assert!(read_confirmation(true, &mut "n\n".as_bytes()));
+        assert!(read_confirmation(false, &mut "Yes\n".as_bytes()));
+        assert!(!read_confirmation(false, &mut "n\n".as_bytes()));
+        assert!(!read_confirmation(false, &mut "".as_bytes()));
+    }
+
+    #[test]
+    fn picks_are_numbered_from_one() {
+        assert_eq!(read_choice(3, &mut " 2\n".as_bytes()), Some(1));
+        assert_eq!(read_choice(3, &mut "3".as_bytes()), Some(2));
+        assert_eq!(read_choice(3, &mut "0\n".as_bytes()), None);
+        assert_eq!(read_choice(3, &mut "4\n".as_bytes()), None);
+        assert_eq!(read_choice(3, &mut "8.3\n".as_bytes()), None);
+        assert_eq!(read_choice(3, &mut "".as_bytes()), None);
+    }
+}
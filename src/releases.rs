@@ -0,0 +1,821 @@
+/*!
+ * Release discovery for palawija
+ *
+ * Fetches the list of PHP releases published on php.net and provides the
+ * version comparison helpers used by the `available` command. The
+ * structured releases API is preferred; the HTML releases page and the
+ * php-src tags on GitHub are fallbacks.
+ */
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::date::Date;
+use crate::download;
+use crate::error::NetworkError;
+use crate::http_cache;
+use crate::paths;
+
+/// URL of the official PHP releases page
+pub const RELEASES_URL: &str = "https://www.php.net/releases/";
+
+/// php.net's JSON releases API; `&version=<major>` lists every release of that major
+pub const RELEASES_API_URL: &str = "https://www.php.net/releases/index.php?json";
+
+/// Base URL of the source tarballs named in the releases API
+const DISTRIBUTIONS_URL: &str = "https://www.php.net/distributions";
+
+/// GitHub API endpoint listing the tags of php-src, used when the releases page can't be parsed
+pub const TAGS_API_URL: &str = "https://api.github.com/repos/php/php-src/tags";
+
+/// Seconds each request for the release listing may take unless `--timeout` says otherwise
+pub const DEFAULT_FETCH_TIMEOUT: u64 = 30;
+
+/// Tags requested per page of the GitHub API (its maximum)
+const TAGS_PER_PAGE: usize = 100;
+
+static STATUS_ON_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Sends the progress of fetching the listing to stderr from now on, keeping stdout for results
+pub fn set_status_on_stderr() {
+    STATUS_ON_STDERR.store(true, AtomicOrdering::Relaxed);
+}
+
+/// Reports a step of fetching the release listing
+fn status(message: &str) {
+    if STATUS_ON_STDERR.load(AtomicOrdering::Relaxed) {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// Upper bound on tag pages fetched, to stay well inside the unauthenticated rate limit
+const MAX_TAG_PAGES: usize = 30;
+
+/// Where a list of versions came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReleaseSource {
+    /// The php.net JSON releases API
+    ReleasesApi,
+    /// The php.net releases page
+    ReleasesPage,
+    /// The php-src tags on GitHub
+    GitHubTags,
+}
+
+impl std::fmt::Display for ReleaseSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReleaseSource::ReleasesApi => write!(f, "php.net releases API"),
+            ReleaseSource::ReleasesPage => write!(f, "php.net releases page"),
+            ReleaseSource::GitHubTags => write!(f, "GitHub php/php-src tags"),
+        }
+    }
+}
+
+/// Versions found by `fetch_available_versions`, with their source
+#[derive(Debug)]
+pub struct ReleaseList {
+    /// Versions sorted newest first, without duplicates
+    pub versions: Vec<String>,
+    /// Which source produced them
+    pub source: ReleaseSource,
+    /// Release dates and tarball URLs, by version; only the releases API provides them
+    pub details: BTreeMap<String, ReleaseDetails>,
+}
+
+/// What the releases API knows about one release
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReleaseDetails {
+    /// Release date as published (e.g. "23 Nov 2023")
+    pub date: Option<String>,
+    /// Download URLs of the source tarballs
+    pub source_urls: Vec<String>,
+}
+
+impl ReleaseList {
+    /// The published release date of `version`, if known
+    pub fn date(&self, version: &str) -> Option<&str> {
+        self.details.get(version)?.date.as_deref()
+    }
+
+    /// The release date of `version` as a date, if known and readable
+    pub fn release_date(&self, version: &str) -> Option<Date> {
+        Date::parse_published(self.date(version)?)
+    }
+}
+
+/// Bounds of `available --released-after/--released-before`, both inclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReleaseWindow {
+    pub after: Option<Date>,
+    pub before: Option<Date>,
+}
+
+impl ReleaseWindow {
+    /// Whether any bound is set
+    pub fn is_set(&self) -> bool {
+        self.after.is_some() || self.before.is_some()
+    }
+
+    /// Whether a release on `date` falls inside the window
+    pub fn contains(&self, date: Date) -> bool {
+        self.after.is_none_or(|after| date >= after) && self.before.is_none_or(|before| date <= before)
+    }
+}
+
+impl std::fmt::Display for ReleaseWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.after, self.before) {
+            (Some(after), Some(before)) => write!(f, "between {} and {}", after, before),
+            (Some(after), None) => write!(f, "on or after {}", after),
+            (None, Some(before)) => write!(f, "on or before {}", before),
+            (None, None) => write!(f, "at any time"),
+        }
+    }
+}
+
+/**
+ * Fetches every published PHP version
+ *
+ * The php.net releases API is tried first. If it fails or returns nothing,
+ * the HTML releases page is scraped, and if that yields no versions either
+ * (e.g. after a site redesign), the php-src tags on GitHub are used instead.
+ * php.net answers go through `http_cache`, so repeated runs revalidate
+ * rather than download them again.
+ *
+ * # Arguments
+ * * `timeout` - Seconds each request may take, 0 for no limit
+ *
+ * # Returns
+ * * `Result<ReleaseList, Box<dyn std::error::Error>>` - Versions sorted newest first,
+ *   and the source they came from
+ */
+pub fn fetch_available_versions(timeout: u64) -> Result<ReleaseList, Box<dyn std::error::Error>> {
+    match fetch_releases_api(timeout) {
+        Ok(details) if !details.is_empty() => {
+            let mut versions: Vec<String> = details.keys().cloned().collect();
+            versions.sort_by(|a, b| compare_versions(b, a));
+            return Ok(ReleaseList { versions, source: ReleaseSource::ReleasesApi, details });
+        }
+        // Every other source is on the network too, so don't wait for them all to time out
+        Err(e) if matches!(e.downcast_ref::<NetworkError>(), Some(NetworkError::Timeout { .. })) => return Err(e),
+        Ok(_) => status("⚠️  The releases API listed no versions, falling back to the releases page"),
+        Err(e) => status(&format!("⚠️  Releases API unavailable ({}), falling back to the releases page", e)),
+    }
+
+    let versions = fetch_releases_page(timeout)?;
+    if !versions.is_empty() {
+        return Ok(ReleaseList { versions, source: ReleaseSource::ReleasesPage, details: BTreeMap::new() });
+    }
+
+    status("⚠️  No versions found on the releases page, falling back to GitHub tags");
+    Ok(ReleaseList { versions: fetch_github_tags(timeout)?, source: ReleaseSource::GitHubTags, details: BTreeMap::new() })
+}
+
+/**
+ * Fetches every release from the php.net JSON API
+ *
+ * The summary endpoint names the major versions; each major is then listed
+ * in full with `&version=<major>&max=1000`.
+ *
+ * # Returns
+ * * `Result<BTreeMap<String, ReleaseDetails>, Box<dyn std::error::Error>>` - Details by version
+ */
+fn fetch_releases_api(timeout: u64) -> Result<BTreeMap<String, ReleaseDetails>, Box<dyn std::error::Error>> {
+    status(&format!("📡 Connecting to {}...", RELEASES_API_URL));
+    let summary: serde_json::Value = serde_json::from_str(&http_cache::get(RELEASES_API_URL, timeout, true)?)?;
+    let majors: Vec<&String> = summary.as_object()
+        .ok_or("unexpected response from the releases API")?
+        .keys()
+        .filter(|major| major.chars().all(|c| c.is_ascii_digit()))
+        .collect();
+
+    let mut details = BTreeMap::new();
+    for major in majors {
+        let url = format!("{}&version={}&max=1000", RELEASES_API_URL, major);
+        details.extend(parse_release_api(&serde_json::from_str(&http_cache::get(&url, timeout, true)?)?));
+    }
+    status(&format!("✅ Successfully retrieved {} releases", details.len()));
+    Ok(details)
+}
+
+fn fetch_json(url: &str, timeout: u64) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    let mut curl = Command::new("curl");
+    curl.arg("-s")              // Silent mode
+        .arg("-f")              // Fail on HTTP errors
+        .arg("-L");             // Follow redirects
+    download::set_timeout(&mut curl, timeout);
+    let output = curl.arg(url).output()?;
+    if !output.status.success() {
+        return Err(NetworkError::from_curl(output.status, url, timeout).into());
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/**
+ * Reads the releases of one `&version=<major>` API response
+ *
+ * The response maps each version to its announcement data; entries whose key
+ * isn't a version are skipped.
+ */
+pub fn parse_release_api(response: &serde_json::Value) -> BTreeMap<String, ReleaseDetails> {
+    let Some(releases) = response.as_object() else {
+        return BTreeMap::new();
+    };
+    releases.iter()
+        .filter(|(version, _)| version.contains('.') && version.chars().all(|c| c.is_ascii_alphanumeric() || c == '.'))
+        .map(|(version, release)| {
+            let source_urls = release.get("source")
+                .and_then(|sources| sources.as_array())
+                .map(|sources| sources.iter()
+                    .filter_map(|source| source.get("filename")?.as_str())
+                    .map(|filename| format!("{}/{}", DISTRIBUTIONS_URL, filename))
+                    .collect())
+                .unwrap_or_default();
+            let date = release.get("date").and_then(|date| date.as_str()).map(str::to_string);
+            (version.clone(), ReleaseDetails { date, source_urls })
+        })
+        .collect()
+}
+
+/// Announcement and changelog links of one release (`info --remote`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseNotes {
+    pub version: String,
+    /// Release date as published (e.g. "21 Dec 2023")
+    pub date: Option<String>,
+    /// Tags such as "security"
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// The release announcement, if php.net published one
+    pub announcement: Option<String>,
+    /// The version's changelog entry, or the major's changelog page for older releases
+    pub changelog: String,
+    /// Whether the releases API knew the version; older releases predate it
+    pub from_api: bool,
+}
+
+/**
+ * Fetches the release notes of one version, cached in `~/.palawija/cache/releases/`
+ *
+ * Published releases don't change, so a cached entry is used as it is. Only
+ * answers from the releases API are cached; versions it doesn't know get the
+ * generic `ChangeLog-<major>.php` link.
+ *
+ * # Arguments
+ * * `version` - A full version such as 8.3.1
+ * * `timeout` - Seconds the request may take, 0 for no limit
+ *
+ * # Returns
+ * * `Result<ReleaseNotes, Box<dyn std::error::Error>>` - The notes, or the network error
+ */
+pub fn fetch_release_notes(version: &str, timeout: u64) -> Result<ReleaseNotes, Box<dyn std::error::Error>> {
+    let cache_path = paths::cache_dir()?.join("releases").join(format!("{}.json", version));
+    if let Ok(content) = std::fs::read_to_string(&cache_path) {
+        match serde_json::from_str(&content) {
+            Ok(notes) => return Ok(notes),
+            Err(e) => eprintln!("⚠️  Ignoring corrupt release cache {}: {}", cache_path.display(), e),
+        }
+    }
+
+    let url = format!("{}&version={}", RELEASES_API_URL, version);
+    println!("📡 Connecting to {}...", url);
+    let notes = parse_release_notes(version, &fetch_json(&url, timeout)?);
+    if notes.from_api
+        && let Err(e) = atomic::write_json_atomic(&cache_path, &notes)
+    {
+        eprintln!("⚠️  Could not cache the release notes: {}", e);
+    }
+    Ok(notes)
+}
+
+/**
+ * Reads a `&version=<full version>` releases API response
+ *
+ * The API answers `{"error": ...}` for versions it doesn't list, which
+ * includes releases older than the API itself.
+ */
+pub fn parse_release_notes(version: &str, response: &serde_json::Value) -> ReleaseNotes {
+    let major = version.split('.').next().unwrap_or_default();
+    let known = response.get("error").is_none() && response.get("date").is_some();
+    if !known {
+        return ReleaseNotes {
+            version: version.to_string(),
+            date: None,
+            tags: Vec::new(),
+            announcement: None,
+            changelog: format!("https://www.php.net/ChangeLog-{}.php", major),
+            from_api: false,
+        };
+    }
+
+    let announced = response.get("announcement").and_then(|announcement| announcement.as_bool()).unwrap_or(false);
+    ReleaseNotes {
+        version: version.to_string(),
+        date: response.get("date").and_then(|date| date.as_str()).map(str::to_string),
+        tags: response.get("tags")
+            .and_then(|tags| tags.as_array())
+            .map(|tags| tags.iter().filter_map(|tag| tag.as_str()).map(str::to_string).collect())
+            .unwrap_or_default(),
+        announcement: announced.then(|| format!("https://www.php.net/releases/{}.php", version.replace('.', "_"))),
+        changelog: format!("https://www.php.net/ChangeLog-{}.php#{}", major, version),
+        from_api: true,
+    }
+}
+
+/// Fetches the releases page and returns every version found on it
+fn fetch_releases_page(timeout: u64) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    status(&format!("📡 Connecting to {}...", RELEASES_URL));
+    let html = http_cache::get(RELEASES_URL, timeout, false)?;
+
+    status("✅ Successfully retrieved releases page");
+    status("🔍 Parsing available versions...\n");
+
+    Ok(parse_release_versions(&html))
+}
+
+/**
+ * Fetches the `php-*` tags of php-src from the GitHub API
+ *
+ * Pages are requested until one comes back short or `MAX_TAG_PAGES` is reached.
+ *
+ * # Returns
+ * * `Result<Vec<String>, Box<dyn std::error::Error>>` - Versions sorted newest first,
+ *   without duplicates
+ */
+fn fetch_github_tags(timeout: u64) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    status(&format!("📡 Connecting to {}...", TAGS_API_URL));
+
+    let mut names = Vec::new();
+    for page in 1..=MAX_TAG_PAGES {
+        let mut curl = Command::new("curl");
+        curl.arg("-s")              // Silent mode
+            .arg("-f")              // Fail on HTTP errors (e.g. rate limiting)
+            .arg("-L");             // Follow redirects
+        download::set_timeout(&mut curl, timeout);
+        let output = curl
+            .arg("-H")
+            .arg("Accept: application/vnd.github+json")
+            .arg(format!("{}?per_page={}&page={}", TAGS_API_URL, TAGS_PER_PAGE, page))
+            .output()?;
+
+        if !output.status.success() {
+            return match NetworkError::from_curl(output.status, TAGS_API_URL, timeout) {
+                NetworkError::Failed { .. } => {
+                    Err("🌐 Failed to fetch php-src tags from GitHub. Check your internet connection or try again later.".into())
+                }
+                error => Err(error.into()),
+            };
+        }
+
+        let tags: Vec<Tag> = serde_json::from_slice(&output.stdout)
+            .map_err(|e| format!("❌ Unexpected response from the GitHub tags API: {}", e))?;
+        let count = tags.len();
+        names.extend(tags.into_iter().map(|tag| tag.name));
+        if count < TAGS_PER_PAGE {
+            break;
+        }
+    }
+
+    status(&format!("✅ Successfully retrieved {} tags", names.len()));
+    status("🔍 Parsing available versions...\n");
+    Ok(parse_tag_versions(names.iter().map(String::as_str)))
+}
+
+/// One entry of the GitHub tags API response
+#[derive(serde::Deserialize)]
+struct Tag {
+    name: String,
+}
+
+/**
+ * Turns php-src tag names into versions
+ *
+ * Only `php-<version>` tags are kept (`php-8.3.0` becomes `8.3.0`); branch
+ * markers and other tags are ignored.
+ *
+ * # Returns
+ * * `Vec<String>` - Versions sorted newest first, without duplicates
+ */
+pub fn parse_tag_versions<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut versions: Vec<String> = names.into_iter()
+        .filter_map(|name| name.strip_prefix("php-"))
+        .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()) && version.contains('.'))
+        .filter(|version| version.chars().all(|c| c.is_ascii_alphanumeric() || c == '.'))
+        .map(|version| version.to_string())
+        .collect();
+
+    versions.sort_by(|a, b| compare_versions(b, a));
+    versions.dedup();
+    versions
+}
+
+/**
+ * Extracts PHP version numbers from the releases page HTML
+ *
+ * # Arguments
+ * * `html` - Raw HTML of the releases page
+ *
+ * # Returns
+ * * `Vec<String>` - Versions sorted newest first, without duplicates
+ */
+pub fn parse_release_versions(html: &str) -> Vec<String> {
+    let mut versions = Vec::new();
+
+    // Parse HTML to extract PHP version numbers
+    for line in html.lines() {
+        if line.contains("php-") && line.contains(".tar.gz")
+            && let Some(start) = line.find("php-")
+        {
+            let start_idx = start + 4;
+            if let Some(end) = line[start_idx..].find(".tar.gz") {
+                let version = &line[start_idx..start_idx + end];
+                // Validate version format (should contain dots and numbers)
+                if version.contains('.') && version.chars().any(|c| c.is_numeric()) {
+                    versions.push(version.to_string());
+                }
+            }
+        }
+    }
+
+    // Sort versions in descending order (newest first)
+    versions.sort_by(|a, b| compare_versions(b, a));
+    versions.dedup(); // Remove duplicates
+    versions
+}
+
+/// One comma-separated part of an `available` filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionFilter {
+    /// Matches a branch or major series (e.g. "8" or "8.2")
+    Prefix(String),
+    /// Matches a single full version (e.g. "8.2.15")
+    Exact(String),
+}
+
+impl VersionFilter {
+    /**
+     * Parses a filter such as "8", "8.1,8.2" or "8.2.15"
+     *
+     * # Returns
+     * * `Result<Vec<VersionFilter>, String>` - One filter per comma-separated
+     *   part, or a message naming the first invalid part
+     */
+    pub fn parse_list(input: &str) -> Result<Vec<Self>, String> {
+        input.split(',').map(|part| Self::parse(part.trim())).collect()
+    }
+
+    fn parse(part: &str) -> Result<Self, String> {
+        let components: Vec<&str> = part.split('.').collect();
+        let numeric = |c: &&str| !c.is_empty() && c.chars().all(|ch| ch.is_ascii_digit());
+        let valid = match components.as_slice() {
+            [_] | [_, _] => components.iter().all(numeric),
+            [major, minor, patch] => {
+                numeric(major) && numeric(minor)
+                    && patch.starts_with(|ch: char| ch.is_ascii_digit())
+                    && patch.chars().all(|ch| ch.is_ascii_alphanumeric())
+            }
+            _ => false,
+        };
+        if !valid {
+            return Err(format!("'{}' is not a version or version prefix (e.g. 8, 8.2 or 8.2.15)", part));
+        }
+
+        Ok(if components.len() == 3 {
+            VersionFilter::Exact(part.to_string())
+        } else {
+            VersionFilter::Prefix(part.to_string())
+        })
+    }
+
+    /// Returns true if `version` is selected by this filter
+    pub fn matches(&self, version: &str) -> bool {
+        match self {
+            VersionFilter::Prefix(prefix) => version.starts_with(&format!("{}.", prefix)),
+            VersionFilter::Exact(exact) => version == exact,
+        }
+    }
+}
+
+/// Versions selected by any of `filters` (the union), in their original order
+pub fn filter_versions<'a>(versions: &'a [String], filters: &[VersionFilter]) -> Vec<&'a String> {
+    versions.iter()
+        .filter(|version| filters.iter().any(|filter| filter.matches(version)))
+        .collect()
+}
+
+/**
+ * Splits a version into the release it builds and a build or vendor suffix
+ *
+ * The suffix follows the first `+` or `-`, as in "8.2.15-custom" or
+ * "8.3.0+deb12". It names the install (and its directory) but plays no
+ * part in branch, status or release comparisons.
+ *
+ * # Returns
+ * * `(&str, Option<&str>)` - The release ("8.2.15") and the suffix without its separator
+ */
+pub fn split_suffix(version: &str) -> (&str, Option<&str>) {
+    match version.find(['+', '-']) {
+        Some(index) => (&version[..index], Some(&version[index + 1..])),
+        None => (version, None),
+    }
+}
+
+/// The php.net release a version builds, without any build or vendor suffix
+pub fn release_of(version: &str) -> &str {
+    split_suffix(version).0
+}
+
+/**
+ * Splits a version into its numeric components
+ *
+ * Only the leading digits of each dot-separated component are used, so
+ * "8.4.0RC1" yields `[8, 4, 0]`; a build or vendor suffix is ignored.
+ */
+pub fn version_parts(version: &str) -> Vec<u32> {
+    release_of(version)
+        .split('.')
+        .map_while(|part| {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+/// Returns true for alpha, beta and release-candidate versions (e.g. "8.4.0RC1") and `-dev` snapshots
+pub fn is_prerelease(version: &str) -> bool {
+    let (release, suffix) = split_suffix(version);
+    release.chars().any(|c| c.is_ascii_alphabetic()) || suffix == Some("dev")
+}
+
+/// Returns true if `input` names a release series ("latest", "8" or "8.3") rather than one release
+pub fn is_version_series(input: &str) -> bool {
+    input == "latest" || matches!(VersionFilter::parse_list(input).as_deref(), Ok([VersionFilter::Prefix(_)]))
+}
+
+/**
+ * Picks the newest release of a series such as "latest" or "8.3"
+ *
+ * Prereleases are skipped unless `include_prerelease` is set, so a series is
+ * never silently resolved to an RC.
+ *
+ * # Arguments
+ * * `versions` - Available versions, newest first
+ * * `series` - "latest" or a major/minor prefix
+ * * `include_prerelease` - Also consider alpha, beta and RC releases
+ */
+pub fn newest_in_series<'a>(versions: &'a [String], series: &str, include_prerelease: bool) -> Option<&'a String> {
+    let prefix = format!("{}.", series);
+    versions.iter()
+        .filter(|version| series == "latest" || version.starts_with(&prefix))
+        .find(|version| include_prerelease || !is_prerelease(version))
+}
+
+/**
+ * Groups versions by their major.minor branch
+ *
+ * # Returns
+ * * `Vec<(String, Vec<&String>)>` - Branches in order of first appearance, each
+ *   with its versions in their original order
+ */
+pub fn group_by_branch(versions: &[String]) -> Vec<(String, Vec<&String>)> {
+    let mut groups: Vec<(String, Vec<&String>)> = Vec::new();
+    for version in versions {
+        let branch = branch_of(version);
+        match groups.iter_mut().find(|(existing, _)| *existing == branch) {
+            Some((_, members)) => members.push(version),
+            None => groups.push((branch, vec![version])),
+        }
+    }
+    groups
+}
+
+/// Returns the major.minor branch of a version (e.g. "8.2.15" -> "8.2")
+pub fn branch_of(version: &str) -> String {
+    version.split('.').take(2).collect::<Vec<_>>().join(".")
+}
+
+/**
+ * Picks the version spelled most like `input`, for "did you mean" hints
+ *
+ * Distance is the Levenshtein edit distance; ties go to the version listed
+ * first, i.e. the newest when `versions` is sorted newest first.
+ */
+pub fn closest_version<'a>(versions: &'a [String], input: &str) -> Option<&'a String> {
+    versions.iter().min_by_key(|version| edit_distance(version, input))
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/**
+ * Orders two versions numerically, placing a prerelease before its final release
+ *
+ * Prereleases of one release go dev < alpha < beta < RC, each stage by its
+ * number (RC2 before RC10). Builds of the same release differing only in
+ * their suffix sort after the plain release, alphabetically by suffix.
+ *
+ * # Returns
+ * * `Ordering` - How `a` compares to `b` (`Greater` means `a` is newer)
+ */
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    version_parts(a)
+        .cmp(&version_parts(b))
+        .then_with(|| release_stage(a).cmp(&release_stage(b)))
+        .then_with(|| a.cmp(b))
+}
+
+/**
+ * Where a version stands on the way to its final release
+ *
+ * # Returns
+ * * `(u8, u32)` - The stage (0 for `-dev` snapshots and unknown tags, then
+ *   alpha, beta and RC, 4 for a final release) and the stage's number
+ */
+fn release_stage(version: &str) -> (u8, u32) {
+    let (release, suffix) = split_suffix(version);
+    if suffix == Some("dev") {
+        return (0, 0);
+    }
+    let Some(start) = release.find(|c: char| c.is_ascii_alphabetic()) else {
+        return (4, 0);
+    };
+    let tag = &release[start..];
+    let digits = tag.find(|c: char| c.is_ascii_digit()).unwrap_or(tag.len());
+    let number = tag[digits..].parse().unwrap_or(0);
+    match tag[..digits].to_ascii_lowercase().as_str() {
+        "alpha" => (1, number),
+        "beta" => (2, number),
+        "rc" => (3, number),
+        _ => (0, number),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_api_responses_are_parsed() {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!("../tests/fixtures/releases-api-8.json")).unwrap();
+        let releases = parse_release_api(&fixture);
+
+        assert_eq!(releases.keys().collect::<Vec<_>>(), ["8.2.15", "8.3.0", "8.3.1"]);
+        assert_eq!(releases["8.3.1"].date.as_deref(), Some("21 Dec 2023"));
+        assert_eq!(releases["8.3.0"].source_urls, [
+            "https://www.php.net/distributions/php-8.3.0.tar.gz",
+            "https://www.php.net/distributions/php-8.3.0.tar.bz2",
+            "https://www.php.net/distributions/php-8.3.0.tar.xz",
+        ]);
+        assert!(parse_release_api(&serde_json::json!([])).is_empty());
+    }
+
+    #[test]
+    fn release_notes_link_to_the_announcement_and_changelog() {
+        let fixture: serde_json::Value = serde_json::from_str(include_str!("../tests/fixtures/releases-api-8.json")).unwrap();
+        let notes = parse_release_notes("8.3.1", &fixture["8.3.1"]);
+        assert_eq!(notes.date.as_deref(), Some("21 Dec 2023"));
+        assert_eq!(notes.tags, ["security"]);
+        assert_eq!(notes.announcement.as_deref(), Some("https://www.php.net/releases/8_3_1.php"));
+        assert_eq!(notes.changelog, "https://www.php.net/ChangeLog-8.php#8.3.1");
+
+        let old = parse_release_notes("5.1.2", &serde_json::json!({"error": "Unknown version"}));
+        assert!(!old.from_api);
+        assert_eq!(old.announcement, None);
+        assert_eq!(old.changelog, "https://www.php.net/ChangeLog-5.php");
+    }
+
+    fn versions() -> Vec<String> {
+        ["8.3.1", "8.3.0", "8.2.15", "8.2.1", "8.1.27", "7.4.33"].iter().map(|v| v.to_string()).collect()
+    }
+
+    fn select(filter: &str) -> Vec<String> {
+        let versions = versions();
+        let filters = VersionFilter::parse_list(filter).unwrap();
+        filter_versions(&versions, &filters).into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn single_prefix() {
+        assert_eq!(select("8.2"), ["8.2.15", "8.2.1"]);
+        assert_eq!(select("7"), ["7.4.33"]);
+    }
+
+    #[test]
+    fn multiple_prefixes_are_unioned() {
+        assert_eq!(select("8.1, 8.3"), ["8.3.1", "8.3.0", "8.1.27"]);
+        assert_eq!(select("8.2,8"), ["8.3.1", "8.3.0", "8.2.15", "8.2.1", "8.1.27"]);
+    }
+
+    #[test]
+    fn full_version_matches_exactly() {
+        assert_eq!(select("8.2.1"), ["8.2.1"]);
+        assert_eq!(VersionFilter::parse_list("8.4.0RC1").unwrap(), [VersionFilter::Exact("8.4.0RC1".into())]);
+    }
+
+    #[test]
+    fn versions_are_grouped_by_branch() {
+        let versions = versions();
+        let groups = group_by_branch(&versions);
+        let summary: Vec<(&str, usize)> = groups.iter().map(|(branch, members)| (branch.as_str(), members.len())).collect();
+        assert_eq!(summary, [("8.3", 2), ("8.2", 2), ("8.1", 1), ("7.4", 1)]);
+        assert_eq!(groups[0].1[0], "8.3.1");
+    }
+
+    #[test]
+    fn prefix_without_matches() {
+        assert!(select("5.6").is_empty());
+    }
+
+    #[test]
+    fn php_tags_become_versions() {
+        let tags = ["php-8.2.15", "php-8.3.0", "php-8.3.0RC1", "PHP-5.6", "php-src-v1", "php-8.3.0"];
+        assert_eq!(parse_tag_versions(tags), ["8.3.0", "8.3.0RC1", "8.2.15"]);
+    }
+
+    #[test]
+    fn series_resolve_to_stable_releases_by_default() {
+        let versions: Vec<String> = ["8.4.0RC1", "8.3.1", "8.3.0", "8.2.15"].iter().map(|v| v.to_string()).collect();
+        assert_eq!(newest_in_series(&versions, "latest", false).map(String::as_str), Some("8.3.1"));
+        assert_eq!(newest_in_series(&versions, "latest", true).map(String::as_str), Some("8.4.0RC1"));
+        assert_eq!(newest_in_series(&versions, "8.4", false), None);
+        assert_eq!(newest_in_series(&versions, "8", false).map(String::as_str), Some("8.3.1"));
+        assert!(is_version_series("latest") && is_version_series("8.3"));
+        assert!(!is_version_series("8.4.0RC1"));
+    }
+
+    #[test]
+    fn closest_version_is_suggested() {
+        let versions: Vec<String> = ["8.3.1", "8.3.0", "8.2.15", "7.4.33"].iter().map(|v| v.to_string()).collect();
+        assert_eq!(edit_distance("8.9.0", "8.3.0"), 1);
+        assert_eq!(closest_version(&versions, "8.9.0").map(String::as_str), Some("8.3.0"));
+        assert_eq!(closest_version(&versions, "7.4.3").map(String::as_str), Some("7.4.33"));
+        assert_eq!(closest_version(&[], "8.3.0"), None);
+    }
+
+    #[test]
+    fn invalid_filters_are_rejected() {
+        assert!(VersionFilter::parse_list("").is_err());
+        assert!(VersionFilter::parse_list("8.").is_err());
+        assert!(VersionFilter::parse_list("8.2,latest").is_err());
+        assert!(VersionFilter::parse_list("8.2.1.4").is_err());
+    }
+
+    #[test]
+    fn build_suffixes_are_kept_out_of_comparisons() {
+        assert_eq!(split_suffix("8.2.15-custom"), ("8.2.15", Some("custom")));
+        assert_eq!(split_suffix("8.3.0+deb12.1"), ("8.3.0", Some("deb12.1")));
+        assert_eq!(split_suffix("8.3.0"), ("8.3.0", None));
+        assert_eq!(version_parts("8.3.0+deb12.1"), [8, 3, 0]);
+        assert_eq!(branch_of("8.2.15-custom"), "8.2");
+        assert!(!is_prerelease("8.2.15-custom"));
+        assert!(is_prerelease("8.4.0-dev"));
+        assert!(is_prerelease("8.4.0RC1-custom"));
+        assert_eq!(compare_versions("8.2.15-custom", "8.2.15"), Ordering::Greater);
+        assert_eq!(compare_versions("8.2.15-custom", "8.2.16"), Ordering::Less);
+    }
+
+    #[test]
+    fn prereleases_are_ordered_by_stage_and_number() {
+        let mut versions = ["8.4.0", "8.4.0RC10", "8.4.0beta1", "8.4.0RC2", "8.4.0alpha3", "8.4.0RC1", "8.4.0-dev", "8.3.14", "8.4.1"]
+            .map(String::from).to_vec();
+        versions.sort_by(|a, b| compare_versions(a, b));
+        assert_eq!(versions, ["8.3.14", "8.4.0-dev", "8.4.0alpha3", "8.4.0beta1", "8.4.0RC1", "8.4.0RC2", "8.4.0RC10", "8.4.0", "8.4.1"]);
+        assert_eq!(compare_versions("8.4.0rc3", "8.4.0RC2"), Ordering::Greater);
+        assert_eq!(compare_versions("8.10.0", "8.9.0"), Ordering::Greater);
+        assert_eq!(compare_versions("8.3.0", "8.3.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn release_windows_include_their_bounds() {
+        assert_eq!(Date::parse_published("21 Dec 2023"), Some(Date::new(2023, 12, 21)));
+        assert_eq!(Date::parse_published("1 Sept 2022"), Some(Date::new(2022, 9, 1)));
+        assert_eq!(Date::parse_published("31 Feb 2023"), None);
+
+        let window = ReleaseWindow { after: Some(Date::new(2023, 1, 1)), before: Some(Date::new(2023, 12, 21)) };
+        assert!(window.contains(Date::new(2023, 1, 1)));
+        assert!(window.contains(Date::new(2023, 12, 21)));
+        assert!(!window.contains(Date::new(2022, 12, 31)));
+        assert!(!window.contains(Date::new(2024, 1, 1)));
+        assert!(!ReleaseWindow::default().is_set());
+    }
+}
@@ -0,0 +1,187 @@
+/*!
+ * Shareable diagnostic bundle (`palawija doctor --report`)
+ *
+ * Collects what maintainers usually ask for in a bug report and prints it as
+ * one Markdown or JSON block. The only redaction is the home directory
+ * prefix, which is shown as `~`.
+ */
+
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use serde::Serialize;
+
+use crate::installs;
+use crate::paths;
+use crate::pin;
+use crate::self_update;
+
+/// Environment variables included in the report when set
+const REPORTED_VARS: &[&str] = &[
+    "HOME", "PATH", "SHELL",
+    "http_proxy", "https_proxy", "no_proxy", "HTTP_PROXY", "HTTPS_PROXY", "NO_PROXY", "ALL_PROXY",
+    "PALAWIJA_LIMIT_RATE",
+];
+
+/// Everything `doctor --report` collects
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub palawija_version: String,
+    pub os: Option<String>,
+    pub kernel: Option<String>,
+    pub arch: String,
+    pub env: BTreeMap<String, String>,
+    pub install_root: String,
+    pub installs: Vec<ReportedInstall>,
+    /// The global php symlink
+    pub link: String,
+    /// Where the global php symlink points, if it is one
+    pub active_link: Option<String>,
+    pub pinned: Option<String>,
+}
+
+/// One installed version as listed in the report
+#[derive(Debug, Serialize)]
+pub struct ReportedInstall {
+    pub version: String,
+    pub compiled: bool,
+    pub active: bool,
+}
+
+impl Report {
+    /// Gathers the report, with the home prefix already replaced by `~`
+    pub fn collect() -> Result<Self, Box<dyn std::error::Error>> {
+        let home = paths::palawija_home()?.display().to_string();
+        let install_root = paths::install_root()?;
+        let active = paths::active_version();
+
+        let installs = installs::scan(&install_root)
+            .map(|scan| scan.versions)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|version| ReportedInstall {
+                compiled: paths::php_binary(&install_root.join(format!("php-{}", version))).is_file(),
+                active: active.as_deref() == Some(version.as_str()),
+                version,
+            })
+            .collect();
+
+        let env = REPORTED_VARS.iter()
+            .filter_map(|var| std::env::var(var).ok().map(|value| (var.to_string(), redact_home(&value, &home))))
+            .collect();
+
+        Ok(Report {
+            palawija_version: self_update::CURRENT_VERSION.to_string(),
+            os: std::fs::read_to_string("/etc/os-release").ok().and_then(|content| os_name(&content)),
+            kernel: uname(),
+            arch: std::env::consts::ARCH.to_string(),
+            env,
+            install_root: redact_home(&install_root.display().to_string(), &home),
+            installs,
+            link: redact_home(&paths::php_link().display().to_string(), &home),
+            active_link: paths::active_php_binary().map(|target| redact_home(&target.display().to_string(), &home)),
+            pinned: pin::read_pin(),
+        })
+    }
+
+    /// The report as a Markdown block for an issue
+    pub fn to_markdown(&self) -> String {
+        let or_unknown = |value: &Option<String>| value.clone().unwrap_or_else(|| "unknown".to_string());
+        let mut out = String::from("## palawija diagnostic report\n\n");
+        out.push_str(&format!("- **palawija**: {}\n", self.palawija_version));
+        out.push_str(&format!("- **OS**: {}\n", or_unknown(&self.os)));
+        out.push_str(&format!("- **Kernel**: {}\n", or_unknown(&self.kernel)));
+        out.push_str(&format!("- **Arch**: {}\n", self.arch));
+        out.push_str(&format!(
+            "- **Active symlink**: `{}` -> {}\n",
+            self.link,
+            self.active_link.as_deref().map(|target| format!("`{}`", target)).unwrap_or_else(|| "none".to_string())
+        ));
+        out.push_str(&format!("- **Pinned**: {}\n", self.pinned.as_deref().unwrap_or("none")));
+
+        out.push_str("\n### Environment\n\n| Variable | Value |\n|---|---|\n");
+        for (var, value) in &self.env {
+            out.push_str(&format!("| {} | `{}` |\n", var, value));
+        }
+
+        out.push_str(&format!("\n### Installed versions (`{}`)\n\n", self.install_root));
+        if self.installs.is_empty() {
+            out.push_str("None\n");
+        } else {
+            out.push_str("| Version | Compiled | Active |\n|---|---|---|\n");
+            for install in &self.installs {
+                out.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    install.version,
+                    if install.compiled { "yes" } else { "no" },
+                    if install.active { "yes" } else { "" }
+                ));
+            }
+        }
+        out
+    }
+}
+
+/**
+ * Replaces the home directory prefix of every path in `value` with `~`
+ *
+ * Only whole path components match, so `/home/al` doesn't touch `/home/alice`;
+ * `:`-separated lists like PATH are handled entry by entry.
+ */
+pub fn redact_home(value: &str, home: &str) -> String {
+    let home = home.trim_end_matches('/');
+    if home.is_empty() {
+        return value.to_string();
+    }
+    value.split(':')
+        .map(|entry| match entry.strip_prefix(home) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("~{}", rest),
+            _ => entry.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// The distribution name from the contents of `/etc/os-release`
+fn os_name(os_release: &str) -> Option<String> {
+    let field = |key: &str| os_release.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+        .map(|value| value.trim().trim_matches('"').to_string());
+    field("PRETTY_NAME").or_else(|| field("NAME"))
+}
+
+fn uname() -> Option<String> {
+    let output = Command::new("uname").arg("-sr").output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Prints the report in the requested form
+pub fn print_report(json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let report = Report::collect()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.to_markdown());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn home_prefix_is_redacted() {
+        assert_eq!(redact_home("/home/al/.palawija", "/home/al"), "~/.palawija");
+        assert_eq!(redact_home("/home/al", "/home/al/"), "~");
+        assert_eq!(redact_home("/home/alice/bin", "/home/al"), "/home/alice/bin");
+        assert_eq!(redact_home("/home/al/bin:/usr/bin:/home/al/.local/bin", "/home/al"), "~/bin:/usr/bin:~/.local/bin");
+    }
+
+    #[test]
+    fn os_name_prefers_pretty_name() {
+        assert_eq!(os_name("NAME=\"Ubuntu\"\nPRETTY_NAME=\"Ubuntu 24.04 LTS\"\n").as_deref(), Some("Ubuntu 24.04 LTS"));
+        assert_eq!(os_name("NAME=Alpine\n").as_deref(), Some("Alpine"));
+        assert_eq!(os_name(""), None);
+    }
+}
@@ -0,0 +1,288 @@
+/*!
+ * JSON Schemas of palawija's machine-readable output (`palawija json-schema`)
+ *
+ * One schema per JSON format: `list --json`, `doctor --report --json`,
+ * the `--error-format json` object and the `install --progress json`
+ * events. The schemas are written by hand next to each other; the tests
+ * check them against the serialized structs, so a field added to one of
+ * those without a schema change fails the build.
+ */
+
+use serde_json::{Value, json};
+
+/// Dialect every schema declares
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// The JSON outputs with a schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Output {
+    /// `palawija list --json`
+    List,
+    /// `palawija doctor --report --json`
+    Doctor,
+    /// Failures with `--error-format json` (stderr)
+    Error,
+    /// `install --progress json` events, one per line
+    Progress,
+}
+
+/// The schema describing `output`
+pub fn schema(output: Output) -> Value {
+    let (title, mut schema) = match output {
+        Output::List => ("palawija list --json", list()),
+        Output::Doctor => ("palawija doctor --report --json", doctor_report()),
+        Output::Error => ("palawija --error-format json", error()),
+        Output::Progress => ("palawija install --progress json (one event per line)", progress()),
+    };
+    let object = schema.as_object_mut().expect("schemas are objects");
+    object.insert("$schema".to_string(), DIALECT.into());
+    object.insert("title".to_string(), title.into());
+    schema
+}
+
+fn list() -> Value {
+    json!({
+        "type": "object",
+        "required": ["install_root", "versions"],
+        "additionalProperties": false,
+        "properties": {
+            "install_root": { "type": "string" },
+            "versions": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["version", "path", "compiled", "active", "manifest"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "version": { "type": "string" },
+                        "path": { "type": "string" },
+                        "size_bytes": { "type": "integer", "minimum": 0, "description": "Only with --sizes" },
+                        "compiled": { "type": "boolean" },
+                        "active": { "type": "boolean" },
+                        "manifest": { "type": ["object", "null"], "properties": manifest_properties(),
+                                      "additionalProperties": false,
+                                      "required": ["version", "source_url", "sha256", "installed_at", "compiled", "configure_flags", "extensions"] }
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn manifest_properties() -> Value {
+    json!({
+        "version": { "type": "string" },
+        "source_url": { "type": "string" },
+        "sha256": { "type": ["string", "null"] },
+        "installed_at": { "type": "string", "description": "YYYY-MM-DD" },
+        "compiled": { "type": "boolean" },
+        "configure_flags": { "type": "array", "items": { "type": "string" } },
+        "extensions": { "type": "array", "items": { "type": "string" } },
+        "extension_files": {
+            "type": "array",
+            "items": {
+                "type": "object",
+                "required": ["name", "path", "ini_line"],
+                "additionalProperties": false,
+                "properties": {
+                    "name": { "type": "string" },
+                    "path": { "type": "string" },
+                    "ini_line": { "type": "string" }
+                }
+            }
+        },
+        "git_ref": { "type": "string" },
+        "git_commit": { "type": "string" }
+    })
+}
+
+fn doctor_report() -> Value {
+    json!({
+        "type": "object",
+        "required": ["palawija_version", "os", "kernel", "arch", "env", "install_root", "installs", "link", "active_link", "pinned"],
+        "additionalProperties": false,
+        "properties": {
+            "palawija_version": { "type": "string" },
+            "os": { "type": ["string", "null"] },
+            "kernel": { "type": ["string", "null"] },
+            "arch": { "type": "string" },
+            "env": { "type": "object", "additionalProperties": { "type": "string" } },
+            "install_root": { "type": "string" },
+            "installs": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["version", "compiled", "active"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "version": { "type": "string" },
+                        "compiled": { "type": "boolean" },
+                        "active": { "type": "boolean" }
+                    }
+                }
+            },
+            "link": { "type": "string" },
+            "active_link": { "type": ["string", "null"] },
+            "pinned": { "type": ["string", "null"] }
+        }
+    })
+}
+
+fn error() -> Value {
+    json!({
+        "type": "object",
+        "required": ["error"],
+        "additionalProperties": false,
+        "properties": {
+            "error": {
+                "type": "object",
+                "required": ["kind", "message"],
+                "additionalProperties": false,
+                "properties": {
+                    "kind": { "enum": ["error", "home_unavailable", "root_not_writable", "version_not_installed",
+                                       "version_not_compiled", "version_not_found", "network", "unsupported_platform",
+                                       "no_php", "unmanaged_php"] },
+                    "message": { "type": "string" },
+                    "reason": { "type": "string", "description": "home_unavailable: why; network: timeout, resolve, connect, http or failed" },
+                    "root": { "type": "string" },
+                    "read_only_filesystem": { "type": "boolean" },
+                    "version": { "type": "string" },
+                    "suggestion": { "type": ["string", "null"] },
+                    "os": { "type": "string" },
+                    "name": { "type": "string" },
+                    "path": { "type": "string" },
+                    "url": { "type": "string" },
+                    "timeout_seconds": { "type": "integer", "minimum": 0 },
+                    "http_status": { "type": "integer", "minimum": 100, "maximum": 599 },
+                    "curl_exit_code": { "type": ["integer", "null"] }
+                }
+            }
+        }
+    })
+}
+
+fn progress() -> Value {
+    let event = |phase: &str, fields: Value| {
+        let mut properties = json!({ "phase": { "const": phase } });
+        let mut required = vec![Value::from("phase")];
+        for (name, field) in fields.as_object().expect("fields are an object") {
+            properties[name] = field.clone();
+            required.push(name.clone().into());
+        }
+        json!({ "type": "object", "required": required, "additionalProperties": false, "properties": properties })
+    };
+    json!({
+        "oneOf": [
+            event("download", json!({ "pct": { "type": "integer", "minimum": 0, "maximum": 100 } })),
+            event("extract", json!({ "file": { "type": "string" } })),
+            event("configure", json!({})),
+            event("compile", json!({})),
+            event("install", json!({})),
+            event("done", json!({ "version": { "type": "string" } })),
+            event("error", json!({ "version": { "type": "string" }, "message": { "type": "string" } })),
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::error::{self, NetworkError, PalawijaError};
+    use crate::installs::InstallInfo;
+    use crate::manifest::{ExtensionFile, Manifest};
+    use crate::progress::Event;
+    use crate::report::{Report, ReportedInstall};
+
+    /// Checks the parts of JSON Schema these schemas use: type, const, enum, oneOf, required, properties, items
+    fn conforms(schema: &Value, value: &Value) -> bool {
+        if let Some(variants) = schema.get("oneOf").and_then(Value::as_array) {
+            return variants.iter().filter(|variant| conforms(variant, value)).count() == 1;
+        }
+        if schema.get("const").is_some_and(|expected| expected != value)
+            || schema.get("enum").and_then(Value::as_array).is_some_and(|allowed| !allowed.contains(value))
+        {
+            return false;
+        }
+        let type_name = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(n) if n.is_u64() || n.is_i64() => "integer",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let type_ok = match schema.get("type") {
+            Some(Value::String(expected)) => expected == type_name,
+            Some(Value::Array(expected)) => expected.iter().any(|t| t == type_name),
+            _ => true,
+        };
+        if !type_ok {
+            return false;
+        }
+        match value {
+            Value::Array(items) => schema.get("items").is_none_or(|item| items.iter().all(|value| conforms(item, value))),
+            Value::Object(fields) => {
+                let required = schema.get("required").and_then(Value::as_array).cloned().unwrap_or_default();
+                let properties = schema.get("properties").and_then(Value::as_object);
+                required.iter().filter_map(Value::as_str).all(|name| fields.contains_key(name))
+                    && fields.iter().all(|(name, field)| match (properties.and_then(|p| p.get(name)), schema.get("additionalProperties")) {
+                        (Some(property), _) => conforms(property, field),
+                        (None, Some(Value::Bool(false))) => false,
+                        (None, Some(extra @ Value::Object(_))) => conforms(extra, field),
+                        (None, _) => true,
+                    })
+            }
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn serialized_output_matches_the_schemas() {
+        let mut manifest = Manifest::new("8.3.0", "https://www.php.net/distributions/php-8.3.0.tar.xz", Some("abc".to_string()));
+        manifest.git_ref = Some("master".to_string());
+        manifest.git_commit = Some("0123456789".to_string());
+        manifest.extension_files = vec![ExtensionFile { name: "redis".to_string(), path: PathBuf::from("/x/redis.so"), ini_line: "extension=redis".to_string() }];
+        let info = |manifest, size_bytes| InstallInfo {
+            version: "8.3.0".to_string(), path: PathBuf::from("/home/me/.palawija/php-8.3.0"),
+            size_bytes, compiled: true, active: false, manifest,
+        };
+        let list = json!({ "install_root": "/home/me/.palawija", "versions": [info(Some(manifest), Some(1024)), info(None, None)] });
+        assert!(conforms(&schema(Output::List), &list));
+        assert!(!conforms(&schema(Output::List), &json!({ "install_root": "/", "versions": [{ "version": "8.3.0" }] })));
+
+        let report = Report {
+            palawija_version: "1.0.0".to_string(), os: None, kernel: Some("6.1".to_string()), arch: "x86_64".to_string(),
+            env: [("HOME".to_string(), "~".to_string())].into(), install_root: "~/.palawija".to_string(),
+            installs: vec![ReportedInstall { version: "8.3.0".to_string(), compiled: true, active: true }],
+            link: "/usr/local/bin/php".to_string(), active_link: None, pinned: Some("8.3.0".to_string()),
+        };
+        assert!(conforms(&schema(Output::Doctor), &serde_json::to_value(&report).unwrap()));
+
+        let errors: Vec<Box<dyn std::error::Error>> = vec![
+            "plain".into(),
+            PalawijaError::HomeUnavailable { reason: "unset".to_string() }.into(),
+            PalawijaError::RootNotWritable { root: "/r".to_string(), read_only_filesystem: true }.into(),
+            PalawijaError::VersionNotReleased { version: "8.9.9".to_string(), suggestion: None }.into(),
+            PalawijaError::UnsupportedPlatform { os: "macos".to_string() }.into(),
+            PalawijaError::NoPhpInPath { name: "php".to_string() }.into(),
+            PalawijaError::UnmanagedPhp { path: "/usr/bin/php".to_string() }.into(),
+            NetworkError::Timeout { url: "https://php.net".to_string(), seconds: 5 }.into(),
+            NetworkError::Http { url: "https://php.net".to_string(), status: 429 }.into(),
+            NetworkError::Failed { url: "https://php.net".to_string(), code: None }.into(),
+        ];
+        for failure in &errors {
+            assert!(conforms(&schema(Output::Error), &error::to_json("", failure.as_ref())), "{}", failure);
+        }
+
+        let events = [
+            Event::Download { pct: 42 }, Event::Extract { file: "php-8.3.0/configure" }, Event::Configure, Event::Compile,
+            Event::Install, Event::Done { version: "8.3.0" }, Event::Error { version: "8.3.0", message: "boom" },
+        ];
+        for event in &events {
+            assert!(conforms(&schema(Output::Progress), &serde_json::to_value(event).unwrap()), "{:?}", event);
+        }
+    }
+}
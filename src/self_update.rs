@@ -0,0 +1,239 @@
+/*!
+ * Upgrading the palawija binary itself (`palawija self-update`)
+ *
+ * The latest GitHub release is compared against the compiled-in version; the
+ * Linux binary asset for this architecture is downloaded next to the running
+ * executable, verified, and renamed over it so the swap is atomic. The
+ * temporary file is removed if anything fails or the update is interrupted.
+ */
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::Command;
+
+use serde::Deserialize;
+
+use crate::cleanup;
+use crate::download;
+use crate::releases;
+
+/// Version of this palawija build
+pub const CURRENT_VERSION: &str = "1.0.0";
+
+/// GitHub API endpoint describing the latest palawija release
+pub const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/agritaloka/palawija/releases/latest";
+
+/// The parts of a GitHub release palawija needs
+#[derive(Debug, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+/// A downloadable file attached to a release
+#[derive(Debug, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+    /// Checksum GitHub computed for the asset (e.g. "sha256:abc...")
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+impl Release {
+    /// The release version without a leading `v` (e.g. "v1.2.0" -> "1.2.0")
+    pub fn version(&self) -> &str {
+        self.tag_name.strip_prefix('v').unwrap_or(&self.tag_name)
+    }
+
+    /// The Linux binary asset for `arch` (e.g. "x86_64"), ignoring checksum files
+    pub fn binary_asset(&self, arch: &str) -> Option<&Asset> {
+        self.assets.iter().find(|asset| {
+            let name = asset.name.to_lowercase();
+            name.contains("linux") && name.contains(arch) && !name.ends_with(".sha256")
+        })
+    }
+
+    /// A `<asset>.sha256` file published alongside an asset, if any
+    pub fn checksum_asset(&self, asset: &Asset) -> Option<&Asset> {
+        let name = format!("{}.sha256", asset.name);
+        self.assets.iter().find(|candidate| candidate.name == name)
+    }
+}
+
+/**
+ * Updates the running palawija binary to the latest release
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Success or error details
+ */
+pub fn self_update() -> Result<(), Box<dyn std::error::Error>> {
+    println!("📡 Checking {}...", LATEST_RELEASE_URL);
+    let release = fetch_latest_release()?;
+    let latest = release.version();
+
+    println!("📦 Installed version: {}", CURRENT_VERSION);
+    println!("🌐 Latest release:    {}", latest);
+    if releases::compare_versions(latest, CURRENT_VERSION) != std::cmp::Ordering::Greater {
+        println!("\n✅ palawija is already up to date");
+        return Ok(());
+    }
+
+    let arch = std::env::consts::ARCH;
+    let asset = release.binary_asset(arch)
+        .ok_or_else(|| format!("❌ Release {} has no Linux binary for {}", release.tag_name, arch))?;
+    let expected_sha256 = expected_checksum(&release, asset)?;
+
+    let current_exe = std::env::current_exe()?;
+    let exe_dir = current_exe.parent().ok_or("❌ Could not determine the palawija install directory")?;
+    let temp_path = exe_dir.join(".palawija-update.tmp");
+
+    // Probe write access first so a root-owned install fails before downloading
+    if let Err(e) = std::fs::File::create(&temp_path) {
+        if e.kind() == std::io::ErrorKind::PermissionDenied {
+            return Err(format!(
+                "❌ No permission to replace {}\n💡 Re-run with elevated privileges: sudo {} self-update",
+                current_exe.display(),
+                current_exe.display()
+            ).into());
+        }
+        return Err(e.into());
+    }
+    cleanup::track_partial_file(&temp_path);
+
+    println!("⬇️  Downloading {}...", asset.name);
+    let result = download_verified(&asset.browser_download_url, &temp_path, &expected_sha256)
+        .and_then(|()| replace_executable(&temp_path, &current_exe));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    cleanup::release_partial_file(&temp_path);
+    result?;
+
+    println!("\n🎉 palawija updated from {} to {}", CURRENT_VERSION, latest);
+    Ok(())
+}
+
+/// Fetches and parses the latest release from the GitHub API
+fn fetch_latest_release() -> Result<Release, Box<dyn std::error::Error>> {
+    let output = Command::new("curl")
+        .arg("-s")
+        .arg("-L")
+        .arg("-f")
+        .arg("--max-time")
+        .arg("30")
+        .arg("-H")
+        .arg("Accept: application/vnd.github+json")
+        .arg(LATEST_RELEASE_URL)
+        .output()?;
+
+    if !output.status.success() {
+        return Err("🌐 Failed to query the latest palawija release. Check your internet connection.".into());
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/**
+ * Determines the SHA-256 the downloaded binary must match
+ *
+ * Prefers the digest GitHub reports for the asset and falls back to a
+ * `<asset>.sha256` file; an update that can't be verified is refused.
+ */
+fn expected_checksum(release: &Release, asset: &Asset) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(sum) = asset.digest.as_deref().and_then(|digest| digest.strip_prefix("sha256:")) {
+        return Ok(sum.to_lowercase());
+    }
+
+    let checksum_asset = release.checksum_asset(asset)
+        .ok_or_else(|| format!("❌ No checksum published for {} - refusing to update", asset.name))?;
+    let output = Command::new("curl")
+        .arg("-s")
+        .arg("-L")
+        .arg("-f")
+        .arg("--max-time")
+        .arg("30")
+        .arg(&checksum_asset.browser_download_url)
+        .output()?;
+    if !output.status.success() {
+        return Err(format!("❌ Could not download {}", checksum_asset.name).into());
+    }
+    parse_checksum_file(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| format!("❌ {} doesn't contain a SHA-256", checksum_asset.name).into())
+}
+
+/// Extracts the digest from a `sha256sum`-style file ("<hex>  <name>" or just "<hex>")
+fn parse_checksum_file(content: &str) -> Option<String> {
+    let sum = content.split_whitespace().next()?;
+    (sum.len() == 64 && sum.chars().all(|c| c.is_ascii_hexdigit())).then(|| sum.to_lowercase())
+}
+
+/// Downloads `url` to `path` and checks it against `expected_sha256`
+fn download_verified(url: &str, path: &Path, expected_sha256: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let status = Command::new("curl")
+        .arg("-L")              // Follow redirects
+        .arg("-f")              // Fail on HTTP errors
+        .arg("--progress-bar")  // Show progress bar
+        .arg("--max-time")      // Set timeout
+        .arg("300")             // 5 minutes timeout
+        .arg(url)
+        .arg("-o")
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(format!("❌ Download failed: {}", url).into());
+    }
+
+    let actual = download::sha256_file(path);
+    if actual.as_deref() != Some(expected_sha256) {
+        return Err(format!(
+            "❌ Checksum mismatch for the downloaded binary\n   expected: {}\n   actual:   {}",
+            expected_sha256,
+            actual.as_deref().unwrap_or("unknown")
+        ).into());
+    }
+    println!("🔐 Checksum verified");
+    Ok(())
+}
+
+/// Makes the downloaded binary executable and renames it over the running one
+fn replace_executable(new_binary: &Path, current_exe: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::set_permissions(new_binary, std::fs::Permissions::from_mode(0o755))?;
+    std::fs::rename(new_binary, current_exe)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asset(name: &str) -> Asset {
+        Asset { name: name.to_string(), browser_download_url: format!("https://example.invalid/{}", name), digest: None }
+    }
+
+    #[test]
+    fn linux_binary_for_the_architecture_is_selected() {
+        let release = Release {
+            tag_name: "v1.2.0".to_string(),
+            assets: vec![
+                asset("palawija-aarch64-linux"),
+                asset("palawija-x86_64-linux.sha256"),
+                asset("palawija-x86_64-linux"),
+                asset("palawija-x86_64-darwin"),
+            ],
+        };
+        assert_eq!(release.version(), "1.2.0");
+        let binary = release.binary_asset("x86_64").unwrap();
+        assert_eq!(binary.name, "palawija-x86_64-linux");
+        assert_eq!(release.checksum_asset(binary).unwrap().name, "palawija-x86_64-linux.sha256");
+        assert!(release.binary_asset("riscv64").is_none());
+    }
+
+    #[test]
+    fn checksum_files_are_parsed() {
+        let sum = "a".repeat(64);
+        assert_eq!(parse_checksum_file(&format!("{}  palawija-x86_64-linux\n", sum)), Some(sum.clone()));
+        assert_eq!(parse_checksum_file(&sum.to_uppercase()), Some(sum));
+        assert_eq!(parse_checksum_file("not-a-checksum"), None);
+    }
+}
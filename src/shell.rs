@@ -0,0 +1,154 @@
+/*!
+ * Detection of the user's login shell and its configuration syntax
+ */
+
+use std::path::{Path, PathBuf};
+
+/// What `env` does with PATH entries inside `~/.palawija` that are already there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathMode {
+    /// Drop every palawija entry before adding the new one
+    Replace,
+    /// Only move the new entry to the front, keeping other palawija entries
+    Prepend,
+}
+
+/**
+ * Puts `dir` first in a PATH without piling up entries on repeated calls
+ *
+ * # Arguments
+ * * `current` - The PATH entries in order
+ * * `dir` - The directory to put first; any later copy of it is dropped
+ * * `install_root` - Entries below it are palawija's, dropped with `PathMode::Replace`
+ * * `mode` - Whether other palawija entries stay
+ *
+ * # Returns
+ * * `Vec<PathBuf>` - The new PATH entries
+ */
+pub fn rewrite_path(current: &[PathBuf], dir: &Path, install_root: &Path, mode: PathMode) -> Vec<PathBuf> {
+    let mut entries = vec![dir.to_path_buf()];
+    for entry in current {
+        let palawija_entry = entry.starts_with(install_root);
+        if entry != dir && !(mode == PathMode::Replace && palawija_entry) && !entries.contains(entry) {
+            entries.push(entry.clone());
+        }
+    }
+    entries
+}
+
+/// Shells palawija knows the rc file and syntax of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+    /// Any other POSIX-style shell, configured through `~/.profile`
+    #[value(name = "sh")]
+    Posix,
+}
+
+impl Shell {
+    /// Detects the user's shell from `$SHELL`, falling back to [`Shell::Posix`]
+    pub fn detect() -> Self {
+        std::env::var("SHELL").map(|shell| Self::from_path(&shell)).unwrap_or(Shell::Posix)
+    }
+
+    /// Determines the shell from its executable path (e.g. `/usr/bin/zsh`)
+    pub fn from_path(shell: &str) -> Self {
+        match shell.rsplit('/').next().unwrap_or(shell) {
+            "bash" => Shell::Bash,
+            "zsh" => Shell::Zsh,
+            "fish" => Shell::Fish,
+            "pwsh" | "powershell" => Shell::Powershell,
+            _ => Shell::Posix,
+        }
+    }
+
+    /// The rc file the shell reads for interactive sessions, relative to `$HOME`
+    pub fn rc_file(self) -> &'static str {
+        match self {
+            Shell::Bash => ".bashrc",
+            Shell::Zsh => ".zshrc",
+            Shell::Fish => ".config/fish/config.fish",
+            Shell::Powershell => ".config/powershell/Microsoft.Powershell_profile.ps1",
+            Shell::Posix => ".profile",
+        }
+    }
+
+    /// The line that puts `dir` in front of PATH (for rc files and `eval`)
+    pub fn path_prepend_line(self, dir: &Path) -> String {
+        match self {
+            Shell::Fish => format!("set -gx PATH \"{}\" $PATH", dir.display()),
+            Shell::Powershell => format!("$env:PATH = \"{}:$env:PATH\"", dir.display()),
+            _ => format!("export PATH=\"{}:$PATH\"", dir.display()),
+        }
+    }
+
+    /// The line that sets PATH to exactly `entries`
+    pub fn path_set_line(self, entries: &[PathBuf]) -> String {
+        let entries: Vec<String> = entries.iter().map(|entry| entry.display().to_string()).collect();
+        match self {
+            Shell::Fish => {
+                let quoted: Vec<String> = entries.iter().map(|entry| format!("\"{}\"", entry)).collect();
+                format!("set -gx PATH {}", quoted.join(" "))
+            }
+            _ => self.export_line("PATH", &entries.join(":")),
+        }
+    }
+
+    /// The line that exports `key` with `value` to child processes
+    pub fn export_line(self, key: &str, value: &str) -> String {
+        match self {
+            Shell::Fish => format!("set -gx {} \"{}\"", key, value),
+            Shell::Powershell => format!("$env:{} = \"{}\"", key, value),
+            _ => format!("export {}=\"{}\"", key, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shells_are_detected_from_their_path() {
+        assert_eq!(Shell::from_path("/bin/bash"), Shell::Bash);
+        assert_eq!(Shell::from_path("/usr/bin/zsh"), Shell::Zsh);
+        assert_eq!(Shell::from_path("fish"), Shell::Fish);
+        assert_eq!(Shell::from_path("/usr/bin/pwsh"), Shell::Powershell);
+        assert_eq!(Shell::from_path("/bin/dash"), Shell::Posix);
+    }
+
+    #[test]
+    fn path_lines_use_the_shell_syntax() {
+        let dir = Path::new("/usr/local/bin");
+        assert_eq!(Shell::Bash.path_prepend_line(dir), "export PATH=\"/usr/local/bin:$PATH\"");
+        assert_eq!(Shell::Fish.path_prepend_line(dir), "set -gx PATH \"/usr/local/bin\" $PATH");
+        assert_eq!(Shell::Powershell.path_prepend_line(dir), "$env:PATH = \"/usr/local/bin:$env:PATH\"");
+    }
+
+    #[test]
+    fn repeated_path_rewrites_do_not_pile_up() {
+        let root = Path::new("/home/al/.palawija");
+        let current: Vec<PathBuf> = ["/home/al/.palawija/php-8.2.15/bin", "/usr/bin", "/home/al/.palawija/envs/api/bin", "/usr/bin"]
+            .iter().map(PathBuf::from).collect();
+        let dir = Path::new("/home/al/.palawija/php-8.3.0/bin");
+
+        let replaced = rewrite_path(&current, dir, root, PathMode::Replace);
+        assert_eq!(replaced, [dir, Path::new("/usr/bin")]);
+        assert_eq!(rewrite_path(&replaced, dir, root, PathMode::Replace), replaced);
+
+        let prepended = rewrite_path(&current, dir, root, PathMode::Prepend);
+        assert_eq!(prepended.len(), 4);
+        assert_eq!(rewrite_path(&prepended, dir, root, PathMode::Prepend), prepended);
+        assert_eq!(Shell::Fish.path_set_line(&replaced), "set -gx PATH \"/home/al/.palawija/php-8.3.0/bin\" \"/usr/bin\"");
+    }
+
+    #[test]
+    fn exports_use_the_shell_syntax() {
+        assert_eq!(Shell::Zsh.export_line("PHP_VERSION", "8.3.0"), "export PHP_VERSION=\"8.3.0\"");
+        assert_eq!(Shell::Fish.export_line("PHP_VERSION", "8.3.0"), "set -gx PHP_VERSION \"8.3.0\"");
+        assert_eq!(Shell::Powershell.export_line("PHP_VERSION", "8.3.0"), "$env:PHP_VERSION = \"8.3.0\"");
+    }
+}
@@ -0,0 +1,435 @@
+/*!
+ * GPG signatures of source tarballs (`install --verify-gpg`)
+ *
+ * php.net publishes a detached `.asc` signature next to every tarball,
+ * made by one of the branch's release managers. Signatures are checked with
+ * `gpgv` against palawija's own keyring, `~/.palawija/keyring.gpg`, so the
+ * user's personal keyring and trust settings play no part.
+ *
+ * Trust comes from fingerprints, per release branch: the ones bundled in
+ * `RELEASE_MANAGER_KEYS` plus any listed in `~/.palawija/trusted-keys`, so
+ * a rotated or new release manager key can be trusted without a palawija
+ * release. Each line of that file is a primary key fingerprint (spaces
+ * allowed), optionally followed by the branches it signs; without branches
+ * the key is trusted for all of them. `#` starts a comment:
+ *
+ * ```text
+ * # Derick Rethans, PHP 7.4
+ * 5A52 8807 81F7 5560 8BF8  15FC 910D EB46 F53E A312  7.4
+ * ```
+ *
+ * A signature only passes when its primary key is trusted for the
+ * tarball's branch, and a branch without any trusted key is refused before
+ * anything is downloaded. The keyring just holds those keys, fetched by
+ * fingerprint and imported on first use; a keyserver response is only
+ * imported when it holds exactly the requested key, and any other key that
+ * ends up in the keyring is never trusted.
+ */
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::download;
+use crate::error::NetworkError;
+use crate::paths;
+use crate::releases;
+
+/// Primary key fingerprints of each branch's release managers, as listed on https://www.php.net/gpg-keys.php
+pub const RELEASE_MANAGER_KEYS: &[(&str, &[&str])] = &[
+    ("8.4", &[
+        "AFD8691FDAEDF03BDF6E460563F15A9B715376CA",
+        "9D7F99A0CB8F05C8A6958D6256A97AF7600A39A6",
+        "0616E93D95AF471243E26761770426E17EBBB3DD",
+    ]),
+    ("8.3", &["1198C0117593497A5EC5C199286AF1F9897469DC", "C28D937575603EB4ABB725861C0779DC5C0A9DE4"]),
+    ("8.2", &["39B641343D8C104B2B146DC3F9C39DC0B9698544", "E60913E4DF209907D8E30D96659A97C9CF2A795A"]),
+    ("8.1", &["528995BFEDFBA7191D46839EF9BA0ADA31CBD89E", "F1F692238FBC1666E5A5CCD4199F9DFEF6FFBAFD"]),
+];
+
+/// Where release-manager keys are fetched from by fingerprint
+const KEYSERVER_URL: &str = "https://keys.openpgp.org/vks/v1/by-fingerprint";
+
+/// Seconds each key or signature download may take
+const FETCH_TIMEOUT_SECS: u64 = 30;
+
+/// What `gpgv` made of a signature
+#[derive(Debug, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Good signature by the key with this primary fingerprint
+    Valid { fingerprint: String },
+    /// The signature doesn't match the file
+    Bad,
+    /// The signing key isn't in the keyring
+    UnknownKey { key_id: String },
+    /// `gpgv` reported no signature at all
+    Missing,
+}
+
+/// A fingerprint trusted to sign tarballs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedKey {
+    /// Primary key fingerprint, upper case without spaces
+    pub fingerprint: String,
+    /// Branches the key signs (e.g. "8.3"); empty for every branch
+    pub branches: Vec<String>,
+}
+
+impl TrustedKey {
+    fn signs(&self, branch: &str) -> bool {
+        self.branches.is_empty() || self.branches.iter().any(|signed| signed == branch)
+    }
+}
+
+/// palawija's keyring of trusted release-manager keys
+pub fn keyring_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::install_root()?.join("keyring.gpg"))
+}
+
+/// The user's list of extra trusted fingerprints (`~/.palawija/trusted-keys`)
+pub fn trusted_keys_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::install_root()?.join("trusted-keys"))
+}
+
+/**
+ * Every trusted key: the bundled release managers, then the user's list
+ *
+ * # Returns
+ * * `Result<Vec<TrustedKey>, Box<dyn std::error::Error>>` - Error if the
+ *   user's list can't be read or has a malformed line
+ */
+pub fn trusted_keys() -> Result<Vec<TrustedKey>, Box<dyn std::error::Error>> {
+    let mut keys: Vec<TrustedKey> = RELEASE_MANAGER_KEYS.iter()
+        .flat_map(|(branch, fingerprints)| fingerprints.iter().map(|fingerprint| TrustedKey {
+            fingerprint: fingerprint.to_string(),
+            branches: vec![branch.to_string()],
+        }))
+        .collect();
+
+    let path = trusted_keys_path()?;
+    if path.exists() {
+        let content = std::fs::read_to_string(&path)
+            .map_err(|e| format!("❌ Could not read {}: {}", path.display(), e))?;
+        keys.extend(parse_trusted_keys(&content).map_err(|e| format!("❌ {}: {}", path.display(), e))?);
+    }
+    Ok(keys)
+}
+
+/// Reads a trusted-keys file: a fingerprint per line, optionally followed by branches
+pub fn parse_trusted_keys(content: &str) -> Result<Vec<TrustedKey>, String> {
+    let mut keys = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // Fingerprints are usually copied in groups of four, so the words
+        // up to the first branch make up the fingerprint
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let split = words.iter().position(|word| word.contains('.')).unwrap_or(words.len());
+        let fingerprint = words[..split].concat().to_uppercase();
+        if fingerprint.len() != 40 || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("line {}: '{}' isn't a 40-digit key fingerprint", number + 1, words[..split].join(" ")));
+        }
+        keys.push(TrustedKey { fingerprint, branches: words[split..].iter().map(|branch| branch.to_string()).collect() });
+    }
+    Ok(keys)
+}
+
+/**
+ * Fails unless some trusted key signs the branch of `version`
+ *
+ * Run before downloading, so a `--verify-gpg` install of a branch palawija
+ * can't check doesn't fetch a tarball only to refuse it.
+ */
+pub fn check_branch_trusted(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let branch = releases::branch_of(version);
+    if trusted_keys()?.iter().any(|key| key.signs(&branch)) {
+        return Ok(());
+    }
+    Err(format!(
+        "❌ No trusted release manager key for PHP {}, so its signature can't be verified\n💡 Add the fingerprint of the {} release manager (see https://www.php.net/gpg-keys.php) to {} as '<fingerprint> {}'",
+        branch, branch, trusted_keys_path()?.display(), branch
+    ).into())
+}
+
+/**
+ * Checks a downloaded tarball against its `.asc` signature on php.net
+ *
+ * Fails closed: a signature that can't be downloaded, is bad, or was made
+ * by a key outside palawija's keyring is an error.
+ *
+ * # Arguments
+ * * `version` - The PHP version; its branch decides which keys are trusted
+ * * `url` - The URL the tarball came from; the signature is `<url>.asc`
+ * * `tarball` - The tarball on disk
+ *
+ * # Returns
+ * * `Result<String, Box<dyn std::error::Error>>` - Fingerprint of the signing key
+ */
+pub fn verify_tarball(version: &str, url: &str, tarball: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let branch = releases::branch_of(version);
+    let keys = trusted_keys()?;
+    let branch_keys: Vec<&str> = keys.iter()
+        .filter(|key| key.signs(&branch))
+        .map(|key| key.fingerprint.as_str())
+        .collect();
+
+    let signature_url = format!("{}.asc", url);
+    // Kept in the user's cache, since the tarball may sit in a read-only shared cache
+    let signature = paths::cache_dir()?.join(format!(
+        "{}.asc",
+        tarball.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+    ));
+    println!("🔏 Downloading signature {}...", signature_url);
+    if let Err(e) = fetch(&signature_url, &signature) {
+        return Err(format!("❌ No GPG signature could be downloaded for {}: {}", tarball.display(), e).into());
+    }
+
+    let keyring = keyring_path()?;
+    import_release_keys(&keyring, &branch_keys);
+
+    let output = Command::new("gpgv")
+        .arg("--homedir").arg(gnupg_home()?)
+        .arg("--keyring").arg(&keyring)
+        .arg("--status-fd").arg("1")
+        .arg(&signature)
+        .arg(tarball)
+        .output()
+        .map_err(|e| format!("❌ Could not run gpgv: {}\n💡 Install GnuPG to use --verify-gpg", e))?;
+    let _ = std::fs::remove_file(&signature);
+
+    match parse_status(&String::from_utf8_lossy(&output.stdout)) {
+        SignatureStatus::Valid { fingerprint } if output.status.success() && is_trusted(&branch_keys, &fingerprint) => Ok(fingerprint),
+        SignatureStatus::Valid { fingerprint } if output.status.success() => Err(format!(
+            "❌ {} is signed by key {}, which isn't one of the PHP {} release manager keys palawija trusts\n💡 Check the key on https://www.php.net/gpg-keys.php, then add it to {}",
+            tarball.display(), fingerprint, branch, trusted_keys_path()?.display()
+        ).into()),
+        SignatureStatus::Valid { .. } | SignatureStatus::Bad => {
+            Err(format!("❌ The GPG signature of {} is invalid", tarball.display()).into())
+        }
+        SignatureStatus::UnknownKey { key_id } => Err(format!(
+            "❌ {} is signed by key {}, which isn't in {}\n💡 The release manager keys are imported on first use; check the network, or, for a new release manager (see https://www.php.net/gpg-keys.php), add their fingerprint to {}",
+            tarball.display(), key_id, keyring.display(), trusted_keys_path()?.display()
+        ).into()),
+        SignatureStatus::Missing => Err(format!("❌ {} holds no usable GPG signature", signature_url).into()),
+    }
+}
+
+/// Whether `fingerprint` is one of the `trusted` keys
+fn is_trusted(trusted: &[&str], fingerprint: &str) -> bool {
+    trusted.iter().any(|known| known.eq_ignore_ascii_case(fingerprint))
+}
+
+/// Reads the `[GNUPG:]` status lines `gpgv --status-fd` prints
+pub fn parse_status(status: &str) -> SignatureStatus {
+    let mut result = SignatureStatus::Missing;
+    for line in status.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["[GNUPG:]", "VALIDSIG", signing, rest @ ..] => {
+                // The primary key's fingerprint comes last when the signing key is a subkey
+                let fingerprint = rest.get(8).unwrap_or(signing);
+                return SignatureStatus::Valid { fingerprint: fingerprint.to_uppercase() };
+            }
+            ["[GNUPG:]", "BADSIG", ..] => return SignatureStatus::Bad,
+            ["[GNUPG:]", "NO_PUBKEY", key_id, ..] | ["[GNUPG:]", "ERRSIG", key_id, ..] => {
+                result = SignatureStatus::UnknownKey { key_id: key_id.to_string() };
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/**
+ * Imports every trusted key of a branch the keyring is still missing
+ *
+ * Each key is fetched by fingerprint and only imported if the response is
+ * that one key and nothing else. Failures are reported and skipped; a
+ * signature by a key that couldn't be imported then fails verification.
+ */
+fn import_release_keys(keyring: &Path, fingerprints: &[&str]) {
+    let present = keyring_fingerprints(keyring);
+    let missing: Vec<&str> = fingerprints.iter()
+        .copied()
+        .filter(|fingerprint| !present.iter().any(|known| known == fingerprint))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+
+    println!("🔑 Importing {} release manager key(s) into {}...", missing.len(), keyring.display());
+    for fingerprint in missing {
+        if let Err(e) = import_key(keyring, fingerprint) {
+            println!("⚠️  Could not import key {}: {}", fingerprint, e);
+        }
+    }
+}
+
+fn import_key(keyring: &Path, fingerprint: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let key_file = keyring.with_file_name(format!(".key-{}.asc", fingerprint));
+    fetch(&format!("{}/{}", KEYSERVER_URL, fingerprint), &key_file)?;
+
+    let shown = gpg(keyring)?.args(["--with-colons", "--import-options", "show-only", "--import"]).arg(&key_file).output()?;
+    if let Err(e) = check_key_listing(&String::from_utf8_lossy(&shown.stdout), fingerprint) {
+        let _ = std::fs::remove_file(&key_file);
+        return Err(e.into());
+    }
+    let imported = gpg(keyring)?.args(["--quiet", "--import"]).arg(&key_file).output()?;
+    let _ = std::fs::remove_file(&key_file);
+    if !imported.status.success() {
+        return Err(String::from_utf8_lossy(&imported.stderr).trim().to_string().into());
+    }
+    Ok(())
+}
+
+/**
+ * Checks that a key file holds the key with `fingerprint` and no other
+ *
+ * An extra key next to the requested one would be imported along with it,
+ * so a tampered keyserver response is refused as a whole.
+ *
+ * # Arguments
+ * * `listing` - `gpg --with-colons --import-options show-only` output for the file
+ * * `fingerprint` - The primary key fingerprint that was requested
+ */
+fn check_key_listing(listing: &str, fingerprint: &str) -> Result<(), String> {
+    match fingerprints(listing).as_slice() {
+        [only] if only.eq_ignore_ascii_case(fingerprint) => Ok(()),
+        [] | [_] => Err("the keyserver returned a different key".to_string()),
+        keys => Err(format!("the keyserver returned {} keys instead of just {}", keys.len(), fingerprint)),
+    }
+}
+
+/// Primary key fingerprints already in the keyring
+fn keyring_fingerprints(keyring: &Path) -> Vec<String> {
+    if !keyring.exists() {
+        return Vec::new();
+    }
+    gpg(keyring).ok()
+        .and_then(|mut command| command.args(["--with-colons", "--list-keys"]).output().ok())
+        .map(|output| fingerprints(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or_default()
+}
+
+/// Fingerprints of the primary keys in `gpg --with-colons` output
+fn fingerprints(listing: &str) -> Vec<String> {
+    let mut fingerprints = Vec::new();
+    let mut primary = false;
+    for line in listing.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields.first() {
+            Some(&"pub") => primary = true,
+            Some(&"sub") => primary = false,
+            Some(&"fpr") if primary => {
+                fingerprints.extend(fields.get(9).map(|fingerprint| fingerprint.to_uppercase()));
+                primary = false;
+            }
+            _ => {}
+        }
+    }
+    fingerprints
+}
+
+/// A `gpg` command that works on palawija's keyring only
+fn gpg(keyring: &Path) -> Result<Command, Box<dyn std::error::Error>> {
+    let mut command = Command::new("gpg");
+    command.arg("--homedir").arg(gnupg_home()?)
+        .arg("--batch")
+        .arg("--no-default-keyring")
+        .arg("--keyring").arg(keyring);
+    Ok(command)
+}
+
+/// Private GnuPG home, so the user's `~/.gnupg` is neither read nor changed
+fn gnupg_home() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let home = paths::install_root()?.join("gnupg");
+    if !home.exists() {
+        std::fs::create_dir_all(&home)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&home, std::fs::Permissions::from_mode(0o700))?;
+        }
+    }
+    Ok(home)
+}
+
+fn fetch(url: &str, destination: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut curl = Command::new("curl");
+    curl.arg("-s")              // Silent mode
+        .arg("-f")              // Fail on HTTP errors
+        .arg("-L");             // Follow redirects
+    download::set_timeout(&mut curl, FETCH_TIMEOUT_SECS);
+    let status = curl.arg(url).arg("-o").arg(destination).status()?;
+    if !status.success() {
+        let _ = std::fs::remove_file(destination);
+        return Err(NetworkError::from_curl(status, url, FETCH_TIMEOUT_SECS).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpgv_status_lines_are_read() {
+        let good = "[GNUPG:] NEWSIG\n[GNUPG:] KEY_CONSIDERED 1198C0117593497A5EC5C199286AF1F9897469DC 0\n\
+                    [GNUPG:] GOODSIG 286AF1F9897469DC Someone <rm@php.net>\n\
+                    [GNUPG:] VALIDSIG D3A8A0F9F1B4C36F2A5D3C6E2B1B0F4C8E4E7A11 2023-11-23 1700740000 0 4 0 1 10 00 1198c0117593497a5ec5c199286af1f9897469dc\n";
+        assert_eq!(parse_status(good), SignatureStatus::Valid { fingerprint: "1198C0117593497A5EC5C199286AF1F9897469DC".to_string() });
+        assert_eq!(parse_status("[GNUPG:] BADSIG 286AF1F9897469DC Someone\n"), SignatureStatus::Bad);
+        assert_eq!(
+            parse_status("[GNUPG:] ERRSIG 0123456789ABCDEF 1 10 00 1700740000 9 -\n[GNUPG:] NO_PUBKEY 0123456789ABCDEF\n"),
+            SignatureStatus::UnknownKey { key_id: "0123456789ABCDEF".to_string() }
+        );
+        assert_eq!(parse_status(""), SignatureStatus::Missing);
+    }
+
+    #[test]
+    fn only_primary_fingerprints_are_listed() {
+        let listing = "pub:-:4096:1:286AF1F9897469DC:1600000000:::-:::scESC::::::23::0:\n\
+                       fpr:::::::::1198C0117593497A5EC5C199286AF1F9897469DC:\n\
+                       uid:-::::1600000000::X::Someone <rm@php.net>::::::::::0:\n\
+                       sub:-:4096:1:AAAAAAAAAAAAAAAA:1600000000::::::e::::::23:\n\
+                       fpr:::::::::BBBBBBBBBBBBBBBBBBBBBBBBAAAAAAAAAAAAAAAA:\n";
+        assert_eq!(fingerprints(listing), ["1198C0117593497A5EC5C199286AF1F9897469DC"]);
+
+        let requested = "1198C0117593497A5EC5C199286AF1F9897469DC";
+        assert!(check_key_listing(listing, requested).is_ok());
+        assert!(check_key_listing(listing, "C28D937575603EB4ABB725861C0779DC5C0A9DE4").is_err());
+        let with_extra_key = format!("{}pub:-:4096:1:1111111111111111:1600000000:::-:::scESC:\nfpr:::::::::EEEEEEEEEEEEEEEEEEEEEEEE1111111111111111:\n", listing);
+        assert_eq!(check_key_listing(&with_extra_key, requested).unwrap_err(), format!("the keyserver returned 2 keys instead of just {}", requested));
+        assert!(check_key_listing("", requested).is_err());
+    }
+
+    #[test]
+    fn bundled_and_user_keys_are_trusted_per_branch() {
+        let (root, _lock) = paths::test_root();
+        let _ = std::fs::remove_file(root.join("trusted-keys"));
+        let for_branch = |branch: &str| -> Vec<String> {
+            trusted_keys().unwrap().into_iter().filter(|key| key.signs(branch)).map(|key| key.fingerprint).collect()
+        };
+        assert!(for_branch("8.3").contains(&"1198C0117593497A5EC5C199286AF1F9897469DC".to_string()));
+        assert!(!for_branch("8.2").contains(&"1198C0117593497A5EC5C199286AF1F9897469DC".to_string()));
+        assert!(for_branch("7.4").is_empty());
+        assert!(check_branch_trusted("8.3.0").is_ok());
+        assert!(check_branch_trusted("7.4.33").unwrap_err().to_string().contains("No trusted release manager key for PHP 7.4"));
+
+        std::fs::write(root.join("trusted-keys"), "# Derick Rethans\n5A52 8807 81F7 5560 8BF8  15FC 910D EB46 F53E A312  7.4 7.3\n\neeeeeeeeeeeeeeeeeeeeeeee1111111111111111\n").unwrap();
+        assert_eq!(for_branch("7.4"), ["5A52880781F755608BF815FC910DEB46F53EA312", "EEEEEEEEEEEEEEEEEEEEEEEE1111111111111111"]);
+        assert!(for_branch("8.3").contains(&"1198C0117593497A5EC5C199286AF1F9897469DC".to_string()));
+        assert!(for_branch("8.3").contains(&"EEEEEEEEEEEEEEEEEEEEEEEE1111111111111111".to_string()));
+        assert!(check_branch_trusted("7.4.33").is_ok());
+        assert!(is_trusted(&["1198C0117593497A5EC5C199286AF1F9897469DC"], "1198c0117593497a5ec5c199286af1f9897469dc"));
+
+        std::fs::write(root.join("trusted-keys"), "5A52880781F7 7.4\n").unwrap();
+        assert!(trusted_keys().unwrap_err().to_string().contains("line 1: '5A52880781F7' isn't a 40-digit key fingerprint"));
+        std::fs::remove_file(root.join("trusted-keys")).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+/*!
+ * Disk-space preflight for installs
+ *
+ * Running out of space halfway through an extraction or build leaves cryptic
+ * tar/make errors, so `install` estimates what it needs up front and stops
+ * early when the filesystem of `~/.palawija` can't hold it.
+ */
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::download::{self, ArchiveFormat};
+
+/// Extracted source tree size relative to the compressed tarball
+const EXTRACT_FACTOR: u64 = 8;
+
+/// Source tree plus build objects and installed files relative to the tarball
+const COMPILE_FACTOR: u64 = 30;
+
+/**
+ * Aborts when the install root's filesystem looks too small for an install
+ *
+ * The estimate is the tarball (when it still has to be downloaded) plus a
+ * conservative multiple of its size for the extracted, and possibly
+ * compiled, tree. If either size can't be determined the check is skipped.
+ *
+ * # Arguments
+ * * `install_root` - Directory the version will be installed below
+ * * `version` - PHP version being installed
+ * * `local_tarball` - The `--from-source` tarball, if any
+ * * `format` - The requested archive format, if any
+ * * `compile` - Whether the tree will also be built
+ * * `timeout` - The `--download-timeout`, if given; also bounds the size lookup
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if space is insufficient
+ */
+pub fn preflight(
+    install_root: &Path,
+    version: &str,
+    local_tarball: Option<&Path>,
+    format: Option<ArchiveFormat>,
+    compile: bool,
+    timeout: Option<u64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let timeout = timeout.unwrap_or(download::DEFAULT_LOOKUP_TIMEOUT);
+    let (Some((tarball_size, needs_download)), Some(available)) =
+        (tarball_size(version, local_tarball, format, timeout), available_bytes(install_root))
+    else {
+        println!("⚠️  Could not estimate the space needed - skipping disk-space check");
+        return Ok(());
+    };
+
+    let required = required_bytes(tarball_size, needs_download, compile);
+    if required > available {
+        return Err(format!(
+            "insufficient disk space: need ~{}, have {} on the filesystem of {}\n💡 Free some space, or skip this check with --no-space-check",
+            format_bytes(required), format_bytes(available), install_root.display()
+        ).into());
+    }
+    println!("💽 Disk space OK: need ~{}, have {}", format_bytes(required), format_bytes(available));
+    Ok(())
+}
+
+/// Estimated bytes an install needs for a tarball of `tarball_size` bytes
+pub fn required_bytes(tarball_size: u64, needs_download: bool, compile: bool) -> u64 {
+    let factor = if compile { COMPILE_FACTOR } else { EXTRACT_FACTOR };
+    let download = if needs_download { tarball_size } else { 0 };
+    tarball_size.saturating_mul(factor).saturating_add(download)
+}
+
+/// Size of the tarball to install and whether it still has to be downloaded
+fn tarball_size(version: &str, local_tarball: Option<&Path>, format: Option<ArchiveFormat>, timeout: u64) -> Option<(u64, bool)> {
+    if let Some(path) = local_tarball {
+        return Some((std::fs::metadata(path).ok()?.len(), false));
+    }
+    let candidates = ArchiveFormat::candidates(format);
+    let cached = candidates.iter()
+        .filter_map(|&candidate| download::cached_tarball(version, candidate).ok())
+        .find_map(|path| std::fs::metadata(path).ok());
+    if let Some(metadata) = cached {
+        return Some((metadata.len(), false));
+    }
+    candidates.into_iter()
+        .find_map(|candidate| remote_size(&download::tarball_url(version, candidate), timeout))
+        .map(|size| (size, true))
+}
+
+/// The `Content-Length` of a URL after redirects, from a HEAD request that may take `timeout` seconds (0 for no limit)
+fn remote_size(url: &str, timeout: u64) -> Option<u64> {
+    let mut curl = Command::new("curl");
+    curl.arg("-s")              // Silent mode
+        .arg("-I")              // HEAD request
+        .arg("-L")              // Follow redirects
+        .arg("-f");             // Fail on HTTP errors
+    download::set_timeout(&mut curl, timeout);
+    let output = curl.arg(url).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_content_length(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The last `Content-Length` in a (possibly redirected) header dump
+fn parse_content_length(headers: &str) -> Option<u64> {
+    headers.lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .filter_map(|(_, value)| value.trim().parse().ok())
+        .next_back()
+}
+
+/// Free bytes on the filesystem holding `path` (or its nearest existing ancestor)
+fn available_bytes(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|dir| dir.exists())?;
+    let output = Command::new("df").arg("-Pk").arg(existing).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // POSIX format: Filesystem 1024-blocks Used Available Capacity Mounted-on
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let kib: u64 = stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Formats a byte count with a binary unit (e.g. "1.5 GiB")
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    if unit == "B" { format!("{} B", bytes) } else { format!("{:.1} {}", value, unit) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimates_scale_with_the_tarball() {
+        assert_eq!(required_bytes(20, true, false), 20 * EXTRACT_FACTOR + 20);
+        assert_eq!(required_bytes(20, false, true), 20 * COMPILE_FACTOR);
+        assert_eq!(required_bytes(u64::MAX, true, true), u64::MAX);
+    }
+
+    #[test]
+    fn content_length_of_the_final_response_wins() {
+        let headers = "HTTP/2 302\r\ncontent-length: 0\r\n\r\nHTTP/2 200\r\nContent-Length: 19655321\r\n";
+        assert_eq!(parse_content_length(headers), Some(19_655_321));
+        assert_eq!(parse_content_length("HTTP/2 200\r\n"), None);
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MiB");
+    }
+}
@@ -0,0 +1,241 @@
+/*!
+ * Resumable install state (`~/.palawija/state/php-<version>.json`)
+ *
+ * `install` records each phase it completes, so running it again after an
+ * interruption picks up where it stopped: a verified tarball isn't
+ * downloaded again and a complete source tree isn't extracted again. The
+ * file is removed once the install finishes, and `prune` removes the ones
+ * left behind by installs that were abandoned.
+ */
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::date::Date;
+use crate::download::{self, Tarball};
+use crate::paths;
+
+/// The last install phase that completed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    /// The tarball is in the cache and its checksum was recorded
+    Downloaded,
+    /// The source tree and its manifest are in the version directory
+    Extracted,
+    /// `make install` finished
+    Compiled,
+}
+
+impl Phase {
+    /// What had happened when the install stopped
+    pub fn label(self) -> &'static str {
+        match self {
+            Phase::Downloaded => "downloaded",
+            Phase::Extracted => "extracted",
+            Phase::Compiled => "compiled",
+        }
+    }
+}
+
+/// What a re-run of `install` needs to resume a version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallState {
+    pub version: String,
+    pub phase: Phase,
+    /// The downloaded tarball, once there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tarball: Option<PathBuf>,
+    /// URL the tarball came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// SHA-256 of the tarball when it was downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Date the phase completed (YYYY-MM-DD)
+    pub updated_at: String,
+}
+
+/// Location of the state file of a version
+pub fn state_path(version: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::state_dir()?.join(format!("php-{}.json", version)))
+}
+
+impl InstallState {
+    /// State of an install of `version` that has completed `phase`
+    pub fn new(version: &str, phase: Phase) -> Self {
+        InstallState {
+            version: version.to_string(),
+            phase,
+            tarball: None,
+            url: None,
+            sha256: None,
+            updated_at: Date::today().to_string(),
+        }
+    }
+
+    /// State recording that `tarball` was downloaded and verified
+    pub fn downloaded(version: &str, tarball: &Tarball) -> Self {
+        InstallState {
+            tarball: Some(tarball.path.clone()),
+            url: Some(tarball.url.clone()),
+            sha256: tarball.sha256.clone(),
+            ..InstallState::new(version, Phase::Downloaded)
+        }
+    }
+
+    /**
+     * Loads the state of an interrupted install of `version`
+     *
+     * # Returns
+     * * `Option<InstallState>` - `None` if there is none; a corrupt file is reported and ignored
+     */
+    pub fn load(version: &str) -> Option<Self> {
+        let path = state_path(version).ok()?;
+        load_file(&path)
+    }
+
+    /// Records that the install completed `phase`
+    pub fn advance(&mut self, phase: Phase) {
+        self.phase = phase;
+        self.updated_at = Date::today().to_string();
+        self.record();
+    }
+
+    /**
+     * Writes the state file atomically
+     *
+     * A failure is only reported: the install itself can go on, it just
+     * can't be resumed from this point.
+     */
+    pub fn record(&self) {
+        let result = state_path(&self.version).and_then(|path| atomic::write_json_atomic(&path, self));
+        if let Err(e) = result {
+            eprintln!("⚠️  Could not record install progress: {}", e);
+        }
+    }
+
+    /**
+     * The recorded tarball, if it is still there and unchanged
+     *
+     * Only a tarball whose checksum was recorded qualifies, so this never
+     * needs the network.
+     */
+    pub fn verified_tarball(&self) -> Option<Tarball> {
+        let path = self.tarball.as_ref().filter(|path| path.is_file())?;
+        let expected = self.sha256.as_deref()?;
+        (download::sha256_file(path).as_deref() == Some(expected)).then(|| Tarball {
+            path: path.clone(),
+            url: self.url.clone().unwrap_or_default(),
+            sha256: self.sha256.clone(),
+        })
+    }
+}
+
+/**
+ * Records that the source tree of `version` is in place
+ *
+ * Installs that downloaded nothing (`--from-source`, `--git`) have no state
+ * yet, so it is started here; either way it is written, so a failed or
+ * interrupted build resumes from the extracted tree instead of reporting
+ * the version as already downloaded.
+ *
+ * # Returns
+ * * `&mut InstallState` - The state, at `Phase::Extracted` or later
+ */
+pub fn mark_extracted<'a>(progress: &'a mut Option<InstallState>, version: &str) -> &'a mut InstallState {
+    let state = progress.get_or_insert_with(|| InstallState::new(version, Phase::Extracted));
+    if state.phase <= Phase::Extracted {
+        state.advance(Phase::Extracted);
+    }
+    state
+}
+
+/// Removes the state file of `version` after a finished install
+pub fn clear(version: &str) {
+    if let Ok(path) = state_path(version) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Every state file left behind by interrupted installs, sorted by file name
+pub fn leftovers() -> Vec<(PathBuf, Option<InstallState>)> {
+    let Ok(entries) = paths::state_dir().and_then(|dir| Ok(std::fs::read_dir(dir)?)) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(PathBuf, Option<InstallState>)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let state = load_file(&path);
+            (path, state)
+        })
+        .collect();
+    found.sort_by(|a, b| a.0.cmp(&b.0));
+    found
+}
+
+fn load_file(path: &Path) -> Option<InstallState> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            eprintln!("⚠️  Ignoring corrupt install state {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_an_unchanged_tarball_is_reused() {
+        let dir = std::env::temp_dir().join(format!("palawija-state-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("php-8.3.1.tar.gz");
+        std::fs::write(&path, b"tarball").unwrap();
+        let tarball = Tarball { path: path.clone(), url: "https://example.test/php".to_string(), sha256: download::sha256_file(&path) };
+
+        let state = InstallState::downloaded("8.3.1", &tarball);
+        assert!(state.verified_tarball().is_some());
+        assert!(InstallState { sha256: None, ..state.clone() }.verified_tarball().is_none());
+
+        std::fs::write(&path, b"truncated").unwrap();
+        assert!(state.verified_tarball().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_builds_resume_from_the_extracted_tree() {
+        let _root = paths::test_root();
+        let version = "8.3.599";
+
+        // A --from-source install has no state until its tree is extracted
+        let mut progress = None;
+        mark_extracted(&mut progress, version);
+        // ...and then the build fails before reaching Phase::Compiled
+        let resumed = InstallState::load(version).expect("the extracted phase was recorded");
+        assert_eq!(resumed.phase, Phase::Extracted);
+
+        let mut progress = Some(resumed);
+        mark_extracted(&mut progress, version).advance(Phase::Compiled);
+        assert_eq!(mark_extracted(&mut progress, version).phase, Phase::Compiled);
+        assert_eq!(InstallState::load(version).map(|state| state.phase), Some(Phase::Compiled));
+
+        clear(version);
+        assert!(InstallState::load(version).is_none());
+    }
+
+    #[test]
+    fn phases_round_trip_in_lowercase() {
+        let json = r#"{"version":"8.3.1","phase":"extracted","updated_at":"2026-01-02"}"#;
+        let state: InstallState = serde_json::from_str(json).unwrap();
+        assert_eq!(state.phase, Phase::Extracted);
+        assert!(serde_json::to_string(&state).unwrap().contains("\"phase\":\"extracted\""));
+    }
+}
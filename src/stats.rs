@@ -0,0 +1,118 @@
+/*!
+ * Local usage statistics (`~/.palawija/stats.json`, `palawija stats`)
+ *
+ * Counts the installs and switches palawija performs. The file stays on this
+ * machine: palawija never sends it anywhere, and `stats --reset` deletes it.
+ */
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::atomic;
+use crate::history;
+use crate::paths;
+
+/// Everything counted in the stats file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    /// Successful installs
+    pub installs: u64,
+    /// Successful switches with `use`
+    pub switches: u64,
+    /// Switches per version switched to
+    pub switches_by_version: BTreeMap<String, u64>,
+    /// When the last switch happened (`YYYY-MM-DDTHH:MM:SSZ`)
+    pub last_switch: Option<String>,
+    /// When counting started, i.e. the first record after a reset
+    pub since: Option<String>,
+}
+
+/// Location of the stats file
+pub fn stats_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(paths::install_root()?.join("stats.json"))
+}
+
+impl Stats {
+    /// Loads the stats; a missing or corrupt file counts as empty
+    pub fn load() -> Self {
+        let Ok(path) = stats_path() else {
+            return Stats::default();
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Stats::default();
+        };
+        serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("⚠️  Ignoring corrupt stats file {}: {}", path.display(), e);
+            Stats::default()
+        })
+    }
+
+    /// The version switched to most often (on ties, the one sorting last)
+    pub fn most_switched(&self) -> Option<(&str, u64)> {
+        self.switches_by_version.iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(version, count)| (version.as_str(), *count))
+    }
+
+    fn record_switch(&mut self, version: &str, timestamp: String) {
+        self.switches += 1;
+        *self.switches_by_version.entry(version.to_string()).or_insert(0) += 1;
+        self.since.get_or_insert_with(|| timestamp.clone());
+        self.last_switch = Some(timestamp);
+    }
+
+    fn record_install(&mut self, timestamp: String) {
+        self.installs += 1;
+        self.since.get_or_insert(timestamp);
+    }
+}
+
+/// Counts a successful install (best effort: stats never fail a command)
+pub fn record_install() {
+    update(|stats| stats.record_install(history::now()));
+}
+
+/// Counts a successful switch to `version` (best effort)
+pub fn record_switch(version: &str) {
+    update(|stats| stats.record_switch(version, history::now()));
+}
+
+/// Deletes the stats file
+pub fn reset() -> Result<bool, Box<dyn std::error::Error>> {
+    match std::fs::remove_file(stats_path()?) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn update(change: impl FnOnce(&mut Stats)) {
+    let mut stats = Stats::load();
+    change(&mut stats);
+    if let Err(e) = stats_path().and_then(|path| atomic::write_json_atomic(&path, &stats)) {
+        eprintln!("⚠️  Could not update the local stats: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_are_counted_per_version() {
+        let mut stats = Stats::default();
+        stats.record_install("2024-06-01T10:00:00Z".to_string());
+        stats.record_switch("8.2.15", "2024-06-01T11:00:00Z".to_string());
+        stats.record_switch("8.3.0", "2024-06-01T12:00:00Z".to_string());
+        stats.record_switch("8.2.15", "2024-06-02T09:00:00Z".to_string());
+
+        assert_eq!(stats.installs, 1);
+        assert_eq!(stats.switches, 3);
+        assert_eq!(stats.most_switched(), Some(("8.2.15", 2)));
+        assert_eq!(stats.last_switch.as_deref(), Some("2024-06-02T09:00:00Z"));
+        assert_eq!(stats.since.as_deref(), Some("2024-06-01T10:00:00Z"));
+    }
+}
@@ -0,0 +1,208 @@
+/*!
+ * PHP branch support schedule
+ *
+ * Every release branch gets active support (bug fixes) followed by a period
+ * of security-only support, after which it is end of life. The schedule below
+ * mirrors https://www.php.net/supported-versions.php and can be overridden
+ * with `~/.palawija/support.json` when branches are extended or added:
+ *
+ * ```json
+ * { "8.5": { "active_until": "2027-12-31", "security_until": "2029-12-31" } }
+ * ```
+ */
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::date::Date;
+use crate::paths;
+use crate::releases;
+
+/// Support status of a PHP release branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchStatus {
+    /// Receiving bug fixes and security fixes
+    Active,
+    /// Security fixes only - still safe for production
+    Lts,
+    /// No longer receiving any fixes
+    Eol,
+}
+
+impl BranchStatus {
+    /// Short name shown in tables
+    pub fn label(self) -> &'static str {
+        match self {
+            BranchStatus::Active => "Active",
+            BranchStatus::Lts => "LTS",
+            BranchStatus::Eol => "EOL",
+        }
+    }
+}
+
+/// End dates of the two support phases of a branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BranchSupport {
+    #[serde(deserialize_with = "deserialize_date")]
+    pub active_until: Date,
+    #[serde(deserialize_with = "deserialize_date")]
+    pub security_until: Date,
+}
+
+/// Embedded support schedule: (branch, active support end, security support end)
+const EMBEDDED_SCHEDULE: &[(&str, Date, Date)] = &[
+    ("5.6", Date::new(2017, 1, 19), Date::new(2018, 12, 31)),
+    ("7.0", Date::new(2017, 12, 3), Date::new(2019, 1, 10)),
+    ("7.1", Date::new(2018, 12, 1), Date::new(2019, 12, 1)),
+    ("7.2", Date::new(2019, 11, 30), Date::new(2020, 11, 30)),
+    ("7.3", Date::new(2020, 12, 6), Date::new(2021, 12, 6)),
+    ("7.4", Date::new(2021, 11, 28), Date::new(2022, 11, 28)),
+    ("8.0", Date::new(2022, 11, 26), Date::new(2023, 11, 26)),
+    ("8.1", Date::new(2023, 11, 25), Date::new(2025, 12, 31)),
+    ("8.2", Date::new(2024, 12, 31), Date::new(2026, 12, 31)),
+    ("8.3", Date::new(2025, 12, 31), Date::new(2027, 12, 31)),
+    ("8.4", Date::new(2026, 12, 31), Date::new(2028, 12, 31)),
+    ("8.5", Date::new(2027, 12, 31), Date::new(2029, 12, 31)),
+];
+
+/// Support schedule keyed by major.minor branch
+#[derive(Debug, Clone)]
+pub struct SupportSchedule {
+    branches: BTreeMap<String, BranchSupport>,
+}
+
+impl SupportSchedule {
+    /// The schedule compiled into this release of palawija
+    pub fn embedded() -> Self {
+        let branches = EMBEDDED_SCHEDULE
+            .iter()
+            .map(|(branch, active_until, security_until)| {
+                (branch.to_string(), BranchSupport { active_until: *active_until, security_until: *security_until })
+            })
+            .collect();
+        SupportSchedule { branches }
+    }
+
+    /**
+     * Loads the embedded schedule and applies `~/.palawija/support.json` on top
+     *
+     * A malformed override file is reported and ignored so a typo never
+     * breaks version listings.
+     */
+    pub fn load() -> Self {
+        let mut schedule = Self::embedded();
+
+        if let Some(path) = override_path().filter(|p| p.exists()) {
+            match std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| schedule.apply_override(&content))
+            {
+                Ok(()) => {}
+                Err(e) => eprintln!("⚠️  Ignoring invalid support schedule {}: {}", path.display(), e),
+            }
+        }
+
+        schedule
+    }
+
+    /**
+     * Merges branch entries from a JSON override document
+     *
+     * # Arguments
+     * * `json` - Object mapping branch names to `active_until`/`security_until` dates
+     */
+    pub fn apply_override(&mut self, json: &str) -> Result<(), String> {
+        let overrides: BTreeMap<String, BranchSupport> =
+            serde_json::from_str(json).map_err(|e| e.to_string())?;
+        self.branches.extend(overrides);
+        Ok(())
+    }
+
+    /**
+     * Determines the support status of a branch on a given day
+     *
+     * Branches newer than anything in the schedule are assumed to be active
+     * (they were released after this schedule was written); unknown older
+     * branches are end of life.
+     *
+     * # Arguments
+     * * `branch` - Release branch such as "8.2"
+     * * `today` - The date to evaluate the schedule at
+     */
+    pub fn status(&self, branch: &str, today: Date) -> BranchStatus {
+        match self.branches.get(branch) {
+            Some(support) if today <= support.active_until => BranchStatus::Active,
+            Some(support) if today <= support.security_until => BranchStatus::Lts,
+            Some(_) => BranchStatus::Eol,
+            None => {
+                let newest_known = self.branches.keys()
+                    .max_by(|a, b| releases::compare_versions(a, b));
+                match newest_known {
+                    Some(newest) if releases::compare_versions(branch, newest).is_gt() => BranchStatus::Active,
+                    _ => BranchStatus::Eol,
+                }
+            }
+        }
+    }
+
+    /// Support status of a branch as of today
+    pub fn status_today(&self, branch: &str) -> BranchStatus {
+        self.status(branch, Date::today())
+    }
+}
+
+/// Location of the user's support schedule override
+fn override_path() -> Option<PathBuf> {
+    paths::install_root().ok().map(|root| root.join("support.json"))
+}
+
+fn deserialize_date<'de, D>(deserializer: D) -> Result<Date, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    Date::parse(&raw).ok_or_else(|| serde::de::Error::custom(format!("invalid date '{}', expected YYYY-MM-DD", raw)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TODAY: Date = Date::new(2024, 6, 1);
+
+    #[test]
+    fn known_branches_follow_schedule() {
+        let schedule = SupportSchedule::embedded();
+        assert_eq!(schedule.status("8.3", TODAY), BranchStatus::Active);
+        assert_eq!(schedule.status("8.2", TODAY), BranchStatus::Active);
+        assert_eq!(schedule.status("8.1", TODAY), BranchStatus::Lts);
+        assert_eq!(schedule.status("8.0", TODAY), BranchStatus::Eol);
+        assert_eq!(schedule.status("7.4", TODAY), BranchStatus::Eol);
+    }
+
+    #[test]
+    fn status_changes_as_branches_age() {
+        let schedule = SupportSchedule::embedded();
+        assert_eq!(schedule.status("8.2", Date::new(2025, 6, 1)), BranchStatus::Lts);
+        assert_eq!(schedule.status("8.2", Date::new(2027, 1, 1)), BranchStatus::Eol);
+    }
+
+    #[test]
+    fn unknown_branches_are_judged_by_age() {
+        let schedule = SupportSchedule::embedded();
+        assert_eq!(schedule.status("9.0", TODAY), BranchStatus::Active);
+        assert_eq!(schedule.status("5.4", TODAY), BranchStatus::Eol);
+    }
+
+    #[test]
+    fn override_replaces_embedded_dates() {
+        let mut schedule = SupportSchedule::embedded();
+        schedule
+            .apply_override(r#"{"8.0": {"active_until": "2024-12-31", "security_until": "2025-12-31"}}"#)
+            .unwrap();
+        assert_eq!(schedule.status("8.0", TODAY), BranchStatus::Active);
+        assert!(schedule.apply_override(r#"{"8.0": {"active_until": "soon"}}"#).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+/*!
+ * Aligned text tables (`list --format table`, `available --format table`)
+ *
+ * Columns are padded to their widest cell. When the table is wider than the
+ * terminal, the last column (a path, typically) is shortened from the left
+ * so the part that tells entries apart stays visible.
+ */
+
+use std::io::IsTerminal;
+use std::process::{Command, Stdio};
+
+/// Width assumed when the terminal doesn't report one
+const DEFAULT_WIDTH: usize = 80;
+
+/// Narrowest the last column is shortened to
+const MIN_LAST_COLUMN: usize = 12;
+
+/// Spaces between columns
+const GAP: &str = "  ";
+
+/// Human-readable layouts of `list` and `available`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Layout {
+    /// One emoji line per version
+    List,
+    /// Aligned columns, fitted to the terminal width
+    Table,
+}
+
+/// A table of text cells under a header row
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Starts a table with the given column headers
+    pub fn new(headers: &[&str]) -> Self {
+        Table { headers: headers.iter().map(|h| h.to_string()).collect(), rows: Vec::new() }
+    }
+
+    /// Adds a row; missing cells are left empty
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /**
+     * Lays the table out as lines of text
+     *
+     * # Arguments
+     * * `max_width` - Shorten the last column so lines fit, if given
+     *
+     * # Returns
+     * * `Vec<String>` - The header line, a rule, and one line per row
+     */
+    pub fn render(&self, max_width: Option<usize>) -> Vec<String> {
+        let columns = self.headers.len();
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (index, cell) in row.iter().take(columns).enumerate() {
+                widths[index] = widths[index].max(cell.chars().count());
+            }
+        }
+
+        if let (Some(max_width), Some(last)) = (max_width, columns.checked_sub(1)) {
+            let fixed: usize = widths[..last].iter().map(|w| w + GAP.len()).sum();
+            widths[last] = widths[last].min(max_width.saturating_sub(fixed).max(MIN_LAST_COLUMN));
+        }
+
+        let mut lines = vec![self.line(&self.headers, &widths)];
+        lines.push(widths.iter().map(|w| "─".repeat(*w)).collect::<Vec<_>>().join(GAP));
+        lines.extend(self.rows.iter().map(|row| self.line(row, &widths)));
+        lines
+    }
+
+    /// Prints the table, fitted to the terminal when stdout is one
+    pub fn print(&self) {
+        for line in self.render(terminal_width()) {
+            println!("{}", line);
+        }
+    }
+
+    fn line(&self, cells: &[String], widths: &[usize]) -> String {
+        let last = widths.len() - 1;
+        let cells: Vec<String> = widths.iter().enumerate()
+            .map(|(index, width)| {
+                let cell = cells.get(index).map(String::as_str).unwrap_or("");
+                if index == last {
+                    shorten_left(cell, *width)
+                } else {
+                    format!("{:<width$}", cell, width = width)
+                }
+            })
+            .collect();
+        cells.join(GAP)
+    }
+}
+
+/// Cuts `text` down to `width` characters, keeping its end
+fn shorten_left(text: &str, width: usize) -> String {
+    let length = text.chars().count();
+    if length <= width {
+        return text.to_string();
+    }
+    let kept: String = text.chars().skip(length - width + 1).collect();
+    format!("…{}", kept)
+}
+
+/**
+ * Width of the terminal stdout is attached to
+ *
+ * `COLUMNS` wins when set; otherwise `stty` is asked. Output going to a
+ * pipe or file isn't limited.
+ *
+ * # Returns
+ * * `Option<usize>` - The width, or `None` when output shouldn't be fitted
+ */
+pub fn terminal_width() -> Option<usize> {
+    if let Some(columns) = std::env::var("COLUMNS").ok().and_then(|c| c.trim().parse().ok()) {
+        return Some(columns);
+    }
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let output = Command::new("stty").arg("size").stdin(Stdio::inherit()).output().ok()?;
+    let size = String::from_utf8_lossy(&output.stdout);
+    Some(size.split_whitespace().nth(1).and_then(|cols| cols.parse().ok()).unwrap_or(DEFAULT_WIDTH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns_align_and_the_last_one_shrinks() {
+        let mut table = Table::new(&["VERSION", "PATH"]);
+        table.push(vec!["8.3.0".to_string(), "/home/me/.palawija/php-8.3.0".to_string()]);
+        table.push(vec!["8.2.15".to_string(), "/home/me/.palawija/php-8.2.15".to_string()]);
+
+        let lines = table.render(None);
+        assert_eq!(lines[0], "VERSION  PATH");
+        assert_eq!(lines[2], "8.3.0    /home/me/.palawija/php-8.3.0");
+
+        let lines = table.render(Some(24));
+        assert_eq!(lines[3], "8.2.15   …ija/php-8.2.15");
+        assert_eq!(lines[3].chars().count(), 24);
+    }
+}
@@ -0,0 +1,77 @@
+/*!
+ * Companion tools of an installed version (`palawija bin`)
+ *
+ * Besides `php`, a build can install `php-cgi`, `phpize`, `php-config`,
+ * `phpdbg`, `pecl` and `pear` into its `bin` directory, depending on the
+ * configure flags it was built with.
+ */
+
+use std::path::{Path, PathBuf};
+
+/// Companion tools palawija knows about, with the configure flag that enables each
+/// (`None` for tools every default build installs)
+const KNOWN_TOOLS: &[(&str, Option<&str>)] = &[
+    ("php", None),
+    ("php-cgi", None),
+    ("phpize", None),
+    ("php-config", None),
+    ("phpdbg", Some("--enable-phpdbg")),
+    ("pecl", Some("--with-pear")),
+    ("pear", Some("--with-pear")),
+];
+
+/// Returns true if `name` is one of the known companion tools
+pub fn is_known_tool(name: &str) -> bool {
+    KNOWN_TOOLS.iter().any(|(tool, _)| *tool == name)
+}
+
+/**
+ * Finds a tool in a version's `bin` directory
+ *
+ * # Returns
+ * * `Result<PathBuf, String>` - The tool's path, or why it isn't there
+ */
+pub fn tool_path(version_dir: &Path, version: &str, tool: &str) -> Result<PathBuf, String> {
+    let path = version_dir.join("bin").join(tool);
+    if path.is_file() {
+        return Ok(path);
+    }
+    Err(match KNOWN_TOOLS.iter().find(|(name, _)| *name == tool) {
+        Some((_, Some(flag))) => format!(
+            "{} was not built for PHP {}\n💡 Rebuild with the {} configure flag to get it",
+            tool, version, flag
+        ),
+        Some((_, None)) => format!(
+            "{} is missing from PHP {}, although default builds include it\n💡 The build may be incomplete: palawija reinstall {} --compile",
+            tool, version, version
+        ),
+        None => format!("{} not found in {}", tool, version_dir.join("bin").display()),
+    })
+}
+
+/// Every executable in a version's `bin` directory, sorted
+pub fn list_tools(version_dir: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(version_dir.join("bin")) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_file())
+        .filter_map(|entry| entry.file_name().to_str().map(|name| name.to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_tools_name_their_configure_flag() {
+        let error = tool_path(Path::new("/nonexistent"), "8.3.0", "pecl").unwrap_err();
+        assert!(error.contains("--with-pear"));
+        assert!(is_known_tool("phpdbg"));
+        assert!(!is_known_tool("8.3.0"));
+    }
+}
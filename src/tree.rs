@@ -0,0 +1,159 @@
+/*!
+ * Directory layout and disk usage of installed versions (`list --tree`)
+ *
+ * Each version shows its key subdirectories with their sizes, what the rest
+ * of the directory takes (the source tree and build files, usually the
+ * bulk) and the highlights of its manifest. Sizes are computed as each line
+ * is printed, so the first versions show up while later ones are still
+ * being measured, and only the key subdirectories are expanded, down to
+ * `--depth` levels.
+ */
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::git;
+use crate::installs::{self, InstallInfo};
+use crate::manifest::Manifest;
+use crate::space;
+
+/// Subdirectories of a version shown in the tree
+const KEY_DIRS: [&str; 4] = ["bin", "etc", "lib", "include"];
+
+/// Levels shown below each version unless `--depth` says otherwise
+pub const DEFAULT_DEPTH: u32 = 1;
+
+/// Deepest `--depth` accepted
+pub const MAX_DEPTH: u32 = 4;
+
+/// Subdirectories listed per directory, largest first; the rest are counted
+const MAX_CHILDREN: usize = 8;
+
+/// Column the sizes start at, counted from the first tree branch
+const SIZE_COLUMN: usize = 24;
+
+/// Indentation of the tree below a version header
+const INDENT: &str = "   ";
+
+/**
+ * Prints each version with its key subdirectories and their sizes
+ *
+ * # Arguments
+ * * `infos` - The versions to show
+ * * `depth` - How many directory levels to show below each version
+ */
+pub fn print_tree(infos: &[InstallInfo], depth: u32) {
+    for info in infos {
+        let total = installs::dir_size(&info.path);
+        let status = match (info.compiled, info.active) {
+            (true, true) => "compiled, active",
+            (true, false) => "compiled",
+            (false, _) => "source only",
+        };
+        println!("🌳 PHP {} ({}, {})", info.version, space::format_bytes(total), status);
+        println!("   📂 {}", info.path.display());
+        for line in highlights(info.manifest.as_ref()) {
+            println!("   📄 {}", line);
+        }
+
+        let mut listed = 0;
+        for dir in KEY_DIRS.iter().map(|name| info.path.join(name)).filter(|dir| dir.is_dir()) {
+            let size = installs::dir_size(&dir);
+            listed += size;
+            print_entry(&dir, size, INDENT, false, depth.saturating_sub(1));
+        }
+        println!(
+            "{}└── {:<width$} {}  (source tree, build files and the rest)",
+            INDENT,
+            "other",
+            space::format_bytes(total.saturating_sub(listed)),
+            width = SIZE_COLUMN
+        );
+        println!();
+    }
+}
+
+/// Prints one directory and, while `levels` remain, its largest subdirectories
+fn print_entry(dir: &Path, size: u64, prefix: &str, last: bool, levels: u32) {
+    let name = dir.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    // Deeper entries get narrower name columns, so every size lines up
+    let width = SIZE_COLUMN.saturating_sub(prefix.chars().count() - INDENT.len());
+    println!("{}{} {:<width$} {}", prefix, if last { "└──" } else { "├──" }, name, space::format_bytes(size), width = width);
+    let _ = std::io::stdout().flush();
+    if levels == 0 {
+        return;
+    }
+
+    let prefix = format!("{}{}   ", prefix, if last { " " } else { "│" });
+    let (children, hidden) = largest_subdirs(dir);
+    for (index, (child, size)) in children.iter().enumerate() {
+        print_entry(child, *size, &prefix, hidden == 0 && index + 1 == children.len(), levels - 1);
+    }
+    if hidden > 0 {
+        println!("{}└── … {} more", prefix, hidden);
+    }
+}
+
+/// The `MAX_CHILDREN` largest subdirectories of `dir`, and how many were left out
+fn largest_subdirs(dir: &Path) -> (Vec<(PathBuf, u64)>, usize) {
+    let mut children: Vec<(PathBuf, u64)> = std::fs::read_dir(dir).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+        .map(|entry| {
+            let path = entry.path();
+            let size = installs::dir_size(&path);
+            (path, size)
+        })
+        .collect();
+    children.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let hidden = children.len().saturating_sub(MAX_CHILDREN);
+    children.truncate(MAX_CHILDREN);
+    (children, hidden)
+}
+
+/// The manifest details worth a glance: origin, build and extensions
+fn highlights(manifest: Option<&Manifest>) -> Vec<String> {
+    let Some(manifest) = manifest else {
+        return vec!["No manifest (installed by hand or by an older palawija)".to_string()];
+    };
+    let origin = match (&manifest.git_ref, &manifest.git_commit) {
+        (Some(git_ref), Some(commit)) => format!("{} at {}", git_ref, git::short_commit(commit)),
+        _ => manifest.source_url.clone(),
+    };
+    let mut lines = vec![format!("Installed {} from {}", manifest.installed_at, origin)];
+    if manifest.compiled {
+        lines.push(format!("Compiled with {} configure flag(s)", manifest.configure_flags.len()));
+    }
+    if !manifest.extensions.is_empty() {
+        lines.push(format!("Extensions: {}", manifest.extensions.join(", ")));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subdirectories_are_ranked_by_size() {
+        let root = std::env::temp_dir().join(format!("palawija-tree-{}", std::process::id()));
+        for (name, bytes) in [("small", 10), ("large", 5000), ("medium", 700)] {
+            std::fs::create_dir_all(root.join(name)).unwrap();
+            std::fs::write(root.join(name).join("file"), vec![0u8; bytes]).unwrap();
+        }
+        std::fs::write(root.join("not-a-dir"), "").unwrap();
+
+        let (children, hidden) = largest_subdirs(&root);
+        let names: Vec<_> = children.iter().map(|(path, _)| path.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, ["large", "medium", "small"]);
+        assert_eq!(hidden, 0);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let mut manifest = Manifest::new("8.3.0", "https://www.php.net/distributions/php-8.3.0.tar.xz", None);
+        manifest.extensions = vec!["redis".to_string()];
+        let lines = highlights(Some(&manifest));
+        assert!(lines[0].ends_with("from https://www.php.net/distributions/php-8.3.0.tar.xz"));
+        assert_eq!(lines.last().unwrap(), "Extensions: redis");
+    }
+}
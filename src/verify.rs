@@ -0,0 +1,155 @@
+/*!
+ * Integrity checks for an installed version (`palawija verify`)
+ *
+ * Every check prints a pass/fail line; any failure makes the command exit
+ * non-zero so CI can gate on it.
+ */
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use crate::download;
+use crate::elf;
+use crate::git;
+use crate::manifest::Manifest;
+use crate::paths;
+use crate::probe::{self, Probe};
+
+/// Result of a single check
+enum Check {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+/**
+ * Runs every integrity check against an installed version
+ *
+ * Checks, in order: the version directory exists, `bin/php` is an
+ * executable for this machine, `php --version` runs and reports the expected
+ * version, and - when the manifest has a checksum and the tarball is still
+ * cached - the cached source tarball still matches it.
+ *
+ * # Returns
+ * * `Result<(), Box<dyn std::error::Error>>` - Error if any check failed
+ */
+pub fn run_verify(version: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let version_dir = paths::version_dir(version)?;
+    println!("🔎 Verifying PHP {} in {}\n", version, version_dir.display());
+
+    let checks = if version_dir.is_dir() {
+        let binary = paths::php_binary(&version_dir);
+        vec![
+            Check::Pass("Version directory exists".to_string()),
+            check_binary(&binary),
+            check_reported_version(&binary, version),
+            check_tarball(&version_dir),
+        ]
+    } else {
+        vec![Check::Fail(format!("Version directory {} does not exist", version_dir.display()))]
+    };
+
+    let mut failures = 0;
+    for check in &checks {
+        match check {
+            Check::Pass(message) => println!("   ✅ {}", message),
+            Check::Fail(message) => {
+                failures += 1;
+                println!("   ❌ {}", message);
+            }
+            Check::Skip(message) => println!("   ⏭️  {}", message),
+        }
+    }
+
+    println!("\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    if failures == 0 {
+        println!("✅ PHP {} is intact", version);
+        Ok(())
+    } else {
+        Err(format!("{} check(s) failed for PHP {}", failures, version).into())
+    }
+}
+
+/// `bin/php` exists, is executable and was built for this machine
+fn check_binary(binary: &Path) -> Check {
+    let Ok(metadata) = std::fs::metadata(binary) else {
+        return Check::Fail(format!("{} not found (source only? compile it first)", binary.display()));
+    };
+    if metadata.permissions().mode() & 0o111 == 0 {
+        return Check::Fail(format!("{} is not executable", binary.display()));
+    }
+    match elf::check_compatible(binary) {
+        Ok(()) => Check::Pass(format!("{} is executable", binary.display())),
+        Err(e) => Check::Fail(e),
+    }
+}
+
+/// `php --version` runs and reports the version the directory is named after
+fn check_reported_version(binary: &Path, version: &str) -> Check {
+    if !binary.exists() {
+        return Check::Skip("php --version (no binary)".to_string());
+    }
+    match probe::php_version(binary) {
+        Probe::Output(output) => match crate::parse_php_version(&output) {
+            Some(reported) if reported == version => Check::Pass(format!("php --version reports {}", reported)),
+            Some(reported) if git::git_ref(version).is_some() => {
+                Check::Pass(format!("php --version reports {} (Git snapshot)", reported))
+            }
+            Some(reported) => Check::Fail(format!("php --version reports {} instead of {}", reported, version)),
+            None => Check::Fail("Could not parse the output of php --version".to_string()),
+        },
+        Probe::Failed(status) => Check::Fail(format!("php --version failed ({})", status)),
+        Probe::TimedOut => Check::Fail(probe::TIMEOUT_MESSAGE.to_string()),
+        Probe::SpawnFailed(e) => Check::Fail(format!("Could not run php --version: {}", e)),
+    }
+}
+
+/// The cached source tarball still matches the checksum recorded at install time
+fn check_tarball(version_dir: &Path) -> Check {
+    let Some(manifest) = Manifest::load(version_dir) else {
+        return Check::Skip("Source checksum (no manifest)".to_string());
+    };
+    let Some(expected) = manifest.sha256 else {
+        return Check::Skip("Source checksum (none recorded in the manifest)".to_string());
+    };
+    // Downloads are cached under the file name of their URL
+    let file_name = manifest.source_url.rsplit('/').next().unwrap_or_default();
+    let Ok(cached) = paths::cache_dir().map(|dir| dir.join(file_name)) else {
+        return Check::Skip("Source checksum (cache directory unavailable)".to_string());
+    };
+    if !cached.exists() {
+        return Check::Skip("Source checksum (tarball no longer cached)".to_string());
+    }
+    match download::sha256_file(&cached) {
+        Some(actual) if actual == expected => Check::Pass("Cached source tarball matches the recorded SHA-256".to_string()),
+        Some(actual) => Check::Fail(format!("Cached tarball SHA-256 {} doesn't match the recorded {}", actual, expected)),
+        None => Check::Fail(format!("Could not compute the SHA-256 of {}", cached.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_and_reported_version_are_checked() {
+        let version_dir = std::env::temp_dir().join(format!("palawija-verify-{}", std::process::id()));
+        let binary = paths::php_binary(&version_dir);
+        std::fs::create_dir_all(binary.parent().unwrap()).unwrap();
+        assert!(matches!(check_binary(&binary), Check::Fail(_)));
+        assert!(matches!(check_reported_version(&binary, "8.3.0"), Check::Skip(_)));
+
+        std::fs::write(&binary, "#!/bin/sh\necho 'PHP 8.3.0 (cli) (built: Nov 21 2023 10:00:00) (NTS)'\n").unwrap();
+        assert!(matches!(check_binary(&binary), Check::Fail(message) if message.contains("not executable")));
+        std::fs::set_permissions(&binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(matches!(check_binary(&binary), Check::Pass(_)));
+        assert!(matches!(check_reported_version(&binary, "8.3.0"), Check::Pass(_)));
+        assert!(matches!(check_reported_version(&binary, "8.2.15"), Check::Fail(message) if message.contains("instead of 8.2.15")));
+
+        // Without a recorded checksum there's nothing to compare the tarball against
+        assert!(matches!(check_tarball(&version_dir), Check::Skip(message) if message.contains("no manifest")));
+        Manifest::new("8.3.0", "https://www.php.net/distributions/php-8.3.0.tar.xz", None).save(&version_dir).unwrap();
+        assert!(matches!(check_tarball(&version_dir), Check::Skip(message) if message.contains("none recorded")));
+        std::fs::remove_dir_all(&version_dir).unwrap();
+    }
+}
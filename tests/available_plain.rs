@@ -0,0 +1,57 @@
+//! `available --plain` against a fake curl that only the GitHub tags fallback answers
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// Fails the releases API, serves a releases page without tarballs and a single page of php-src tags
+const FAKE_CURL: &str = r#"#!/bin/sh
+for arg; do url="$arg"; done
+case "$url" in
+    *"index.php?json"*) exit 22 ;;
+    "https://www.php.net/releases/") echo "<html><body>No releases here</body></html>" ;;
+    *"php-src/tags"*) echo '[{"name": "php-8.3.1"}, {"name": "php-8.3.0"}, {"name": "php-8.4.0RC1"}, {"name": "PHP-8.3"}]' ;;
+    *) exit 22 ;;
+esac
+"#;
+
+fn sandbox(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("palawija-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("fake-bin")).unwrap();
+    let curl = root.join("fake-bin").join("curl");
+    std::fs::write(&curl, FAKE_CURL).unwrap();
+    std::fs::set_permissions(&curl, std::fs::Permissions::from_mode(0o755)).unwrap();
+    root
+}
+
+fn palawija(root: &Path, args: &[&str]) -> Output {
+    let path = format!("{}:{}", root.join("fake-bin").display(), std::env::var("PATH").unwrap_or_default());
+    Command::new(env!("CARGO_BIN_EXE_palawija"))
+        .args(args)
+        .current_dir(root)
+        .env("PATH", path)
+        .env("HOME", root)
+        .env("PALAWIJA_ROOT", root.join("palawija"))
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn tags_fallback_prints_only_versions_on_stdout() {
+    let root = sandbox("plain-tags");
+
+    let output = palawija(&root, &["-v", "available", "--plain"]);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "8.4.0RC1\n8.3.1\n8.3.0\n");
+    assert!(stderr.contains("falling back to GitHub tags"), "{}", stderr);
+    assert!(stderr.contains("Successfully retrieved 4 tags"), "{}", stderr);
+    assert!(stderr.contains("🗄️  https://www.php.net/releases/: refetched"), "{}", stderr);
+
+    let output = palawija(&root, &["available", "8", "--plain", "--stable-only", "--limit", "1"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "8.3.1\n");
+
+    std::fs::remove_dir_all(&root).unwrap();
+}